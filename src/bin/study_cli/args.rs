@@ -0,0 +1,483 @@
+use crate::commands;
+use crate::daemon::DEFAULT_NOTIFY_COMMAND;
+use crate::storage::ConflictPolicy;
+use chrono::{DateTime, NaiveTime, Utc};
+use clap::{Parser, Subcommand};
+use std::error::Error;
+use std::process::Command as Process;
+use vultan::export::ExportFormat;
+use vultan::state::codec::StateFormat;
+use vultan::state::config::CONFIG_FILE_NAME;
+use vultan::state::file::Compression;
+use vultan::state::filter::CardFilter;
+use vultan::state::hand::QueueOrder;
+
+const DEFAULT_STATE_FILE_NAME: &str = ".vultan.ron";
+const DEFAULT_SHARDED_STATE_FILE_NAME: &str = ".vultan/state.ron";
+const DEFAULT_LOG_DIR_NAME: &str = ".vultan-logs";
+const DEFAULT_DEVICE_NAME: &str = "local";
+const DEFAULT_PASSPHRASE_ENV: &str = "VULTAN_PASSPHRASE";
+const DEFAULT_HOOKS_FILE_NAME: &str = ".vultan/hooks.ron";
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "study-cli",
+    version,
+    about = "Spaced repetition over a directory of notes"
+)]
+pub struct Args {
+    /// Directory containing the markdown notes to study
+    #[arg(long, global = true, default_value = ".")]
+    pub notes_dir: String,
+
+    /// File used to persist scheduling state [default: <NOTES_DIR>/.vultan.ron, or
+    /// <NOTES_DIR>/.vultan/state.ron with --shard-state]
+    #[arg(long, global = true)]
+    pub state_file: Option<String>,
+
+    /// Keep each deck's cards in a file of their own, in a "decks" directory beside the state
+    /// file, so that saving only rewrites the decks whose cards changed
+    #[arg(long, global = true)]
+    pub shard_state: bool,
+
+    /// Fail rather than skip notes that can't be read as cards, e.g. to check a shared deck in CI
+    #[arg(long, global = true)]
+    pub strict: bool,
+
+    /// Directory holding the review logs of every device [default: <NOTES_DIR>/.vultan-logs]
+    #[arg(long, global = true)]
+    pub log_dir: Option<String>,
+
+    /// Name of this device's review log [default: the host name]
+    #[arg(long, global = true)]
+    pub device_name: Option<String>,
+
+    /// What to do when another process modified the state file since it was loaded
+    #[arg(long, global = true, value_enum, default_value_t = ConflictPolicy::Merge)]
+    pub on_conflict: ConflictPolicy,
+
+    /// Environment variable holding the passphrase the state file, review logs and other files
+    /// beside them are encrypted with; they're left in plaintext when it's unset
+    #[arg(long, global = true, default_value = DEFAULT_PASSPHRASE_ENV)]
+    pub passphrase_env: String,
+
+    /// Shell command printing the state file's passphrase, e.g. a keyring lookup such as
+    /// `secret-tool lookup vultan notes`; takes precedence over --passphrase-env
+    #[arg(long, global = true)]
+    pub passphrase_command: Option<String>,
+
+    /// Compress the state file when saving it, with gzip or zstd; compressed state files are
+    /// read whether or not this is given
+    #[arg(long, global = true)]
+    pub compress_state: Option<Compression>,
+
+    /// Format of the state file: ron, json or yaml; by default, taken from the state file's
+    /// extension, which is RON unless it's .json, .yaml or .yml
+    #[arg(long, global = true)]
+    pub state_format: Option<StateFormat>,
+
+    /// RON file overriding the colours and borders of the interactive screens, and the config
+    /// file's [theme]
+    #[arg(long, global = true)]
+    pub theme_file: Option<String>,
+
+    /// TOML file of preferences layered over the state file's: [parsing], [decks."<NAME>"] and
+    /// [theme] [default: <NOTES_DIR>/vultan.toml, if it exists]
+    #[arg(long, global = true)]
+    pub config: Option<String>,
+
+    /// RON file of shell commands to run on_session_start, on_session_end and on_card_reviewed
+    /// [default: <NOTES_DIR>/.vultan/hooks.ron, if it exists]
+    #[arg(long, global = true)]
+    pub hooks_file: Option<String>,
+
+    /// Reviews to aim for each day, in decks whose options set no daily_goal
+    #[arg(long, global = true)]
+    pub daily_goal: Option<usize>,
+
+    /// Use plain line-by-line output without colours or screen clearing
+    #[arg(long, global = true)]
+    pub plain: bool,
+
+    /// Emit machine-readable JSON instead of text (non-interactive commands only)
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Trace loading, dealing and reviewing to stderr, with timings; RUST_LOG, e.g.
+    /// `RUST_LOG=vultan=trace`, picks what is traced instead
+    #[arg(long, global = true)]
+    pub verbose: bool,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Review the due cards of a deck, picking one interactively if none is given
+    Study {
+        #[arg(long)]
+        deck_name: Option<String>,
+        /// Only review cards with this tag (repeatable; any of them will do)
+        #[arg(long = "include-tag")]
+        include_tags: Vec<String>,
+        /// Leave out cards with this tag (repeatable)
+        #[arg(long = "exclude-tag")]
+        exclude_tags: Vec<String>,
+        /// Order to review cards in: random, oldest-due-first, ease-ascending or
+        /// deck-round-robin [default: the deck's, or random]
+        #[arg(long)]
+        order: Option<QueueOrder>,
+        /// Shuffle with this seed, e.g. one logged as a past review's session_seed, to replay
+        /// that session's order [default: a random seed]
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Work for this many minutes at a time, with a break after each period during which
+        /// no cards are shown
+        #[arg(long)]
+        focus_minutes: Option<u32>,
+        /// Length of the breaks between --focus-minutes periods
+        #[arg(long, default_value_t = 5, requires = "focus_minutes")]
+        break_minutes: u32,
+        /// Also deal the cards falling due within this many days, scheduling them as if reviewed
+        /// on their due dates
+        #[arg(long)]
+        review_ahead: Option<u32>,
+        /// Deal at most this many cards, those due longest, whatever the deck's limits allow
+        #[arg(long)]
+        max_cards: Option<usize>,
+        /// Deal only one card of each note (true), or all of them (false)
+        /// [default: the deck's bury_siblings, or false]
+        #[arg(long)]
+        bury_siblings: Option<bool>,
+        /// End the session once this many minutes have passed, after the card under review
+        #[arg(long)]
+        max_minutes: Option<u32>,
+        /// Offer [i] at the answer to type in the next interval, e.g. 3d or 2w, in place of a
+        /// grade
+        #[arg(long)]
+        manual_intervals: bool,
+    },
+    /// Browse decks interactively: study, inspect stats, edit coefficients and chart reviews
+    Browse,
+    /// List every deck with its card and due counts
+    List,
+    /// Show scheduling statistics for one or all decks
+    Stats {
+        #[arg(long)]
+        deck_name: Option<String>,
+    },
+    /// Propose interval coefficients for a deck from its review history
+    Optimize {
+        #[arg(long)]
+        deck_name: String,
+        /// Share of reviews that should be recalled, between 0 and 1
+        #[arg(long, default_value_t = 0.9)]
+        target_retention: f64,
+        /// Save the proposed coefficients to the deck's options
+        #[arg(long)]
+        apply: bool,
+    },
+    /// Project the daily number of reviews over the coming months
+    Simulate {
+        /// Only project this deck's cards [default: every deck]
+        #[arg(long)]
+        deck_name: Option<String>,
+        #[arg(long, default_value_t = 3)]
+        months: u32,
+        /// New cards studied each day: unseen cards first, then imagined additions
+        #[arg(long, default_value_t = 20)]
+        new_cards_per_day: usize,
+        /// Share of reviews assumed to be recalled, between 0 and 1
+        #[arg(long, default_value_t = 0.9)]
+        retention: f64,
+        /// Try these coefficients instead of the decks' own (unset ones keep the deck's)
+        #[arg(long)]
+        pass_coef: Option<f64>,
+        #[arg(long)]
+        easy_coef: Option<f64>,
+        #[arg(long)]
+        fail_coef: Option<f64>,
+    },
+    /// Push due dates back by a number of days, e.g. while away from studying
+    Postpone {
+        #[arg(long)]
+        days: u32,
+        /// Only postpone this deck's cards [default: every deck]
+        #[arg(long, visible_alias = "deck")]
+        deck_name: Option<String>,
+        /// Spread the cards already due over this many days after the return
+        #[arg(long)]
+        spread: Option<u32>,
+        /// List the new due dates without saving them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Move cards off the days with the most reviews due onto quieter days nearby
+    Rebalance {
+        /// Days a card may be moved by, earlier or later
+        #[arg(long, default_value_t = 7)]
+        window: u32,
+        /// Most reviews to leave on a day [default: the mean of the days within the window]
+        #[arg(long)]
+        max_per_day: Option<usize>,
+        /// Only move this deck's cards [default: every deck]
+        #[arg(long, visible_alias = "deck")]
+        deck_name: Option<String>,
+        /// List the new due dates without saving them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Make a card due at a given date, whatever its schedule, e.g. the morning of an exam
+    SetDue {
+        /// As in the state file, or relative to the notes directory
+        card_path: String,
+        /// As YYYY-MM-DD, or YYYY-MM-DD HH:MM, in local time
+        #[arg(value_parser = commands::parse_local_date_time)]
+        due: DateTime<Utc>,
+    },
+    /// Forget the schedules of a card, or of a deck's cards, to learn them again as new
+    Reset {
+        /// As in the state file, or relative to the notes directory
+        #[arg(required_unless_present = "deck_name")]
+        card_path: Option<String>,
+        #[arg(long, visible_alias = "deck", conflicts_with = "card_path")]
+        deck_name: Option<String>,
+        /// Reset without asking for confirmation
+        #[arg(long)]
+        yes: bool,
+    },
+    /// List the due cards, or count them, exiting with 1 if there are more than --threshold
+    Due {
+        #[arg(long, visible_alias = "deck")]
+        deck_name: Option<String>,
+        /// Print only the number of due cards
+        #[arg(long)]
+        count: bool,
+        /// Most due cards to exit successfully with, e.g. for a shell prompt or status bar
+        #[arg(long)]
+        threshold: Option<usize>,
+    },
+    /// Show today's reviews, toward the daily goal, and the cards still due, per top-level deck
+    Today {
+        #[arg(long, visible_alias = "deck")]
+        deck_name: Option<String>,
+    },
+    /// List the cards flagged during review
+    Flagged {
+        #[arg(long)]
+        deck_name: Option<String>,
+    },
+    /// Search the questions and answers of every card, best matches first
+    Search {
+        /// Words to look for, in order and ignoring case
+        query: String,
+        /// Treat the query as a regular expression
+        #[arg(long)]
+        regex: bool,
+    },
+    /// Serve a review web page and JSON API, e.g. for studying from a phone on the LAN
+    Serve {
+        /// Address to listen on; use 0.0.0.0:<PORT> to accept connections from other devices
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        address: String,
+    },
+    /// Export the review history of every device as Anki revlog rows (CSV, or JSON with --json)
+    ExportRevlog {
+        /// File to write to [default: stdout]
+        #[arg(long)]
+        output: Option<String>,
+    },
+    /// Export a deck's cards to share with people who don't use vultan
+    Export {
+        /// Format to export to: html, a folder holding a single self-contained page, or latex or
+        /// typst, source of printable two-sided flashcards
+        #[arg(long)]
+        format: ExportFormat,
+        #[arg(long = "deck")]
+        deck_name: String,
+        /// Folder to export to
+        #[arg(long)]
+        output: String,
+        /// Hide each answer until its card is clicked (html)
+        #[arg(long)]
+        reveal: bool,
+    },
+    /// Write a note per row of a CSV or JSON data file, filling in a template's {{field}}
+    /// placeholders
+    Generate {
+        /// CSV file with a header line, or JSON array of objects
+        data: String,
+        /// Note template, e.g. "tags: spanish\n# Question\n{{word}}\n# Answer\n{{meaning}}\n----\n"
+        #[arg(long)]
+        template: String,
+        /// Template of each note's name [default: the row's first field]
+        #[arg(long)]
+        name: Option<String>,
+        /// Directory, within the notes directory, to write the notes to
+        #[arg(long, default_value = "generated")]
+        output_dir: String,
+    },
+    /// Import an Anki text (.txt/.csv) export as notes, keeping any scheduling columns
+    ImportAnki {
+        /// Anki "Notes in Plain Text" export
+        file: String,
+        /// Comma-separated meaning of each column: question, answer, deck, due, interval,
+        /// ease, or _ to skip a column
+        #[arg(long, default_value = "question,answer")]
+        columns: String,
+        /// Deck for records without a deck column
+        #[arg(long, default_value = "anki")]
+        deck_name: String,
+        /// Directory, within the notes directory, to write the imported notes to
+        #[arg(long, default_value = "anki")]
+        output_dir: String,
+    },
+    /// Import a Mochi export's data.json, from the .mochi archive, as notes with their schedules
+    ImportMochi {
+        /// data.json extracted from the .mochi archive
+        file: String,
+        /// Directory, within the notes directory, to write the imported notes to
+        #[arg(long, default_value = "mochi")]
+        output_dir: String,
+    },
+    /// Import the flashcards of a RemNote markdown export as notes
+    ImportRemnote {
+        /// RemNote markdown export
+        file: String,
+        /// Deck for the imported cards
+        #[arg(long, default_value = "remnote")]
+        deck_name: String,
+        /// Directory, within the notes directory, to write the imported notes to
+        #[arg(long, default_value = "remnote")]
+        output_dir: String,
+    },
+    /// Import a SuperMemo Q&A text export as notes, keeping any exported repetition data
+    ImportSupermemo {
+        /// SuperMemo Q&A text export
+        file: String,
+        /// Deck for the imported cards
+        #[arg(long, default_value = "supermemo")]
+        deck_name: String,
+        /// Directory, within the notes directory, to write the imported notes to
+        #[arg(long, default_value = "supermemo")]
+        output_dir: String,
+    },
+    /// Exchange review logs with a shared folder (e.g. Syncthing or Dropbox) and merge them
+    Sync {
+        /// Shared folder that every device syncs its review log through
+        remote_dir: String,
+    },
+    /// Add and remove decks of many cards at once, rewriting their notes
+    Bulk {
+        /// Cards to change, as deck:<name> or tag:<name> (repeatable; all must match)
+        /// [default: every card]
+        #[arg(long = "filter")]
+        filters: Vec<CardFilter>,
+        /// Deck to add the cards to (repeatable)
+        #[arg(long = "add-deck")]
+        add_decks: Vec<String>,
+        /// Deck to remove the cards from (repeatable)
+        #[arg(long = "remove-deck")]
+        remove_decks: Vec<String>,
+    },
+    /// Rename a deck and its sub-decks, rewriting their notes and keeping their coefficients
+    RenameDeck { old_name: String, new_name: String },
+    /// Remove the cards whose notes were deleted from the state file and their decks
+    Prune {
+        /// List the cards that would be pruned without pruning them
+        #[arg(long)]
+        dry_run: bool,
+        /// Keep the pruned cards, with their schedules, in <NOTES_DIR>/.vultan/archived_cards.ron
+        #[arg(long)]
+        archive: bool,
+    },
+    /// List the changes saved to the state file, with when and by which command they were made
+    Audit {
+        /// Only list the changes since this date, as YYYY-MM-DD, or YYYY-MM-DD HH:MM
+        #[arg(long, value_parser = commands::parse_local_date_time)]
+        since: Option<DateTime<Utc>>,
+    },
+    /// List the notes that couldn't be read as cards, with suggestions for fixing them
+    Errors,
+    /// Check the state file for inconsistencies, such as decks listing missing cards
+    Doctor {
+        /// Make the safe repairs and save the state
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Keep running, and send a desktop notification of the decks with cards due at given times
+    Daemon {
+        /// Time of day to notify at, as HH:MM (repeatable)
+        #[arg(long = "at", value_parser = parse_time_of_day)]
+        times: Vec<NaiveTime>,
+        /// Minutes between notifications, when no --at times are given
+        #[arg(long, default_value_t = 60)]
+        every: u32,
+        /// Shell command sending the notification, given its text in VULTAN_MESSAGE
+        #[arg(long, default_value = DEFAULT_NOTIFY_COMMAND)]
+        notify_command: String,
+    },
+}
+
+fn parse_time_of_day(input: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(input, "%H:%M")
+        .map_err(|e| format!("Expected a time such as 09:30 -> {}", e))
+}
+
+impl Args {
+    pub fn state_file_path(&self) -> String {
+        match &self.state_file {
+            Some(path) => path.clone(),
+            None if self.shard_state => self.in_notes_dir(DEFAULT_SHARDED_STATE_FILE_NAME),
+            None => self.in_notes_dir(DEFAULT_STATE_FILE_NAME),
+        }
+    }
+
+    pub fn config_file_path(&self) -> String {
+        match &self.config {
+            Some(path) => path.clone(),
+            None => self.in_notes_dir(CONFIG_FILE_NAME),
+        }
+    }
+
+    pub fn default_hooks_file_path(&self) -> String {
+        self.in_notes_dir(DEFAULT_HOOKS_FILE_NAME)
+    }
+
+    pub fn log_dir_path(&self) -> String {
+        match &self.log_dir {
+            Some(path) => path.clone(),
+            None => self.in_notes_dir(DEFAULT_LOG_DIR_NAME),
+        }
+    }
+
+    pub fn device_name(&self) -> String {
+        self.device_name
+            .clone()
+            .or_else(|| std::env::var("HOSTNAME").ok())
+            .or_else(|| std::env::var("COMPUTERNAME").ok())
+            .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| DEFAULT_DEVICE_NAME.to_string())
+    }
+
+    pub fn passphrase(&self) -> Result<Option<String>, Box<dyn Error>> {
+        let Some(command) = &self.passphrase_command else {
+            return Ok(std::env::var(&self.passphrase_env)
+                .ok()
+                .filter(|passphrase| !passphrase.is_empty()));
+        };
+        let output = Process::new("sh").arg("-c").arg(command).output()?;
+        if !output.status.success() {
+            return Err(format!("Passphrase command failed: {}", command).into());
+        }
+        let passphrase = String::from_utf8(output.stdout)?;
+        Ok(Some(passphrase.trim_end_matches(['\r', '\n']).to_string()))
+    }
+
+    fn in_notes_dir(&self, file_name: &str) -> String {
+        format!("{}/{}", self.notes_dir.trim_end_matches('/'), file_name)
+    }
+}
@@ -0,0 +1,151 @@
+use crate::commands::{
+    all_deck_stats, format_deck_stats, format_mastery, format_streak, revise_deck, tree_label,
+    SessionOptions,
+};
+use crate::heatmap;
+use crate::hooks::Hooks;
+use crate::repl::{edit_deck_options, edit_interval_coefficients};
+use crate::storage::Storage;
+use crate::terminal::Terminal;
+use chrono::Local;
+use std::error::Error;
+use std::io::{BufRead, Write};
+use vultan::state::deck::DeckStats;
+use vultan::state::hand::DealOptions;
+use vultan::state::review_log::daily_review_counts;
+use vultan::state::State;
+
+const INSTRUCTIONS: &str = "[n] study  [s n] stats  [e n] edit settings  [h] heatmap  [q] quit > ";
+const SETTINGS_HELP: &str = "Interval coefficients, daily limits and order.\n\
+    [Enter] keeps a setting; - leaves it to the parent decks.";
+
+#[derive(Debug, PartialEq)]
+enum BrowserCommand {
+    Study(usize),
+    Stats(usize),
+    Edit(usize),
+    Heatmap,
+    Quit,
+}
+
+pub fn run(
+    terminal: &mut Terminal<impl BufRead, impl Write>,
+    state: State,
+    storage: &Storage,
+    hooks: &Hooks,
+    session_options: &SessionOptions,
+) -> Result<(), Box<dyn Error>> {
+    let mut state = state;
+    loop {
+        let decks = all_deck_stats(&state)?;
+        let listing = format!(
+            "{}\n\n{}",
+            format_listing(&decks),
+            format_streak(&storage.streak()?)
+        );
+        terminal.section("Decks", &listing)?;
+        let command = match parse_command(&terminal.prompt(INSTRUCTIONS)?, decks.len()) {
+            Some(command) => command,
+            None => continue,
+        };
+        state = match command {
+            BrowserCommand::Quit => return Ok(()),
+            BrowserCommand::Study(i) => {
+                let deal_options = DealOptions::default();
+                revise_deck(
+                    terminal,
+                    state,
+                    &decks[i].name,
+                    &deal_options,
+                    storage,
+                    hooks,
+                    session_options,
+                )?
+            }
+            BrowserCommand::Stats(i) => {
+                terminal.section(&decks[i].name, &format_deck_stats(&decks[i]))?;
+                terminal.prompt("[Enter] back")?;
+                state
+            }
+            BrowserCommand::Heatmap => {
+                let counts = daily_review_counts(&storage.read_logs()?, &Local);
+                let today = Local::now().date_naive();
+                terminal.section("Reviews", &heatmap::render(&counts, today, heatmap::WEEKS))?;
+                terminal.prompt("[Enter] back")?;
+                state
+            }
+            BrowserCommand::Edit(i) => {
+                let deck = state.decks()[&decks[i].name].clone();
+                let current = state.deck_options(&deck.name)?.interval_coefficients;
+                terminal.section(&deck.name, SETTINGS_HELP)?;
+                let coefficients =
+                    edit_interval_coefficients(terminal, &current.unwrap_or_default())?;
+                let options = edit_deck_options(terminal, &deck.options)?;
+                let deck = deck
+                    .with_options(options)
+                    .with_interval_coefficients(coefficients);
+                storage.edit_deck(state, deck)?
+            }
+        };
+    }
+}
+
+fn format_listing(decks: &[DeckStats]) -> String {
+    decks
+        .iter()
+        .enumerate()
+        .map(|(i, d)| {
+            format!(
+                "[{}] {:<32} {:>5} due {:>5} new {:>5} mastered",
+                i + 1,
+                tree_label(&d.name),
+                d.due,
+                d.new,
+                format_mastery(d.mastery)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn parse_command(line: &str, number_of_decks: usize) -> Option<BrowserCommand> {
+    let mut words = line.split_whitespace();
+    let (action, index) = match (words.next()?, words.next(), words.next()) {
+        ("q", None, None) => return Some(BrowserCommand::Quit),
+        ("h", None, None) => return Some(BrowserCommand::Heatmap),
+        (action @ ("s" | "e"), Some(n), None) => (action, n),
+        (n, None, None) => ("", n),
+        _ => return None,
+    };
+    let index = index.parse::<usize>().ok()?.checked_sub(1)?;
+    if index >= number_of_decks {
+        return None;
+    }
+    Some(match action {
+        "s" => BrowserCommand::Stats(index),
+        "e" => BrowserCommand::Edit(index),
+        _ => BrowserCommand::Study(index),
+    })
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case::study("2", Some(BrowserCommand::Study(1)))]
+    #[case::stats("s 1", Some(BrowserCommand::Stats(0)))]
+    #[case::edit(" e  3 \n", Some(BrowserCommand::Edit(2)))]
+    #[case::quit("q\n", Some(BrowserCommand::Quit))]
+    #[case::heatmap("h", Some(BrowserCommand::Heatmap))]
+    #[case::out_of_range("4", None)]
+    #[case::zero("s 0", None)]
+    #[case::unknown_action("x 1", None)]
+    #[case::missing_deck("e", None)]
+    #[case::empty("", None)]
+    fn parse_command(#[case] line: &str, #[case] expected: Option<BrowserCommand>) {
+        assert_eq!(expected, super::parse_command(line, 3));
+    }
+}
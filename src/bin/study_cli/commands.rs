@@ -0,0 +1,1300 @@
+use crate::focus::Focus;
+use crate::hooks::{Event, Hooks};
+use crate::repl::{self, SessionEvent};
+use crate::storage::{Change, Storage};
+use crate::terminal::Terminal;
+use crate::theme::Theme;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use serde::Serialize;
+use std::error::Error;
+use std::io::{BufRead, Write};
+use std::time::Duration;
+use vultan::export::{html, latex, typst, ExportFormat};
+use vultan::state::audit::{AuditEntry, Mutation};
+use vultan::state::card::{Card, LoadFailure, RevisionSettings};
+use vultan::state::deck::{
+    self, DailyGoal, DeckStats, IntervalCoefficients, Tuning, HIERARCHY_SEPARATOR,
+};
+use vultan::state::diff::{self, ScheduleChange};
+use vultan::state::file::FileHandle;
+use vultan::state::hand::{DealError, DealOptions, Session};
+use vultan::state::id::DeckName;
+use vultan::state::postpone::Postponement;
+use vultan::state::rebalance::RebalanceOptions;
+use vultan::state::review_log::{to_anki_revlog, AnkiRevlogEntry};
+use vultan::state::search::{self, Query, Snippet};
+use vultan::state::simulate::{DailyLoad, SimulationOptions};
+use vultan::state::streak::Streak;
+use vultan::state::State;
+
+// Settings of a study session that don't come from its deck's options.
+#[derive(Clone, Debug, Default)]
+pub struct SessionOptions {
+    // Used when no deck in the studied deck's chain sets a daily_goal.
+    pub daily_goal: Option<usize>,
+    pub focus: Option<Focus>,
+    pub time_limit: Option<Duration>,
+    // Whether an interval may be typed in at the answer in place of a grade.
+    pub manual_intervals: bool,
+    // Stripped from cards' paths to show them relative to the notes directory.
+    pub notes_prefix: String,
+}
+
+impl SessionOptions {
+    pub fn with_daily_goal(self, daily_goal: Option<usize>) -> Self {
+        Self { daily_goal, ..self }
+    }
+
+    pub fn with_focus(self, focus: Option<Focus>) -> Self {
+        Self { focus, ..self }
+    }
+
+    pub fn with_time_limit(self, time_limit: Option<Duration>) -> Self {
+        Self { time_limit, ..self }
+    }
+
+    pub fn with_manual_intervals(self, manual_intervals: bool) -> Self {
+        Self {
+            manual_intervals,
+            ..self
+        }
+    }
+
+    pub fn with_notes_prefix(self, notes_prefix: String) -> Self {
+        Self {
+            notes_prefix,
+            ..self
+        }
+    }
+}
+
+pub fn study(
+    terminal: &mut Terminal<impl BufRead, impl Write>,
+    state: State,
+    deck_name: Option<&str>,
+    deal_options: &DealOptions,
+    storage: &Storage,
+    hooks: &Hooks,
+    session_options: &SessionOptions,
+) -> Result<(), Box<dyn Error>> {
+    let deck_name = match deck_name {
+        Some(name) => name.to_string(),
+        None => match repl::select_deck(terminal, &all_deck_stats(&state)?)? {
+            Some(name) => name,
+            None => return Ok(()),
+        },
+    };
+    revise_deck(
+        terminal,
+        state,
+        &deck_name,
+        deal_options,
+        storage,
+        hooks,
+        session_options,
+    )?;
+    Ok(())
+}
+
+pub fn revise_deck(
+    terminal: &mut Terminal<impl BufRead, impl Write>,
+    state: State,
+    deck_name: &str,
+    deal_options: &DealOptions,
+    storage: &Storage,
+    hooks: &Hooks,
+    session_options: &SessionOptions,
+) -> Result<State, Box<dyn Error>> {
+    let links = storage.link_index();
+    // An interrupted session of the deck can be picked up instead, as long as some of its cards
+    // are left to review.
+    let resumed = match storage.checkpoint()? {
+        Some(checkpoint)
+            if checkpoint.deck_name == deck_name
+                && repl::confirm_resume(terminal, &checkpoint)? =>
+        {
+            state
+                .resume(&checkpoint)
+                .ok()
+                .map(|session| (session, checkpoint.seed))
+        }
+        _ => None,
+    };
+    let (session, seed) = match resumed {
+        Some(resumed) => resumed,
+        None => {
+            // Every session is seeded, so that its order can be replayed from the review log.
+            let seed = deal_options.seed.unwrap_or_else(rand::random);
+            let deal_options = deal_options.clone().with_seed(Some(seed));
+            match state.deal_with_options(deck_name, &deal_options) {
+                Err(DealError::NoDueCards { next_due, .. }) => {
+                    if !repl::confirm_cram(terminal, deck_name, next_due)? {
+                        return Ok(state);
+                    }
+                    return cram_deck(terminal, state, deck_name, storage, session_options);
+                }
+                hand => (Session::from(hand?), Some(seed)),
+            }
+        }
+    };
+    let goal = state.daily_goal(
+        deck_name,
+        session_options.daily_goal,
+        &storage.read_logs_of(&state)?,
+        start_of_today(),
+    )?;
+    storage.start_session(seed);
+    hooks.run(&Event::SessionStart {
+        deck_name,
+        card_count: session.remaining(),
+    });
+    let mut persisted = state.clone();
+    let mut review_count = 0;
+    let revised_cards = repl::run(
+        terminal,
+        session,
+        &state,
+        &links,
+        Some(goal),
+        session_options,
+        |event| match event {
+            SessionEvent::Revised {
+                card,
+                score,
+                answer_time,
+            } => {
+                persisted = storage.record(
+                    std::mem::take(&mut persisted),
+                    card,
+                    score,
+                    Some(answer_time),
+                );
+                review_count += 1;
+                hooks.run(&Event::CardReviewed {
+                    deck_name,
+                    card,
+                    score,
+                });
+            }
+            SessionEvent::Rescheduled(card) => {
+                match storage.reschedule(persisted.clone(), vec![card.clone()]) {
+                    Ok(rescheduled) => persisted = rescheduled,
+                    Err(error) => eprintln!("{}", error),
+                }
+            }
+            SessionEvent::Progressed(session) => {
+                let checkpoint = session.checkpoint(deck_name).with_seed(seed);
+                if let Err(error) = storage.write_checkpoint(Some(&checkpoint)) {
+                    eprintln!("Unable to save the session's progress -> {}", error);
+                }
+            }
+            SessionEvent::Ended => {
+                if let Err(error) = storage.write_checkpoint(None) {
+                    eprintln!("Unable to clear the session's progress -> {}", error);
+                }
+            }
+        },
+    );
+    hooks.run(&Event::SessionEnd {
+        deck_name,
+        review_count,
+    });
+    if review_count > 0 {
+        let summary = format!(
+            "{} review(s) of {}\n{}",
+            review_count,
+            deck_name,
+            format_streak(&storage.streak()?)
+        );
+        terminal.section("Session complete", &summary)?;
+    }
+    let saved = storage.change(state.clone(), Change::Cards(revised_cards))?;
+    let changes = diff::schedule_changes(&state, &saved);
+    if !changes.is_empty() {
+        let lines: Vec<String> = changes.iter().map(format_schedule_change).collect();
+        terminal.section("Schedule changes", &lines.join("\n"))?;
+    }
+    Ok(saved)
+}
+
+// E.g. "bio/krebs.md: due 2026-10-16 -> 2026-10-22 (+6d), interval 2.5d -> 6.3d (+3.8d),
+// memorisation factor 1300 -> 1450 (+150)".
+fn format_schedule_change(change: &ScheduleChange) -> String {
+    let date = |due: DateTime<Utc>| due.with_timezone(&Local).format("%Y-%m-%d").to_string();
+    format!(
+        "{}: due {} -> {} ({:+}d), interval {:.1}d -> {:.1}d ({:+.1}d), \
+         memorisation factor {:.0} -> {:.0} ({:+.0})",
+        change.path,
+        date(change.before.due),
+        date(change.after.due),
+        change.due_days(),
+        change.before.interval,
+        change.after.interval,
+        change.interval_days(),
+        change.before.memorisation_factor,
+        change.after.memorisation_factor,
+        change.memorisation_factor()
+    )
+}
+
+// Crammed scores are thrown away, but cards suspended, flagged or given a due date while cramming
+// keep those.
+fn cram_deck(
+    terminal: &mut Terminal<impl BufRead, impl Write>,
+    state: State,
+    deck_name: &str,
+    storage: &Storage,
+    session_options: &SessionOptions,
+) -> Result<State, Box<dyn Error>> {
+    let mut rescheduled = vec![];
+    let crammed = repl::run(
+        terminal,
+        Session::from(state.cram(deck_name)?),
+        &state,
+        &storage.link_index(),
+        None,
+        session_options,
+        |event| {
+            if let SessionEvent::Rescheduled(card) = event {
+                rescheduled.push(card.clone());
+            }
+        },
+    );
+    let state = match rescheduled.is_empty() {
+        true => state,
+        false => storage.reschedule(state, rescheduled)?,
+    };
+    let marked: Vec<Card> = crammed
+        .into_iter()
+        .filter_map(|card| {
+            let original = state.cards().get(&card.path)?;
+            let unchanged =
+                (original.suspended, original.flagged) == (card.suspended, card.flagged);
+            (!unchanged).then(|| {
+                original
+                    .clone()
+                    .with_suspended(card.suspended)
+                    .with_flagged(card.flagged)
+            })
+        })
+        .collect();
+    match marked.is_empty() {
+        true => Ok(state),
+        false => storage.change(state, Change::Cards(marked)),
+    }
+}
+
+pub fn sync(state: State, remote_dir: &str, storage: &Storage) -> Result<(), Box<dyn Error>> {
+    let copied = storage.sync(remote_dir)?;
+    storage.save(storage.apply_logs(state)?)?;
+    println!("Exchanged {} review log(s) with {}", copied, remote_dir);
+    Ok(())
+}
+
+pub fn prune(
+    state: State,
+    storage: &Storage,
+    dry_run: bool,
+    archive: bool,
+) -> Result<(), Box<dyn Error>> {
+    let missing = state.missing_card_paths(|path| std::path::Path::new(path).exists());
+    for path in missing.iter() {
+        println!("{}", path);
+    }
+    if dry_run {
+        println!("Would prune {} card(s)", missing.len());
+        return Ok(());
+    }
+    if missing.is_empty() {
+        return Ok(());
+    }
+    if archive {
+        let cards = missing
+            .iter()
+            .filter_map(|path| state.cards().get(path))
+            .cloned()
+            .collect();
+        storage.archive_cards(cards)?;
+    }
+    let count = missing.len();
+    storage.change(state, Change::Pruned(missing))?;
+    println!("Pruned {} card(s)", count);
+    Ok(())
+}
+
+pub fn postpone(
+    state: State,
+    storage: &Storage,
+    deck_name: Option<&str>,
+    postponement: &Postponement,
+    dry_run: bool,
+) -> Result<(), Box<dyn Error>> {
+    let postponed = state.postponed(deck_name, postponement)?;
+    reschedule(
+        state,
+        storage,
+        postponed,
+        dry_run,
+        ("postpone", "Postponed"),
+    )
+}
+
+pub fn rebalance(
+    state: State,
+    storage: &Storage,
+    deck_name: Option<&str>,
+    options: &RebalanceOptions,
+    dry_run: bool,
+) -> Result<(), Box<dyn Error>> {
+    let moved = state.rebalanced(deck_name, options)?;
+    reschedule(state, storage, moved, dry_run, ("move", "Moved"))
+}
+
+// Lists the new due dates, and saves them unless it's a dry run.
+fn reschedule(
+    state: State,
+    storage: &Storage,
+    cards: Vec<Card>,
+    dry_run: bool,
+    (verb, past_tense): (&str, &str),
+) -> Result<(), Box<dyn Error>> {
+    for card in cards.iter() {
+        if let Some(before) = state.cards().get(&card.path) {
+            println!("{}", format_rescheduled(before, card));
+        }
+    }
+    if dry_run {
+        println!("Would {} {} card(s)", verb, cards.len());
+        return Ok(());
+    }
+    let count = cards.len();
+    storage.reschedule(state, cards)?;
+    println!("{} {} card(s)", past_tense, count);
+    Ok(())
+}
+
+fn format_rescheduled(before: &Card, after: &Card) -> String {
+    let date = |due: DateTime<Utc>| due.with_timezone(&Local).format("%Y-%m-%d").to_string();
+    format!(
+        "{}: {} -> {}",
+        after.path,
+        date(before.revision_settings.due),
+        date(after.revision_settings.due)
+    )
+}
+
+pub fn set_due(
+    state: State,
+    storage: &Storage,
+    card_path: &str,
+    due: DateTime<Utc>,
+) -> Result<(), Box<dyn Error>> {
+    let card = find_card(&state, storage, card_path)?;
+    let mut rescheduled = card.clone();
+    rescheduled.revision_settings.due = due;
+    println!("{}", format_rescheduled(card, &rescheduled));
+    storage.reschedule(state, vec![rescheduled])?;
+    Ok(())
+}
+
+// Back to the schedule of a card never studied, e.g. after rewriting it substantially. Flags and
+// suspensions are kept.
+pub fn reset(
+    state: State,
+    storage: &Storage,
+    card_path: Option<&str>,
+    deck_name: Option<&str>,
+    yes: bool,
+) -> Result<(), Box<dyn Error>> {
+    let mut cards: Vec<Card> = match (card_path, deck_name) {
+        (Some(path), _) => vec![find_card(&state, storage, path)?.clone()],
+        (None, Some(name)) if !state.decks().contains_key(name) => {
+            return Err(format!("No deck named '{}' exists.", name).into())
+        }
+        (None, Some(name)) => state
+            .cards()
+            .values()
+            .filter(|card| card.in_deck(name))
+            .cloned()
+            .collect(),
+        (None, None) => return Err("Give the path of a card to reset, or a --deck".into()),
+    };
+    cards.sort_by(|a, b| a.path.cmp(&b.path));
+    for card in cards.iter() {
+        println!("{}", card.path);
+    }
+    let question = format!(
+        "Reset the schedules of {} card(s), to be learnt again as new?",
+        cards.len()
+    );
+    if !yes
+        && !confirm(
+            &mut std::io::stdin().lock(),
+            &mut std::io::stdout(),
+            &question,
+        )?
+    {
+        println!("Nothing was reset");
+        return Ok(());
+    }
+    let count = cards.len();
+    let cards = cards
+        .into_iter()
+        .map(|card| card.with_revision_settings(RevisionSettings::default()))
+        .collect();
+    storage.reschedule(state, cards)?;
+    println!("Reset {} card(s)", count);
+    Ok(())
+}
+
+// Anything but yes is a no, including the end of the input.
+fn confirm(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    question: &str,
+) -> std::io::Result<bool> {
+    write!(output, "{} [y/N] ", question)?;
+    output.flush()?;
+    let mut line = String::new();
+    input.read_line(&mut line)?;
+    Ok(matches!(line.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+// As in the state file, or relative to the notes directory.
+fn find_card<'s>(
+    state: &'s State,
+    storage: &Storage,
+    card_path: &str,
+) -> Result<&'s Card, Box<dyn Error>> {
+    state
+        .cards()
+        .get(card_path)
+        .or_else(|| state.cards().get(&storage.note_path(card_path)))
+        .ok_or_else(|| format!("No card at '{}'", card_path).into())
+}
+
+// A date alone is the start of that day, so that e.g. a card given it is due all of it.
+pub fn parse_local_date_time(input: &str) -> Result<DateTime<Utc>, String> {
+    let at = NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M")
+        .or_else(|_| {
+            NaiveDate::parse_from_str(input, "%Y-%m-%d").map(|date| date.and_time(NaiveTime::MIN))
+        })
+        .map_err(|_| "Expected a date such as 2026-06-01, or 2026-06-01 08:00".to_string())?;
+    Local
+        .from_local_datetime(&at)
+        .earliest()
+        .map(|at| at.with_timezone(&Utc))
+        .ok_or_else(|| format!("{} doesn't exist in the local time zone", input))
+}
+
+pub fn audit(
+    storage: &Storage,
+    since: Option<DateTime<Utc>>,
+    json: bool,
+) -> Result<(), Box<dyn Error>> {
+    let entries: Vec<AuditEntry> = storage
+        .read_audit_log()?
+        .into_iter()
+        .filter(|entry| since.is_none_or(|since| entry.at >= since))
+        .collect();
+    if json {
+        return print_json(&entries);
+    }
+    for entry in entries.iter() {
+        println!("{}", format_audit_entry(entry));
+    }
+    Ok(())
+}
+
+// E.g. "2026-10-16 09:30:12 [study --deck-name bio] added card bio/krebs.md".
+fn format_audit_entry(entry: &AuditEntry) -> String {
+    let mutation = match &entry.mutation {
+        Mutation::CardAdded { path } => format!("added card {}", path),
+        Mutation::CardRemoved { path } => format!("removed card {}", path),
+        Mutation::CardRescheduled(change) => {
+            format!("rescheduled {}", format_schedule_change(change))
+        }
+        Mutation::CardChanged { path } => format!("changed card {}", path),
+        Mutation::DeckAdded { name } => format!("added deck {}", name),
+        Mutation::DeckRemoved { name } => format!("removed deck {}", name),
+        Mutation::DeckChanged { name } => format!("changed deck {}", name),
+        Mutation::ConfigChanged => "changed the card parsing config".to_string(),
+    };
+    format!(
+        "{} [{}] {}",
+        entry.at.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S"),
+        entry.command,
+        mutation
+    )
+}
+
+pub fn export_revlog(
+    storage: &Storage,
+    output: Option<&str>,
+    json: bool,
+) -> Result<(), Box<dyn Error>> {
+    let entries = to_anki_revlog(&storage.read_logs()?);
+    let content = match json {
+        true => serde_json::to_string_pretty(&entries)?,
+        false => std::iter::once(AnkiRevlogEntry::CSV_HEADER.to_string())
+            .chain(entries.iter().map(AnkiRevlogEntry::to_csv_row))
+            .collect::<Vec<String>>()
+            .join("\n"),
+    };
+    match output {
+        Some(path) => std::fs::write(path, format!("{}\n", content))?,
+        None => writeln!(std::io::stdout().lock(), "{}", content)?,
+    }
+    Ok(())
+}
+
+pub fn export(
+    state: &State,
+    format: ExportFormat,
+    deck_name: &str,
+    output: &str,
+    reveal: bool,
+) -> Result<(), Box<dyn Error>> {
+    let cards = exported_cards(state, deck_name)?;
+    let cards: Vec<&Card> = cards.iter().collect();
+    let (file_name, content) = match format {
+        ExportFormat::Html => (
+            html::INDEX_FILE_NAME,
+            html::render(deck_name, &cards, reveal),
+        ),
+        ExportFormat::Latex => (latex::FILE_NAME, latex::render(deck_name, &cards)),
+        ExportFormat::Typst => (typst::FILE_NAME, typst::render(deck_name, &cards)),
+    };
+    std::fs::create_dir_all(output)?;
+    let path = std::path::Path::new(output).join(file_name);
+    std::fs::write(&path, content)?;
+    println!("Exported {} card(s) to {}", cards.len(), path.display());
+    Ok(())
+}
+
+// Ordered by path, with their content read from their notes if the state only keeps metadata.
+fn exported_cards(state: &State, deck_name: &str) -> Result<Vec<Card>, Box<dyn Error>> {
+    let mut cards: Vec<&Card> = state.cards_in_deck(deck_name);
+    if cards.is_empty() {
+        return Err(format!("Deck \"{}\" has no cards to export", deck_name).into());
+    }
+    cards.sort_by(|a, b| a.path.cmp(&b.path));
+    cards
+        .into_iter()
+        .map(|card| match state.metadata_only() {
+            true => Ok(state.reload_card(FileHandle::from(card.path.to_string()))?),
+            false => Ok(card.clone()),
+        })
+        .collect()
+}
+
+pub fn list(state: &State, json: bool) -> Result<(), Box<dyn Error>> {
+    let all_stats = all_deck_stats(state)?;
+    if json {
+        return print_json(&all_stats);
+    }
+    println!(
+        "{:<32} {:>8} {:>8} {:>8}",
+        "DECK", "CARDS", "DUE", "MASTERY"
+    );
+    for stats in all_stats.iter() {
+        let label = tree_label(&stats.name);
+        let mastery = format_mastery(stats.mastery);
+        println!(
+            "{:<32} {:>8} {:>8} {:>8}",
+            label, stats.cards, stats.due, mastery
+        );
+    }
+    Ok(())
+}
+
+pub fn stats(
+    state: &State,
+    deck_name: Option<&str>,
+    streak: &Streak,
+    json: bool,
+) -> Result<(), Box<dyn Error>> {
+    let all_stats = match deck_name {
+        Some(name) => vec![state.deck_stats(name)?],
+        None => all_deck_stats(state)?,
+    };
+    if json {
+        return print_json(&all_stats);
+    }
+    for stats in all_stats.iter() {
+        println!("{}", stats.name);
+        println!("{}", format_deck_stats(stats));
+    }
+    println!("{}", format_streak(streak));
+    Ok(())
+}
+
+pub fn optimize(
+    state: State,
+    deck_name: &str,
+    target_retention: f64,
+    apply: bool,
+    storage: &Storage,
+    json: bool,
+) -> Result<(), Box<dyn Error>> {
+    let events = storage.read_logs_of(&state)?;
+    let tuning = state.tune_coefficients(deck_name, &events, target_retention)?;
+    if json {
+        print_json(&tuning)?;
+    } else {
+        println!("{}", format_tuning(deck_name, &tuning));
+    }
+    if apply {
+        let deck = state.decks()[deck_name]
+            .clone()
+            .with_interval_coefficients(tuning.proposed);
+        storage.edit_deck(state, deck)?;
+    } else if !json {
+        println!("Run again with --apply to save the proposed coefficients.");
+    }
+    Ok(())
+}
+
+fn format_tuning(deck_name: &str, tuning: &Tuning) -> String {
+    let coefficients = |c: &IntervalCoefficients| {
+        format!(
+            "pass {:.2}  easy {:.2}  fail {:.2}",
+            c.pass_coef, c.easy_coef, c.fail_coef
+        )
+    };
+    [
+        format!(
+            "{}: {} review(s), {:.1}% recalled",
+            deck_name,
+            tuning.reviews,
+            tuning.observed_retention * 100.0
+        ),
+        format!("  current:  {}", coefficients(&tuning.current)),
+        format!(
+            "  proposed: {}  ({:.1}% predicted)",
+            coefficients(&tuning.proposed),
+            tuning.predicted_retention * 100.0
+        ),
+    ]
+    .join("\n")
+}
+
+// Coefficients given on the command line, completed from the deck's or the defaults.
+pub fn coefficients_to_try(
+    state: &State,
+    deck_name: Option<&str>,
+    (pass_coef, easy_coef, fail_coef): (Option<f64>, Option<f64>, Option<f64>),
+) -> Result<Option<IntervalCoefficients>, Box<dyn Error>> {
+    if pass_coef.is_none() && easy_coef.is_none() && fail_coef.is_none() {
+        return Ok(None);
+    }
+    let base = match deck_name {
+        Some(name) => state.deck_options(name)?.interval_coefficients,
+        None => None,
+    }
+    .unwrap_or_default();
+    Ok(Some(IntervalCoefficients::new(
+        pass_coef.unwrap_or(base.pass_coef),
+        easy_coef.unwrap_or(base.easy_coef),
+        fail_coef.unwrap_or(base.fail_coef),
+    )))
+}
+
+pub fn simulate(
+    state: &State,
+    deck_name: Option<&str>,
+    options: &SimulationOptions,
+    json: bool,
+) -> Result<(), Box<dyn Error>> {
+    let loads = state.simulate(deck_name, options)?;
+    match json {
+        true => print_json(&loads),
+        false => {
+            println!("{}", format_simulation(&loads));
+            Ok(())
+        }
+    }
+}
+
+const SIMULATION_BAR_WIDTH: usize = 40;
+
+fn format_simulation(loads: &[DailyLoad]) -> String {
+    let busiest = loads
+        .iter()
+        .map(|l| l.new + l.reviews)
+        .max()
+        .unwrap_or_default();
+    let mut lines = vec![format!("{:<12} {:>5} {:>8}", "DATE", "NEW", "REVIEWS")];
+    for load in loads.iter() {
+        let bar = match busiest {
+            0 => 0,
+            _ => ((load.new + load.reviews) * SIMULATION_BAR_WIDTH).div_ceil(busiest),
+        };
+        let line = format!(
+            "{:<12} {:>5} {:>8}  {}",
+            load.date.to_string(),
+            load.new,
+            load.reviews,
+            "█".repeat(bar)
+        );
+        lines.push(line.trim_end().to_string());
+    }
+    if let Some(peak) = loads
+        .iter()
+        .max_by_key(|l| (l.reviews, std::cmp::Reverse(l.date)))
+    {
+        let total: usize = loads.iter().map(|l| l.reviews).sum();
+        lines.push(format!(
+            "Mean {:.1} reviews a day, peaking at {} on {}",
+            total as f64 / loads.len() as f64,
+            peak.reviews,
+            peak.date
+        ));
+    }
+    lines.join("\n")
+}
+
+pub fn search(
+    state: &State,
+    query: &Query,
+    theme: &Theme,
+    json: bool,
+) -> Result<(), Box<dyn Error>> {
+    let hits = search::search(state.cards().values(), query);
+    if json {
+        return print_json(&hits);
+    }
+    for hit in hits.iter() {
+        println!("{}", theme.heading.paint(hit.path));
+        println!("  {}", highlight(&hit.snippet, theme));
+    }
+    Ok(())
+}
+
+fn highlight(snippet: &Snippet, theme: &Theme) -> String {
+    let mut highlighted = String::new();
+    let mut end = 0;
+    for range in snippet.highlights.iter() {
+        highlighted.push_str(&snippet.text[end..range.start]);
+        highlighted.push_str(&theme.highlight.paint(&snippet.text[range.clone()]));
+        end = range.end;
+    }
+    highlighted.push_str(&snippet.text[end..]);
+    highlighted
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct FlaggedCard<'c> {
+    pub path: &'c str,
+    pub decks: &'c [String],
+    pub question: &'c str,
+}
+
+pub fn flagged(state: &State, deck_name: Option<&str>, json: bool) -> Result<(), Box<dyn Error>> {
+    let cards = flagged_cards(state, deck_name);
+    if json {
+        return print_json(&cards);
+    }
+    for card in cards.iter() {
+        println!("{}", card.path);
+        println!(
+            "  {}",
+            card.question.lines().next().unwrap_or_default().trim()
+        );
+    }
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct DueCard<'c> {
+    pub path: &'c str,
+    pub due: DateTime<Utc>,
+}
+
+// Prints the due cards, or just their number, and returns the number for an exit code to be made
+// of.
+pub fn due(
+    state: &State,
+    deck_name: Option<&str>,
+    count: bool,
+    json: bool,
+) -> Result<usize, Box<dyn Error>> {
+    let cards = due_cards(state, deck_name)?;
+    match (count, json) {
+        (true, true) => print_json(&cards.len())?,
+        (true, false) => println!("{}", cards.len()),
+        (false, true) => print_json(&cards)?,
+        (false, false) => cards.iter().for_each(|card| println!("{}", card.path)),
+    }
+    Ok(cards.len())
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ReportedFailure<'f> {
+    #[serde(flatten)]
+    pub failure: &'f LoadFailure,
+    pub suggestion: String,
+}
+
+pub fn errors(failures: &[LoadFailure], json: bool) -> Result<(), Box<dyn Error>> {
+    if json {
+        let reported: Vec<ReportedFailure> = failures
+            .iter()
+            .map(|failure| ReportedFailure {
+                failure,
+                suggestion: failure.suggestion(),
+            })
+            .collect();
+        return print_json(&reported);
+    }
+    if failures.is_empty() {
+        println!("Every note was read as a card");
+    }
+    for failure in failures.iter() {
+        println!("{}", failure.path);
+        println!("  {}", failure.message);
+        println!("  {}", failure.suggestion());
+    }
+    Ok(())
+}
+
+// Fails when problems are left, so that scripts can tell a healthy state from one that isn't.
+pub fn doctor(
+    state: State,
+    storage: &Storage,
+    fix: bool,
+    json: bool,
+) -> Result<(), Box<dyn Error>> {
+    let problems = state.diagnose();
+    if json {
+        print_json(&problems)?;
+    } else {
+        for problem in problems.iter() {
+            println!("{}", problem);
+        }
+    }
+    if problems.is_empty() {
+        if !json {
+            println!("No problems found");
+        }
+        return Ok(());
+    }
+    if !fix {
+        return Err(format!(
+            "Found {} problem(s); run doctor with --fix to repair them",
+            problems.len()
+        )
+        .into());
+    }
+    let remaining = storage.change(state, Change::Repaired)?.diagnose();
+    if !remaining.is_empty() {
+        return Err(format!("{} problem(s) couldn't be repaired", remaining.len()).into());
+    }
+    if !json {
+        println!("Repaired {} problem(s)", problems.len());
+    }
+    Ok(())
+}
+
+fn flagged_cards<'s>(state: &'s State, deck_name: Option<&str>) -> Vec<FlaggedCard<'s>> {
+    let mut cards: Vec<FlaggedCard> = state
+        .cards()
+        .values()
+        .filter(|card| card.flagged && deck_name.map_or(true, |name| card.in_deck(name)))
+        .map(|card| FlaggedCard {
+            path: &card.path,
+            decks: &card.decks,
+            question: &card.question,
+        })
+        .collect();
+    cards.sort_by(|a, b| a.path.cmp(b.path));
+    cards
+}
+
+// Of the deck, or of every top-level deck, as sub-decks' reviews are counted in their parents'.
+pub fn daily_goals(
+    state: &State,
+    storage: &Storage,
+    deck_name: Option<&str>,
+    default_goal: Option<usize>,
+) -> Result<Vec<DailyGoal>, Box<dyn Error>> {
+    let events = storage.read_logs_of(state)?;
+    let since = start_of_today();
+    let deck_names: Vec<String> = match deck_name {
+        Some(name) => vec![name.to_string()],
+        None => all_deck_stats(state)?
+            .into_iter()
+            .map(|stats| stats.name)
+            .filter(|name| !name.contains(HIERARCHY_SEPARATOR))
+            .collect(),
+    };
+    Ok(deck_names
+        .iter()
+        .map(|name| state.daily_goal(name, default_goal, &events, since))
+        .collect::<Result<_, _>>()?)
+}
+
+pub fn today(
+    state: &State,
+    storage: &Storage,
+    deck_name: Option<&str>,
+    default_goal: Option<usize>,
+    json: bool,
+) -> Result<(), Box<dyn Error>> {
+    let goals = daily_goals(state, storage, deck_name, default_goal)?;
+    if json {
+        return print_json(&goals);
+    }
+    for goal in goals.iter() {
+        println!("{}: {}", goal.name, format_daily_goal(goal));
+    }
+    Ok(())
+}
+
+// Local midnight, from which the day's reviews are counted.
+fn start_of_today() -> DateTime<Utc> {
+    let midnight = Local::now().date_naive().and_time(NaiveTime::MIN);
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map_or_else(Utc::now, |midnight| midnight.with_timezone(&Utc))
+}
+
+// Soonest due first.
+fn due_cards<'s>(
+    state: &'s State,
+    deck_name: Option<&str>,
+) -> Result<Vec<DueCard<'s>>, Box<dyn Error>> {
+    if let Some(name) = deck_name.filter(|name| !state.decks().contains_key(*name)) {
+        return Err(format!("No deck named '{}' exists.", name).into());
+    }
+    let mut cards: Vec<DueCard> = state
+        .cards()
+        .values()
+        .filter(|card| card.is_due() && deck_name.is_none_or(|name| card.in_deck(name)))
+        .map(|card| DueCard {
+            path: &card.path,
+            due: card.revision_settings.due,
+        })
+        .collect();
+    cards.sort_by(|a, b| a.due.cmp(&b.due).then(a.path.cmp(b.path)));
+    Ok(cards)
+}
+
+pub fn format_deck_stats(stats: &DeckStats) -> String {
+    [
+        format!("  cards:                    {}", stats.cards),
+        format!("  due:                      {}", stats.due),
+        format!("  new:                      {}", stats.new),
+        format!("  mean interval (days):     {:.1}", stats.mean_interval),
+        format!(
+            "  mean memorisation factor: {:.0}",
+            stats.mean_memorisation_factor
+        ),
+        format!(
+            "  mastery:                  {}",
+            format_mastery(stats.mastery)
+        ),
+    ]
+    .join("\n")
+}
+
+pub fn format_daily_goal(goal: &DailyGoal) -> String {
+    let reviewed = match goal.goal {
+        Some(target) => format!("{}/{} reviews today", goal.reviewed, target),
+        None => format!("{} review(s) today", goal.reviewed),
+    };
+    let outlook = match goal.goal {
+        Some(_) if goal.is_met() => ", goal met".to_string(),
+        Some(_) if !goal.is_reachable() => format!(
+            ", {} short of the goal once all are reviewed",
+            goal.remaining() - goal.due
+        ),
+        _ => String::new(),
+    };
+    format!("{}, {} due{}", reviewed, goal.due, outlook)
+}
+
+pub fn format_streak(streak: &Streak) -> String {
+    format!("Streak: {} day(s) (best {})", streak.current, streak.best)
+}
+
+pub fn format_mastery(mastery: f64) -> String {
+    format!("{:.0}%", mastery * 100.0)
+}
+
+fn print_json(value: &impl Serialize) -> Result<(), Box<dyn Error>> {
+    let content = serde_json::to_string_pretty(value)?;
+    writeln!(std::io::stdout().lock(), "{}", content)?;
+    Ok(())
+}
+
+// Indents nested decks under their parents, which `all_deck_stats` lists first.
+pub fn tree_label(deck_name: &str) -> String {
+    format!(
+        "{}{}",
+        "  ".repeat(deck::depth(deck_name)),
+        deck::leaf_name(deck_name)
+    )
+}
+
+pub fn all_deck_stats(state: &State) -> Result<Vec<DeckStats>, Box<dyn Error>> {
+    let mut deck_names: Vec<&DeckName> = state.decks().keys().collect();
+    deck_names.sort_by(|a, b| {
+        a.split(HIERARCHY_SEPARATOR)
+            .cmp(b.split(HIERARCHY_SEPARATOR))
+    });
+    Ok(deck_names
+        .into_iter()
+        .map(|name| state.deck_stats(name))
+        .collect::<Result<_, _>>()?)
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use rstest::*;
+
+    #[test]
+    fn all_deck_stats_lists_nested_decks_under_their_parents() {
+        let make_card = |path: &str, deck: &str| {
+            let mut card = Card::default();
+            card.path = path.into();
+            card.decks = vec![deck.to_string()];
+            card
+        };
+        let state = State::default().with_loaded_cards(vec![
+            make_card("a", "lang::spanish"),
+            make_card("b", "lang-notes"),
+            make_card("c", "lang::french"),
+        ]);
+        let labels: Vec<String> = all_deck_stats(&state)
+            .unwrap()
+            .iter()
+            .map(|stats| tree_label(&stats.name))
+            .collect();
+        assert_eq!(vec!["lang", "  french", "  spanish", "lang-notes"], labels);
+    }
+
+    #[test]
+    fn format_tuning() {
+        let tuning = Tuning {
+            reviews: 40,
+            observed_retention: 0.75,
+            predicted_retention: 0.8,
+            current: IntervalCoefficients::default(),
+            proposed: IntervalCoefficients::new(0.8, 1.3, 0.0),
+        };
+        let expected = [
+            "biology: 40 review(s), 75.0% recalled",
+            "  current:  pass 1.00  easy 1.30  fail 0.00",
+            "  proposed: pass 0.80  easy 1.30  fail 0.00  (80.0% predicted)",
+        ]
+        .join("\n");
+        assert_eq!(expected, super::format_tuning("biology", &tuning));
+    }
+
+    #[test]
+    fn format_simulation() {
+        let day = |d| chrono::NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        let loads = vec![
+            DailyLoad {
+                date: day(1),
+                new: 10,
+                reviews: 0,
+            },
+            DailyLoad {
+                date: day(2),
+                new: 10,
+                reviews: 30,
+            },
+        ];
+        let expected = [
+            "DATE           NEW  REVIEWS",
+            &format!("2024-03-01      10        0  {}", "█".repeat(10)),
+            &format!("2024-03-02      10       30  {}", "█".repeat(40)),
+            "Mean 15.0 reviews a day, peaking at 30 on 2024-03-02",
+        ]
+        .join("\n");
+        assert_eq!(expected, super::format_simulation(&loads));
+    }
+
+    #[test]
+    fn coefficients_to_try_completes_the_given_ones() {
+        let state = State::default();
+        assert_eq!(
+            None,
+            coefficients_to_try(&state, None, (None, None, None)).unwrap()
+        );
+        let expected = IntervalCoefficients::new(2.0, 1.3, 0.5);
+        let actual = coefficients_to_try(&state, None, (Some(2.0), None, Some(0.5))).unwrap();
+        assert_eq!(Some(expected), actual);
+    }
+
+    #[test]
+    fn highlight_paints_each_match() {
+        let snippet = Snippet {
+            text: "the Krebs cycle, or krebs".to_string(),
+            highlights: vec![4..9, 20..25],
+        };
+        let mut theme = Theme::plain();
+        theme.highlight.bold = true;
+        assert_eq!(
+            "the \x1B[1mKrebs\x1B[0m cycle, or \x1B[1mkrebs\x1B[0m",
+            highlight(&snippet, &theme)
+        );
+        assert_eq!(snippet.text, highlight(&snippet, &Theme::plain()));
+    }
+
+    #[test]
+    fn flagged_cards_are_listed_by_path() {
+        let make_card = |path: &str, deck: &str, flagged: bool| {
+            let mut card = Card::default().with_flagged(flagged);
+            card.path = path.into();
+            card.decks = vec![deck.to_string()];
+            card
+        };
+        let state = State::default().with_loaded_cards(vec![
+            make_card("c", "lang::spanish", true),
+            make_card("b", "lang", false),
+            make_card("a", "maths", true),
+        ]);
+        let paths = |deck_name| -> Vec<&str> {
+            flagged_cards(&state, deck_name)
+                .iter()
+                .map(|card| card.path)
+                .collect()
+        };
+        assert_eq!(vec!["a", "c"], paths(None));
+        assert_eq!(vec!["c"], paths(Some("lang")));
+    }
+
+    #[rstest]
+    #[case::under_way(Some(50), 12, 40, "12/50 reviews today, 40 due")]
+    #[case::out_of_reach(
+        Some(50),
+        12,
+        20,
+        "12/50 reviews today, 20 due, 18 short of the goal once all are reviewed"
+    )]
+    #[case::met(Some(50), 50, 3, "50/50 reviews today, 3 due, goal met")]
+    #[case::without_a_goal(None, 1, 3, "1 review(s) today, 3 due")]
+    fn format_daily_goal(
+        #[case] target: Option<usize>,
+        #[case] reviewed: usize,
+        #[case] due: usize,
+        #[case] expected: &str,
+    ) {
+        let goal = DailyGoal {
+            name: "deck".to_string(),
+            goal: target,
+            reviewed,
+            due,
+        };
+        assert_eq!(expected, super::format_daily_goal(&goal));
+    }
+
+    #[test]
+    fn due_cards_are_listed_soonest_first() {
+        let make_card = |path: &str, deck: &str, days_ago: i64| {
+            let mut card = Card {
+                path: path.into(),
+                decks: vec![deck.to_string()],
+                ..Card::default()
+            };
+            card.revision_settings.due = Utc::now() - chrono::Duration::days(days_ago);
+            card
+        };
+        let state = State::default().with_loaded_cards(vec![
+            make_card("c", "lang::spanish", 1),
+            make_card("b", "lang", -1),
+            make_card("a", "maths", 2),
+        ]);
+        let paths = |deck_name| -> Vec<&str> {
+            due_cards(&state, deck_name)
+                .unwrap()
+                .iter()
+                .map(|card| card.path)
+                .collect()
+        };
+        assert_eq!(vec!["a", "c"], paths(None));
+        assert_eq!(vec!["c"], paths(Some("lang")));
+        assert!(due_cards(&state, Some("history")).is_err());
+    }
+
+    #[rstest]
+    #[case::yes("y\n", true)]
+    #[case::capitalised("Yes\n", true)]
+    #[case::no("n\n", false)]
+    #[case::by_default("\n", false)]
+    #[case::at_the_end_of_the_input("", false)]
+    fn confirm(#[case] input: &str, #[case] expected: bool) {
+        let mut output = Vec::new();
+        let actual = super::confirm(&mut input.as_bytes(), &mut output, "Reset 2 card(s)?");
+        assert_eq!(expected, actual.unwrap());
+        assert_eq!(
+            "Reset 2 card(s)? [y/N] ",
+            String::from_utf8(output).unwrap()
+        );
+    }
+
+    #[test]
+    fn format_audit_entry() {
+        let entry = |mutation| AuditEntry {
+            at: Local
+                .with_ymd_and_hms(2026, 10, 16, 9, 30, 12)
+                .unwrap()
+                .with_timezone(&Utc),
+            command: "prune".to_string(),
+            mutation,
+        };
+        let removed = Mutation::CardRemoved {
+            path: "bio/krebs.md".to_string(),
+        };
+        assert_eq!(
+            "2026-10-16 09:30:12 [prune] removed card bio/krebs.md",
+            super::format_audit_entry(&entry(removed))
+        );
+        assert!(super::format_audit_entry(&entry(Mutation::ConfigChanged))
+            .ends_with("[prune] changed the card parsing config"));
+    }
+
+    #[test]
+    fn format_schedule_change() {
+        let due = |day| {
+            Local
+                .with_ymd_and_hms(2026, 10, day, 9, 0, 0)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        let change = ScheduleChange {
+            path: "bio/krebs.md".to_string(),
+            before: RevisionSettings::new(due(16), 2.5, 1300.0),
+            after: RevisionSettings::new(due(22), 6.25, 1450.0),
+        };
+        assert_eq!(
+            "bio/krebs.md: due 2026-10-16 -> 2026-10-22 (+6d), interval 2.5d -> 6.2d (+3.8d), \
+             memorisation factor 1300 -> 1450 (+150)",
+            super::format_schedule_change(&change)
+        );
+    }
+
+    #[test]
+    fn format_rescheduled() {
+        let make_card = |due: DateTime<Local>| {
+            let mut card = Card {
+                path: "notes/krebs.md".into(),
+                ..Card::default()
+            };
+            card.revision_settings.due = due.with_timezone(&Utc);
+            card
+        };
+        let before = make_card(Local.with_ymd_and_hms(2026, 10, 16, 9, 0, 0).unwrap());
+        let after = make_card(Local.with_ymd_and_hms(2026, 10, 30, 9, 0, 0).unwrap());
+        assert_eq!(
+            "notes/krebs.md: 2026-10-16 -> 2026-10-30",
+            super::format_rescheduled(&before, &after)
+        );
+    }
+}
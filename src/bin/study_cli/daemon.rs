@@ -0,0 +1,144 @@
+use crate::commands;
+use crate::storage::Storage;
+use chrono::{Duration, Local, NaiveDateTime, NaiveTime};
+use std::error::Error;
+use std::process::{Command, Stdio};
+use vultan::state::deck::{DeckStats, HIERARCHY_SEPARATOR};
+
+pub const DEFAULT_NOTIFY_COMMAND: &str = "notify-send vultan \"$VULTAN_MESSAGE\"";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Schedule {
+    Every(Duration),
+    // Times of day, in local time.
+    At(Vec<NaiveTime>),
+}
+
+impl Schedule {
+    pub fn next_run(&self, now: NaiveDateTime) -> NaiveDateTime {
+        match self {
+            Schedule::Every(period) => now + *period,
+            Schedule::At(times) => {
+                let today = now.date();
+                let tomorrow = today + Duration::days(1);
+                times
+                    .iter()
+                    .map(|time| today.and_time(*time))
+                    .filter(|run| *run > now)
+                    .chain(times.iter().map(|time| tomorrow.and_time(*time)))
+                    .min()
+                    .unwrap_or(now + Duration::days(1))
+            }
+        }
+    }
+}
+
+// One line per top-level deck with cards due, as sub-decks' are counted in their parents'.
+pub fn due_message(stats: &[DeckStats]) -> Option<String> {
+    let lines: Vec<String> = stats
+        .iter()
+        .filter(|stats| !stats.name.contains(HIERARCHY_SEPARATOR) && stats.due > 0)
+        .map(|stats| match stats.due {
+            1 => format!("1 card due in {}", stats.name),
+            due => format!("{} cards due in {}", due, stats.name),
+        })
+        .collect();
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+// Reloads the notes and state at every run, so that reviews made and notes written in the meantime
+// are counted. Runs until interrupted; a check that fails, e.g. on a state file caught mid-sync, is
+// reported and tried again at the next run.
+pub fn run(storage: &Storage, schedule: &Schedule, command: &str) -> Result<(), Box<dyn Error>> {
+    loop {
+        let now = Local::now().naive_local();
+        let next_run = schedule.next_run(now);
+        println!("Next check at {}", next_run.format("%Y-%m-%d %H:%M"));
+        std::thread::sleep((next_run - now).to_std().unwrap_or_default());
+        if let Err(error) = check(storage, command) {
+            eprintln!("Unable to check for due cards -> {}", error);
+        }
+    }
+}
+
+fn check(storage: &Storage, command: &str) -> Result<(), Box<dyn Error>> {
+    let state = storage.load()?;
+    if let Some(message) = due_message(&commands::all_deck_stats(&state)?) {
+        notify(command, &message);
+    }
+    Ok(())
+}
+
+// A failed notification is reported rather than stopping the daemon.
+fn notify(command: &str, message: &str) {
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("VULTAN_MESSAGE", message)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .status();
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(status) => eprintln!("The notify command exited with {}", status),
+        Err(error) => eprintln!("Unable to run the notify command -> {}", error),
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use rstest::*;
+
+    fn at(time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn make_stats(name: &str, due: usize) -> DeckStats {
+        DeckStats {
+            name: name.to_string(),
+            cards: due,
+            due,
+            new: 0,
+            mean_interval: 0.0,
+            mean_memorisation_factor: 0.0,
+            mastery: 0.0,
+        }
+    }
+
+    #[rstest]
+    #[case::later_today("2026-10-16 08:30", "2026-10-16 09:00")]
+    #[case::between_times("2026-10-16 09:00", "2026-10-16 18:00")]
+    #[case::tomorrow("2026-10-16 18:30", "2026-10-17 09:00")]
+    fn next_run_at_times(#[case] now: &str, #[case] expected: &str) {
+        let times = ["18:00", "09:00"]
+            .map(|time| NaiveTime::parse_from_str(time, "%H:%M").unwrap())
+            .to_vec();
+        assert_eq!(at(expected), Schedule::At(times).next_run(at(now)));
+    }
+
+    #[test]
+    fn next_run_every() {
+        let schedule = Schedule::Every(Duration::minutes(90));
+        assert_eq!(
+            at("2026-10-16 10:00"),
+            schedule.next_run(at("2026-10-16 08:30"))
+        );
+    }
+
+    #[test]
+    fn due_message_of_top_level_decks() {
+        let stats = [
+            make_stats("topic-1", 37),
+            make_stats("topic-1::sub", 12),
+            make_stats("topic-2", 0),
+            make_stats("topic-3", 1),
+        ];
+        assert_eq!(
+            Some("37 cards due in topic-1\n1 card due in topic-3".to_string()),
+            due_message(&stats)
+        );
+        assert_eq!(None, due_message(&[make_stats("topic-2", 0)]));
+    }
+}
@@ -0,0 +1,97 @@
+use std::time::{Duration, Instant};
+
+// Lengths of the work periods and of the breaks between them, as in the Pomodoro technique.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Focus {
+    pub work: Duration,
+    pub rest: Duration,
+}
+
+impl Focus {
+    pub fn from_minutes(work: u32, rest: u32) -> Self {
+        Self {
+            work: Duration::from_secs(u64::from(work) * 60),
+            rest: Duration::from_secs(u64::from(rest) * 60),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Phase {
+    Work,
+    Break,
+}
+
+// The terminal reads whole lines, so the timer is checked between cards rather than ticking on
+// screen: a break starts at the first card after the work period ends, and the next work period
+// when the break is over and the reviewer comes back to it.
+#[derive(Clone, Debug)]
+pub struct FocusTimer {
+    focus: Focus,
+    phase: Phase,
+    since: Instant,
+}
+
+impl FocusTimer {
+    pub fn start(focus: Focus, now: Instant) -> Self {
+        Self {
+            focus,
+            phase: Phase::Work,
+            since: now,
+        }
+    }
+
+    pub fn tick(&mut self, now: Instant) -> Phase {
+        if self.left(now).is_zero() {
+            self.phase = match self.phase {
+                Phase::Work => Phase::Break,
+                Phase::Break => Phase::Work,
+            };
+            self.since = now;
+        }
+        self.phase
+    }
+
+    pub fn left(&self, now: Instant) -> Duration {
+        let length = match self.phase {
+            Phase::Work => self.focus.work,
+            Phase::Break => self.focus.rest,
+        };
+        length.saturating_sub(now.saturating_duration_since(self.since))
+    }
+}
+
+// Rounded up, so that a period doesn't show 0 minutes before it's over.
+pub fn format_minutes(left: Duration) -> String {
+    format!("{}m", left.as_secs().div_ceil(60))
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+
+    #[test]
+    fn tick_alternates_work_and_breaks() {
+        let start = Instant::now();
+        let minutes = |n: u64| start + Duration::from_secs(n * 60);
+        let mut timer = FocusTimer::start(Focus::from_minutes(25, 5), start);
+        assert_eq!(Phase::Work, timer.tick(minutes(24)));
+        assert_eq!(Duration::from_secs(60), timer.left(minutes(24)));
+        assert_eq!(Phase::Break, timer.tick(minutes(27)));
+        assert_eq!(Duration::from_secs(300), timer.left(minutes(27)));
+        assert_eq!(Phase::Break, timer.tick(minutes(31)));
+        // The break is timed from when it started, not from when the work period ended.
+        assert_eq!(Phase::Work, timer.tick(minutes(40)));
+        assert_eq!(Duration::from_secs(25 * 60), timer.left(minutes(40)));
+    }
+
+    #[test]
+    fn format_minutes() {
+        assert_eq!(
+            "18m",
+            super::format_minutes(Duration::from_secs(17 * 60 + 1))
+        );
+        assert_eq!("0m", super::format_minutes(Duration::ZERO));
+    }
+}
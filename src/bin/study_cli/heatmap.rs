@@ -0,0 +1,122 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use std::collections::BTreeMap;
+
+pub const WEEKS: usize = 52;
+const EMPTY: char = '·';
+const LEVELS: [char; 4] = ['░', '▒', '▓', '█'];
+const DAY_LABELS: [&str; 7] = ["Mon ", "    ", "Wed ", "    ", "Fri ", "    ", "    "];
+const LABEL_WIDTH: usize = 4;
+
+// A GitHub-style calendar: one column per week, ending with the current one, and one row per
+// weekday. Shades are relative to the busiest day shown.
+pub fn render(counts: &BTreeMap<NaiveDate, usize>, today: NaiveDate, weeks: usize) -> String {
+    let weeks = weeks.max(1);
+    let first_monday = today
+        - Duration::days(i64::from(today.weekday().num_days_from_monday()))
+        - Duration::weeks(weeks as i64 - 1);
+    let shown = |date: &NaiveDate| (first_monday..=today).contains(date);
+    let busiest = counts
+        .iter()
+        .filter(|(date, _)| shown(date))
+        .map(|(_, count)| *count)
+        .max()
+        .unwrap_or_default();
+    let total: usize = counts
+        .iter()
+        .filter(|(date, _)| shown(date))
+        .map(|(_, count)| count)
+        .sum();
+    let mut lines = vec![month_labels(first_monday, weeks)];
+    for (weekday, label) in DAY_LABELS.iter().enumerate() {
+        let cells: String = (0..weeks)
+            .map(|week| first_monday + Duration::days((week * 7 + weekday) as i64))
+            .map(|date| match date > today {
+                true => ' ',
+                false => shade(counts.get(&date).copied().unwrap_or_default(), busiest),
+            })
+            .collect();
+        lines.push(format!("{}{}", label, cells).trim_end().to_string());
+    }
+    lines.push(String::new());
+    lines.push(format!(
+        "{}Less {}{} More    {} review(s) in {} week(s)",
+        " ".repeat(LABEL_WIDTH),
+        EMPTY,
+        LEVELS.iter().collect::<String>(),
+        total,
+        weeks
+    ));
+    lines.join("\n")
+}
+
+fn shade(count: usize, busiest: usize) -> char {
+    match count {
+        0 => EMPTY,
+        _ => LEVELS[((count * LEVELS.len()).div_ceil(busiest) - 1).min(LEVELS.len() - 1)],
+    }
+}
+
+// Months are named above the first week that starts in them, where there's room.
+fn month_labels(first_monday: NaiveDate, weeks: usize) -> String {
+    let mut labels = vec![' '; weeks];
+    let mut free_from = 0;
+    for week in 0..weeks {
+        let monday = first_monday + Duration::weeks(week as i64);
+        let name = monday.format("%b").to_string();
+        if monday.day() <= 7 && week >= free_from && week + name.len() <= weeks {
+            labels.splice(week..week + name.len(), name.chars());
+            free_from = week + name.len() + 1;
+        }
+    }
+    let labels: String = labels.into_iter().collect();
+    format!("{}{}", " ".repeat(LABEL_WIDTH), labels)
+        .trim_end()
+        .to_string()
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use rstest::*;
+
+    fn day(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+
+    #[rstest]
+    #[case::none(0, EMPTY)]
+    #[case::few(1, '░')]
+    #[case::some(3, '▒')]
+    #[case::many(5, '▓')]
+    #[case::busiest(8, '█')]
+    fn shade(#[case] count: usize, #[case] expected: char) {
+        assert_eq!(expected, super::shade(count, 8));
+    }
+
+    #[test]
+    fn render() {
+        // Wednesday 13 March 2024, so the last column stops after Wednesday.
+        let today = day(3, 13);
+        let counts = BTreeMap::from([
+            (day(2, 26), 4),
+            (day(3, 6), 1),
+            (day(3, 13), 2),
+            (day(1, 1), 100),
+        ]);
+        let expected = [
+            "      Feb",
+            "Mon ·····█··",
+            "    ········",
+            "Wed ······░▒",
+            "    ·······",
+            "Fri ·······",
+            "    ·······",
+            "    ·······",
+            "",
+            "    Less ·░▒▓█ More    7 review(s) in 8 week(s)",
+        ]
+        .join("\n");
+        assert_eq!(expected, super::render(&counts, today, 8));
+    }
+}
@@ -0,0 +1,177 @@
+use serde::Deserialize;
+use std::error::Error;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use vultan::state::card::{Card, Score};
+
+// Shell commands run as a session goes, e.g. to tick off a habit tracker. Each is told about its
+// event through VULTAN_* environment variables.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Hooks {
+    pub on_session_start: Option<String>,
+    pub on_session_end: Option<String>,
+    pub on_card_reviewed: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum Event<'e> {
+    SessionStart {
+        deck_name: &'e str,
+        card_count: usize,
+    },
+    SessionEnd {
+        deck_name: &'e str,
+        review_count: usize,
+    },
+    CardReviewed {
+        deck_name: &'e str,
+        card: &'e Card,
+        score: Score,
+    },
+}
+
+impl Event<'_> {
+    fn name(&self) -> &'static str {
+        match self {
+            Event::SessionStart { .. } => "session_start",
+            Event::SessionEnd { .. } => "session_end",
+            Event::CardReviewed { .. } => "card_reviewed",
+        }
+    }
+
+    fn variables(&self) -> Vec<(&'static str, String)> {
+        let mut variables = vec![("VULTAN_EVENT", self.name().to_string())];
+        match self {
+            Event::SessionStart {
+                deck_name,
+                card_count,
+            } => variables.extend([
+                ("VULTAN_DECK", deck_name.to_string()),
+                ("VULTAN_CARD_COUNT", card_count.to_string()),
+            ]),
+            Event::SessionEnd {
+                deck_name,
+                review_count,
+            } => variables.extend([
+                ("VULTAN_DECK", deck_name.to_string()),
+                ("VULTAN_REVIEW_COUNT", review_count.to_string()),
+            ]),
+            Event::CardReviewed {
+                deck_name,
+                card,
+                score,
+            } => variables.extend([
+                ("VULTAN_DECK", deck_name.to_string()),
+                ("VULTAN_CARD_PATH", card.path.to_string()),
+                ("VULTAN_SCORE", format!("{:?}", score).to_lowercase()),
+                (
+                    "VULTAN_INTERVAL",
+                    card.revision_settings.interval.to_string(),
+                ),
+                ("VULTAN_DUE", card.revision_settings.due.to_rfc3339()),
+            ]),
+        }
+        variables
+    }
+}
+
+impl Hooks {
+    pub fn read(path: &str) -> Result<Self, Box<dyn Error>> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read Hooks from {} -> {}", path, e))?;
+        Ok(ron::from_str(&content)
+            .map_err(|e| format!("Unable to parse Hooks from {} -> {}", path, e))?)
+    }
+
+    // The default hooks file is optional, unlike one given explicitly.
+    pub fn read_if_present(path: &str) -> Result<Self, Box<dyn Error>> {
+        match Path::new(path).exists() {
+            true => Self::read(path),
+            false => Ok(Self::default()),
+        }
+    }
+
+    // Hooks run in turn, so a slow one should background itself. A failing hook is reported rather
+    // than interrupting the session.
+    pub fn run(&self, event: &Event) {
+        let hook = match event {
+            Event::SessionStart { .. } => &self.on_session_start,
+            Event::SessionEnd { .. } => &self.on_session_end,
+            Event::CardReviewed { .. } => &self.on_card_reviewed,
+        };
+        let Some(command) = hook else {
+            return;
+        };
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .envs(event.variables())
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .status();
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => eprintln!("The {} hook exited with {}", event.name(), status),
+            Err(error) => eprintln!("Unable to run the {} hook -> {}", event.name(), error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn read_if_present() {
+        let dir = std::env::temp_dir().join(format!("vultan_hooks_read_{}", std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("hooks.ron").to_string_lossy().to_string();
+        assert_eq!(Hooks::default(), Hooks::read_if_present(&path).unwrap());
+        fs::write(&path, "(on_session_end: Some(\"true\"))").unwrap();
+        let expected = Hooks {
+            on_session_end: Some("true".to_string()),
+            ..Hooks::default()
+        };
+        assert_eq!(expected, Hooks::read_if_present(&path).unwrap());
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run_passes_the_event_in_the_environment() {
+        let dir = std::env::temp_dir().join(format!("vultan_hooks_run_{}", std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let output = dir.join("event");
+        let hooks = Hooks {
+            on_card_reviewed: Some(format!(
+                "echo \"$VULTAN_EVENT $VULTAN_DECK $VULTAN_CARD_PATH $VULTAN_SCORE\" > {}",
+                output.display()
+            )),
+            ..Hooks::default()
+        };
+        let card = Card {
+            path: "bio/a.md".into(),
+            ..Card::default()
+        };
+        hooks.run(&Event::SessionStart {
+            deck_name: "bio",
+            card_count: 1,
+        });
+        assert!(!output.exists());
+        hooks.run(&Event::CardReviewed {
+            deck_name: "bio",
+            card: &card,
+            score: Score::Easy,
+        });
+        assert_eq!(
+            "card_reviewed bio bio/a.md easy\n",
+            fs::read_to_string(&output).unwrap()
+        );
+        fs::remove_dir_all(dir).ok();
+    }
+}
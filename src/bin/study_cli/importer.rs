@@ -0,0 +1,254 @@
+use crate::storage::{Change, Storage};
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::Path;
+use vultan::import::template::{Row, Template};
+use vultan::import::ImportedCard;
+use vultan::state::card::parser::registry::ParserRegistry;
+use vultan::state::card::parser::{Parse, Parser, ParsingConfig};
+use vultan::state::card::Card;
+use vultan::state::deck::HIERARCHY_SEPARATOR;
+use vultan::state::file::FileHandle;
+use vultan::state::State;
+
+const MAX_SLUG_LENGTH: usize = 48;
+
+pub fn import(
+    state: State,
+    storage: &Storage,
+    imported_cards: Vec<ImportedCard>,
+    output_dir: &str,
+) -> Result<(), Box<dyn Error>> {
+    let config = state.card_parsing_config().clone();
+    let parser = Parser::from(config.clone())?;
+    let mut used_paths = HashSet::new();
+    let mut cards = Vec::with_capacity(imported_cards.len());
+    for imported in imported_cards.into_iter() {
+        let card = to_card(&config, &parser, imported, |question| {
+            let relative_path = unique_relative_path(output_dir, question, &mut used_paths, |p| {
+                Path::new(&storage.note_path(p)).exists()
+            });
+            storage.note_path(&relative_path)
+        })?;
+        write_note(&config, &card)?;
+        cards.push(card);
+    }
+    let count = cards.len();
+    storage.change(state, Change::Loaded(cards))?;
+    println!(
+        "Imported {} card(s) into {}",
+        count,
+        storage.note_path(output_dir)
+    );
+    Ok(())
+}
+
+// Writes a note per row, named after the row's first field unless a name template is given.
+// Every note is checked to read back as a card before any is written.
+pub fn generate(
+    state: &State,
+    storage: &Storage,
+    rows: Vec<Row>,
+    template: &Template,
+    name_template: Option<&Template>,
+    output_dir: &str,
+) -> Result<(), Box<dyn Error>> {
+    let parser = ParserRegistry::parser_for(state.card_parsing_config().clone())?;
+    let mut notes = Vec::with_capacity(rows.len());
+    for (i, row) in rows.iter().enumerate() {
+        let content = template
+            .render(row)
+            .map_err(|e| format!("Unable to generate a note for row {} -> {}", i + 1, e))?;
+        parser.parse(&content).map_err(|e| {
+            format!(
+                "The note generated for row {} can't be read as a card -> {}",
+                i + 1,
+                e
+            )
+        })?;
+        let name = match name_template {
+            Some(name_template) => name_template.render(row)?,
+            None => row
+                .first()
+                .map(|(_, value)| value.clone())
+                .unwrap_or_default(),
+        };
+        notes.push((name, content));
+    }
+    let mut used_paths = HashSet::new();
+    for (name, content) in notes.iter() {
+        let relative_path = unique_relative_path(output_dir, name, &mut used_paths, |p| {
+            Path::new(&storage.note_path(p)).exists()
+        });
+        let path = storage.note_path(&relative_path);
+        if let Some(parent) = Path::new(&path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        FileHandle::from(path.clone())
+            .write(content.clone())
+            .map_err(|e| format!("Unable to write {} -> {}", path, e))?;
+    }
+    println!(
+        "Generated {} note(s) in {}",
+        notes.len(),
+        storage.note_path(output_dir)
+    );
+    Ok(())
+}
+
+fn to_card(
+    config: &ParsingConfig,
+    parser: &Parser,
+    imported: ImportedCard,
+    make_path: impl FnOnce(&str) -> String,
+) -> Result<Card, Box<dyn Error>> {
+    let decks: Vec<String> = imported
+        .decks
+        .iter()
+        .map(|d| sanitise_deck_name(d, &config.deck_delimiter))
+        .collect();
+    let content = config.render(&decks, &imported.question, &imported.answer);
+    let parsed = parser.parse(&content)?;
+    if parsed.question != imported.question.trim() || parsed.answer != imported.answer.trim() {
+        return Err(format!(
+            "Unable to write \"{}\" in a form the card parsing config can read back",
+            imported.question
+        )
+        .into());
+    }
+    Ok(Card::new(
+        make_path(&imported.question),
+        decks,
+        imported.question,
+        imported.answer,
+        imported.revision_settings,
+    ))
+}
+
+fn write_note(config: &ParsingConfig, card: &Card) -> Result<(), Box<dyn Error>> {
+    if let Some(parent) = Path::new(card.path.as_str()).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let content = config.render(&card.decks, &card.question, &card.answer);
+    FileHandle::from(card.path.to_string())
+        .write(content)
+        .map_err(|e| format!("Unable to write {} -> {}", card.path, e))?;
+    Ok(())
+}
+
+// Anki's "::" nesting is kept, but a lone deck delimiter would split the deck in two.
+fn sanitise_deck_name(deck_name: &str, deck_delimiter: &str) -> String {
+    deck_name
+        .split(HIERARCHY_SEPARATOR)
+        .map(|part| part.replace(deck_delimiter, "-"))
+        .collect::<Vec<String>>()
+        .join(HIERARCHY_SEPARATOR)
+}
+
+fn slugify(text: &str) -> String {
+    let slug = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<String>>()
+        .join("-");
+    match slug.char_indices().nth(MAX_SLUG_LENGTH) {
+        Some((end, _)) => slug[..end].trim_end_matches('-').to_string(),
+        None if slug.is_empty() => "card".to_string(),
+        None => slug,
+    }
+}
+
+fn unique_relative_path(
+    output_dir: &str,
+    question: &str,
+    used_paths: &mut HashSet<String>,
+    exists: impl Fn(&str) -> bool,
+) -> String {
+    let slug = slugify(question);
+    let output_dir = output_dir.trim_end_matches('/');
+    let mut candidate = format!("{}/{}.md", output_dir, slug);
+    let mut suffix = 1;
+    while used_paths.contains(&candidate) || exists(&candidate) {
+        suffix += 1;
+        candidate = format!("{}/{}-{}.md", output_dir, slug, suffix);
+    }
+    used_paths.insert(candidate.clone());
+    candidate
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use rstest::*;
+    use vultan::state::card::RevisionSettings;
+
+    #[rstest]
+    #[case::words(
+        "What is the powerhouse of the cell?",
+        "what-is-the-powerhouse-of-the-cell"
+    )]
+    #[case::symbols_only("???", "card")]
+    #[case::truncated(
+        "one two three four five six seven eight nine ten eleven",
+        "one-two-three-four-five-six-seven-eight-nine-ten"
+    )]
+    fn slugify(#[case] text: &str, #[case] expected: &str) {
+        assert_eq!(expected, super::slugify(text));
+    }
+
+    #[test]
+    fn unique_relative_path() {
+        let mut used_paths = HashSet::new();
+        let exists = |p: &str| p == "anki/hi-2.md";
+        assert_eq!(
+            "anki/hi.md",
+            super::unique_relative_path("anki/", "hi", &mut used_paths, exists)
+        );
+        assert_eq!(
+            "anki/hi-3.md",
+            super::unique_relative_path("anki", "Hi!", &mut used_paths, exists)
+        );
+    }
+
+    #[test]
+    fn sanitise_deck_name() {
+        assert_eq!(
+            "lang::spanish",
+            super::sanitise_deck_name("lang::spanish", ":")
+        );
+        assert_eq!("a-b::c", super::sanitise_deck_name("a:b::c", ":"));
+    }
+
+    #[test]
+    fn to_card_keeps_imported_scheduling() {
+        let config = ParsingConfig::default();
+        let parser = Parser::from(config.clone()).unwrap();
+        let revision_settings = RevisionSettings::new(chrono::Utc::now(), 12.0, 2500.0);
+        let imported = ImportedCard {
+            decks: vec!["lang::spanish".to_string()],
+            question: "hola?".to_string(),
+            answer: "hello".to_string(),
+            revision_settings: revision_settings.clone(),
+        };
+        let actual = to_card(&config, &parser, imported, |_| "anki/hola.md".to_string()).unwrap();
+        assert_eq!("anki/hola.md", actual.path);
+        assert_eq!(vec!["lang::spanish".to_string()], actual.decks);
+        assert_eq!(revision_settings, actual.revision_settings);
+    }
+
+    #[test]
+    fn to_card_fails_when_the_note_cannot_be_read_back() {
+        let config = ParsingConfig::default();
+        let parser = Parser::from(config.clone()).unwrap();
+        let imported = ImportedCard {
+            decks: vec!["deck".to_string()],
+            question: "why\n# Answer\nbecause".to_string(),
+            answer: "hello".to_string(),
+            revision_settings: RevisionSettings::default(),
+        };
+        let actual = to_card(&config, &parser, imported, |_| "unused".to_string());
+        assert!(actual.is_err());
+    }
+}
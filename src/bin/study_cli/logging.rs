@@ -0,0 +1,48 @@
+use std::io::IsTerminal;
+use tracing_subscriber::filter::EnvFilter;
+use tracing_subscriber::fmt::format::FmtSpan;
+
+const VERBOSE_FILTER: &str = "study_cli=debug,vultan=debug";
+
+// Nothing is traced unless RUST_LOG is set or --verbose is given. Traces go to stderr, so that
+// they can be redirected away from the interactive screens, e.g. with `2> trace.log`.
+pub fn init(verbose: bool) {
+    let rust_log = std::env::var(EnvFilter::DEFAULT_ENV).ok();
+    if let Some(filter) = filter(verbose, rust_log.as_deref()) {
+        tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .with_span_events(FmtSpan::CLOSE)
+            .with_writer(std::io::stderr)
+            .with_ansi(std::io::stderr().is_terminal())
+            .init();
+    }
+}
+
+fn filter(verbose: bool, rust_log: Option<&str>) -> Option<EnvFilter> {
+    match (rust_log, verbose) {
+        (Some(directives), _) => Some(EnvFilter::new(directives)),
+        (None, true) => Some(EnvFilter::new(VERBOSE_FILTER)),
+        (None, false) => None,
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case::quiet(false, None, None)]
+    #[case::verbose(true, None, Some(VERBOSE_FILTER))]
+    #[case::rust_log(false, Some("vultan=trace"), Some("vultan=trace"))]
+    #[case::rust_log_over_verbose(true, Some("warn"), Some("warn"))]
+    fn filter_from_flag_and_env(
+        #[case] verbose: bool,
+        #[case] rust_log: Option<&str>,
+        #[case] expected: Option<&str>,
+    ) {
+        let actual = filter(verbose, rust_log).map(|filter| filter.to_string());
+        assert_eq!(expected.map(str::to_string), actual);
+    }
+}
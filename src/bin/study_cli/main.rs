@@ -0,0 +1,340 @@
+mod args;
+mod browser;
+mod commands;
+mod daemon;
+mod focus;
+mod heatmap;
+mod hooks;
+mod importer;
+mod logging;
+mod progress;
+mod repl;
+mod retag;
+mod server;
+mod storage;
+mod terminal;
+mod theme;
+
+use args::{Args, Command};
+use clap::Parser as _;
+use commands::SessionOptions;
+use daemon::Schedule;
+use focus::Focus;
+use hooks::Hooks;
+use std::error::Error;
+use std::io::IsTerminal;
+use std::process::ExitCode;
+use std::time::Duration;
+use storage::Storage;
+use terminal::Terminal;
+use theme::Theme;
+use vultan::import::anki::{self, ColumnMapping};
+use vultan::import::template::{self, DataFormat, Template};
+use vultan::import::{mochi, remnote, supermemo};
+use vultan::state::doctor;
+use vultan::state::hand::DealOptions;
+use vultan::state::postpone::Postponement;
+use vultan::state::rebalance::RebalanceOptions;
+use vultan::state::search::Query;
+use vultan::state::simulate::SimulationOptions;
+
+fn main() -> Result<ExitCode, Box<dyn Error>> {
+    terminal::install_panic_hook();
+    let args = Args::parse();
+    logging::init(args.verbose);
+    let passphrase = args.passphrase()?;
+    if args.shard_state
+        && (passphrase.is_some() || args.compress_state.is_some() || args.state_format.is_some())
+    {
+        return Err(
+            "--shard-state can't be combined with a passphrase, --compress-state or --state-format"
+                .into(),
+        );
+    }
+    let storage = Storage::new(
+        &args.notes_dir,
+        &args.state_file_path(),
+        &args.log_dir_path(),
+        &args.device_name(),
+    )
+    .with_conflict_policy(args.on_conflict)
+    .with_passphrase(passphrase)
+    .with_compression(args.compress_state)
+    .with_format(args.state_format)
+    .with_sharding(args.shard_state)
+    .with_config_path(Some(args.config_file_path()))
+    .with_strict_loading(match args.command {
+        // Listing the failures is how a strict load's are looked into.
+        Command::Errors => Some(false),
+        _ => args.strict.then_some(true),
+    })
+    .with_command_line(std::env::args().skip(1).collect::<Vec<String>>().join(" "))
+    .with_progress(!args.plain && !args.json && std::io::stderr().is_terminal());
+    let state = storage.load()?;
+    let load_failures = storage.load_failures();
+    if !load_failures.is_empty() && !matches!(args.command, Command::Errors) {
+        eprintln!(
+            "{} note(s) couldn't be read as cards; run `study-cli errors` to see why",
+            load_failures.len()
+        );
+    }
+    if state.card_parsing_config().warn_duplicates
+        && !matches!(args.command, Command::Doctor { .. })
+    {
+        for paths in doctor::duplicate_questions(state.cards().values()) {
+            eprintln!("{} have the same question", paths.join(", "));
+        }
+    }
+    let theme = match &args.theme_file {
+        Some(path) => Theme::read(path)?,
+        None => Theme::read_from_config(&args.config_file_path())?.unwrap_or_default(),
+    };
+    let hooks = match &args.hooks_file {
+        Some(path) => Hooks::read(path)?,
+        None => Hooks::read_if_present(&args.default_hooks_file_path())?,
+    };
+    match &args.command {
+        Command::Study {
+            deck_name,
+            include_tags,
+            exclude_tags,
+            order,
+            seed,
+            focus_minutes,
+            break_minutes,
+            review_ahead,
+            max_cards,
+            bury_siblings,
+            max_minutes,
+            manual_intervals,
+        } => {
+            let terminal = &mut Terminal::stdio(&theme).with_plain_output(args.plain);
+            let deal_options = DealOptions::default()
+                .with_include_tags(include_tags.clone())
+                .with_exclude_tags(exclude_tags.clone())
+                .with_order(*order)
+                .with_seed(*seed)
+                .with_max_cards(*max_cards)
+                .with_bury_siblings(*bury_siblings)
+                .with_review_ahead(
+                    review_ahead.map(|days| chrono::Duration::days(i64::from(days))),
+                );
+            let session_options = SessionOptions::default()
+                .with_daily_goal(args.daily_goal)
+                .with_focus(focus_minutes.map(|work| Focus::from_minutes(work, *break_minutes)))
+                .with_time_limit(
+                    max_minutes.map(|minutes| Duration::from_secs(u64::from(minutes) * 60)),
+                )
+                .with_manual_intervals(*manual_intervals)
+                .with_notes_prefix(storage.notes_prefix());
+            commands::study(
+                terminal,
+                state,
+                deck_name.as_deref(),
+                &deal_options,
+                &storage,
+                &hooks,
+                &session_options,
+            )?
+        }
+        Command::Browse => {
+            let terminal = &mut Terminal::stdio(&theme).with_plain_output(args.plain);
+            let session_options = SessionOptions::default()
+                .with_daily_goal(args.daily_goal)
+                .with_notes_prefix(storage.notes_prefix());
+            browser::run(terminal, state, &storage, &hooks, &session_options)?
+        }
+        Command::List => commands::list(&state, args.json)?,
+        Command::Stats { deck_name } => {
+            commands::stats(&state, deck_name.as_deref(), &storage.streak()?, args.json)?
+        }
+        Command::Optimize {
+            deck_name,
+            target_retention,
+            apply,
+        } => commands::optimize(
+            state,
+            deck_name,
+            *target_retention,
+            *apply,
+            &storage,
+            args.json,
+        )?,
+        Command::Simulate {
+            deck_name,
+            months,
+            new_cards_per_day,
+            retention,
+            pass_coef,
+            easy_coef,
+            fail_coef,
+        } => {
+            let coefficients = commands::coefficients_to_try(
+                &state,
+                deck_name.as_deref(),
+                (*pass_coef, *easy_coef, *fail_coef),
+            )?;
+            let today = chrono::Local::now().date_naive();
+            let days = (today + chrono::Months::new(*months) - today).num_days();
+            let options = SimulationOptions::default()
+                .with_days(days as usize)
+                .with_new_cards_per_day(*new_cards_per_day)
+                .with_retention(*retention)
+                .with_coefficients(coefficients);
+            commands::simulate(&state, deck_name.as_deref(), &options, args.json)?
+        }
+        Command::Postpone {
+            days,
+            deck_name,
+            spread,
+            dry_run,
+        } => {
+            let postponement = Postponement::default()
+                .with_days(*days)
+                .with_spread_days(*spread);
+            commands::postpone(
+                state,
+                &storage,
+                deck_name.as_deref(),
+                &postponement,
+                *dry_run,
+            )?
+        }
+        Command::Rebalance {
+            window,
+            max_per_day,
+            deck_name,
+            dry_run,
+        } => {
+            let options = RebalanceOptions::default()
+                .with_window_days(*window)
+                .with_max_per_day(*max_per_day);
+            commands::rebalance(state, &storage, deck_name.as_deref(), &options, *dry_run)?
+        }
+        Command::SetDue { card_path, due } => commands::set_due(state, &storage, card_path, *due)?,
+        Command::Reset {
+            card_path,
+            deck_name,
+            yes,
+        } => commands::reset(
+            state,
+            &storage,
+            card_path.as_deref(),
+            deck_name.as_deref(),
+            *yes,
+        )?,
+        Command::Due {
+            deck_name,
+            count,
+            threshold,
+        } => {
+            let due = commands::due(&state, deck_name.as_deref(), *count, args.json)?;
+            if threshold.is_some_and(|threshold| due > threshold) {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Command::Today { deck_name } => commands::today(
+            &state,
+            &storage,
+            deck_name.as_deref(),
+            args.daily_goal,
+            args.json,
+        )?,
+        Command::Flagged { deck_name } => {
+            commands::flagged(&state, deck_name.as_deref(), args.json)?
+        }
+        Command::Search { query, regex } => {
+            let query = match regex {
+                true => Query::regex(query)?,
+                false => Query::text(query)?,
+            };
+            let theme = match args.plain {
+                true => Theme::plain(),
+                false => theme,
+            };
+            commands::search(&state, &query, &theme, args.json)?
+        }
+        Command::Serve { address } => server::serve(state, address, &storage)?,
+        Command::ExportRevlog { output } => {
+            commands::export_revlog(&storage, output.as_deref(), args.json)?
+        }
+        Command::Export {
+            format,
+            deck_name,
+            output,
+            reveal,
+        } => commands::export(&state, *format, deck_name, output, *reveal)?,
+        Command::Generate {
+            data,
+            template,
+            name,
+            output_dir,
+        } => {
+            let content = std::fs::read_to_string(data)?;
+            let rows = template::read_rows(&content, DataFormat::from_path(data)?)?;
+            let template = Template::new(&std::fs::read_to_string(template)?);
+            let name = name.as_deref().map(Template::new);
+            importer::generate(&state, &storage, rows, &template, name.as_ref(), output_dir)?
+        }
+        Command::ImportAnki {
+            file,
+            columns,
+            deck_name,
+            output_dir,
+        } => {
+            let mapping = ColumnMapping::parse(columns)?;
+            let content = std::fs::read_to_string(file)?;
+            let cards = anki::parse(&content, &mapping, deck_name)?;
+            importer::import(state, &storage, cards, output_dir)?
+        }
+        Command::ImportMochi { file, output_dir } => {
+            let cards = mochi::parse(&std::fs::read_to_string(file)?)?;
+            importer::import(state, &storage, cards, output_dir)?
+        }
+        Command::ImportRemnote {
+            file,
+            deck_name,
+            output_dir,
+        } => {
+            let cards = remnote::parse(&std::fs::read_to_string(file)?, deck_name)?;
+            importer::import(state, &storage, cards, output_dir)?
+        }
+        Command::ImportSupermemo {
+            file,
+            deck_name,
+            output_dir,
+        } => {
+            let cards = supermemo::parse(&std::fs::read_to_string(file)?, deck_name)?;
+            importer::import(state, &storage, cards, output_dir)?
+        }
+        Command::Sync { remote_dir } => commands::sync(state, remote_dir, &storage)?,
+        Command::Bulk {
+            filters,
+            add_decks,
+            remove_decks,
+        } => {
+            retag::bulk(state, &storage, filters, add_decks, remove_decks)?;
+        }
+        Command::RenameDeck { old_name, new_name } => {
+            retag::rename_deck(state, &storage, old_name, new_name)?;
+        }
+        Command::Prune { dry_run, archive } => {
+            commands::prune(state, &storage, *dry_run, *archive)?
+        }
+        Command::Audit { since } => commands::audit(&storage, *since, args.json)?,
+        Command::Errors => commands::errors(&load_failures, args.json)?,
+        Command::Doctor { fix } => commands::doctor(state, &storage, *fix, args.json)?,
+        Command::Daemon {
+            times,
+            every,
+            notify_command,
+        } => {
+            let schedule = match times.is_empty() {
+                true => Schedule::Every(chrono::Duration::minutes((*every).max(1).into())),
+                false => Schedule::At(times.clone()),
+            };
+            daemon::run(&storage, &schedule, notify_command)?
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,110 @@
+use std::io::Write;
+use vultan::state::card::LoadProgress;
+
+const WIDTH: usize = 30;
+// Smaller vaults load too quickly for a bar to be more than a flicker.
+const MIN_NOTES: usize = 100;
+
+// Drawn over a single line of stderr while the notes load, and cleared once they have.
+pub struct ProgressBar<W: Write> {
+    output: W,
+    drawn: Option<usize>,
+}
+
+impl<W: Write> ProgressBar<W> {
+    pub fn new(output: W) -> Self {
+        Self {
+            output,
+            drawn: None,
+        }
+    }
+
+    // Only redrawn when the bar grows, and at the end, to keep up with fast loads.
+    pub fn update(&mut self, progress: &LoadProgress) {
+        if progress.scanned < MIN_NOTES {
+            return;
+        }
+        let filled = progress.done() * WIDTH / progress.scanned;
+        if self.drawn == Some(filled) && progress.done() < progress.scanned {
+            return;
+        }
+        self.drawn = Some(filled);
+        let _ = write!(self.output, "\r{}", format_progress(progress));
+        let _ = self.output.flush();
+    }
+
+    pub fn finish(&mut self) {
+        if self.drawn.take().is_some() {
+            let _ = write!(self.output, "\r\x1b[2K");
+            let _ = self.output.flush();
+        }
+    }
+}
+
+// E.g. "Loading notes [##########--------------------] 1200/3600 (4 failed)".
+pub fn format_progress(progress: &LoadProgress) -> String {
+    let filled = match progress.scanned {
+        0 => WIDTH,
+        scanned => progress.done() * WIDTH / scanned,
+    };
+    let failed = match progress.failed {
+        0 => String::new(),
+        failed => format!(" ({} failed)", failed),
+    };
+    format!(
+        "Loading notes [{}{}] {}/{}{}",
+        "#".repeat(filled),
+        "-".repeat(WIDTH - filled),
+        progress.done(),
+        progress.scanned,
+        failed
+    )
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+
+    fn progress(scanned: usize, parsed: usize, failed: usize) -> LoadProgress {
+        LoadProgress {
+            scanned,
+            parsed,
+            failed,
+        }
+    }
+
+    #[test]
+    fn format_progress_fills_the_bar_with_the_notes_done() {
+        assert_eq!(
+            "Loading notes [##########--------------------] 1200/3600 (4 failed)",
+            format_progress(&progress(3600, 1196, 4))
+        );
+        assert_eq!(
+            "Loading notes [##############################] 0/0",
+            format_progress(&progress(0, 0, 0))
+        );
+    }
+
+    #[test]
+    fn progress_bar_draws_as_it_grows_then_clears() {
+        let mut output = Vec::new();
+        let mut bar = ProgressBar::new(&mut output);
+        for parsed in 0..=200 {
+            bar.update(&progress(200, parsed, 0));
+        }
+        bar.finish();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(WIDTH + 1, output.matches("\rLoading notes").count());
+        assert!(output.ends_with("200/200\r\x1b[2K"));
+    }
+
+    #[test]
+    fn progress_bar_skips_small_vaults() {
+        let mut output = Vec::new();
+        let mut bar = ProgressBar::new(&mut output);
+        bar.update(&progress(10, 10, 0));
+        bar.finish();
+        assert!(output.is_empty());
+    }
+}
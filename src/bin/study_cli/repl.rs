@@ -0,0 +1,1781 @@
+use crate::commands::{self, tree_label, SessionOptions};
+use crate::focus::{self, FocusTimer, Phase};
+use crate::terminal::Terminal;
+use chrono::{DateTime, Local, Utc};
+use std::error::Error;
+use std::fmt::Display;
+use std::io::{self, BufRead, Write};
+use std::process::Command;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tracing::{debug, debug_span, info_span};
+use vultan::state::card::{occlusion, typed_answer, Card, Grade, Score};
+use vultan::state::deck::{DailyGoal, DeckOptions, DeckStats, IntervalCoefficients};
+use vultan::state::file::FileHandle;
+use vultan::state::hand::{Checkpoint, Progress, QueueOrder, Session};
+use vultan::state::link_index::{self, LinkIndex};
+use vultan::state::search;
+use vultan::state::State;
+
+const SUGGESTED: &str = " (suggested)";
+const LINKS_INSTRUCTION: &str = "[l] linked notes  ";
+const FINDER_INSTRUCTION: &str = "[/] find  ";
+const EDIT_INSTRUCTION: &str = "[E] edit  ";
+const FLAG_INSTRUCTION: &str = "[F] flag  ";
+const UNFLAG_INSTRUCTION: &str = "[F] unflag  ";
+const SKIP_INSTRUCTION: &str = "[S] skip  ";
+const DUE_INSTRUCTION: &str = "[D] set due  ";
+const NOTE_INSTRUCTION: &str = "[Y] copy path  [O] open note  ";
+const INTERVAL_INSTRUCTION: &str = "[i] interval  ";
+const SPLIT_VIEW_INSTRUCTION: &str = "[V] split view  ";
+const SINGLE_VIEW_INSTRUCTION: &str = "[V] single view  ";
+const TYPED_ANSWER_PROMPT: &str = "Your answer > ";
+const DEFAULT_EDITOR: &str = "vi";
+#[cfg(target_os = "macos")]
+const OPENER: &str = "open";
+#[cfg(target_os = "windows")]
+const OPENER: &str = "explorer";
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const OPENER: &str = "xdg-open";
+const PREVIEW_LINES: usize = 8;
+const FINDER_RESULTS: usize = 9;
+
+enum Action {
+    // The grade, and how long after the question was shown it came.
+    Score(Grade, Duration),
+    // An interval in days, given in place of a grade, with the answer time.
+    Interval(f64, Duration),
+    JumpTo(Card),
+    Suspend(Card),
+    Refresh(Card),
+    SetDue(Card, DateTime<Utc>),
+    Skip,
+    CheckAgain,
+    Finish,
+}
+
+// What to do next, whether the card was flagged for later attention while reviewing it, and
+// whether the answer was last shown beside the question.
+struct Reviewed {
+    action: Action,
+    flagged: bool,
+    split_view: bool,
+}
+
+// What run reports as the session goes.
+pub enum SessionEvent<'e> {
+    Revised {
+        card: &'e Card,
+        score: Score,
+        answer_time: Duration,
+    },
+    // The current card was given a due date by hand, and left the session.
+    Rescheduled(&'e Card),
+    // After every action, so that the session can be resumed from there if it's interrupted.
+    Progressed(&'e Session<'e>),
+    // The session ran out of cards, or time, or was finished; it isn't when abandoned on an error.
+    Ended,
+}
+
+pub fn run(
+    terminal: &mut Terminal<impl BufRead, impl Write>,
+    session: Session,
+    state: &State,
+    links: &LinkIndex,
+    goal: Option<DailyGoal>,
+    options: &SessionOptions,
+    mut on_event: impl FnMut(SessionEvent),
+) -> Vec<Card> {
+    let mut goal = goal;
+    let mut session = session;
+    let mut abandoned = false;
+    let mut split_view = false;
+    let started = Instant::now();
+    let mut timer = options.focus.map(|focus| FocusTimer::start(focus, started));
+    let _session_span = info_span!("session", cards = session.remaining()).entered();
+    while let Some(card) = session.next_card() {
+        // Checked between cards, so that the card being reviewed when time runs out is finished.
+        if options
+            .time_limit
+            .is_some_and(|limit| started.elapsed() >= limit)
+        {
+            debug!("session time limit reached");
+            break;
+        }
+        let _card_span = debug_span!("card", path = %card.path).entered();
+        let was_flagged = card.flagged;
+        let progress = session.progress();
+        let now = Instant::now();
+        let phase = timer.as_mut().map(|timer| timer.tick(now));
+        let focus_left = timer.as_ref().map(|timer| timer.left(now));
+        let action = match (phase, session.waiting_until()) {
+            (Some(Phase::Break), _) => take_break(terminal, focus_left.unwrap_or_default()),
+            (_, Some(until)) => wait(terminal, until, session.remaining()),
+            _ => review(
+                terminal,
+                card,
+                Gauges {
+                    progress,
+                    goal: goal.as_ref(),
+                    focus_left,
+                    note: card
+                        .note_path()
+                        .strip_prefix(&options.notes_prefix)
+                        .unwrap_or(card.note_path()),
+                },
+                state,
+                links,
+                |answer_time, typed| match typed {
+                    Some(typed) => session.suggest_typed_score(typed),
+                    None => session.suggest_score(answer_time),
+                },
+                &Scoring {
+                    buttons: score_buttons(&session),
+                    manual_intervals: options.manual_intervals,
+                    typed_answers: session.typed_answers().is_some(),
+                    split_view,
+                },
+            )
+            .map(|reviewed| {
+                if reviewed.flagged != was_flagged {
+                    session.toggle_flag();
+                }
+                split_view = reviewed.split_view;
+                reviewed.action
+            }),
+        };
+        match action {
+            Ok(Action::Score(grade, answer_time)) => {
+                debug!(score = ?grade.score, ?answer_time, "scored Card");
+                if let Some(card) = session.submit_timed_grade(&grade, answer_time) {
+                    on_event(SessionEvent::Revised {
+                        card,
+                        score: grade.score,
+                        answer_time,
+                    });
+                    goal = goal.map(DailyGoal::with_review);
+                }
+            }
+            // Logged as a pass, the card having been recalled well enough to be put off.
+            Ok(Action::Interval(interval, answer_time)) => {
+                debug!(interval, ?answer_time, "gave Card an interval");
+                if let Some(card) = session.submit_interval(interval, answer_time) {
+                    on_event(SessionEvent::Revised {
+                        card,
+                        score: Score::Pass,
+                        answer_time,
+                    });
+                    goal = goal.map(DailyGoal::with_review);
+                }
+            }
+            Ok(Action::JumpTo(card)) => session.jump_to(&card),
+            Ok(Action::Suspend(card)) => {
+                session.suspend(&card);
+            }
+            Ok(Action::Refresh(card)) => session.refresh(&card),
+            Ok(Action::SetDue(card, due)) => {
+                on_event(SessionEvent::Rescheduled(session.set_due(&card, due)));
+            }
+            Ok(Action::Skip) => {
+                session.skip();
+            }
+            Ok(Action::CheckAgain) => {}
+            Ok(Action::Finish) => break,
+            Err(error) => {
+                terminal.restore();
+                eprintln!("\nSession abandoned: {}", error);
+                abandoned = true;
+                break;
+            }
+        }
+        on_event(SessionEvent::Progressed(&session));
+    }
+    if !abandoned {
+        on_event(SessionEvent::Ended);
+    }
+    session.finish()
+}
+
+// The card is left unshown until the break is over.
+fn take_break(
+    terminal: &mut Terminal<impl BufRead, impl Write>,
+    left: Duration,
+) -> io::Result<Action> {
+    let message = format!(
+        "Time for a break: {} left before the next card.",
+        focus::format_minutes(left)
+    );
+    terminal.section("Break", &message)?;
+    loop {
+        match terminal
+            .prompt("[Enter] check again  [q] finish > ")?
+            .trim()
+        {
+            "" => return Ok(Action::CheckAgain),
+            "q" => return Ok(Action::Finish),
+            _ => continue,
+        }
+    }
+}
+
+// Shown when only failed cards are left and they're all waiting out their relearn delay.
+fn wait(
+    terminal: &mut Terminal<impl BufRead, impl Write>,
+    until: DateTime<Utc>,
+    remaining: usize,
+) -> io::Result<Action> {
+    let message = format!(
+        "Nothing available until {}.\n{} failed card(s) are waiting before you see them again.",
+        until.with_timezone(&Local).format("%H:%M"),
+        remaining
+    );
+    terminal.section("Relearning", &message)?;
+    loop {
+        match terminal
+            .prompt("[Enter] check again  [q] finish > ")?
+            .trim()
+        {
+            "" => return Ok(Action::CheckAgain),
+            "q" => return Ok(Action::Finish),
+            _ => continue,
+        }
+    }
+}
+
+pub fn select_deck(
+    terminal: &mut Terminal<impl BufRead, impl Write>,
+    decks: &[DeckStats],
+) -> io::Result<Option<String>> {
+    if decks.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "No decks found"));
+    }
+    let listing: Vec<String> = decks
+        .iter()
+        .enumerate()
+        .map(|(i, d)| format!("[{}] {} ({} due)", i + 1, tree_label(&d.name), d.due))
+        .collect();
+    terminal.section("Decks", &listing.join("\n"))?;
+    loop {
+        match terminal.prompt("Deck number, or [q] quit > ")?.trim() {
+            "q" => return Ok(None),
+            selection => {
+                let index = selection
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|n| n.checked_sub(1));
+                if let Some(deck) = index.and_then(|i| decks.get(i)) {
+                    return Ok(Some(deck.name.clone()));
+                }
+            }
+        }
+    }
+}
+
+pub fn confirm_resume(
+    terminal: &mut Terminal<impl BufRead, impl Write>,
+    checkpoint: &Checkpoint,
+) -> io::Result<bool> {
+    let message = format!(
+        "A session of {} was interrupted with {} card(s) left, after {} revised.",
+        checkpoint.deck_name,
+        checkpoint.queue.len(),
+        checkpoint.revised.len()
+    );
+    terminal.section("Resume", &message)?;
+    loop {
+        match terminal
+            .prompt("[r] resume where it stopped  [n] deal a new session > ")?
+            .trim()
+        {
+            "r" => return Ok(true),
+            "n" => return Ok(false),
+            _ => continue,
+        }
+    }
+}
+
+pub fn confirm_cram(
+    terminal: &mut Terminal<impl BufRead, impl Write>,
+    deck_name: &str,
+    next_due: Option<DateTime<Utc>>,
+) -> io::Result<bool> {
+    let next_due = match next_due {
+        Some(due) => format!(
+            "The next card is due {}.",
+            due.with_timezone(&Local).format("%Y-%m-%d %H:%M")
+        ),
+        None => "This deck has no cards.".to_string(),
+    };
+    let message = format!("No cards in {} are due.\n{}", deck_name, next_due);
+    terminal.section("All done", &message)?;
+    loop {
+        match terminal
+            .prompt("[c] cram (scores are not saved)  [q] quit > ")?
+            .trim()
+        {
+            "c" => return Ok(true),
+            "q" => return Ok(false),
+            _ => continue,
+        }
+    }
+}
+
+fn review(
+    terminal: &mut Terminal<impl BufRead, impl Write>,
+    card: &Card,
+    gauges: Gauges,
+    state: &State,
+    links: &LinkIndex,
+    // Given the answer typed in, with typed answers.
+    suggest_score: impl Fn(Duration, Option<&str>) -> Option<Score>,
+    scoring: &Scoring,
+) -> io::Result<Reviewed> {
+    let mut flagged = card.flagged;
+    let mut split_view = scoring.split_view;
+    let reviewed = |action, flagged, split_view| {
+        Ok(Reviewed {
+            action,
+            flagged,
+            split_view,
+        })
+    };
+    // Gauges made from progress alone leave the note out of the title.
+    let note = match gauges.note {
+        "" => card.path.as_str(),
+        note => note,
+    };
+    let all_cards: Vec<&Card> = state.cards().values().collect();
+    let question = link_index::render(&occlusion::question(&card.question));
+    let linked_paths = links.linked_paths(&card.question);
+    let shown_at = Instant::now();
+    loop {
+        terminal.section(&title("Question", gauges, flagged), &question)?;
+        let prompt = instructions(
+            match scoring.typed_answers {
+                true => "[Enter] type the answer",
+                false => "[Enter] show answer",
+            },
+            &linked_paths,
+            !all_cards.is_empty(),
+            flagged,
+        );
+        match terminal.prompt(&prompt)?.trim() {
+            "l" if !linked_paths.is_empty() => show_linked_notes(terminal, &linked_paths)?,
+            "/" if !all_cards.is_empty() => {
+                if let Some(action) = find_card(terminal, &all_cards)? {
+                    return reviewed(action, flagged, split_view);
+                }
+            }
+            "E" | "e" => {
+                if let Some(edited) = edit(terminal, state, &card.path)? {
+                    return reviewed(Action::Refresh(edited), flagged, split_view);
+                }
+            }
+            "F" | "f" => flagged = !flagged,
+            "Y" | "y" => copy_path(terminal, note)?,
+            "O" | "o" => open(terminal, card.note_path())?,
+            "S" | "s" => return reviewed(Action::Skip, flagged, split_view),
+            "D" | "d" => {
+                if let Some(due) = ask_due_date(terminal)? {
+                    return reviewed(Action::SetDue(card.clone(), due), flagged, split_view);
+                }
+            }
+            _ => break,
+        }
+    }
+    let typed = match scoring.typed_answers {
+        true => Some(terminal.prompt(TYPED_ANSWER_PROMPT)?.trim().to_string()),
+        false => None,
+    };
+    let mut answer = link_index::render(&occlusion::answer(&card.question, &card.answer));
+    if let Some(typed) = &typed {
+        answer.push_str(&compare_typed_answer(typed, &card.answer));
+    }
+    let linked_paths = links.linked_paths(&format!("{}\n{}", card.question, card.answer));
+    show_answer(
+        terminal,
+        &title("Answer", gauges, flagged),
+        &question,
+        &answer,
+        split_view,
+    )?;
+    let suggested = suggest_score(shown_at.elapsed(), typed.as_deref());
+    let mut score_prompt = score_instructions(suggested, &scoring.buttons);
+    if scoring.manual_intervals {
+        score_prompt.insert_str(0, INTERVAL_INSTRUCTION);
+    }
+    let grades: Vec<Grade> = scoring
+        .buttons
+        .iter()
+        .map(|(grade, _)| grade.clone())
+        .collect();
+    loop {
+        let view_instruction = match split_view {
+            true => SINGLE_VIEW_INSTRUCTION,
+            false => SPLIT_VIEW_INSTRUCTION,
+        };
+        let prompt = instructions(
+            &format!("{}{}", view_instruction, score_prompt),
+            &linked_paths,
+            false,
+            flagged,
+        );
+        match terminal.prompt(&prompt)?.trim() {
+            "l" if !linked_paths.is_empty() => {
+                show_linked_notes(terminal, &linked_paths)?;
+                show_answer(
+                    terminal,
+                    &title("Answer", gauges, flagged),
+                    &question,
+                    &answer,
+                    split_view,
+                )?;
+            }
+            "E" | "e" => match edit(terminal, state, &card.path)? {
+                Some(edited) => return reviewed(Action::Refresh(edited), flagged, split_view),
+                None => show_answer(
+                    terminal,
+                    &title("Answer", gauges, flagged),
+                    &question,
+                    &answer,
+                    split_view,
+                )?,
+            },
+            "Y" | "y" => {
+                copy_path(terminal, note)?;
+                show_answer(
+                    terminal,
+                    &title("Answer", gauges, flagged),
+                    &question,
+                    &answer,
+                    split_view,
+                )?;
+            }
+            "O" | "o" => {
+                open(terminal, card.note_path())?;
+                show_answer(
+                    terminal,
+                    &title("Answer", gauges, flagged),
+                    &question,
+                    &answer,
+                    split_view,
+                )?;
+            }
+            "S" | "s" => return reviewed(Action::Skip, flagged, split_view),
+            "V" | "v" => {
+                split_view = !split_view;
+                show_answer(
+                    terminal,
+                    &title("Answer", gauges, flagged),
+                    &question,
+                    &answer,
+                    split_view,
+                )?;
+            }
+            "F" | "f" => {
+                flagged = !flagged;
+                show_answer(
+                    terminal,
+                    &title("Answer", gauges, flagged),
+                    &question,
+                    &answer,
+                    split_view,
+                )?;
+            }
+            "D" | "d" => match ask_due_date(terminal)? {
+                Some(due) => {
+                    return reviewed(Action::SetDue(card.clone(), due), flagged, split_view)
+                }
+                None => show_answer(
+                    terminal,
+                    &title("Answer", gauges, flagged),
+                    &question,
+                    &answer,
+                    split_view,
+                )?,
+            },
+            "i" if scoring.manual_intervals => {
+                let answer_time = shown_at.elapsed();
+                match ask_interval(terminal)? {
+                    Some(interval) => {
+                        return reviewed(
+                            Action::Interval(interval, answer_time),
+                            flagged,
+                            split_view,
+                        )
+                    }
+                    None => show_answer(
+                        terminal,
+                        &title("Answer", gauges, flagged),
+                        &question,
+                        &answer,
+                        split_view,
+                    )?,
+                }
+            }
+            line => {
+                if let Some(grade) = parse_grade(line, &grades) {
+                    return reviewed(
+                        Action::Score(grade.clone(), shown_at.elapsed()),
+                        flagged,
+                        split_view,
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn ask_interval(terminal: &mut Terminal<impl BufRead, impl Write>) -> io::Result<Option<f64>> {
+    loop {
+        let line = terminal.prompt("Interval, e.g. 3d, 2w, 1.5mo or 1y ([Enter] cancel) > ")?;
+        match line.trim() {
+            "" => return Ok(None),
+            line => {
+                if let Some(interval) = parse_interval(line) {
+                    return Ok(Some(interval));
+                }
+            }
+        }
+    }
+}
+
+// In days, from a number of days, weeks, months or years, as format_interval shows them.
+fn parse_interval(input: &str) -> Option<f64> {
+    let (number, days_per_unit) = [("mo", 30.0), ("d", 1.0), ("w", 7.0), ("y", 365.0)]
+        .into_iter()
+        .find_map(|(unit, days)| Some((input.strip_suffix(unit)?, days)))?;
+    let days = number.trim().parse::<f64>().ok()? * days_per_unit;
+    (days.is_finite() && days > 0.0).then_some(days)
+}
+
+// Asks again until the date can be read; an empty line cancels.
+fn ask_due_date(
+    terminal: &mut Terminal<impl BufRead, impl Write>,
+) -> io::Result<Option<DateTime<Utc>>> {
+    let mut prompt = "Due date, as YYYY-MM-DD [HH:MM] ([Enter] cancel) > ".to_string();
+    loop {
+        match terminal.prompt(&prompt)?.trim() {
+            "" => return Ok(None),
+            line => match commands::parse_local_date_time(line) {
+                Ok(due) => return Ok(Some(due)),
+                Err(error) => prompt = format!("{}; due date ([Enter] cancel) > ", error),
+            },
+        }
+    }
+}
+
+// The grades offered for the answer, with the intervals they'd give, whether an interval may be
+// typed in instead, whether the answer is typed in before it's shown, and whether it's shown
+// beside the question at first.
+struct Scoring {
+    buttons: Vec<(Grade, Option<f64>)>,
+    manual_intervals: bool,
+    typed_answers: bool,
+    split_view: bool,
+}
+
+// Beside the question in the split view, in place of it otherwise.
+fn show_answer(
+    terminal: &mut Terminal<impl BufRead, impl Write>,
+    title: &str,
+    question: &str,
+    answer: &str,
+    split_view: bool,
+) -> io::Result<()> {
+    match split_view {
+        true => terminal.split_section(("Question", question), (title, answer)),
+        false => terminal.section(title, answer),
+    }
+}
+
+// Shown under the answer, e.g. "You typed: mitocondria (92% match)".
+fn compare_typed_answer(typed: &str, answer: &str) -> String {
+    let similarity = (1.0 - typed_answer::distance(typed, answer)) * 100.0;
+    format!("\n\nYou typed: {} ({:.0}% match)", typed, similarity)
+}
+
+// What the question and answer titles show of the card, the session and the day.
+#[derive(Clone, Copy, Debug)]
+struct Gauges<'g> {
+    progress: Progress,
+    goal: Option<&'g DailyGoal>,
+    // Of the current work period, with a focus timer.
+    focus_left: Option<Duration>,
+    // The card's path, relative to the notes directory.
+    note: &'g str,
+}
+
+impl From<Progress> for Gauges<'_> {
+    fn from(progress: Progress) -> Self {
+        Self {
+            progress,
+            goal: None,
+            focus_left: None,
+            note: "",
+        }
+    }
+}
+
+// E.g. "Question [3/10, 1 skipped] bio/krebs.md": the position counts skipped cards as well as
+// revised ones.
+fn title(name: &str, gauges: Gauges, flagged: bool) -> String {
+    let Gauges {
+        progress,
+        goal,
+        focus_left,
+        note,
+    } = gauges;
+    let mut title = name.to_string();
+    if progress.total() > 0 {
+        let position = progress.revised + progress.skipped + 1;
+        let gauge = match progress.skipped {
+            0 => format!(" [{}/{}]", position, progress.total()),
+            skipped => format!(" [{}/{}, {} skipped]", position, progress.total(), skipped),
+        };
+        title.push_str(&gauge);
+    }
+    if let Some(DailyGoal {
+        goal: Some(target),
+        reviewed,
+        ..
+    }) = goal
+    {
+        title.push_str(&format!(" [today {}/{}]", reviewed, target));
+    }
+    if let Some(left) = focus_left {
+        title.push_str(&format!(" [focus {}]", focus::format_minutes(left)));
+    }
+    if flagged {
+        title.push_str(" [flagged]");
+    }
+    if !note.is_empty() {
+        title.push_str(&format!(" {}", note));
+    }
+    title
+}
+
+// Copies the path relative to the notes directory, and shows it for terminals without a
+// clipboard.
+fn copy_path(terminal: &mut Terminal<impl BufRead, impl Write>, note: &str) -> io::Result<()> {
+    terminal.copy_to_clipboard(note)?;
+    terminal.prompt(&format!("Copied {} ([Enter] back) > ", note))?;
+    Ok(())
+}
+
+// Opens the card's note with the system's opener, e.g. in an app made for its file type.
+fn open(terminal: &mut Terminal<impl BufRead, impl Write>, path: &str) -> io::Result<()> {
+    if let Err(error) = open_with(terminal, path, OPENER) {
+        terminal.section("Unable to open note", &error.to_string())?;
+        terminal.prompt("[Enter] back")?;
+    }
+    Ok(())
+}
+
+fn open_with(
+    terminal: &mut Terminal<impl BufRead, impl Write>,
+    path: &str,
+    opener: &str,
+) -> Result<(), Box<dyn Error>> {
+    let status = terminal
+        .run_external(Command::new(opener).arg(path))
+        .map_err(|e| format!("Unable to run {} -> {}", opener, e))?;
+    match status.success() {
+        true => Ok(()),
+        false => Err(format!("{} exited with {}", opener, status).into()),
+    }
+}
+
+// Opens the card's note in $VISUAL or $EDITOR, then re-parses it so the session shows the edit.
+fn edit(
+    terminal: &mut Terminal<impl BufRead, impl Write>,
+    state: &State,
+    path: &str,
+) -> io::Result<Option<Card>> {
+    let editor = editor_command(std::env::var("VISUAL").ok(), std::env::var("EDITOR").ok());
+    match edit_and_reload(terminal, state, path, &editor) {
+        Ok(card) => Ok(Some(card)),
+        Err(error) => {
+            terminal.section("Unable to edit card", &error.to_string())?;
+            terminal.prompt("[Enter] back")?;
+            Ok(None)
+        }
+    }
+}
+
+fn edit_and_reload(
+    terminal: &mut Terminal<impl BufRead, impl Write>,
+    state: &State,
+    path: &str,
+    editor: &str,
+) -> Result<Card, Box<dyn Error>> {
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or(DEFAULT_EDITOR);
+    let status = terminal
+        .run_external(Command::new(program).args(words).arg(path))
+        .map_err(|e| format!("Unable to run {} -> {}", editor, e))?;
+    if !status.success() {
+        return Err(format!("{} exited with {}", editor, status).into());
+    }
+    Ok(state.reload_card(FileHandle::from(path.to_string()))?)
+}
+
+fn editor_command(visual: Option<String>, editor: Option<String>) -> String {
+    visual
+        .into_iter()
+        .chain(editor)
+        .find(|command| !command.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_string())
+}
+
+// Searches every card, not just the hand, so any note can be reviewed or suspended mid-session.
+fn find_card(
+    terminal: &mut Terminal<impl BufRead, impl Write>,
+    all_cards: &[&Card],
+) -> io::Result<Option<Action>> {
+    loop {
+        let pattern = terminal.prompt("Find > ")?;
+        let pattern = pattern.trim();
+        if pattern.is_empty() {
+            return Ok(None);
+        }
+        let found: Vec<&Card> = search::fuzzy_find(all_cards.iter().copied(), pattern)
+            .into_iter()
+            .take(FINDER_RESULTS)
+            .collect();
+        let listing: Vec<String> = found
+            .iter()
+            .enumerate()
+            .map(|(i, card)| {
+                format!(
+                    "[{}] {}\n    {}",
+                    i + 1,
+                    card.path,
+                    first_line(&card.question)
+                )
+            })
+            .collect();
+        let listing = match listing.is_empty() {
+            true => "No matching cards".to_string(),
+            false => listing.join("\n"),
+        };
+        terminal.section(&format!("Find: {}", pattern), &listing)?;
+        let selection = terminal.prompt("Card number, [/] find again, or [Enter] back > ")?;
+        let card = match selection.trim() {
+            "/" => continue,
+            selection => match selection
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| n.checked_sub(1))
+                .and_then(|i| found.get(i))
+            {
+                Some(card) => *card,
+                None => return Ok(None),
+            },
+        };
+        let preview = format!(
+            "{}\n\n{}",
+            link_index::render(&card.question),
+            link_index::render(&card.answer)
+        );
+        terminal.section(&card.path, &preview)?;
+        return match terminal
+            .prompt("[j] review now  [s] suspend  [Enter] back > ")?
+            .trim()
+        {
+            "j" => Ok(Some(Action::JumpTo(card.clone()))),
+            "s" => Ok(Some(Action::Suspend(card.clone()))),
+            _ => Ok(None),
+        };
+    }
+}
+
+fn first_line(text: &str) -> String {
+    let line = text
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or_default();
+    link_index::render(line.trim())
+}
+
+// The grades the session's grading offers, each with the interval it would give the next card.
+fn score_buttons(session: &Session) -> Vec<(Grade, Option<f64>)> {
+    session
+        .grading()
+        .grades()
+        .into_iter()
+        .map(|grade| {
+            let interval = session.preview(&grade).map(|settings| settings.interval);
+            (grade, interval)
+        })
+        .collect()
+}
+
+// Each button shows the interval its grade would give, as Anki's do. The suggestion, from how long
+// the card took to recall, is marked on the first grade of its score, but never chosen for the
+// user.
+fn score_instructions(suggested: Option<Score>, buttons: &[(Grade, Option<f64>)]) -> String {
+    let suggested = suggested.and_then(|score| buttons.iter().position(|(g, _)| g.score == score));
+    let buttons: Vec<String> = buttons
+        .iter()
+        .enumerate()
+        .map(|(index, (grade, interval))| {
+            let mut button = format!("[{}] {}", index + 1, grade.label.to_uppercase());
+            if let Some(interval) = interval {
+                button.push_str(&format!(" — {}", format_interval(*interval)));
+            }
+            if Some(index) == suggested {
+                button.push_str(SUGGESTED);
+            }
+            button
+        })
+        .collect();
+    format!("{} > ", buttons.join("  "))
+}
+
+// In days, months or years, e.g. "6d", "2.5mo" or "1.2y".
+fn format_interval(days: f64) -> String {
+    match days {
+        days if days < 1.0 => "<1d".to_string(),
+        days if days < 31.0 => format!("{}d", days.round()),
+        days if days < 365.0 => format!("{:.1}mo", days / 30.0),
+        days => format!("{:.1}y", days / 365.0),
+    }
+}
+
+fn instructions(base: &str, linked_paths: &[&str], finder: bool, flagged: bool) -> String {
+    let mut instructions = String::new();
+    if !linked_paths.is_empty() {
+        instructions.push_str(LINKS_INSTRUCTION);
+    }
+    if finder {
+        instructions.push_str(FINDER_INSTRUCTION);
+    }
+    let flag_instruction = match flagged {
+        true => UNFLAG_INSTRUCTION,
+        false => FLAG_INSTRUCTION,
+    };
+    format!(
+        "{}{}{}{}{}{}{}",
+        instructions,
+        EDIT_INSTRUCTION,
+        flag_instruction,
+        SKIP_INSTRUCTION,
+        DUE_INSTRUCTION,
+        NOTE_INSTRUCTION,
+        base
+    )
+}
+
+fn show_linked_notes(
+    terminal: &mut Terminal<impl BufRead, impl Write>,
+    linked_paths: &[&str],
+) -> io::Result<()> {
+    let previews: Vec<String> = linked_paths
+        .iter()
+        .map(|path| format!("{}\n{}", path, preview(path)))
+        .collect();
+    terminal.section("Linked notes", &previews.join("\n\n"))?;
+    terminal.prompt("[Enter] back")?;
+    Ok(())
+}
+
+fn preview(path: &str) -> String {
+    match std::fs::read_to_string(path) {
+        Ok(content) => content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .take(PREVIEW_LINES)
+            .map(|line| format!("  {}", link_index::render(line)))
+            .collect::<Vec<String>>()
+            .join("\n"),
+        Err(error) => format!("  (unable to read: {})", error),
+    }
+}
+
+pub fn edit_interval_coefficients(
+    terminal: &mut Terminal<impl BufRead, impl Write>,
+    current: &IntervalCoefficients,
+) -> io::Result<IntervalCoefficients> {
+    Ok(IntervalCoefficients::new(
+        read_f64(terminal, "pass coefficient", current.pass_coef)?,
+        read_f64(terminal, "easy coefficient", current.easy_coef)?,
+        read_f64(terminal, "fail coefficient", current.fail_coef)?,
+    ))
+}
+
+// The deck's own limits and order; those it leaves unset are taken from its parent decks.
+pub fn edit_deck_options(
+    terminal: &mut Terminal<impl BufRead, impl Write>,
+    current: &DeckOptions,
+) -> io::Result<DeckOptions> {
+    let max_new_cards = read_setting(terminal, "max new cards", current.max_new_cards)?;
+    let max_reviews = read_setting(terminal, "max reviews", current.max_reviews)?;
+    let order = read_setting::<QueueOrder>(
+        terminal,
+        "order (random, oldest-due-first, ease-ascending, deck-round-robin)",
+        current.order,
+    )?;
+    Ok(current
+        .clone()
+        .with_max_new_cards(max_new_cards)
+        .with_max_reviews(max_reviews)
+        .with_order(order))
+}
+
+// An empty line keeps the current value and "-" unsets it; asks again, with the reason, until
+// the value can be read.
+fn read_setting<T>(
+    terminal: &mut Terminal<impl BufRead, impl Write>,
+    label: &str,
+    current: Option<T>,
+) -> io::Result<Option<T>>
+where
+    T: Display + FromStr,
+    T::Err: Display,
+{
+    let shown = match &current {
+        Some(value) => value.to_string(),
+        None => "inherited".to_string(),
+    };
+    let mut prompt = format!("{} [{}] > ", label, shown);
+    loop {
+        match terminal.prompt(&prompt)?.trim() {
+            "" => return Ok(current),
+            "-" => return Ok(None),
+            value => match value.parse::<T>() {
+                Ok(value) => return Ok(Some(value)),
+                Err(error) => prompt = format!("{}; {} [{}] > ", error, label, shown),
+            },
+        }
+    }
+}
+
+fn read_f64(
+    terminal: &mut Terminal<impl BufRead, impl Write>,
+    label: &str,
+    current: f64,
+) -> io::Result<f64> {
+    loop {
+        match terminal
+            .prompt(&format!("{} [{}] > ", label, current))?
+            .trim()
+        {
+            "" => return Ok(current),
+            value => {
+                if let Ok(value) = value.parse::<f64>() {
+                    return Ok(value);
+                }
+            }
+        }
+    }
+}
+
+// Scores are keyed 1, 2, ... in the order they're offered.
+pub fn parse_grade<'g>(line: &str, grades: &'g [Grade]) -> Option<&'g Grade> {
+    let key: usize = line.trim().parse().ok()?;
+    grades.get(key.checked_sub(1)?)
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use crate::theme::Theme;
+    use chrono::TimeZone;
+    use rstest::*;
+    use std::io::Cursor;
+    use vultan::state::card::Grading;
+
+    fn unprojected_buttons() -> Vec<(Grade, Option<f64>)> {
+        Grading::FourWay
+            .grades()
+            .into_iter()
+            .map(|grade| (grade, None))
+            .collect()
+    }
+
+    fn unprojected_scoring() -> Scoring {
+        Scoring {
+            buttons: unprojected_buttons(),
+            manual_intervals: false,
+            typed_answers: false,
+            split_view: false,
+        }
+    }
+
+    fn make_deck_stats(name: &str, due: usize) -> DeckStats {
+        DeckStats {
+            name: name.to_string(),
+            cards: due,
+            due,
+            new: 0,
+            mean_interval: 0.0,
+            mean_memorisation_factor: 0.0,
+            mastery: 0.0,
+        }
+    }
+
+    #[rstest]
+    #[case::fail("1", Some(Score::Fail))]
+    #[case::hard("2\n", Some(Score::Hard))]
+    #[case::pass(" 3 ", Some(Score::Pass))]
+    #[case::easy("4", Some(Score::Easy))]
+    #[case::out_of_range("5", None)]
+    #[case::not_a_number("pass", None)]
+    fn parse_grade(#[case] line: &str, #[case] expected: Option<Score>) {
+        let grades = Grading::FourWay.grades();
+        let actual = super::parse_grade(line, &grades).map(|grade| grade.score);
+        assert_eq!(expected, actual);
+    }
+
+    #[rstest]
+    #[case::fail("1", Some(Score::Fail))]
+    #[case::pass("2", Some(Score::Pass))]
+    #[case::out_of_range("3", None)]
+    #[case::zero("0", None)]
+    fn parse_grade_when_pass_fail(#[case] line: &str, #[case] expected: Option<Score>) {
+        let grades = Grading::PassFail.grades();
+        let actual = super::parse_grade(line, &grades).map(|grade| grade.score);
+        assert_eq!(expected, actual);
+    }
+
+    #[rstest]
+    #[case::valid_selection("2\n", Some("b"))]
+    #[case::reprompts_after_invalid_selection("0\n3\nx\n1\n", Some("a"))]
+    #[case::quit("q\n", None)]
+    fn select_deck(#[case] input: &str, #[case] expected: Option<&str>) {
+        let decks = vec![make_deck_stats("a", 1), make_deck_stats("b", 0)];
+        let theme = Theme::default();
+        let mut terminal = Terminal::new(Cursor::new(input), Vec::new(), &theme);
+        let actual = super::select_deck(&mut terminal, &decks).unwrap();
+        assert_eq!(expected.map(|s| s.to_string()), actual);
+        let output = terminal.written();
+        assert!(output.contains("[1] a (1 due)"));
+        assert!(output.contains("[2] b (0 due)"));
+    }
+
+    #[test]
+    fn select_deck_when_input_ends() {
+        let decks = vec![make_deck_stats("a", 1)];
+        let theme = Theme::default();
+        let mut terminal = Terminal::new(Cursor::new(""), Vec::new(), &theme);
+        let actual = super::select_deck(&mut terminal, &decks);
+        assert!(actual.is_err());
+    }
+
+    #[rstest]
+    #[case::keeps_current_values_on_empty_input("\n\n\n", (1.0, 1.3, 0.0))]
+    #[case::overrides_given_values("2\n\n0.5\n", (2.0, 1.3, 0.5))]
+    #[case::reprompts_after_invalid_value("x\n2\n3\n4\n", (2.0, 3.0, 4.0))]
+    fn edit_interval_coefficients(#[case] input: &str, #[case] expected: (f64, f64, f64)) {
+        let (pass_coef, easy_coef, fail_coef) = expected;
+        let expected = IntervalCoefficients::new(pass_coef, easy_coef, fail_coef);
+        let current = IntervalCoefficients::default();
+        let theme = Theme::default();
+        let mut terminal = Terminal::new(Cursor::new(input), Vec::new(), &theme);
+        let actual = super::edit_interval_coefficients(&mut terminal, &current);
+        assert_eq!(expected, actual.unwrap());
+    }
+
+    #[rstest]
+    #[case::keeps_current_values_on_empty_input("\n\n\n", (Some(20), None, Some(QueueOrder::Random)))]
+    #[case::overrides_given_values("5\n200\nease-ascending\n", (Some(5), Some(200), Some(QueueOrder::EaseAscending)))]
+    #[case::unsets_values("-\n\n-\n", (None, None, None))]
+    #[case::reprompts_after_invalid_value("x\n10\n\nnewest\n\n", (Some(10), None, Some(QueueOrder::Random)))]
+    fn edit_deck_options(
+        #[case] input: &str,
+        #[case] expected: (Option<usize>, Option<usize>, Option<QueueOrder>),
+    ) {
+        let (max_new_cards, max_reviews, order) = expected;
+        let current = DeckOptions {
+            max_new_cards: Some(20),
+            order: Some(QueueOrder::Random),
+            daily_goal: Some(50),
+            ..DeckOptions::default()
+        };
+        let theme = Theme::default();
+        let mut terminal =
+            Terminal::new(Cursor::new(input), Vec::new(), &theme).with_plain_output(true);
+        let actual = super::edit_deck_options(&mut terminal, &current).unwrap();
+        let expected = DeckOptions {
+            max_new_cards,
+            max_reviews,
+            order,
+            daily_goal: Some(50),
+            ..DeckOptions::default()
+        };
+        assert_eq!(expected, actual);
+        assert!(terminal.written().contains("max reviews [inherited] > "));
+    }
+
+    #[rstest]
+    #[case::resume("x\nr\n", true)]
+    #[case::deal_anew("n\n", false)]
+    fn confirm_resume(#[case] input: &str, #[case] expected: bool) {
+        let theme = Theme::default();
+        let mut terminal = Terminal::new(Cursor::new(input), Vec::new(), &theme);
+        let checkpoint = Checkpoint {
+            deck_name: "bio".to_string(),
+            queue: vec!["a".to_string(), "b".to_string()],
+            revised: vec!["c".to_string()],
+            ..Checkpoint::default()
+        };
+        assert_eq!(
+            expected,
+            super::confirm_resume(&mut terminal, &checkpoint).unwrap()
+        );
+        assert!(terminal
+            .written()
+            .contains("A session of bio was interrupted with 2 card(s) left, after 1 revised."));
+    }
+
+    #[rstest]
+    #[case::cram("x\nc\n", true)]
+    #[case::quit("q\n", false)]
+    fn confirm_cram(#[case] input: &str, #[case] expected: bool) {
+        let next_due = Local.with_ymd_and_hms(2030, 1, 2, 3, 4, 5).unwrap();
+        let theme = Theme::default();
+        let mut terminal = Terminal::new(Cursor::new(input), Vec::new(), &theme);
+        let actual = super::confirm_cram(&mut terminal, "deck", Some(next_due.with_timezone(&Utc)));
+        assert_eq!(expected, actual.unwrap());
+        let output = terminal.written();
+        assert!(output.contains("No cards in deck are due."));
+        assert!(output.contains("The next card is due 2030-01-02 03:04."));
+    }
+
+    #[test]
+    fn review_in_plain_mode() {
+        let theme = Theme::default();
+        let mut card = Card::default();
+        card.question = "what?".to_string();
+        card.answer = "that".to_string();
+        let mut output = Vec::new();
+        let mut terminal =
+            Terminal::new(Cursor::new("\nx\n4\n"), &mut output, &theme).with_plain_output(true);
+        let actual = review(
+            &mut terminal,
+            &card,
+            Progress::default().into(),
+            &State::default(),
+            &LinkIndex::default(),
+            |_, _| None,
+            &unprojected_scoring(),
+        )
+        .unwrap();
+        drop(terminal);
+        let expected_output = format!(
+            "\nQuestion\n\nwhat?\n\n{}[Enter] show answer\nAnswer\n\nthat\n\n{}{}",
+            instructions("", &[], false, false),
+            instructions(
+                &format!(
+                    "{}{}",
+                    SPLIT_VIEW_INSTRUCTION,
+                    score_instructions(None, &unprojected_buttons())
+                ),
+                &[],
+                false,
+                false
+            ),
+            instructions(
+                &format!(
+                    "{}{}",
+                    SPLIT_VIEW_INSTRUCTION,
+                    score_instructions(None, &unprojected_buttons())
+                ),
+                &[],
+                false,
+                false
+            )
+        );
+        assert!(matches!(actual.action, Action::Score(grade, _) if grade.score == Score::Easy));
+        assert!(!actual.flagged);
+        assert_eq!(expected_output, String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn review_toggles_the_split_view() {
+        let theme = Theme::default();
+        let card = Card {
+            question: "what?".to_string(),
+            answer: "that".to_string(),
+            ..Card::default()
+        };
+        let mut output = Vec::new();
+        let mut terminal = Terminal::new(Cursor::new("\nv\n4\n"), &mut output, &theme)
+            .with_plain_output(true)
+            .with_width(23);
+        let actual = review(
+            &mut terminal,
+            &card,
+            Progress::default().into(),
+            &State::default(),
+            &LinkIndex::default(),
+            |_, _| None,
+            &unprojected_scoring(),
+        )
+        .unwrap();
+        drop(terminal);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("\nQuestion   │ Answer\n\nwhat?      │ that\n"));
+        assert!(output.contains(SINGLE_VIEW_INSTRUCTION));
+        assert!(actual.split_view);
+        let scoring = Scoring {
+            split_view: true,
+            ..unprojected_scoring()
+        };
+        let mut terminal =
+            Terminal::new(Cursor::new("\nv\n4\n"), Vec::new(), &theme).with_plain_output(true);
+        let actual = review(
+            &mut terminal,
+            &card,
+            Progress::default().into(),
+            &State::default(),
+            &LinkIndex::default(),
+            |_, _| None,
+            &scoring,
+        )
+        .unwrap();
+        assert!(!actual.split_view);
+    }
+
+    #[test]
+    fn review_with_typed_answers() {
+        let theme = Theme::default();
+        let card = Card {
+            question: "Powerhouse of the cell?".to_string(),
+            answer: "Mitochondria".to_string(),
+            ..Card::default()
+        };
+        let mut output = Vec::new();
+        let mut terminal = Terminal::new(Cursor::new("\nmitocondria\n3\n"), &mut output, &theme)
+            .with_plain_output(true);
+        let scoring = Scoring {
+            typed_answers: true,
+            ..unprojected_scoring()
+        };
+        let actual = review(
+            &mut terminal,
+            &card,
+            Progress::default().into(),
+            &State::default(),
+            &LinkIndex::default(),
+            |_, typed| typed.filter(|t| *t == "mitocondria").map(|_| Score::Pass),
+            &scoring,
+        )
+        .unwrap();
+        drop(terminal);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("[Enter] type the answer"));
+        assert!(output.contains("Mitochondria\n\nYou typed: mitocondria (92% match)"));
+        assert!(output.contains("[3] PASS (suggested)"));
+        assert!(matches!(actual.action, Action::Score(grade, _) if grade.score == Score::Pass));
+    }
+
+    #[rstest]
+    #[case::none(None, "[1] FAIL  [2] HARD  [3] PASS  [4] EASY > ")]
+    #[case::hard(
+        Some(Score::Hard),
+        "[1] FAIL  [2] HARD (suggested)  [3] PASS  [4] EASY > "
+    )]
+    fn score_instructions_mark_the_suggestion(
+        #[case] suggested: Option<Score>,
+        #[case] expected: &str,
+    ) {
+        assert_eq!(
+            expected,
+            super::score_instructions(suggested, &unprojected_buttons())
+        );
+    }
+
+    #[test]
+    fn score_instructions_show_projected_intervals() {
+        let intervals = [Some(0.0), Some(2.4), Some(6.0), Some(75.0)];
+        let buttons: Vec<(Grade, Option<f64>)> = Grading::FourWay
+            .grades()
+            .into_iter()
+            .zip(intervals)
+            .collect();
+        assert_eq!(
+            "[1] FAIL — <1d  [2] HARD — 2d  [3] PASS — 6d (suggested)  [4] EASY — 2.5mo > ",
+            super::score_instructions(Some(Score::Pass), &buttons)
+        );
+        let pass_fail = [buttons[0].clone(), buttons[2].clone()];
+        assert_eq!(
+            "[1] FAIL — <1d  [2] PASS — 6d > ",
+            super::score_instructions(None, &pass_fail)
+        );
+    }
+
+    #[test]
+    fn score_instructions_of_custom_grades() {
+        let grade = |label: &str, score| Grade {
+            label: label.to_string(),
+            ..Grade::of(score)
+        };
+        let buttons = [
+            (grade("Blackout", Score::Fail), None),
+            (grade("Hesitant", Score::Pass), None),
+            (grade("Bright", Score::Pass), None),
+        ];
+        assert_eq!(
+            "[1] BLACKOUT  [2] HESITANT (suggested)  [3] BRIGHT > ",
+            super::score_instructions(Some(Score::Pass), &buttons)
+        );
+    }
+
+    #[rstest]
+    #[case::under_a_day(0.5, "<1d")]
+    #[case::days(20.4, "20d")]
+    #[case::months(45.0, "1.5mo")]
+    #[case::years(730.0, "2.0y")]
+    fn format_interval(#[case] days: f64, #[case] expected: &str) {
+        assert_eq!(expected, super::format_interval(days));
+    }
+
+    #[test]
+    fn select_deck_when_there_are_no_decks() {
+        let theme = Theme::default();
+        let mut terminal = Terminal::new(Cursor::new("1\n"), Vec::new(), &theme);
+        let actual = super::select_deck(&mut terminal, &[]);
+        assert!(actual.is_err());
+    }
+
+    #[test]
+    fn review_shows_linked_notes() {
+        let dir =
+            std::env::temp_dir().join(format!("vultan_repl_linked_notes_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let note_path = dir.join("Krebs Cycle.md").to_string_lossy().to_string();
+        std::fs::write(&note_path, "# Krebs Cycle\n\nmakes [[ATP]]\n").unwrap();
+        let mut links = LinkIndex::default();
+        links.insert(&note_path);
+        let theme = Theme::default();
+        let mut card = Card::default();
+        card.question = "what does the [[krebs cycle|cycle]] make?".to_string();
+        card.answer = "ATP".to_string();
+        let mut output = Vec::new();
+        let mut terminal = Terminal::new(Cursor::new("l\n\n\nl\n\n3\n"), &mut output, &theme)
+            .with_plain_output(true);
+        let actual = review(
+            &mut terminal,
+            &card,
+            Progress::default().into(),
+            &State::default(),
+            &links,
+            |_, _| None,
+            &unprojected_scoring(),
+        )
+        .unwrap();
+        drop(terminal);
+        let output = String::from_utf8(output).unwrap();
+        assert!(matches!(actual.action, Action::Score(grade, _) if grade.score == Score::Pass));
+        assert!(output.contains("what does the cycle make?"));
+        assert!(output.contains(&format!(
+            "{}{}{}{}{}{}{}{}",
+            LINKS_INSTRUCTION,
+            EDIT_INSTRUCTION,
+            FLAG_INSTRUCTION,
+            SKIP_INSTRUCTION,
+            DUE_INSTRUCTION,
+            NOTE_INSTRUCTION,
+            SPLIT_VIEW_INSTRUCTION,
+            score_instructions(None, &unprojected_buttons())
+        )));
+        assert_eq!(2, output.matches("Linked notes").count());
+        assert!(output.contains(&format!("{}\n  # Krebs Cycle\n  makes ATP", note_path)));
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[rstest]
+    #[case::review_now("/\nkrebs\n1\nj\n", Some("bio/krebs.md"), false)]
+    #[case::suspend("/\ncell\n/\natp\n1\ns\n", Some("chem/atp.md"), true)]
+    #[case::back_from_results("/\nkrebs\n\n\n3\n", None, false)]
+    fn review_finds_cards(
+        #[case] input: &str,
+        #[case] expected_path: Option<&str>,
+        #[case] suspend: bool,
+    ) {
+        let make_card = |path: &str, question: &str| {
+            let mut card = Card::default();
+            card.path = path.into();
+            card.question = question.to_string();
+            card
+        };
+        let cards = vec![
+            make_card("bio/krebs.md", "Where does the Krebs cycle happen?"),
+            make_card("chem/atp.md", "What is ATP?"),
+        ];
+        let state = State::default().with_loaded_cards(cards.clone());
+        let theme = Theme::default();
+        let mut output = Vec::new();
+        let mut terminal =
+            Terminal::new(Cursor::new(input), &mut output, &theme).with_plain_output(true);
+        let actual = review(
+            &mut terminal,
+            &cards[1],
+            Progress::default().into(),
+            &state,
+            &LinkIndex::default(),
+            |_, _| None,
+            &unprojected_scoring(),
+        )
+        .unwrap();
+        drop(terminal);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains(&format!("{}{}", FINDER_INSTRUCTION, EDIT_INSTRUCTION)));
+        match (actual.action, expected_path) {
+            (Action::JumpTo(card), Some(path)) if !suspend => assert_eq!(path, card.path),
+            (Action::Suspend(card), Some(path)) if suspend => assert_eq!(path, card.path),
+            (Action::Score(grade, _), None) if grade.score == Score::Pass => {
+                assert!(output.contains("[1] bio/krebs.md\n    Where does the Krebs cycle happen?"))
+            }
+            _ => panic!("unexpected action"),
+        }
+    }
+
+    #[rstest]
+    #[case::visual_first(Some("code -w"), Some("nano"), "code -w")]
+    #[case::editor(None, Some("nano"), "nano")]
+    #[case::blank_visual(Some(" "), Some("nano"), "nano")]
+    #[case::default(None, None, DEFAULT_EDITOR)]
+    fn editor_command(
+        #[case] visual: Option<&str>,
+        #[case] editor: Option<&str>,
+        #[case] expected: &str,
+    ) {
+        let to_string = |s: Option<&str>| s.map(|s| s.to_string());
+        assert_eq!(
+            expected,
+            super::editor_command(to_string(visual), to_string(editor))
+        );
+    }
+
+    #[rstest]
+    #[case::reloads_the_edited_note("true", Ok("edited"))]
+    #[case::fails_when_the_editor_fails("false", Err("false exited with"))]
+    #[case::fails_when_the_editor_is_missing("no-such-editor", Err("Unable to run no-such-editor"))]
+    fn edit_and_reload(#[case] editor: &str, #[case] expected: Result<&str, &str>) {
+        let dir = std::env::temp_dir().join(format!(
+            "vultan_repl_edit_{}_{}",
+            editor,
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let note_path = dir.join("note.md").to_string_lossy().to_string();
+        std::fs::write(
+            &note_path,
+            "tags: a\n# Question\nedited\n# Answer\nyes\n----\n",
+        )
+        .unwrap();
+        let theme = Theme::default();
+        let mut terminal = Terminal::new(Cursor::new(""), Vec::new(), &theme);
+        let actual = super::edit_and_reload(&mut terminal, &State::default(), &note_path, editor);
+        match (actual, expected) {
+            (Ok(card), Ok(question)) => assert_eq!(question, card.question),
+            (Err(error), Err(message)) => assert!(error.to_string().contains(message)),
+            (actual, _) => panic!("unexpected result {:?}", actual),
+        }
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[rstest]
+    #[case::at_the_question("f\n\n3\n", true)]
+    #[case::at_the_answer("\nF\n3\n", true)]
+    #[case::and_back("F\n\nf\n3\n", false)]
+    fn review_flags_cards_without_leaving_them(#[case] input: &str, #[case] expected: bool) {
+        let theme = Theme::default();
+        let mut card = Card::default();
+        card.question = "what?".to_string();
+        let mut terminal =
+            Terminal::new(Cursor::new(input), Vec::new(), &theme).with_plain_output(true);
+        let actual = review(
+            &mut terminal,
+            &card,
+            Progress::default().into(),
+            &State::default(),
+            &LinkIndex::default(),
+            |_, _| None,
+            &unprojected_scoring(),
+        );
+        let actual = actual.unwrap();
+        assert!(matches!(actual.action, Action::Score(grade, _) if grade.score == Score::Pass));
+        assert_eq!(expected, actual.flagged);
+        assert!(terminal.written().contains("[flagged]"));
+    }
+
+    #[rstest]
+    #[case::at_the_question("S\n")]
+    #[case::at_the_answer("\ns\n")]
+    fn review_skips_cards(#[case] input: &str) {
+        let theme = Theme::default();
+        let mut terminal =
+            Terminal::new(Cursor::new(input), Vec::new(), &theme).with_plain_output(true);
+        let progress = Progress {
+            revised: 1,
+            skipped: 1,
+            remaining: 3,
+        };
+        let actual = review(
+            &mut terminal,
+            &Card::default(),
+            progress.into(),
+            &State::default(),
+            &LinkIndex::default(),
+            |_, _| None,
+            &unprojected_scoring(),
+        );
+        assert!(matches!(actual.unwrap().action, Action::Skip));
+        assert!(terminal.written().contains("Question [3/5, 1 skipped]"));
+    }
+
+    #[rstest]
+    #[case::days("3d", Some(3.0))]
+    #[case::weeks("2w", Some(14.0))]
+    #[case::months("1.5mo", Some(45.0))]
+    #[case::years("1y", Some(365.0))]
+    #[case::without_a_unit("3", None)]
+    #[case::zero("0d", None)]
+    #[case::negative("-2d", None)]
+    fn parse_interval(#[case] input: &str, #[case] expected: Option<f64>) {
+        assert_eq!(expected, super::parse_interval(input));
+    }
+
+    #[rstest]
+    #[case::enabled(true, "\ni\nsoon\n2w\n", Some(14.0))]
+    #[case::cancelled(true, "\ni\n\n3\n", None)]
+    #[case::disabled(false, "\ni\n3\n", None)]
+    fn review_takes_manual_intervals(
+        #[case] manual_intervals: bool,
+        #[case] input: &str,
+        #[case] expected: Option<f64>,
+    ) {
+        let theme = Theme::default();
+        let mut terminal =
+            Terminal::new(Cursor::new(input), Vec::new(), &theme).with_plain_output(true);
+        let scoring = Scoring {
+            manual_intervals,
+            ..unprojected_scoring()
+        };
+        let actual = review(
+            &mut terminal,
+            &Card::default(),
+            Progress::default().into(),
+            &State::default(),
+            &LinkIndex::default(),
+            |_, _| None,
+            &scoring,
+        );
+        match (actual.unwrap().action, expected) {
+            (Action::Interval(interval, _), Some(days)) => assert_eq!(days, interval),
+            (Action::Score(grade, _), None) => assert_eq!(Score::Pass, grade.score),
+            _ => panic!("unexpected action"),
+        }
+        assert_eq!(
+            manual_intervals,
+            terminal.written().contains(INTERVAL_INSTRUCTION)
+        );
+    }
+
+    #[rstest]
+    #[case::at_the_question("D\n2030-13-01\n2030-06-01\n", Some("2030-06-01"))]
+    #[case::at_the_answer("\nd\n2030-06-01 08:30\n", Some("2030-06-01 08:30"))]
+    #[case::cancelled("D\n\n\n3\n", None)]
+    fn review_sets_due_dates(#[case] input: &str, #[case] expected: Option<&str>) {
+        let theme = Theme::default();
+        let mut terminal =
+            Terminal::new(Cursor::new(input), Vec::new(), &theme).with_plain_output(true);
+        let actual = review(
+            &mut terminal,
+            &Card::default(),
+            Progress::default().into(),
+            &State::default(),
+            &LinkIndex::default(),
+            |_, _| None,
+            &unprojected_scoring(),
+        );
+        match (actual.unwrap().action, expected) {
+            (Action::SetDue(_, due), Some(date)) => {
+                assert_eq!(commands::parse_local_date_time(date).unwrap(), due)
+            }
+            (Action::Score(grade, _), None) => assert_eq!(Score::Pass, grade.score),
+            _ => panic!("unexpected action"),
+        }
+    }
+
+    #[rstest]
+    #[case::without_a_session(Progress::default(), false, "Question")]
+    #[case::first_card(Progress { revised: 0, skipped: 0, remaining: 4 }, false, "Question [1/4]")]
+    #[case::flagged(Progress { revised: 2, skipped: 0, remaining: 2 }, true, "Question [3/4] [flagged]")]
+    #[case::with_skips(Progress { revised: 1, skipped: 2, remaining: 1 }, false, "Question [4/4, 2 skipped]")]
+    fn title(#[case] progress: Progress, #[case] flagged: bool, #[case] expected: &str) {
+        assert_eq!(expected, super::title("Question", progress.into(), flagged));
+    }
+
+    #[test]
+    fn title_with_a_daily_goal() {
+        let goal = |target| DailyGoal {
+            name: "deck".to_string(),
+            goal: target,
+            reviewed: 12,
+            due: 30,
+        };
+        fn gauges(goal: &DailyGoal) -> Gauges<'_> {
+            Gauges {
+                progress: Progress {
+                    revised: 0,
+                    skipped: 0,
+                    remaining: 4,
+                },
+                goal: Some(goal),
+                focus_left: None,
+                note: "",
+            }
+        }
+        assert_eq!(
+            "Question [1/4] [today 12/50]",
+            super::title("Question", gauges(&goal(Some(50))), false)
+        );
+        assert_eq!(
+            "Question [1/4]",
+            super::title("Question", gauges(&goal(None)), false)
+        );
+    }
+
+    #[rstest]
+    #[case::check_again("x\n\n", Action::CheckAgain)]
+    #[case::finish("q\n", Action::Finish)]
+    fn wait(#[case] input: &str, #[case] expected: Action) {
+        let until = Local.with_ymd_and_hms(2030, 1, 2, 3, 4, 5).unwrap();
+        let theme = Theme::default();
+        let mut terminal = Terminal::new(Cursor::new(input), Vec::new(), &theme);
+        let actual = super::wait(&mut terminal, until.with_timezone(&Utc), 2).unwrap();
+        assert_eq!(
+            std::mem::discriminant(&expected),
+            std::mem::discriminant(&actual)
+        );
+        let output = terminal.written();
+        assert!(output.contains("Nothing available until 03:04."));
+        assert!(output.contains("2 failed card(s) are waiting"));
+    }
+
+    #[test]
+    fn run_ends_once_the_time_limit_passes() {
+        let card = Card {
+            path: "bio/krebs.md".into(),
+            decks: vec!["bio".to_string()],
+            ..Card::default()
+        };
+        let state = State::default().with_loaded_cards(vec![card]);
+        let theme = Theme::default();
+        let mut terminal =
+            Terminal::new(Cursor::new("\n4\n"), Vec::new(), &theme).with_plain_output(true);
+        let options = SessionOptions::default().with_time_limit(Some(Duration::ZERO));
+        let mut revised = 0;
+        let session = Session::from(state.deal("bio").unwrap());
+        let mut ended = false;
+        let links = LinkIndex::default();
+        run(
+            &mut terminal,
+            session,
+            &state,
+            &links,
+            None,
+            &options,
+            |event| match event {
+                SessionEvent::Revised { .. } => revised += 1,
+                SessionEvent::Rescheduled(_) | SessionEvent::Progressed(_) => {}
+                SessionEvent::Ended => ended = true,
+            },
+        );
+        assert_eq!(0, revised);
+        assert!(ended);
+        assert!(!terminal.written().contains("Question"));
+    }
+
+    #[test]
+    fn take_break() {
+        let theme = Theme::default();
+        let mut terminal = Terminal::new(Cursor::new("x\n\n"), Vec::new(), &theme);
+        let actual = super::take_break(&mut terminal, Duration::from_secs(150)).unwrap();
+        assert!(matches!(actual, Action::CheckAgain));
+        assert!(terminal
+            .written()
+            .contains("Time for a break: 3m left before the next card."));
+    }
+
+    #[test]
+    fn title_with_a_focus_timer() {
+        let gauges = Gauges {
+            focus_left: Some(Duration::from_secs(18 * 60)),
+            ..Progress::default().into()
+        };
+        assert_eq!(
+            "Question [focus 18m]",
+            super::title("Question", gauges, false)
+        );
+    }
+
+    #[test]
+    fn title_with_the_note() {
+        let gauges = Gauges {
+            note: "bio/krebs.md",
+            ..Progress::default().into()
+        };
+        assert_eq!(
+            "Question [flagged] bio/krebs.md",
+            super::title("Question", gauges, true)
+        );
+    }
+
+    #[test]
+    fn review_copies_the_note_path() {
+        let theme = Theme::default();
+        let card = Card {
+            path: "notes/bio/krebs.md".into(),
+            question: "what?".to_string(),
+            ..Card::default()
+        };
+        let mut output = Vec::new();
+        let mut terminal = Terminal::new(Cursor::new("y\n\n\ny\n\n4\n"), &mut output, &theme)
+            .with_plain_output(true);
+        let gauges = Gauges {
+            note: "bio/krebs.md",
+            ..Progress::default().into()
+        };
+        let actual = review(
+            &mut terminal,
+            &card,
+            gauges,
+            &State::default(),
+            &LinkIndex::default(),
+            |_, _| None,
+            &unprojected_scoring(),
+        )
+        .unwrap();
+        drop(terminal);
+        let output = String::from_utf8(output).unwrap();
+        assert!(matches!(actual.action, Action::Score(grade, _) if grade.score == Score::Easy));
+        assert_eq!(2, output.matches("\nQuestion bio/krebs.md\n").count());
+        assert_eq!(2, output.matches("\nAnswer bio/krebs.md\n").count());
+        assert_eq!(2, output.matches("Copied bio/krebs.md ").count());
+    }
+
+    #[rstest]
+    #[case::opens_the_note("true", Ok(()))]
+    #[case::fails_when_the_opener_fails("false", Err("false exited with"))]
+    #[case::fails_when_the_opener_is_missing("no-such-opener", Err("Unable to run no-such-opener"))]
+    fn open_with(#[case] opener: &str, #[case] expected: Result<(), &str>) {
+        let theme = Theme::default();
+        let mut terminal =
+            Terminal::new(Cursor::new(""), Vec::new(), &theme).with_plain_output(true);
+        match (
+            super::open_with(&mut terminal, "bio/krebs.md", opener),
+            expected,
+        ) {
+            (Ok(()), Ok(())) => (),
+            (Err(error), Err(message)) => assert!(error.to_string().starts_with(message)),
+            (actual, expected) => panic!("expected {:?}, got {:?}", expected, actual.is_ok()),
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use crate::storage::{Change, Storage};
+use std::error::Error;
+use vultan::state::card::parser::{Parse, Parser};
+use vultan::state::card::Card;
+use vultan::state::deck;
+use vultan::state::file::FileHandle;
+use vultan::state::filter::CardFilter;
+use vultan::state::State;
+
+// Adds and removes decks of every card matching the filters, rewriting their notes.
+pub fn bulk(
+    state: State,
+    storage: &Storage,
+    filters: &[CardFilter],
+    add_decks: &[String],
+    remove_decks: &[String],
+) -> Result<State, Box<dyn Error>> {
+    let mut cards: Vec<&Card> = state
+        .cards()
+        .values()
+        .filter(|card| CardFilter::all_match(filters, card))
+        .collect();
+    cards.sort_by(|a, b| a.path.cmp(&b.path));
+    let mut moves = Vec::new();
+    for card in cards.into_iter() {
+        let mut decks: Vec<String> = card
+            .decks
+            .iter()
+            .filter(|deck| !remove_decks.contains(deck))
+            .cloned()
+            .collect();
+        for deck in add_decks.iter() {
+            if !decks.contains(deck) {
+                decks.push(deck.clone());
+            }
+        }
+        if decks.is_empty() {
+            return Err(format!("{} would be left in no deck", card.path).into());
+        }
+        if decks != card.decks {
+            moves.push((card.clone(), decks));
+        }
+    }
+    let count = moves.len();
+    let cards = redeck(&state, moves)?;
+    let state = storage.change(state, Change::Redecked(cards))?;
+    println!("Moved {} card(s)", count);
+    Ok(state)
+}
+
+// Sub-decks are renamed with the deck, and every deck keeps its coefficients and options.
+pub fn rename_deck(
+    state: State,
+    storage: &Storage,
+    old_name: &str,
+    new_name: &str,
+) -> Result<State, Box<dyn Error>> {
+    if !state.decks().contains_key(old_name) {
+        return Err(format!("There is no deck \"{}\"", old_name).into());
+    }
+    if state.decks().contains_key(new_name) {
+        return Err(format!(
+            "Deck \"{}\" already exists; move cards into it with bulk instead",
+            new_name
+        )
+        .into());
+    }
+    let mut cards: Vec<&Card> = state.cards_in_deck(old_name);
+    cards.sort_by(|a, b| a.path.cmp(&b.path));
+    let moves: Vec<(Card, Vec<String>)> = cards
+        .into_iter()
+        .map(|card| {
+            let decks = card
+                .decks
+                .iter()
+                .map(|name| deck::renamed(name, old_name, new_name).unwrap_or(name.clone()))
+                .collect();
+            (card.clone(), decks)
+        })
+        .collect();
+    let count = moves.len();
+    let cards = redeck(&state, moves)?;
+    let state = storage.change(
+        state,
+        Change::RenamedDeck {
+            old_name: old_name.to_string(),
+            new_name: new_name.to_string(),
+            cards,
+        },
+    )?;
+    println!(
+        "Renamed \"{}\" to \"{}\", rewriting {} note(s)",
+        old_name, new_name, count
+    );
+    Ok(state)
+}
+
+// Every note is checked to read back with its new decks before any is written. The cards are
+// returned with their new decks.
+fn redeck(state: &State, moves: Vec<(Card, Vec<String>)>) -> Result<Vec<Card>, Box<dyn Error>> {
+    let config = state.card_parsing_config();
+    if let Some(name) = &config.parser {
+        return Err(format!(
+            "Notes read by the registered parser \"{}\" can't be rewritten",
+            name
+        )
+        .into());
+    }
+    let parser = Parser::from(config.clone())?;
+    let mut rewrites = Vec::with_capacity(moves.len());
+    for (card, decks) in moves.into_iter() {
+        let content = FileHandle::from(card.path.to_string())
+            .read()
+            .map_err(|e| format!("Unable to read {} -> {}", card.path, e))?;
+        let rewritten = parser
+            .rewrite_decks(&content, &decks)
+            .map_err(|e| format!("Unable to rewrite the decks of {} -> {}", card.path, e))?;
+        let parsed = parser.parse(&rewritten)?;
+        if !parsed.decks.iter().eq(decks.iter()) {
+            return Err(format!(
+                "Unable to write the decks of {} in a form the card parsing config reads back",
+                card.path
+            )
+            .into());
+        }
+        rewrites.push((Card { decks, ..card }, rewritten));
+    }
+    for (card, content) in rewrites.iter() {
+        FileHandle::from(card.path.to_string())
+            .write(content.clone())
+            .map_err(|e| format!("Unable to write {} -> {}", card.path, e))?;
+    }
+    Ok(rewrites.into_iter().map(|(card, _)| card).collect())
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use std::fs;
+    use vultan::state::deck::IntervalCoefficients;
+
+    fn make_notes_dir(name: &str, notes: &[(&str, &str)]) -> String {
+        let dir = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        for (file_name, decks) in notes.iter() {
+            let content = format!("tags: {}\n# Question\nq\n# Answer\na\n----\n", decks);
+            fs::write(dir.join(file_name), content).unwrap();
+        }
+        dir.to_string_lossy().to_string()
+    }
+
+    fn make_storage(notes_dir: &str) -> Storage {
+        Storage::new(
+            notes_dir,
+            &format!("{}/.vultan.ron", notes_dir),
+            &format!("{}/logs", notes_dir),
+            "laptop",
+        )
+    }
+
+    #[test]
+    fn bulk_rewrites_the_matching_notes() {
+        let notes_dir = make_notes_dir(
+            "vultan_retag_bulk",
+            &[("a.md", "old:keep"), ("b.md", "keep"), ("c.md", "other")],
+        );
+        let storage = make_storage(&notes_dir);
+        let state = storage.load().unwrap();
+        let filters = ["deck:keep".parse().unwrap()];
+        let actual = bulk(
+            state,
+            &storage,
+            &filters,
+            &["new".to_string()],
+            &["old".to_string()],
+        )
+        .unwrap();
+        let note = fs::read_to_string(format!("{}/a.md", notes_dir)).unwrap();
+        assert!(note.starts_with("tags: keep:new\n"));
+        let note = fs::read_to_string(format!("{}/c.md", notes_dir)).unwrap();
+        assert!(note.starts_with("tags: other\n"));
+        assert_eq!(2, actual.cards_in_deck("new").len());
+        assert!(actual.cards_in_deck("old").is_empty());
+        assert_eq!(2, storage.load().unwrap().cards_in_deck("new").len());
+        fs::remove_dir_all(notes_dir).ok();
+    }
+
+    #[test]
+    fn rename_deck_keeps_its_coefficients() {
+        let notes_dir = make_notes_dir(
+            "vultan_retag_rename",
+            &[
+                ("a.md", "lang::spanish"),
+                ("b.md", "lang::spanish::verbs:exam"),
+            ],
+        );
+        let storage = make_storage(&notes_dir);
+        let state = storage.load().unwrap();
+        let deck = state.decks()["lang::spanish"]
+            .clone()
+            .with_interval_coefficients(IntervalCoefficients::new(2.0, 3.0, 0.5));
+        let state = storage.edit_deck(state, deck).unwrap();
+        rename_deck(state, &storage, "lang::spanish", "espanol").unwrap();
+        let note = fs::read_to_string(format!("{}/b.md", notes_dir)).unwrap();
+        assert!(note.starts_with("tags: espanol::verbs:exam\n"));
+        let actual = storage.load().unwrap();
+        assert!(!actual.decks().contains_key("lang::spanish"));
+        assert_eq!(2, actual.cards_in_deck("espanol").len());
+        assert_eq!(
+            IntervalCoefficients::new(2.0, 3.0, 0.5),
+            actual.decks()["espanol"].interval_coefficients
+        );
+        fs::remove_dir_all(notes_dir).ok();
+    }
+
+    #[test]
+    fn bulk_refuses_to_leave_a_card_in_no_deck() {
+        let notes_dir = make_notes_dir("vultan_retag_no_deck", &[("a.md", "old")]);
+        let storage = make_storage(&notes_dir);
+        let state = storage.load().unwrap();
+        let actual = bulk(state, &storage, &[], &[], &["old".to_string()]);
+        assert!(actual.is_err());
+        let note = fs::read_to_string(format!("{}/a.md", notes_dir)).unwrap();
+        assert!(note.starts_with("tags: old\n"));
+        fs::remove_dir_all(notes_dir).ok();
+    }
+}
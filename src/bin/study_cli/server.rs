@@ -0,0 +1,463 @@
+use crate::commands::all_deck_stats;
+use crate::repl::parse_grade;
+use crate::storage::Storage;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::time::{Duration, Instant};
+use tiny_http::{Header, Method, Response};
+use vultan::state::card::{occlusion, Card, Score};
+use vultan::state::hand::{DealError, Session};
+use vultan::state::link_index;
+use vultan::state::State;
+
+const INDEX: &str = include_str!("server/index.html");
+
+#[derive(Debug, PartialEq)]
+pub struct Reply {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Reply {
+    fn json(status: u16, value: &impl Serialize) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: serde_json::to_string(value).unwrap_or_default(),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self::json(status, &ErrorBody { error: message })
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'e> {
+    error: &'e str,
+}
+
+#[derive(Serialize)]
+struct CardBody<'c> {
+    path: &'c str,
+    question: String,
+    answer: String,
+}
+
+#[derive(Serialize)]
+struct NextBody<'n> {
+    deck: &'n str,
+    remaining: usize,
+    card: Option<CardBody<'n>>,
+    waiting_until: Option<DateTime<Utc>>,
+    // The labels of the grades to offer, answered with their positions from 1.
+    grades: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct AnswerBody {
+    score: u8,
+}
+
+pub fn serve(state: State, address: &str, storage: &Storage) -> Result<(), Box<dyn Error>> {
+    let server = tiny_http::Server::http(address)
+        .map_err(|e| format!("Unable to serve on {} -> {}", address, e))?;
+    println!("Serving reviews on http://{}", address);
+    let mut reviews = Reviews::new(state, storage);
+    for mut request in server.incoming_requests() {
+        let mut body = String::new();
+        request.as_reader().read_to_string(&mut body).ok();
+        let reply = reviews.handle(request.method(), request.url(), &body);
+        let content_type = Header::from_bytes("Content-Type", reply.content_type)
+            .expect("Content-Type header is valid");
+        let response = Response::from_string(reply.body)
+            .with_status_code(reply.status)
+            .with_header(content_type);
+        request.respond(response).ok();
+    }
+    Ok(())
+}
+
+// Deals and lists decks from the state each review saves, so that reviewed cards aren't dealt
+// again on coming back to their deck.
+pub struct Reviews<'r> {
+    state: State,
+    storage: &'r Storage,
+    session: Option<(String, Session<'static>)>,
+    // When the card awaiting an answer was first served, to time the answer.
+    shown_at: Option<Instant>,
+}
+
+impl<'r> Reviews<'r> {
+    pub fn new(state: State, storage: &'r Storage) -> Self {
+        Self {
+            state,
+            storage,
+            session: None,
+            shown_at: None,
+        }
+    }
+
+    pub fn handle(&mut self, method: &Method, url: &str, body: &str) -> Reply {
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        match (method, path) {
+            (Method::Get, "/") => Reply {
+                status: 200,
+                content_type: "text/html; charset=utf-8",
+                body: INDEX.to_string(),
+            },
+            (Method::Get, "/decks") => match all_deck_stats(&self.state) {
+                Ok(stats) => Reply::json(200, &stats),
+                Err(error) => Reply::error(500, &error.to_string()),
+            },
+            (Method::Get, "/streak") => match self.storage.streak() {
+                Ok(streak) => Reply::json(200, &streak),
+                Err(error) => Reply::error(500, &error.to_string()),
+            },
+            (Method::Get, "/session/next") => self.next(query_value(query, "deck").as_deref()),
+            (Method::Post, "/session/answer") => self.answer(body),
+            _ => Reply::error(404, "Not found"),
+        }
+    }
+
+    fn next(&mut self, deck_name: Option<&str>) -> Reply {
+        let is_current =
+            |name: &str| matches!(&self.session, Some((current, _)) if current == name);
+        match deck_name {
+            Some(name) if !is_current(name) => match self.state.deal(name) {
+                Ok(hand) => {
+                    self.session = Some((name.to_string(), Session::from(hand).into_owned()));
+                    self.shown_at = None;
+                }
+                Err(DealError::NoDueCards { .. }) => {
+                    self.session = None;
+                    return Reply::json(
+                        200,
+                        &NextBody {
+                            deck: name,
+                            remaining: 0,
+                            card: None,
+                            waiting_until: None,
+                            grades: Vec::new(),
+                        },
+                    );
+                }
+                Err(error) => return Reply::error(404, &error.to_string()),
+            },
+            None if self.session.is_none() => {
+                return Reply::error(400, "No session: pass ?deck=<name> to start one")
+            }
+            _ => {}
+        }
+        self.current()
+    }
+
+    fn answer(&mut self, body: &str) -> Reply {
+        let answer = match serde_json::from_str::<AnswerBody>(body) {
+            Ok(answer) => answer,
+            Err(error) => return Reply::error(400, &error.to_string()),
+        };
+        let Some((_, session)) = self.session.as_mut() else {
+            return Reply::error(409, "No session in progress");
+        };
+        let grades = session.grading().grades();
+        let Some(grade) = parse_grade(&answer.score.to_string(), &grades) else {
+            let message = format!("Score must be 1 to {}", grades.len());
+            return Reply::error(400, &message);
+        };
+        if session.waiting_until().is_some() {
+            return Reply::error(409, "No card awaiting an answer");
+        }
+        let answer_time = self.shown_at.take().map(|shown_at| shown_at.elapsed());
+        let revised = match answer_time {
+            Some(answer_time) => session.submit_timed_grade(grade, answer_time),
+            None => session.submit_grade(grade),
+        };
+        match revised.cloned() {
+            Some(card) => self.persist(card, grade.score, answer_time),
+            None => return Reply::error(409, "No card awaiting an answer"),
+        }
+        self.current()
+    }
+
+    fn persist(&mut self, card: Card, score: Score, answer_time: Option<Duration>) {
+        let state = std::mem::take(&mut self.state);
+        self.state = self.storage.record(state, &card, score, answer_time);
+    }
+
+    fn current(&mut self) -> Reply {
+        let awaiting_answer = matches!(
+            &self.session,
+            Some((_, session)) if session.next_card().is_some() && session.waiting_until().is_none()
+        );
+        if !awaiting_answer {
+            self.shown_at = None;
+        } else if self.shown_at.is_none() {
+            self.shown_at = Some(Instant::now());
+        }
+        match &self.session {
+            Some((deck, session)) => Reply::json(
+                200,
+                &NextBody {
+                    deck,
+                    remaining: session.remaining(),
+                    card: session
+                        .next_card()
+                        .filter(|_| session.waiting_until().is_none())
+                        .map(|card| CardBody {
+                            path: &card.path,
+                            question: link_index::render(&occlusion::question(&card.question)),
+                            answer: link_index::render(&occlusion::answer(
+                                &card.question,
+                                &card.answer,
+                            )),
+                        }),
+                    waiting_until: session.waiting_until(),
+                    grades: session
+                        .grading()
+                        .grades()
+                        .into_iter()
+                        .map(|grade| grade.label)
+                        .collect(),
+                },
+            ),
+            None => Reply::error(400, "No session in progress"),
+        }
+    }
+}
+
+fn query_value(query: &str, key: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(k, _)| *k == key)
+        .map(|(_, value)| percent_decode(value))
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok());
+        match (bytes[i], hex.and_then(|h| u8::from_str_radix(h, 16).ok())) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (b'+', _) => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use rstest::*;
+    use serde_json::Value;
+    use vultan::state::card::RevisionSettings;
+    use vultan::state::deck::DeckOptions;
+    use vultan::state::file::FileHandle;
+
+    fn make_state() -> State {
+        let card = Card::new(
+            "octopus.md".to_string(),
+            vec!["cephalopoda".to_string()],
+            "[[Octopus|its]] arms?".to_string(),
+            "eight".to_string(),
+            RevisionSettings::default(),
+        );
+        State::default().with_loaded_cards(vec![card])
+    }
+
+    fn make_temp_dir(name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.to_string_lossy().to_string()
+    }
+
+    fn body_of(reply: &Reply) -> Value {
+        serde_json::from_str(&reply.body).unwrap()
+    }
+
+    #[rstest]
+    #[case::plain("biology", "biology")]
+    #[case::encoded("cell%20biology", "cell biology")]
+    #[case::plus("cell+biology", "cell biology")]
+    #[case::truncated_escape("a%2", "a%2")]
+    fn percent_decode(#[case] value: &str, #[case] expected: &str) {
+        assert_eq!(expected, super::percent_decode(value));
+    }
+
+    #[test]
+    fn query_value() {
+        assert_eq!(
+            Some("b c".to_string()),
+            super::query_value("a=1&deck=b%20c", "deck")
+        );
+        assert_eq!(None, super::query_value("a=1", "deck"));
+    }
+
+    #[test]
+    fn decks() {
+        let state = make_state();
+        let dir = make_temp_dir("vultan_server_decks");
+        let storage = Storage::new("", &format!("{}/state.ron", dir), &dir, "test");
+        let mut reviews = Reviews::new(state, &storage);
+        let reply = reviews.handle(&Method::Get, "/decks", "");
+        assert_eq!(200, reply.status);
+        assert_eq!("cephalopoda", body_of(&reply)[0]["name"]);
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn next_without_session() {
+        let state = make_state();
+        let dir = make_temp_dir("vultan_server_next_without");
+        let storage = Storage::new("", &format!("{}/state.ron", dir), &dir, "test");
+        let mut reviews = Reviews::new(state, &storage);
+        assert_eq!(
+            400,
+            reviews.handle(&Method::Get, "/session/next", "").status
+        );
+        let reply = reviews.handle(&Method::Get, "/session/next?deck=nope", "");
+        assert_eq!(404, reply.status);
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn next_starts_a_session() {
+        let state = make_state();
+        let dir = make_temp_dir("vultan_server_next");
+        let storage = Storage::new("", &format!("{}/state.ron", dir), &dir, "test");
+        let mut reviews = Reviews::new(state, &storage);
+        let reply = reviews.handle(&Method::Get, "/session/next?deck=cephalopoda", "");
+        let body = body_of(&reply);
+        assert_eq!(200, reply.status);
+        assert_eq!(1, body["remaining"]);
+        assert_eq!("its arms?", body["card"]["question"]);
+        assert_eq!("eight", body["card"]["answer"]);
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn answer_without_session() {
+        let state = make_state();
+        let dir = make_temp_dir("vultan_server_answer_without");
+        let storage = Storage::new("", &format!("{}/state.ron", dir), &dir, "test");
+        let mut reviews = Reviews::new(state, &storage);
+        let reply = reviews.handle(&Method::Post, "/session/answer", "{\"score\": 3}");
+        assert_eq!(409, reply.status);
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[rstest]
+    #[case::out_of_range("{\"score\": 5}")]
+    #[case::malformed("score=3")]
+    fn answer_with_invalid_score(#[case] body: &str) {
+        let state = make_state();
+        let dir = make_temp_dir(&format!("vultan_server_invalid_score_{}", body.len()));
+        let storage = Storage::new("", &format!("{}/state.ron", dir), &dir, "test");
+        let mut reviews = Reviews::new(state, &storage);
+        reviews.handle(&Method::Get, "/session/next?deck=cephalopoda", "");
+        let reply = reviews.handle(&Method::Post, "/session/answer", body);
+        assert_eq!(400, reply.status);
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn answer_persists_and_advances() {
+        let state = make_state();
+        let dir = make_temp_dir("vultan_server_answer");
+        let path = format!("{}/state.ron", dir);
+        let storage = Storage::new("", &path, &dir, "test");
+        let mut reviews = Reviews::new(state, &storage);
+        reviews.handle(&Method::Get, "/session/next?deck=cephalopoda", "");
+        let reply = reviews.handle(&Method::Post, "/session/answer", "{\"score\": 4}");
+        let body = body_of(&reply);
+        assert_eq!(200, reply.status);
+        assert_eq!(0, body["remaining"]);
+        assert!(body["card"].is_null());
+        let written = State::read(FileHandle::from(path.clone())).unwrap();
+        assert!(written.cards()["octopus.md"].revision_settings.interval > 0.0);
+        assert!(storage.read_logs().unwrap()[0].answer_time.is_some());
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn answered_cards_are_not_dealt_again_after_switching_decks() {
+        let squid = Card::new(
+            "squid.md".to_string(),
+            vec!["decapodiformes".to_string()],
+            "arms?".to_string(),
+            "ten".to_string(),
+            RevisionSettings::default(),
+        );
+        let state = make_state().with_loaded_cards(vec![squid]);
+        let dir = make_temp_dir("vultan_server_switch");
+        let storage = Storage::new("", &format!("{}/state.ron", dir), &dir, "test");
+        let mut reviews = Reviews::new(state, &storage);
+        reviews.handle(&Method::Get, "/session/next?deck=cephalopoda", "");
+        reviews.handle(&Method::Post, "/session/answer", "{\"score\": 4}");
+        reviews.handle(&Method::Get, "/session/next?deck=decapodiformes", "");
+        let reply = reviews.handle(&Method::Get, "/session/next?deck=cephalopoda", "");
+        let body = body_of(&reply);
+        assert_eq!(0, body["remaining"]);
+        assert!(body["card"].is_null());
+        let reply = reviews.handle(&Method::Get, "/decks", "");
+        let decks = body_of(&reply);
+        let cephalopoda = decks
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|deck| deck["name"] == "cephalopoda")
+            .unwrap();
+        assert_eq!(0, cephalopoda["due"]);
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn answer_waits_out_the_relearn_delay_of_failed_cards() {
+        let state = make_state();
+        let deck = state.decks()["cephalopoda"]
+            .clone()
+            .with_options(DeckOptions::default().with_relearn_delay_minutes(Some(10)));
+        let state = state.with_overriden_decks(vec![deck]);
+        let dir = make_temp_dir("vultan_server_relearn");
+        let storage = Storage::new("", &format!("{}/state.ron", dir), &dir, "test");
+        let mut reviews = Reviews::new(state, &storage);
+        reviews.handle(&Method::Get, "/session/next?deck=cephalopoda", "");
+        let reply = reviews.handle(&Method::Post, "/session/answer", "{\"score\": 1}");
+        let body = body_of(&reply);
+        assert_eq!(1, body["remaining"]);
+        assert!(body["card"].is_null());
+        assert!(body["waiting_until"].is_string());
+        let reply = reviews.handle(&Method::Post, "/session/answer", "{\"score\": 3}");
+        assert_eq!(409, reply.status);
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn unknown_route() {
+        let state = make_state();
+        let dir = make_temp_dir("vultan_server_unknown_route");
+        let storage = Storage::new("", &format!("{}/state.ron", dir), &dir, "test");
+        let mut reviews = Reviews::new(state, &storage);
+        assert_eq!(404, reviews.handle(&Method::Get, "/nope", "").status);
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
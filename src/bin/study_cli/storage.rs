@@ -0,0 +1,1235 @@
+use crate::progress::ProgressBar;
+use std::cell::{Cell, RefCell};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use vultan::state::audit::{self, AuditEntry};
+use vultan::state::card::cache::{CachingParser, CardCache};
+use vultan::state::card::parser::registry::ParserRegistry;
+use vultan::state::card::parser::ScheduleLocation;
+use vultan::state::card::{
+    frontmatter, sidecar, try_load_many, try_load_many_with_progress, Card, LoadFailure,
+    LoadedCards, Score,
+};
+use vultan::state::codec::StateFormat;
+use vultan::state::config::Config;
+use vultan::state::deck::Deck;
+use vultan::state::file::{
+    is_encrypted, CompressedFileHandle, Compression, Discovery, EncryptedFileHandle, FileHandle,
+    IgnoreRules, IO,
+};
+use vultan::state::hand::Checkpoint;
+use vultan::state::id::CardId;
+use vultan::state::link_index::LinkIndex;
+use vultan::state::review_log::{self, ReviewEvent};
+use vultan::state::shards::shard_paths;
+use vultan::state::streak::Streak;
+use vultan::state::State;
+
+const LOG_EXTENSION: &str = "log";
+const LOAD_FAILURES_FILE_PATH: &str = ".vultan/parse_errors.log";
+const ARCHIVE_FILE_PATH: &str = ".vultan/archived_cards.ron";
+const SESSION_FILE_PATH: &str = ".vultan/session.ron";
+const STREAK_FILE_NAME: &str = "streak.ron";
+const AUDIT_LOG_PATH: &str = ".vultan/audit.log";
+const CARD_CACHE_PATH: &str = ".vultan/cache/cards.bin";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum ConflictPolicy {
+    /// Re-read the state file and re-apply this session's changes to it
+    #[default]
+    Merge,
+    /// Replace the state file with this session's state
+    Overwrite,
+    /// Refuse to write the state file
+    Abort,
+}
+
+// A change a command made to the state, kept so that it can be made again on a state file another
+// process has written since.
+#[derive(Clone, Debug)]
+pub enum Change {
+    Cards(Vec<Card>),
+    Decks(Vec<Deck>),
+    // Cards new to the state, as imported.
+    Loaded(Vec<Card>),
+    // Cards moved between decks.
+    Redecked(Vec<Card>),
+    // A deck renamed with its sub-decks, and the cards moved with it.
+    RenamedDeck {
+        old_name: String,
+        new_name: String,
+        cards: Vec<Card>,
+    },
+    Pruned(Vec<CardId>),
+    Repaired,
+}
+
+impl Change {
+    fn apply(&self, state: State) -> State {
+        match self {
+            Change::Cards(cards) => state.with_overriden_cards(cards.clone()),
+            Change::Decks(decks) => state.with_overriden_decks(decks.clone()),
+            Change::Loaded(cards) => state.with_loaded_cards(cards.clone()),
+            Change::Redecked(cards) => state.with_redecked_cards(cards.clone()),
+            Change::RenamedDeck {
+                old_name,
+                new_name,
+                cards,
+            } => state
+                .with_renamed_deck(old_name, new_name)
+                .with_redecked_cards(cards.clone()),
+            Change::Pruned(paths) => state.with_pruned_cards(paths),
+            Change::Repaired => state.with_repairs(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Storage {
+    notes_dir: String,
+    state_file_path: String,
+    log_dir: String,
+    device_name: String,
+    conflict_policy: ConflictPolicy,
+    checksum: Cell<Option<u64>>,
+    conflicted: Cell<bool>,
+    // Every change saved so far, in order.
+    changes: RefCell<Vec<Change>>,
+    link_index: RefCell<LinkIndex>,
+    load_failures: RefCell<Vec<LoadFailure>>,
+    session_seed: Cell<Option<u64>>,
+    passphrase: Option<String>,
+    compression: Option<Compression>,
+    format: Option<StateFormat>,
+    sharded: bool,
+    strict: Option<bool>,
+    config_path: Option<String>,
+    // The state as last read or written, to audit the next save against.
+    saved: RefCell<Option<State>>,
+    command_line: String,
+    show_progress: bool,
+}
+
+impl Storage {
+    pub fn new(notes_dir: &str, state_file_path: &str, log_dir: &str, device_name: &str) -> Self {
+        Self {
+            notes_dir: notes_dir.trim_end_matches('/').to_string(),
+            state_file_path: state_file_path.to_string(),
+            log_dir: log_dir.to_string(),
+            device_name: device_name.to_string(),
+            conflict_policy: ConflictPolicy::default(),
+            checksum: Cell::new(None),
+            conflicted: Cell::new(false),
+            changes: RefCell::new(Vec::new()),
+            link_index: RefCell::new(LinkIndex::default()),
+            load_failures: RefCell::new(Vec::new()),
+            session_seed: Cell::new(None),
+            passphrase: None,
+            compression: None,
+            format: None,
+            sharded: false,
+            strict: None,
+            config_path: None,
+            saved: RefCell::new(None),
+            command_line: String::new(),
+            show_progress: false,
+        }
+    }
+
+    pub fn with_conflict_policy(mut self, conflict_policy: ConflictPolicy) -> Self {
+        self.conflict_policy = conflict_policy;
+        self
+    }
+
+    // With a passphrase, the state file and the files kept beside it are encrypted from their next
+    // write on.
+    pub fn with_passphrase(mut self, passphrase: Option<String>) -> Self {
+        self.passphrase = passphrase;
+        self
+    }
+
+    // Only decides how the state file is written: compressed files are always recognised.
+    pub fn with_compression(mut self, compression: Option<Compression>) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    // Without a format, the state file's extension decides it.
+    pub fn with_format(mut self, format: Option<StateFormat>) -> Self {
+        self.format = format;
+        self
+    }
+
+    // Sharded, the state file only indexes the decks, and each deck's cards are kept in a file
+    // of their own in the "decks" directory beside it.
+    pub fn with_sharding(mut self, sharded: bool) -> Self {
+        self.sharded = sharded;
+        self
+    }
+
+    // Strict, a note failing to parse fails the load. Without a choice, the state file's
+    // strict_loading decides.
+    pub fn with_strict_loading(mut self, strict: Option<bool>) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    // Layered over the state file's settings whenever it's read, if there's a file at the path.
+    pub fn with_config_path(mut self, config_path: Option<String>) -> Self {
+        self.config_path = config_path;
+        self
+    }
+
+    // Logged with the mutations each save makes, to tell what made them.
+    pub fn with_command_line(mut self, command_line: String) -> Self {
+        self.command_line = command_line;
+        self
+    }
+
+    // Draws a progress bar on stderr while the notes load.
+    pub fn with_progress(mut self, show_progress: bool) -> Self {
+        self.show_progress = show_progress;
+        self
+    }
+
+    pub fn load(&self) -> Result<State, Box<dyn Error>> {
+        let state = self.read_state()?;
+        self.saved.replace(Some(state.clone()));
+        self.checksum.set(self.state_checksum());
+        let parser = ParserRegistry::parser_for(state.card_parsing_config().clone())?;
+        let config = state.card_parsing_config();
+        let ignore_rules = IgnoreRules::read(&self.notes_dir, config.use_gitignore)?;
+        let paths = Discovery::new(&config.note_globs)?
+            .with_ignore_rules(ignore_rules)
+            .with_follow_symlinks(config.follow_symlinks)
+            .with_deduplication(config.deduplicate_symlinked_notes)
+            .find_paths(&self.notes_dir)?;
+        let mut link_index = LinkIndex::default();
+        for path in paths.iter() {
+            link_index.insert(path);
+        }
+        self.link_index.replace(link_index);
+        let cache_path = Path::new(&self.notes_dir).join(CARD_CACHE_PATH);
+        let cache_file = self.private_file(&cache_path);
+        let parser = CachingParser::new(parser, CardCache::read(&cache_file, config));
+        let file_handles = paths.into_iter().map(FileHandle::from);
+        let loaded_cards: LoadedCards = match self.show_progress {
+            true => {
+                let mut bar = ProgressBar::new(io::stderr());
+                let loaded = try_load_many_with_progress(file_handles, &parser, |progress| {
+                    bar.update(progress)
+                })
+                .collect();
+                bar.finish();
+                loaded
+            }
+            false => try_load_many(file_handles, &parser).collect(),
+        };
+        // Without the cache, the next load only takes longer.
+        if let Some(dir) = cache_path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let _ = parser.into_cache().write(&cache_file);
+        self.write_load_failures(&loaded_cards.failed)?;
+        self.load_failures.replace(loaded_cards.failed.clone());
+        let strict = self
+            .strict
+            .unwrap_or(state.card_parsing_config().strict_loading);
+        let cards = match strict {
+            true => loaded_cards.strict()?,
+            false => loaded_cards.loaded,
+        };
+        let cards = match state.card_parsing_config().schedule_location {
+            ScheduleLocation::Sidecar => with_sidecar_schedules(cards)?,
+            _ => cards,
+        };
+        self.apply_logs(state.with_loaded_cards(cards))
+    }
+
+    pub fn link_index(&self) -> LinkIndex {
+        self.link_index.borrow().clone()
+    }
+
+    // The notes the last load couldn't read as cards.
+    pub fn load_failures(&self) -> Vec<LoadFailure> {
+        self.load_failures.borrow().clone()
+    }
+
+    // One JSON object per line, rewritten on every load and removed once every note loads.
+    fn write_load_failures(&self, failures: &[LoadFailure]) -> Result<(), Box<dyn Error>> {
+        let path = self.load_failures_path();
+        if failures.is_empty() {
+            if path.exists() {
+                fs::remove_file(&path)?;
+            }
+            return Ok(());
+        }
+        let mut content = String::new();
+        for failure in failures {
+            content.push_str(&serde_json::to_string(failure)?);
+            content.push('\n');
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        self.private_file(&path).write(content)?;
+        Ok(())
+    }
+
+    // Cards pruned for their notes having gone, kept with their schedules in case the notes
+    // come back. Later archives of a path replace earlier ones.
+    pub fn archive_cards(&self, cards: Vec<Card>) -> Result<(), Box<dyn Error>> {
+        let path = Path::new(&self.notes_dir).join(ARCHIVE_FILE_PATH);
+        let mut archived: Vec<Card> = match path.exists() {
+            true => ron::from_str(&self.private_file(&path).read()?)?,
+            false => Vec::new(),
+        };
+        archived.retain(|card| !cards.iter().any(|c| c.path == card.path));
+        archived.extend(cards);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let content = ron::ser::to_string_pretty(&archived, ron::ser::PrettyConfig::default())?;
+        self.private_file(&path).write(content)?;
+        Ok(())
+    }
+
+    // The checkpoint of the last session that didn't run to its end, if any.
+    pub fn checkpoint(&self) -> Result<Option<Checkpoint>, Box<dyn Error>> {
+        let path = Path::new(&self.notes_dir).join(SESSION_FILE_PATH);
+        match path.exists() {
+            true => Ok(Some(ron::from_str(&self.private_file(&path).read()?)?)),
+            false => Ok(None),
+        }
+    }
+
+    // With None, once a session has run to its end.
+    pub fn write_checkpoint(&self, checkpoint: Option<&Checkpoint>) -> Result<(), Box<dyn Error>> {
+        let path = Path::new(&self.notes_dir).join(SESSION_FILE_PATH);
+        let Some(checkpoint) = checkpoint else {
+            if path.exists() {
+                fs::remove_file(&path)?;
+            }
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        self.private_file(&path)
+            .write(ron::to_string(checkpoint)?)?;
+        Ok(())
+    }
+
+    fn load_failures_path(&self) -> PathBuf {
+        Path::new(&self.notes_dir).join(LOAD_FAILURES_FILE_PATH)
+    }
+
+    pub fn apply_logs(&self, state: State) -> Result<State, Box<dyn Error>> {
+        let events = self.read_logs_of(&state)?;
+        Ok(state.with_review_events(events))
+    }
+
+    // The logged reviews of the state's cards, with paths spelled as the state spells them.
+    pub fn read_logs_of(&self, state: &State) -> Result<Vec<ReviewEvent>, Box<dyn Error>> {
+        let card_paths: HashMap<&str, &str> = state
+            .cards()
+            .keys()
+            .map(|path| (self.relative_path(path), path.as_str()))
+            .collect();
+        Ok(self
+            .read_logs()?
+            .into_iter()
+            .filter_map(|mut event| {
+                event.card_path = card_paths.get(event.card_path.as_str())?.to_string();
+                Some(event)
+            })
+            .collect())
+    }
+
+    // For schedules changed outside of a review: written to the notes too when they're kept there,
+    // stopping at the first that can't be. Stamped, so that older logged reviews don't undo them.
+    pub fn reschedule(&self, persisted: State, cards: Vec<Card>) -> Result<State, Box<dyn Error>> {
+        let now = chrono::Utc::now();
+        let cards: Vec<Card> = cards
+            .into_iter()
+            .map(|card| card.with_rescheduled_at(Some(now)))
+            .collect();
+        let location = persisted.card_parsing_config().schedule_location;
+        for card in cards.iter() {
+            write_schedule(location, card)
+                .map_err(|e| format!("Unable to write the schedule of {} -> {}", card.path, e))?;
+        }
+        self.change(persisted, Change::Cards(cards))
+    }
+
+    // Saves the state with the change made, and keeps the change to make again should another
+    // process write the state file.
+    pub fn change(&self, state: State, change: Change) -> Result<State, Box<dyn Error>> {
+        let state = change.apply(state);
+        self.changes.borrow_mut().push(change);
+        self.save(state)
+    }
+
+    pub fn save(&self, state: State) -> Result<State, Box<dyn Error>> {
+        if !self.conflicted.get() && self.state_checksum() != self.checksum.get() {
+            self.conflicted.set(true);
+            if self.conflict_policy == ConflictPolicy::Merge {
+                eprintln!(
+                    "{} was modified by another process; merging this session's changes into it",
+                    self.state_file_path
+                );
+            }
+        }
+        let state = match (self.conflicted.get(), self.conflict_policy) {
+            (false, _) | (true, ConflictPolicy::Overwrite) => state,
+            (true, ConflictPolicy::Abort) => {
+                return Err(format!(
+                    "{} was modified by another process; not overwriting it",
+                    self.state_file_path
+                )
+                .into())
+            }
+            (true, ConflictPolicy::Merge) => self.merge_into_current_state()?,
+        };
+        match self.sharded {
+            true => state.write_sharded(&self.state_file_path)?,
+            false => state.write_with(&self.state_file(), self.format().codec().as_ref())?,
+        }
+        self.checksum.set(self.state_checksum());
+        self.audit(&state);
+        Ok(state)
+    }
+
+    // Failures are reported rather than returned, as the state has been saved by then.
+    fn audit(&self, state: &State) {
+        let Some(before) = self.saved.replace(Some(state.clone())) else {
+            return;
+        };
+        let at = chrono::Utc::now();
+        let entries: Vec<AuditEntry> = audit::mutations(&before, state)
+            .into_iter()
+            .map(|mutation| AuditEntry {
+                at,
+                command: self.command_line.clone(),
+                mutation,
+            })
+            .collect();
+        if entries.is_empty() {
+            return;
+        }
+        let path = Path::new(&self.notes_dir).join(AUDIT_LOG_PATH);
+        let appended = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .map_err(|e| e.to_string())
+            .and_then(|_| {
+                audit::append(self.private_file(&path), &entries).map_err(|e| e.to_string())
+            });
+        if let Err(error) = appended {
+            eprintln!("Unable to write the audit log -> {}", error);
+        }
+    }
+
+    // Every mutation saved so far, oldest first.
+    pub fn read_audit_log(&self) -> Result<Vec<AuditEntry>, Box<dyn Error>> {
+        let path = Path::new(&self.notes_dir).join(AUDIT_LOG_PATH);
+        match path.exists() {
+            true => Ok(audit::read(self.private_file(&path))?),
+            false => Ok(Vec::new()),
+        }
+    }
+
+    pub fn edit_deck(&self, state: State, deck: Deck) -> Result<State, Box<dyn Error>> {
+        self.change(state, Change::Decks(vec![deck]))
+    }
+
+    // Later reviews are logged with the seed their session was dealt with.
+    pub fn start_session(&self, seed: Option<u64>) {
+        self.session_seed.set(seed);
+    }
+
+    // Failures are reported rather than returned so that a review is never lost to
+    // a transient write error: the save at the end of the session retries the state.
+    pub fn record(
+        &self,
+        persisted: State,
+        card: &Card,
+        score: Score,
+        answer_time: Option<Duration>,
+    ) -> State {
+        self.changes
+            .borrow_mut()
+            .push(Change::Cards(vec![card.clone()]));
+        let location = persisted.card_parsing_config().schedule_location;
+        if let Err(error) = write_schedule(location, card) {
+            eprintln!("Unable to write the schedule of {} -> {}", card.path, error);
+        }
+        let persisted = persisted.with_overriden_cards(vec![card.clone()]);
+        let persisted = match self.save(persisted.clone()) {
+            Ok(saved) => saved,
+            Err(error) => {
+                eprintln!("Unable to save the state -> {}", error);
+                persisted
+            }
+        };
+        let mut event = ReviewEvent::new(card, score, &self.device_name, chrono::Utc::now())
+            .with_answer_time(answer_time)
+            .with_session_seed(self.session_seed.get());
+        event.card_path = self.relative_path(&card.path).to_string();
+        let appended = fs::create_dir_all(&self.log_dir)
+            .map_err(|e| e.to_string())
+            .and_then(|_| {
+                let file = self.private_file(Path::new(&self.own_log_path()));
+                review_log::append(file, &event).map_err(|e| e.to_string())
+            });
+        if let Err(error) = appended {
+            eprintln!("{}", error);
+        }
+        let today = chrono::Local::now().date_naive();
+        let streak = self
+            .streak()
+            .and_then(|streak| self.write_streak(&streak.with_review_on(today)));
+        if let Err(error) = streak {
+            eprintln!("Unable to update the study streak -> {}", error);
+        }
+        persisted
+    }
+
+    // Kept beside the review logs, as of today.
+    pub fn streak(&self) -> Result<Streak, Box<dyn Error>> {
+        let path = Path::new(&self.log_dir).join(STREAK_FILE_NAME);
+        let streak: Streak = match path.exists() {
+            true => ron::from_str(&self.private_file(&path).read()?)?,
+            false => Streak::default(),
+        };
+        Ok(streak.as_of(chrono::Local::now().date_naive()))
+    }
+
+    fn write_streak(&self, streak: &Streak) -> Result<(), Box<dyn Error>> {
+        let path = Path::new(&self.log_dir).join(STREAK_FILE_NAME);
+        self.private_file(&path).write(ron::to_string(streak)?)?;
+        Ok(())
+    }
+
+    // Once another process has written the state file, every state this session
+    // holds in memory is stale, so all later saves go through here.
+    fn merge_into_current_state(&self) -> Result<State, Box<dyn Error>> {
+        let merged = self
+            .changes
+            .borrow()
+            .iter()
+            .fold(self.read_state()?, |state, change| change.apply(state));
+        self.apply_logs(merged)
+    }
+
+    fn read_state(&self) -> Result<State, Box<dyn Error>> {
+        let state = self.read_stored_state()?;
+        let config = match &self.config_path {
+            Some(path) => Config::read_if_present(path)?.map(|config| (config, path)),
+            None => None,
+        };
+        match config {
+            Some((config, path)) => Ok(state.with_config(config, path)?),
+            None => Ok(state),
+        }
+    }
+
+    fn read_stored_state(&self) -> Result<State, Box<dyn Error>> {
+        if !Path::new(&self.state_file_path).exists() {
+            return Ok(State::default());
+        }
+        if self.sharded {
+            return Ok(State::read_sharded(&self.state_file_path)?);
+        }
+        let encrypted = is_encrypted(&String::from_utf8_lossy(&fs::read(&self.state_file_path)?));
+        if encrypted && self.passphrase.is_none() {
+            return Err(format!(
+                "{} is encrypted; give its passphrase with --passphrase-env or --passphrase-command",
+                self.state_file_path
+            )
+            .into());
+        }
+        Ok(State::read_with(
+            &self.state_file(),
+            self.format().codec().as_ref(),
+        )?)
+    }
+
+    fn state_checksum(&self) -> Option<u64> {
+        let mut paths = vec![PathBuf::from(&self.state_file_path)];
+        if self.sharded {
+            paths.extend(shard_paths(&self.state_file_path).ok()?);
+        }
+        checksum(&paths)
+    }
+
+    fn format(&self) -> StateFormat {
+        self.format
+            .unwrap_or_else(|| StateFormat::from_path(&self.state_file_path))
+    }
+
+    // Compressed, then encrypted.
+    fn state_file(&self) -> Box<dyn IO> {
+        let inner = self.private_file(Path::new(&self.state_file_path));
+        Box::new(CompressedFileHandle::new(inner, self.compression))
+    }
+
+    // Everything written beside the state file, such as the review logs, the card cache and the
+    // audit log, is encrypted along with it.
+    fn private_file(&self, path: &Path) -> Box<dyn IO> {
+        let file_handle = FileHandle::from(path.to_string_lossy().to_string());
+        match &self.passphrase {
+            Some(passphrase) => Box::new(EncryptedFileHandle::new(file_handle, passphrase)),
+            None => Box::new(file_handle),
+        }
+    }
+
+    pub fn read_logs(&self) -> Result<Vec<ReviewEvent>, Box<dyn Error>> {
+        let mut events = Vec::new();
+        for name in log_names(&self.log_dir)?.into_iter() {
+            let path = Path::new(&self.log_dir).join(name);
+            events.extend(review_log::read(self.private_file(&path))?);
+        }
+        Ok(events)
+    }
+
+    // Logs are append-only and written by a single device, so the longer copy of
+    // a log is always the more recent one.
+    pub fn sync(&self, remote_dir: &str) -> Result<usize, Box<dyn Error>> {
+        fs::create_dir_all(&self.log_dir)?;
+        fs::create_dir_all(remote_dir)?;
+        let mut names = log_names(&self.log_dir)?;
+        names.extend(log_names(remote_dir)?);
+        let mut copied = 0;
+        for name in names.iter() {
+            let local = Path::new(&self.log_dir).join(name);
+            let remote = Path::new(remote_dir).join(name);
+            let (local_len, remote_len) = (file_len(&local), file_len(&remote));
+            if local_len > remote_len {
+                fs::copy(&local, &remote)?;
+                copied += 1;
+            } else if remote_len > local_len {
+                fs::copy(&remote, &local)?;
+                copied += 1;
+            }
+        }
+        Ok(copied)
+    }
+
+    fn own_log_path(&self) -> String {
+        format!("{}/{}.{}", self.log_dir, self.device_name, LOG_EXTENSION)
+    }
+
+    pub fn note_path(&self, relative_path: &str) -> String {
+        format!("{}{}", self.notes_prefix(), relative_path)
+    }
+
+    fn relative_path<'p>(&self, card_path: &'p str) -> &'p str {
+        card_path
+            .strip_prefix(&self.notes_prefix())
+            .unwrap_or(card_path)
+    }
+
+    // Matches how glob spells the paths it finds: "./notes" yields "notes/a.md" and "." yields "a.md".
+    pub fn notes_prefix(&self) -> String {
+        match self.notes_dir.trim_start_matches("./") {
+            "." => String::new(),
+            dir => format!("{}/", dir),
+        }
+    }
+}
+
+fn write_schedule(location: ScheduleLocation, card: &Card) -> Result<(), Box<dyn Error>> {
+    match location {
+        ScheduleLocation::StateFile => Ok(()),
+        ScheduleLocation::Frontmatter => write_frontmatter_schedule(card),
+        ScheduleLocation::Sidecar => {
+            sidecar::write(&card.path, &card.revision_settings).map_err(|e| e.into())
+        }
+    }
+}
+
+fn write_frontmatter_schedule(card: &Card) -> Result<(), Box<dyn Error>> {
+    let note = FileHandle::from(card.path.to_string());
+    let content = note.read()?;
+    note.write(frontmatter::write_schedule(
+        &content,
+        &card.revision_settings,
+    ))?;
+    Ok(())
+}
+
+fn with_sidecar_schedules(cards: Vec<Card>) -> Result<Vec<Card>, Box<dyn Error>> {
+    cards
+        .into_iter()
+        .map(|card| match sidecar::read(&card.path)? {
+            Some(revision_settings) => Ok(card.with_note_schedule(revision_settings)),
+            None => Ok(card),
+        })
+        .collect()
+}
+
+fn log_names(dir: &str) -> Result<BTreeSet<String>, Box<dyn Error>> {
+    if !Path::new(dir).exists() {
+        return Ok(BTreeSet::new());
+    }
+    Ok(fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map_or(false, |e| e == LOG_EXTENSION))
+        .filter_map(|path| path.file_name().map(|n| n.to_string_lossy().to_string()))
+        .collect())
+}
+
+fn checksum(paths: &[PathBuf]) -> Option<u64> {
+    let mut hasher = DefaultHasher::new();
+    for path in paths {
+        fs::read(path).ok()?.hash(&mut hasher);
+    }
+    Some(hasher.finish())
+}
+
+fn file_len(path: &Path) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use rstest::*;
+    use std::path::PathBuf;
+    use vultan::state::card::parser::ParsingConfig;
+    use vultan::state::card::RevisionSettings;
+    use vultan::state::deck::IntervalCoefficients;
+    use vultan::state::hand::Session;
+
+    fn make_temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn make_card(path: &str) -> Card {
+        Card::new(
+            path.to_string(),
+            vec!["deck".to_string()],
+            "q".to_string(),
+            "a".to_string(),
+            RevisionSettings::default(),
+        )
+    }
+
+    #[test]
+    fn relative_path() {
+        let storage = Storage::new("notes/", "state", "logs", "laptop");
+        assert_eq!("bio/a.md", storage.relative_path("notes/bio/a.md"));
+        assert_eq!("elsewhere/a.md", storage.relative_path("elsewhere/a.md"));
+        let storage = Storage::new("./notes", "state", "logs", "laptop");
+        assert_eq!("bio/a.md", storage.relative_path("notes/bio/a.md"));
+    }
+
+    #[test]
+    fn note_path_is_spelled_like_the_paths_glob_finds() {
+        let dir = make_temp_dir("vultan_storage_note_path");
+        fs::create_dir_all(dir.join("bio")).unwrap();
+        fs::write(dir.join("bio/a.md"), "").unwrap();
+        let notes_dir = format!("{}/", dir.to_string_lossy());
+        let storage = Storage::new(&notes_dir, "state", "logs", "laptop");
+        assert_eq!(
+            Discovery::new(&ParsingConfig::default().note_globs)
+                .unwrap()
+                .find_paths(&notes_dir)
+                .unwrap(),
+            vec![storage.note_path("bio/a.md")]
+        );
+        assert_eq!("a.md", Storage::new(".", "s", "l", "d").note_path("a.md"));
+        assert_eq!(
+            "n/a.md",
+            Storage::new("./n", "s", "l", "d").note_path("a.md")
+        );
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn load_reports_the_notes_that_fail_to_parse() {
+        let dir = make_temp_dir("vultan_storage_load_failures");
+        let notes_dir = dir.to_string_lossy().to_string();
+        fs::write(dir.join("a.md"), "no card here").unwrap();
+        let storage = Storage::new(
+            &notes_dir,
+            &format!("{}/.vultan.ron", notes_dir),
+            &format!("{}/logs", notes_dir),
+            "laptop",
+        );
+        storage.load().unwrap();
+        let failures = storage.load_failures();
+        assert_eq!(1, failures.len());
+        assert_eq!(format!("{}/a.md", notes_dir), failures[0].path);
+        let report = fs::read_to_string(dir.join(LOAD_FAILURES_FILE_PATH)).unwrap();
+        assert_eq!(
+            failures[0],
+            serde_json::from_str::<LoadFailure>(report.trim_end()).unwrap()
+        );
+        let storage = storage.with_strict_loading(Some(true));
+        assert!(storage
+            .load()
+            .unwrap_err()
+            .to_string()
+            .starts_with("1 note(s) couldn't be read as cards"));
+        fs::remove_file(dir.join("a.md")).unwrap();
+        storage.load().unwrap();
+        assert!(storage.load_failures().is_empty());
+        assert!(!dir.join(LOAD_FAILURES_FILE_PATH).exists());
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn load_layers_the_config_over_the_state_file() {
+        let dir = make_temp_dir("vultan_storage_config");
+        let notes_dir = dir.to_string_lossy().to_string();
+        fs::write(dir.join("a.md"), "no card here").unwrap();
+        let config_path = dir.join("vultan.toml").to_string_lossy().to_string();
+        fs::write(&config_path, "[parsing]\nstrict_loading = true\n").unwrap();
+        let storage = Storage::new(
+            &notes_dir,
+            &format!("{}/.vultan.ron", notes_dir),
+            &format!("{}/logs", notes_dir),
+            "laptop",
+        );
+        storage.save(State::default()).unwrap();
+        assert!(storage.load().is_ok());
+        let storage = storage.with_config_path(Some(config_path.clone()));
+        assert!(storage.load().is_err());
+        fs::remove_file(dir.join("a.md")).unwrap();
+        storage.save(storage.load().unwrap()).unwrap();
+        let saved = fs::read_to_string(dir.join(".vultan.ron")).unwrap();
+        assert!(saved.contains("strict_loading: false"));
+        fs::write(&config_path, "[parsing]\nstrict_loading = \"yes\"\n").unwrap();
+        assert!(storage
+            .load()
+            .unwrap_err()
+            .to_string()
+            .starts_with("Unable to parse config from"));
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn archive_cards_keeps_the_latest_of_each_path() {
+        let dir = make_temp_dir("vultan_storage_archive");
+        let notes_dir = dir.to_string_lossy().to_string();
+        let storage = Storage::new(&notes_dir, "state", "logs", "laptop");
+        storage
+            .archive_cards(vec![make_card("a"), make_card("b")])
+            .unwrap();
+        let revised = make_card_with_interval("a", 4.0);
+        storage.archive_cards(vec![revised.clone()]).unwrap();
+        let archived: Vec<Card> =
+            ron::from_str(&fs::read_to_string(dir.join(ARCHIVE_FILE_PATH)).unwrap()).unwrap();
+        let actual: Vec<(&str, f64)> = archived
+            .iter()
+            .map(|card| (card.path.as_str(), card.revision_settings.interval))
+            .collect();
+        assert_eq!(vec![("b", 0.0), ("a", 4.0)], actual);
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn checkpoints_are_written_until_cleared() {
+        let dir = make_temp_dir("vultan_storage_checkpoint");
+        let notes_dir = dir.to_string_lossy().to_string();
+        let storage = Storage::new(&notes_dir, "state", "logs", "laptop");
+        assert_eq!(None, storage.checkpoint().unwrap());
+        let checkpoint = Checkpoint {
+            deck_name: "bio".to_string(),
+            seed: Some(7),
+            queue: vec!["a".to_string()],
+            revised: vec!["b".to_string()],
+            skipped: 1,
+        };
+        storage.write_checkpoint(Some(&checkpoint)).unwrap();
+        assert_eq!(Some(checkpoint), storage.checkpoint().unwrap());
+        storage.write_checkpoint(None).unwrap();
+        assert_eq!(None, storage.checkpoint().unwrap());
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[rstest]
+    #[case::frontmatter(ScheduleLocation::Frontmatter, "a.md", "interval: 4\n")]
+    #[case::sidecar(ScheduleLocation::Sidecar, "a.md.vultan", "interval: 4.0,")]
+    fn record_writes_schedules_with_the_notes_they_are_read_back_from(
+        #[case] schedule_location: ScheduleLocation,
+        #[case] written_file: &str,
+        #[case] expected_line: &str,
+    ) {
+        let dir = make_temp_dir(&format!("vultan_storage_{}", written_file));
+        let notes_dir = dir.to_string_lossy().to_string();
+        let note = "---\ntags: bio\n---\n# Question\nq\n# Answer\na\n----\n";
+        fs::write(dir.join("a.md"), note).unwrap();
+        let storage = Storage::new(
+            &notes_dir,
+            &format!("{}/.vultan.ron", notes_dir),
+            &format!("{}/logs", notes_dir),
+            "laptop",
+        );
+        let config = ParsingConfig {
+            schedule_location,
+            ..ParsingConfig::default()
+        };
+        storage
+            .save(State::default().with_card_parsing_config(config))
+            .unwrap();
+        let state = storage.load().unwrap();
+        let path = format!("{}/a.md", notes_dir);
+        let mut card = state.cards()[&path].clone();
+        card.revision_settings.interval = 4.0;
+        let state = storage.record(state, &card, Score::Pass, None);
+        let written = fs::read_to_string(dir.join(written_file)).unwrap();
+        assert!(written.contains(expected_line));
+        // The note's schedule wins over the state file's.
+        card.revision_settings.interval = 9.0;
+        storage
+            .save(state.with_overriden_cards(vec![card]))
+            .unwrap();
+        let actual = storage.load().unwrap();
+        assert_eq!(4.0, actual.cards()[&path].revision_settings.interval);
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn reschedule_survives_the_logged_reviews_on_load() {
+        let dir = make_temp_dir("vultan_storage_reschedule");
+        let notes_dir = dir.to_string_lossy().to_string();
+        fs::write(
+            dir.join("a.md"),
+            "tags: bio\n# Question\nq\n# Answer\na\n----\n",
+        )
+        .unwrap();
+        let storage = Storage::new(
+            &notes_dir,
+            &format!("{}/.vultan.ron", notes_dir),
+            &format!("{}/logs", notes_dir),
+            "laptop",
+        );
+        storage.save(State::default()).unwrap();
+        let state = storage.load().unwrap();
+        let path = format!("{}/a.md", notes_dir);
+        let mut card = state.cards()[&path].clone();
+        card.revision_settings.interval = 4.0;
+        let state = storage.record(state, &card, Score::Pass, None);
+        let due = chrono::Utc::now() + chrono::Duration::days(30);
+        card.revision_settings.due = due;
+        storage.reschedule(state, vec![card]).unwrap();
+        let actual = storage.load().unwrap();
+        assert_eq!(due, actual.cards()[&path].revision_settings.due);
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn due_date_set_in_a_session_survives_its_review_on_load() {
+        let dir = make_temp_dir("vultan_storage_session_set_due");
+        let notes_dir = dir.to_string_lossy().to_string();
+        fs::write(
+            dir.join("a.md"),
+            "tags: bio\n# Question\nq\n# Answer\na\n----\n",
+        )
+        .unwrap();
+        let storage = Storage::new(
+            &notes_dir,
+            &format!("{}/.vultan.ron", notes_dir),
+            &format!("{}/logs", notes_dir),
+            "laptop",
+        );
+        storage.save(State::default()).unwrap();
+        let state = storage.load().unwrap();
+        let path = format!("{}/a.md", notes_dir);
+        let card = state.cards()[&path].clone();
+        let persisted = storage.record(state.clone(), &card, Score::Pass, None);
+        // As the study command does: the card is rescheduled as soon as its due date is set, and
+        // the session's revised cards are saved once it ends.
+        let mut session = Session::from(state.deal("bio").unwrap());
+        let due = chrono::Utc::now() + chrono::Duration::days(30);
+        let rescheduled = session.set_due(&card, due).clone();
+        storage.reschedule(persisted, vec![rescheduled]).unwrap();
+        let revised = session.finish();
+        storage
+            .change(state.clone(), Change::Cards(revised))
+            .unwrap();
+        let actual = storage.load().unwrap();
+        assert_eq!(due, actual.cards()[&path].revision_settings.due);
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn record_appends_events_relative_to_the_notes_dir() {
+        let dir = make_temp_dir("vultan_storage_record");
+        let dir = dir.to_string_lossy().to_string();
+        let storage = Storage::new(
+            "notes",
+            &format!("{}/state.ron", dir),
+            &format!("{}/logs", dir),
+            "laptop",
+        );
+        let mut card = make_card("notes/bio/a.md");
+        let state = State::default().with_loaded_cards(vec![card.clone()]);
+        card.revision_settings.interval = 4.0;
+        storage.start_session(Some(7));
+        let persisted = storage.record(state, &card, Score::Easy, Some(Duration::from_secs(3)));
+        assert_eq!(
+            4.0,
+            persisted.cards()["notes/bio/a.md"]
+                .revision_settings
+                .interval
+        );
+        let log = fs::read_to_string(format!("{}/logs/laptop.log", dir)).unwrap();
+        assert!(log.contains("card_path:\"bio/a.md\""));
+        let events = storage.read_logs().unwrap();
+        assert_eq!(1, events.len());
+        assert_eq!(Score::Easy, events[0].score);
+        assert_eq!(Some(Duration::from_secs(3)), events[0].answer_time);
+        assert_eq!(Some(7), events[0].session_seed);
+        let streak = storage.streak().unwrap();
+        assert_eq!((1, 1), (streak.current, streak.best));
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn apply_logs_matches_events_to_cards_by_relative_path() {
+        let dir = make_temp_dir("vultan_storage_apply_logs");
+        let dir = dir.to_string_lossy().to_string();
+        let state_file_path = format!("{}/state.ron", dir);
+        let log_dir = format!("{}/logs", dir);
+        let phone = Storage::new("/phone/notes", &state_file_path, &log_dir, "phone");
+        let mut card = make_card("/phone/notes/bio/a.md");
+        card.revision_settings.interval = 4.0;
+        phone.record(State::default(), &card, Score::Easy, None);
+        let laptop = Storage::new(".", &state_file_path, &log_dir, "laptop");
+        let state =
+            State::default().with_loaded_cards(vec![make_card("bio/a.md"), make_card("b.md")]);
+        let actual = laptop.apply_logs(state).unwrap();
+        assert_eq!(4.0, actual.cards()["bio/a.md"].revision_settings.interval);
+        assert_eq!(0.0, actual.cards()["b.md"].revision_settings.interval);
+        fs::remove_dir_all(dir).ok();
+    }
+
+    fn make_card_with_interval(path: &str, interval: f64) -> Card {
+        let mut card = make_card(path);
+        card.revision_settings.interval = interval;
+        card
+    }
+
+    fn interval_on_disk(state_file_path: &str, card_path: &str) -> f64 {
+        let state = State::read(FileHandle::from(state_file_path.to_string())).unwrap();
+        state.cards()[card_path].revision_settings.interval
+    }
+
+    fn make_conflicting_storage(name: &str, conflict_policy: ConflictPolicy) -> (Storage, State) {
+        let dir = make_temp_dir(name).to_string_lossy().to_string();
+        let state_file_path = format!("{}/state.ron", dir);
+        let storage = Storage::new(&dir, &state_file_path, &format!("{}/logs", dir), "laptop")
+            .with_conflict_policy(conflict_policy);
+        let state = State::default().with_loaded_cards(vec![make_card("a"), make_card("b")]);
+        let state = storage.save(state).unwrap();
+        let external = state
+            .clone()
+            .with_overriden_cards(vec![make_card_with_interval("b", 9.0)]);
+        external
+            .write(FileHandle::from(state_file_path.clone()))
+            .unwrap();
+        (storage, state)
+    }
+
+    #[test]
+    fn save_merges_revised_cards_into_an_externally_modified_state() {
+        let (storage, state) =
+            make_conflicting_storage("vultan_storage_merge", ConflictPolicy::Merge);
+        let revised = make_card_with_interval("a", 4.0);
+        let persisted = storage.record(state.clone(), &revised, Score::Easy, None);
+        assert_eq!(9.0, persisted.cards()["b"].revision_settings.interval);
+        let state_file_path = storage.state_file_path.clone();
+        assert_eq!(4.0, interval_on_disk(&state_file_path, "a"));
+        assert_eq!(9.0, interval_on_disk(&state_file_path, "b"));
+        storage
+            .save(state.with_overriden_cards(vec![revised]))
+            .unwrap();
+        assert_eq!(9.0, interval_on_disk(&state_file_path, "b"));
+        fs::remove_dir_all(Path::new(&state_file_path).parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn save_merges_every_change_made_since_into_an_externally_modified_state() {
+        let (storage, state) =
+            make_conflicting_storage("vultan_storage_merge_changes", ConflictPolicy::Merge);
+        let rescheduled = make_card_with_interval("a", 4.0);
+        storage
+            .reschedule(state.clone(), vec![rescheduled])
+            .unwrap();
+        storage
+            .change(state.clone(), Change::Pruned(vec!["b".into()]))
+            .unwrap();
+        let deck = state.decks()["deck"]
+            .clone()
+            .with_interval_coefficients(IntervalCoefficients::new(2.0, 3.0, 4.0));
+        storage.edit_deck(state.clone(), deck.clone()).unwrap();
+        storage.save(state).unwrap();
+        let state_file_path = storage.state_file_path.clone();
+        let on_disk = State::read(FileHandle::from(state_file_path.clone())).unwrap();
+        assert_eq!(4.0, on_disk.cards()["a"].revision_settings.interval);
+        assert!(!on_disk.cards().contains_key("b"));
+        assert_eq!(deck, on_disk.decks()["deck"]);
+        fs::remove_dir_all(Path::new(&state_file_path).parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn save_overwrites_an_externally_modified_state_when_asked_to() {
+        let (storage, state) =
+            make_conflicting_storage("vultan_storage_overwrite", ConflictPolicy::Overwrite);
+        storage.save(state).unwrap();
+        let state_file_path = storage.state_file_path.clone();
+        assert_eq!(0.0, interval_on_disk(&state_file_path, "b"));
+        fs::remove_dir_all(Path::new(&state_file_path).parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn save_refuses_to_write_an_externally_modified_state_when_aborting() {
+        let (storage, state) =
+            make_conflicting_storage("vultan_storage_abort", ConflictPolicy::Abort);
+        let actual = storage.save(state).unwrap_err();
+        assert!(actual.to_string().contains("modified by another process"));
+        let state_file_path = storage.state_file_path.clone();
+        assert_eq!(9.0, interval_on_disk(&state_file_path, "b"));
+        fs::remove_dir_all(Path::new(&state_file_path).parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn save_encrypts_the_state_with_a_passphrase() {
+        let dir = make_temp_dir("vultan_storage_encrypted");
+        let notes_dir = dir.to_string_lossy().to_string();
+        let state_file_path = format!("{}/state.ron", notes_dir);
+        let storage = Storage::new(&notes_dir, &state_file_path, "logs", "laptop")
+            .with_passphrase(Some("hunter2".to_string()));
+        let state = State::default().with_loaded_cards(vec![make_card("a")]);
+        storage.save(state.clone()).unwrap();
+        assert!(is_encrypted(&fs::read_to_string(&state_file_path).unwrap()));
+        assert_eq!(state, storage.read_state().unwrap());
+        let storage = storage.with_passphrase(None);
+        let actual = storage.read_state().unwrap_err();
+        assert!(actual.to_string().contains("is encrypted"));
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn files_beside_the_state_are_encrypted_with_a_passphrase() {
+        let dir = make_temp_dir("vultan_storage_encrypted_beside");
+        let notes_dir = dir.to_string_lossy().to_string();
+        let storage = Storage::new(
+            &notes_dir,
+            &format!("{}/state.ron", notes_dir),
+            &format!("{}/logs", notes_dir),
+            "laptop",
+        )
+        .with_passphrase(Some("hunter2".to_string()));
+        let state = State::default().with_loaded_cards(vec![make_card("a")]);
+        let state = storage.save(state).unwrap();
+        let mut card = make_card("a");
+        card.revision_settings.interval = 4.0;
+        storage.record(state, &card, Score::Pass, None);
+        storage.archive_cards(vec![card]).unwrap();
+        for path in [
+            dir.join("logs/laptop.log"),
+            dir.join("logs").join(STREAK_FILE_NAME),
+            dir.join(AUDIT_LOG_PATH),
+            dir.join(ARCHIVE_FILE_PATH),
+        ] {
+            assert!(is_encrypted(&fs::read_to_string(path).unwrap()));
+        }
+        assert_eq!(1, storage.read_logs().unwrap().len());
+        assert!(!storage.read_audit_log().unwrap().is_empty());
+        assert_eq!(1, storage.streak().unwrap().current);
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn save_compresses_the_state() {
+        let dir = make_temp_dir("vultan_storage_compressed");
+        let notes_dir = dir.to_string_lossy().to_string();
+        let state_file_path = format!("{}/state.ron", notes_dir);
+        let storage = Storage::new(&notes_dir, &state_file_path, "logs", "laptop")
+            .with_compression(Some(Compression::Zstd));
+        let state = State::default().with_loaded_cards(vec![make_card("a")]);
+        storage.save(state.clone()).unwrap();
+        assert!(fs::read_to_string(&state_file_path).is_err());
+        let storage = storage.with_compression(None);
+        assert_eq!(state, storage.read_state().unwrap());
+        storage.save(state).unwrap();
+        assert!(fs::read_to_string(&state_file_path).is_ok());
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[rstest]
+    #[case::by_extension("state.yaml", None)]
+    #[case::by_setting("state", Some(StateFormat::Json))]
+    fn save_writes_the_state_in_its_format(
+        #[case] file_name: &str,
+        #[case] format: Option<StateFormat>,
+    ) {
+        let dir = make_temp_dir(&format!("vultan_storage_format_{}", file_name));
+        let notes_dir = dir.to_string_lossy().to_string();
+        let state_file_path = format!("{}/{}", notes_dir, file_name);
+        let storage =
+            Storage::new(&notes_dir, &state_file_path, "logs", "laptop").with_format(format);
+        let state = State::default().with_loaded_cards(vec![make_card("a")]);
+        storage.save(state.clone()).unwrap();
+        let content = fs::read_to_string(&state_file_path).unwrap();
+        let expected = storage.format().codec().decode(&content).unwrap();
+        assert_eq!(state, expected);
+        assert!(ron::from_str::<State>(&content).is_err());
+        assert_eq!(state, storage.read_state().unwrap());
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn save_shards_the_state_by_deck() {
+        let dir = make_temp_dir("vultan_storage_sharded");
+        let notes_dir = dir.to_string_lossy().to_string();
+        let state_file_path = format!("{}/.vultan/state.ron", notes_dir);
+        let storage =
+            Storage::new(&notes_dir, &state_file_path, "logs", "laptop").with_sharding(true);
+        let state = State::default().with_loaded_cards(vec![make_card("a")]);
+        storage.save(state.clone()).unwrap();
+        assert_eq!(1, shard_paths(&state_file_path).unwrap().len());
+        assert_eq!(state, storage.read_state().unwrap());
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn sync_exchanges_the_longer_copy_of_each_log() {
+        let dir = make_temp_dir("vultan_storage_sync");
+        let local = dir.join("local");
+        let remote = dir.join("remote");
+        fs::create_dir_all(&local).unwrap();
+        fs::create_dir_all(&remote).unwrap();
+        fs::write(local.join("laptop.log"), "a\nb\n").unwrap();
+        fs::write(remote.join("laptop.log"), "a\n").unwrap();
+        fs::write(remote.join("phone.log"), "c\n").unwrap();
+        fs::write(remote.join("notes.txt"), "ignored").unwrap();
+        let storage = Storage::new("notes", "state", &local.to_string_lossy(), "laptop");
+        let copied = storage.sync(&remote.to_string_lossy()).unwrap();
+        assert_eq!(2, copied);
+        assert_eq!(
+            "a\nb\n",
+            fs::read_to_string(remote.join("laptop.log")).unwrap()
+        );
+        assert_eq!("c\n", fs::read_to_string(local.join("phone.log")).unwrap());
+        assert!(!local.join("notes.txt").exists());
+        assert_eq!(0, storage.sync(&remote.to_string_lossy()).unwrap());
+        fs::remove_dir_all(dir).ok();
+    }
+}
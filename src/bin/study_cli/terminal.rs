@@ -0,0 +1,318 @@
+use crate::theme::Theme;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::io::{self, BufRead, StdinLock, Stdout, Write};
+use std::process::{Command, ExitStatus};
+use terminal_size::{terminal_size, Width};
+
+const CLEAR_SCREEN: &str = "\x1B[2J\x1B[1;1H";
+const RESTORE: &str = "\x1B[0m\x1B[?25h";
+// When the output isn't a terminal, or its size can't be found.
+const DEFAULT_WIDTH: usize = 80;
+const COLUMN_SEPARATOR: &str = " │ ";
+const MIN_COLUMN_WIDTH: usize = 10;
+
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        print!("{}", RESTORE);
+        io::stdout().flush().ok();
+        default_hook(info);
+    }));
+}
+
+pub struct Terminal<'t, R: BufRead, W: Write> {
+    input: R,
+    output: W,
+    theme: &'t Theme,
+    plain: bool,
+    width: Option<usize>,
+}
+
+impl<'t> Terminal<'t, StdinLock<'static>, Stdout> {
+    pub fn stdio(theme: &'t Theme) -> Self {
+        Self::new(io::stdin().lock(), io::stdout(), theme)
+    }
+}
+
+impl<'t, R: BufRead, W: Write> Terminal<'t, R, W> {
+    pub fn new(input: R, output: W, theme: &'t Theme) -> Self {
+        Self {
+            input,
+            output,
+            theme,
+            plain: false,
+            width: None,
+        }
+    }
+
+    pub fn with_plain_output(mut self, plain: bool) -> Self {
+        self.plain = plain;
+        self
+    }
+
+    pub fn section(&mut self, title: &str, content: &str) -> io::Result<()> {
+        if self.plain {
+            return write!(self.output, "\n{}\n\n{}\n\n", title, content);
+        }
+        let border = match self.theme.border_under(title) {
+            border if border.is_empty() => border,
+            border => format!("\n{}", self.theme.heading.paint(&border)),
+        };
+        write!(
+            self.output,
+            "{}{}{}\n\n{}\n\n",
+            CLEAR_SCREEN,
+            self.theme.heading.paint(title),
+            border,
+            self.theme.text.paint(content)
+        )
+    }
+
+    // Two sections side by side, each wrapped to half the width, e.g. to read a long question
+    // along with its answer.
+    pub fn split_section(&mut self, left: (&str, &str), right: (&str, &str)) -> io::Result<()> {
+        let width = (self
+            .width()
+            .saturating_sub(COLUMN_SEPARATOR.chars().count())
+            / 2)
+        .max(MIN_COLUMN_WIDTH);
+        let (left_lines, right_lines) = (wrap(left.1, width), wrap(right.1, width));
+        let rows = left_lines.len().max(right_lines.len());
+        let mut lines = Vec::with_capacity(rows);
+        for row in 0..rows {
+            let cell = |lines: &[String]| lines.get(row).cloned().unwrap_or_default();
+            let (left_cell, right_cell) = (cell(&left_lines), cell(&right_lines));
+            let line = match self.plain {
+                true => format!("{:<width$}{}{}", left_cell, COLUMN_SEPARATOR, right_cell),
+                false => format!(
+                    "{}{}{}",
+                    self.theme.text.paint(&format!("{:<width$}", left_cell)),
+                    COLUMN_SEPARATOR,
+                    self.theme.text.paint(&right_cell)
+                ),
+            };
+            lines.push(line.trim_end().to_string());
+        }
+        let titles = match self.plain {
+            true => format!("{:<width$}{}{}", left.0, COLUMN_SEPARATOR, right.0),
+            false => format!(
+                "{}{}{}{}",
+                CLEAR_SCREEN,
+                self.theme.heading.paint(&format!("{:<width$}", left.0)),
+                COLUMN_SEPARATOR,
+                self.theme.heading.paint(right.0)
+            ),
+        };
+        match self.plain {
+            true => write!(self.output, "\n{}\n\n{}\n\n", titles, lines.join("\n")),
+            false => write!(self.output, "{}\n\n{}\n\n", titles, lines.join("\n")),
+        }
+    }
+
+    pub fn prompt(&mut self, text: &str) -> io::Result<String> {
+        match self.plain {
+            true => write!(self.output, "{}", text)?,
+            false => write!(self.output, "{}", self.theme.prompt.paint(text))?,
+        }
+        self.output.flush()?;
+        let mut line = String::new();
+        match self.input.read_line(&mut line)? {
+            0 => Err(io::Error::from(io::ErrorKind::UnexpectedEof)),
+            _ => Ok(line),
+        }
+    }
+
+    // Through the OSC 52 escape sequence, which most terminals, and tmux, pass to the system
+    // clipboard, even over ssh. Plain output has no clipboard to copy to.
+    pub fn copy_to_clipboard(&mut self, text: &str) -> io::Result<()> {
+        if self.plain {
+            return Ok(());
+        }
+        write!(self.output, "\x1B]52;c;{}\x07", STANDARD.encode(text))?;
+        self.output.flush()
+    }
+
+    // Hands the screen to another program, such as an editor, until it exits. The next section
+    // redraws the screen afterwards.
+    pub fn run_external(&mut self, command: &mut Command) -> io::Result<ExitStatus> {
+        self.restore();
+        command.status()
+    }
+
+    pub fn restore(&mut self) {
+        if self.plain {
+            return;
+        }
+        write!(self.output, "{}", RESTORE).ok();
+        self.output.flush().ok();
+    }
+
+    fn width(&self) -> usize {
+        self.width.unwrap_or_else(|| match terminal_size() {
+            Some((Width(width), _)) => usize::from(width),
+            None => DEFAULT_WIDTH,
+        })
+    }
+
+    #[cfg(test)]
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    #[cfg(test)]
+    pub fn written(&self) -> String
+    where
+        W: AsRef<[u8]>,
+    {
+        String::from_utf8_lossy(self.output.as_ref()).to_string()
+    }
+}
+
+// Breaks lines at spaces to fit the width, and words longer than it wherever they reach it.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        let mut current = String::new();
+        for word in line.split(' ') {
+            let mut word: Vec<char> = word.chars().collect();
+            let length = current.chars().count();
+            if length > 0 && length + 1 + word.len() > width {
+                lines.push(std::mem::take(&mut current));
+            } else if length > 0 {
+                current.push(' ');
+            }
+            while current.chars().count() + word.len() > width {
+                let rest = word.split_off(width - current.chars().count());
+                current.extend(word);
+                lines.push(std::mem::take(&mut current));
+                word = rest;
+            }
+            current.extend(word);
+        }
+        lines.push(current);
+    }
+    lines
+}
+
+impl<'t, R: BufRead, W: Write> Drop for Terminal<'t, R, W> {
+    fn drop(&mut self) {
+        self.restore();
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use crate::theme::{Colour, Style};
+    use std::io::Cursor;
+
+    #[test]
+    fn section() {
+        let mut theme = Theme::default();
+        theme.border = "-".to_string();
+        let mut terminal = Terminal::new(Cursor::new(""), Vec::new(), &theme);
+        terminal.section("Title", "content").unwrap();
+        let expected = format!(
+            "{}\x1B[1mTitle\x1B[0m\n\x1B[1m-----\x1B[0m\n\ncontent\n\n",
+            CLEAR_SCREEN
+        );
+        assert_eq!(expected, terminal.written());
+    }
+
+    #[test]
+    fn prompt() {
+        let mut theme = Theme::default();
+        theme.prompt = Style {
+            foreground: Some(Colour::Green),
+            bold: false,
+        };
+        let mut terminal = Terminal::new(Cursor::new("answer\n"), Vec::new(), &theme);
+        assert_eq!("answer\n", terminal.prompt("> ").unwrap());
+        assert_eq!("\x1B[32m> \x1B[0m", terminal.written());
+    }
+
+    #[test]
+    fn copy_to_clipboard() {
+        let theme = Theme::default();
+        let mut terminal = Terminal::new(Cursor::new(""), Vec::new(), &theme);
+        terminal.copy_to_clipboard("bio/krebs.md").unwrap();
+        assert_eq!("\x1B]52;c;YmlvL2tyZWJzLm1k\x07", terminal.written());
+    }
+
+    #[test]
+    fn plain_output_has_no_escape_sequences() {
+        let mut theme = Theme::default();
+        theme.border = "-".to_string();
+        theme.prompt.bold = true;
+        let mut output = Vec::new();
+        let mut terminal =
+            Terminal::new(Cursor::new("\n"), &mut output, &theme).with_plain_output(true);
+        terminal.section("Title", "content").unwrap();
+        terminal.prompt("> ").unwrap();
+        terminal.copy_to_clipboard("bio/krebs.md").unwrap();
+        terminal.restore();
+        drop(terminal);
+        assert_eq!(
+            "\nTitle\n\ncontent\n\n> ",
+            String::from_utf8(output).unwrap()
+        );
+    }
+
+    #[test]
+    fn split_section_wraps_each_side_to_half_the_width() {
+        let theme = Theme::default();
+        let mut output = Vec::new();
+        let mut terminal = Terminal::new(Cursor::new(""), &mut output, &theme)
+            .with_plain_output(true)
+            .with_width(33);
+        terminal
+            .split_section(
+                ("Question", "Which organelle makes\nATP?"),
+                ("Answer", "Mitochondria"),
+            )
+            .unwrap();
+        drop(terminal);
+        let expected = [
+            "",
+            "Question        │ Answer",
+            "",
+            "Which organelle │ Mitochondria",
+            "makes           │",
+            "ATP?            │",
+            "",
+            "",
+        ];
+        assert_eq!(expected.join("\n"), String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn wrap_breaks_long_words() {
+        assert_eq!(vec!["abcd", "efg", "", "ab c"], wrap("abcdefg\n\nab c", 4));
+    }
+
+    #[test]
+    fn restore() {
+        let theme = Theme::default();
+        let mut terminal = Terminal::new(Cursor::new(""), Vec::new(), &theme);
+        terminal.restore();
+        assert_eq!(RESTORE, terminal.written());
+    }
+
+    #[test]
+    fn restores_when_dropped() {
+        let theme = Theme::default();
+        let mut output = Vec::new();
+        drop(Terminal::new(Cursor::new(""), &mut output, &theme));
+        assert_eq!(RESTORE.as_bytes(), &output[..]);
+    }
+
+    #[test]
+    fn prompt_when_input_ends() {
+        let theme = Theme::default();
+        let mut terminal = Terminal::new(Cursor::new(""), Vec::new(), &theme);
+        let actual = terminal.prompt("> ").unwrap_err();
+        assert_eq!(io::ErrorKind::UnexpectedEof, actual.kind());
+    }
+}
@@ -0,0 +1,187 @@
+use serde::Deserialize;
+use std::error::Error;
+
+const RESET: &str = "\x1B[0m";
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+pub enum Colour {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+}
+
+impl Colour {
+    fn ansi_code(&self) -> u8 {
+        match self {
+            Colour::Black => 30,
+            Colour::Red => 31,
+            Colour::Green => 32,
+            Colour::Yellow => 33,
+            Colour::Blue => 34,
+            Colour::Magenta => 35,
+            Colour::Cyan => 36,
+            Colour::White => 37,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Style {
+    pub foreground: Option<Colour>,
+    pub bold: bool,
+}
+
+impl Style {
+    pub fn paint(&self, text: &str) -> String {
+        let mut codes = Vec::new();
+        if self.bold {
+            codes.push("1".to_string());
+        }
+        if let Some(colour) = self.foreground {
+            codes.push(colour.ansi_code().to_string());
+        }
+        match codes.len() {
+            0 => text.to_string(),
+            _ => format!("\x1B[{}m{}{}", codes.join(";"), text, RESET),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Theme {
+    pub heading: Style,
+    pub text: Style,
+    pub prompt: Style,
+    pub highlight: Style,
+    pub border: String,
+}
+
+impl Theme {
+    pub fn read(path: &str) -> Result<Self, Box<dyn Error>> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read Theme from {} -> {}", path, e))?;
+        Ok(ron::from_str(&content)
+            .map_err(|e| format!("Unable to parse Theme from {} -> {}", path, e))?)
+    }
+
+    // From the [theme] table of the config file, if there's a file at the path.
+    pub fn read_from_config(path: &str) -> Result<Option<Self>, Box<dyn Error>> {
+        #[derive(Deserialize)]
+        struct Config {
+            theme: Option<Theme>,
+        }
+        if !std::path::Path::new(path).exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Unable to read config from {} -> {}", path, e))?;
+        let config: Config = toml::from_str(&content)
+            .map_err(|e| format!("Unable to parse config from {} -> {}", path, e))?;
+        Ok(config.theme)
+    }
+
+    // For --plain output, where escape codes would only get in the way.
+    pub fn plain() -> Self {
+        Self {
+            heading: Style::default(),
+            text: Style::default(),
+            prompt: Style::default(),
+            highlight: Style::default(),
+            border: String::new(),
+        }
+    }
+
+    pub fn border_under(&self, title: &str) -> String {
+        self.border.repeat(title.chars().count())
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            heading: Style {
+                foreground: None,
+                bold: true,
+            },
+            text: Style::default(),
+            prompt: Style::default(),
+            highlight: Style {
+                foreground: Some(Colour::Yellow),
+                bold: true,
+            },
+            border: String::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case::plain(Style::default(), "hi")]
+    #[case::bold(Style { foreground: None, bold: true }, "\x1B[1mhi\x1B[0m")]
+    #[case::coloured(Style { foreground: Some(Colour::Blue), bold: false }, "\x1B[34mhi\x1B[0m")]
+    #[case::bold_and_coloured(Style { foreground: Some(Colour::Red), bold: true }, "\x1B[1;31mhi\x1B[0m")]
+    fn paint(#[case] style: Style, #[case] expected: &str) {
+        assert_eq!(expected, style.paint("hi"));
+    }
+
+    #[test]
+    fn deserialises_partial_theme_over_defaults() {
+        let expected = Theme {
+            heading: Style {
+                foreground: Some(Colour::Cyan),
+                bold: false,
+            },
+            border: "=".to_string(),
+            ..Theme::default()
+        };
+        let actual: Theme =
+            ron::from_str("(heading: (foreground: Some(Cyan)), border: \"=\")").unwrap();
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn read_from_config() {
+        let path =
+            std::env::temp_dir().join(format!("vultan_theme_config_{}.toml", std::process::id()));
+        let path = path.to_string_lossy().to_string();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(None, Theme::read_from_config(&path).unwrap());
+        std::fs::write(
+            &path,
+            "[parsing]\ndeck_delimiter = \"/\"\n\n[theme]\nborder = \"=\"\nheading = { foreground = \"Cyan\" }\n",
+        )
+        .unwrap();
+        let expected = Theme {
+            heading: Style {
+                foreground: Some(Colour::Cyan),
+                bold: false,
+            },
+            border: "=".to_string(),
+            ..Theme::default()
+        };
+        assert_eq!(Some(expected), Theme::read_from_config(&path).unwrap());
+        std::fs::write(&path, "[parsing]\n").unwrap();
+        assert_eq!(None, Theme::read_from_config(&path).unwrap());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn border_under() {
+        let mut theme = Theme::default();
+        theme.border = "─".to_string();
+        assert_eq!("────", theme.border_under("Deck"));
+        assert_eq!("", Theme::default().border_under("Deck"));
+    }
+}
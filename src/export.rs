@@ -0,0 +1,107 @@
+pub mod html;
+pub mod latex;
+pub mod typst;
+
+use crate::state::card::Card;
+use crate::state::link_index;
+use std::str::FromStr;
+
+const COLUMNS: usize = 2;
+const ROWS: usize = 4;
+const CARDS_PER_SHEET: usize = COLUMNS * ROWS;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    Html,
+    Latex,
+    Typst,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "html" => Ok(ExportFormat::Html),
+            "latex" => Ok(ExportFormat::Latex),
+            "typst" => Ok(ExportFormat::Typst),
+            _ => Err(format!(
+                "Unknown export format \"{}\": expected html, latex or typst",
+                name
+            )),
+        }
+    }
+}
+
+// A sheet of printable flashcards: a page of questions, row by row, and a page of their answers
+// with each row mirrored, so that every answer is printed behind its question when the sheet is
+// printed two-sided and flipped along its long edge. Unused places are None.
+#[derive(Debug, PartialEq)]
+struct Sheet {
+    fronts: Vec<Vec<Option<String>>>,
+    backs: Vec<Vec<Option<String>>>,
+}
+
+fn sheets(cards: &[&Card]) -> Vec<Sheet> {
+    cards
+        .chunks(CARDS_PER_SHEET)
+        .map(|cards| {
+            let place = |index: usize, side: fn(&Card) -> &str| {
+                cards.get(index).map(|card| plain_text(side(card)))
+            };
+            let fronts = (0..ROWS)
+                .map(|row| {
+                    (0..COLUMNS)
+                        .map(|column| place(row * COLUMNS + column, |card| &card.question))
+                        .collect()
+                })
+                .collect();
+            let backs = (0..ROWS)
+                .map(|row| {
+                    (0..COLUMNS)
+                        .rev()
+                        .map(|column| place(row * COLUMNS + column, |card| &card.answer))
+                        .collect()
+                })
+                .collect();
+            Sheet { fronts, backs }
+        })
+        .collect()
+}
+
+// Wikilinks can't be followed on paper, so they're shown as their titles.
+fn plain_text(text: &str) -> String {
+    link_index::render(text).trim().to_string()
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use crate::state::card::RevisionSettings;
+
+    fn make_card(id: usize) -> Card {
+        Card::new(
+            format!("{}.md", id),
+            vec!["deck".to_string()],
+            format!("q{}", id),
+            format!("a{}", id),
+            RevisionSettings::default(),
+        )
+    }
+
+    #[test]
+    fn sheets_mirror_answers_behind_their_questions() {
+        let cards: Vec<Card> = (0..9).map(make_card).collect();
+        let cards: Vec<&Card> = cards.iter().collect();
+        let actual = sheets(&cards);
+        assert_eq!(2, actual.len());
+        let some = |text: &str| Some(text.to_string());
+        assert_eq!(vec![some("q0"), some("q1")], actual[0].fronts[0]);
+        assert_eq!(vec![some("a1"), some("a0")], actual[0].backs[0]);
+        assert_eq!(vec![some("a7"), some("a6")], actual[0].backs[3]);
+        assert_eq!(vec![some("q8"), None], actual[1].fronts[0]);
+        assert_eq!(vec![None, some("a8")], actual[1].backs[0]);
+        assert_eq!(vec![None, None], actual[1].backs[1]);
+    }
+}
@@ -0,0 +1,98 @@
+use crate::state::card::Card;
+use crate::state::link_index;
+use pulldown_cmark::{html, Options, Parser};
+
+pub const INDEX_FILE_NAME: &str = "index.html";
+
+const STYLE: &str = "body{font-family:sans-serif;max-width:48em;margin:2em auto;padding:0 1em}\
+.card{border:1px solid #ccc;border-radius:6px;margin:1em 0;padding:0 1em}\
+.answer{border-top:1px dashed #ccc}\
+.reveal .card{cursor:pointer}\
+.reveal .card:not(.revealed) .answer{display:none}";
+
+const REVEAL_SCRIPT: &str = "document.querySelectorAll('.card').forEach(function(card){\
+card.addEventListener('click',function(){card.classList.toggle('revealed');});});";
+
+// A deck's cards as one page, styled and scripted inline so that it can be shared as is with
+// people who don't use vultan. Revealing on click hides each answer until its card is clicked.
+pub fn render(deck_name: &str, cards: &[&Card], reveal_on_click: bool) -> String {
+    let title = escape(deck_name);
+    let mut page = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>{}</style>\n</head>\n<body{}>\n<h1>{}</h1>\n",
+        title,
+        STYLE,
+        if reveal_on_click {
+            " class=\"reveal\""
+        } else {
+            ""
+        },
+        title
+    );
+    for card in cards.iter() {
+        page.push_str(&format!(
+            "<section class=\"card\">\n<div class=\"question\">\n{}</div>\n\
+             <div class=\"answer\">\n{}</div>\n</section>\n",
+            to_html(&card.question),
+            to_html(&card.answer)
+        ));
+    }
+    if reveal_on_click {
+        page.push_str(&format!("<script>{}</script>\n", REVEAL_SCRIPT));
+    }
+    page.push_str("</body>\n</html>\n");
+    page
+}
+
+// Wikilinks can't be followed outside the vault, so they're shown as their titles.
+fn to_html(markdown: &str) -> String {
+    let markdown = link_index::render(markdown);
+    let mut output = String::new();
+    html::push_html(&mut output, Parser::new_ext(&markdown, Options::all()));
+    output
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use crate::state::card::RevisionSettings;
+
+    fn make_card(question: &str, answer: &str) -> Card {
+        Card::new(
+            "a.md".to_string(),
+            vec!["bio".to_string()],
+            question.to_string(),
+            answer.to_string(),
+            RevisionSettings::default(),
+        )
+    }
+
+    #[test]
+    fn render_cards_as_html() {
+        let card = make_card(
+            "What is **ATP**?",
+            "See [[Krebs Cycle|the cycle]]:\n\n- energy",
+        );
+        let actual = render("bio <cells>", &[&card], false);
+        assert!(actual.contains("<title>bio &lt;cells&gt;</title>"));
+        assert!(actual.contains("<p>What is <strong>ATP</strong>?</p>"));
+        assert!(actual.contains("<p>See the cycle:</p>\n<ul>\n<li>energy</li>\n</ul>"));
+        assert!(!actual.contains("<script>"));
+    }
+
+    #[test]
+    fn render_with_answers_revealed_on_click() {
+        let card = make_card("q", "a");
+        let actual = render("bio", &[&card], true);
+        assert!(actual.contains("<body class=\"reveal\">"));
+        assert!(actual.contains("<script>"));
+    }
+}
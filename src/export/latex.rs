@@ -0,0 +1,82 @@
+use super::{sheets, Sheet, COLUMNS, ROWS};
+use crate::state::card::Card;
+
+pub const FILE_NAME: &str = "cards.tex";
+
+// LaTeX source of a deck's cards laid out for two-sided printing, ready for pdflatex.
+pub fn render(deck_name: &str, cards: &[&Card]) -> String {
+    let mut document = format!(
+        "\\documentclass[a4paper]{{article}}\n\
+         \\usepackage[margin=1cm]{{geometry}}\n\
+         \\usepackage[utf8]{{inputenc}}\n\
+         \\pagestyle{{empty}}\n\
+         \\setlength{{\\parindent}}{{0pt}}\n\
+         \\newcommand{{\\card}}[1]{{\\fbox{{\\parbox[c][{:.3}\\textheight][c]{{{:.3}\\textwidth}}{{\\centering #1}}}}}}\n\
+         \\title{{{}}}\n\
+         \\begin{{document}}\n",
+        0.95 / ROWS as f64,
+        0.95 / COLUMNS as f64,
+        escape(deck_name)
+    );
+    for Sheet { fronts, backs } in sheets(cards) {
+        push_page(&mut document, &fronts);
+        push_page(&mut document, &backs);
+    }
+    document.push_str("\\end{document}\n");
+    document
+}
+
+fn push_page(document: &mut String, rows: &[Vec<Option<String>>]) {
+    for row in rows.iter() {
+        let cells: Vec<String> = row
+            .iter()
+            .map(|text| format!("\\card{{{}}}", escape(text.as_deref().unwrap_or_default())))
+            .collect();
+        document.push_str(&format!("{}\\par\\vfill\n", cells.join("\\hfill\n")));
+    }
+    document.push_str("\\newpage\n");
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '{' | '}' | '$' | '&' | '#' | '%' | '_' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\newline "),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use crate::state::card::RevisionSettings;
+
+    #[test]
+    fn render_questions_then_mirrored_answers() {
+        let card = Card::new(
+            "a.md".to_string(),
+            vec!["deck".to_string()],
+            "What is 50% of $10?\nBriefly".to_string(),
+            "$5".to_string(),
+            RevisionSettings::default(),
+        );
+        let actual = render("maths", &[&card]);
+        assert!(actual.starts_with("\\documentclass[a4paper]{article}"));
+        assert!(actual.contains(
+            "\\card{What is 50\\% of \\$10?\\newline Briefly}\\hfill\n\\card{}\\par\\vfill\n"
+        ));
+        assert!(actual.contains("\\card{}\\hfill\n\\card{\\$5}\\par\\vfill\n"));
+        assert_eq!(2, actual.matches("\\newpage").count());
+        assert!(actual.ends_with("\\end{document}\n"));
+    }
+}
@@ -0,0 +1,81 @@
+use super::{sheets, Sheet, COLUMNS, ROWS};
+use crate::state::card::Card;
+
+pub const FILE_NAME: &str = "cards.typ";
+
+// Typst source of a deck's cards laid out for two-sided printing, ready for `typst compile`.
+pub fn render(deck_name: &str, cards: &[&Card]) -> String {
+    let mut document = format!(
+        "#set document(title: \"{}\")\n\
+         #set page(paper: \"a4\", margin: 1cm)\n\
+         #let card(body) = rect(width: 100%, height: 100%, inset: 1em, \
+         align(center + horizon, body))\n",
+        deck_name.replace('\\', "\\\\").replace('"', "\\\"")
+    );
+    let sheets = sheets(cards);
+    for (index, Sheet { fronts, backs }) in sheets.iter().enumerate() {
+        push_page(&mut document, fronts);
+        document.push_str("#pagebreak()\n");
+        push_page(&mut document, backs);
+        if index + 1 < sheets.len() {
+            document.push_str("#pagebreak()\n");
+        }
+    }
+    document
+}
+
+fn push_page(document: &mut String, rows: &[Vec<Option<String>>]) {
+    document.push_str(&format!(
+        "#grid(\n  columns: ({}),\n  rows: ({}),\n",
+        ["1fr"; COLUMNS].join(", "),
+        ["1fr"; ROWS].join(", ")
+    ));
+    for text in rows.iter().flatten() {
+        document.push_str(&format!(
+            "  card[{}],\n",
+            escape(text.as_deref().unwrap_or_default())
+        ));
+    }
+    document.push_str(")\n");
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' | '#' | '*' | '_' | '$' | '[' | ']' | '<' | '>' | '@' | '`' | '~' | '=' | '-'
+            | '+' | '/' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            // A backslash before a newline breaks the line.
+            '\n' => escaped.push_str("\\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use crate::state::card::RevisionSettings;
+
+    #[test]
+    fn render_questions_then_mirrored_answers() {
+        let card = Card::new(
+            "a.md".to_string(),
+            vec!["deck".to_string()],
+            "What is *1/2* of #10?\nBriefly".to_string(),
+            "5".to_string(),
+            RevisionSettings::default(),
+        );
+        let actual = render("maths", &[&card]);
+        assert!(actual.starts_with("#set document(title: \"maths\")\n"));
+        assert!(actual
+            .contains("  card[What is \\*1\\/2\\* of \\#10?\\\nBriefly],\n  card[],\n  card[],\n"));
+        assert!(actual.contains("  card[],\n  card[5],\n  card[],\n"));
+        assert_eq!(1, actual.matches("#pagebreak()").count());
+    }
+}
@@ -0,0 +1,244 @@
+//! C ABI over the scheduling core. Cards and states cross the boundary as RON
+//! strings; every string returned by this module must be released with
+//! `vultan_string_free` and every state with `vultan_state_free`.
+
+use crate::state::card::{Card, Score};
+use crate::state::deck::IntervalCoefficients;
+use crate::state::State;
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+unsafe fn read_str<'a>(value: *const c_char) -> Option<&'a str> {
+    match value.is_null() {
+        true => None,
+        false => CStr::from_ptr(value).to_str().ok(),
+    }
+}
+
+fn into_c_string(value: String) -> *mut c_char {
+    CString::new(value)
+        .map(CString::into_raw)
+        .unwrap_or(ptr::null_mut())
+}
+
+fn to_ron<T: serde::Serialize>(value: &T) -> *mut c_char {
+    ron::to_string(value)
+        .map(into_c_string)
+        .unwrap_or(ptr::null_mut())
+}
+
+fn score_from_index(index: u8) -> Option<Score> {
+    match index {
+        0 => Some(Score::Fail),
+        1 => Some(Score::Hard),
+        2 => Some(Score::Pass),
+        3 => Some(Score::Easy),
+        _ => None,
+    }
+}
+
+/// Parses a RON encoded State, returning null if it is invalid.
+///
+/// # Safety
+/// `state_ron` must be null or a valid, nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn vultan_state_from_ron(state_ron: *const c_char) -> *mut State {
+    read_str(state_ron)
+        .and_then(|s| ron::from_str::<State>(s).ok())
+        .map(|state| Box::into_raw(Box::new(state)))
+        .unwrap_or(ptr::null_mut())
+}
+
+/// Serialises a State to RON, returning null on failure.
+///
+/// # Safety
+/// `state` must be null or a pointer returned by `vultan_state_from_ron`.
+#[no_mangle]
+pub unsafe extern "C" fn vultan_state_to_ron(state: *const State) -> *mut c_char {
+    match state.as_ref() {
+        Some(state) => to_ron(state),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Replaces the scheduling of the State's card with the same path as the
+/// given RON encoded card. Returns false if either argument is invalid.
+///
+/// # Safety
+/// `state` must be null or a pointer returned by `vultan_state_from_ron`, and
+/// `card_ron` must be null or a valid, nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn vultan_state_override_card(
+    state: *mut State,
+    card_ron: *const c_char,
+) -> bool {
+    let card = read_str(card_ron).and_then(|s| ron::from_str::<Card>(s).ok());
+    match (state.as_mut(), card) {
+        (Some(state), Some(card)) => {
+            *state = std::mem::take(state).with_overriden_cards(vec![card]);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Deals the due cards of a deck as a RON encoded list, returning null if
+/// the deck does not exist or has no due cards.
+///
+/// # Safety
+/// `state` must be null or a pointer returned by `vultan_state_from_ron`, and
+/// `deck_name` must be null or a valid, nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn vultan_deal(state: *const State, deck_name: *const c_char) -> *mut c_char {
+    let hand = match (state.as_ref(), read_str(deck_name)) {
+        (Some(state), Some(deck_name)) => state.deal(deck_name).ok(),
+        _ => None,
+    };
+    match hand {
+        Some(hand) => to_ron(&hand.cards().collect::<Vec<&Card>>()),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Reschedules a RON encoded card for a score from 0 (fail) to 3 (easy),
+/// returning the transformed card, or null if any argument is invalid.
+///
+/// # Safety
+/// `card_ron` must be null or a valid, nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn vultan_card_transform(
+    card_ron: *const c_char,
+    score: u8,
+    pass_coef: f64,
+    easy_coef: f64,
+    fail_coef: f64,
+) -> *mut c_char {
+    let card = read_str(card_ron).and_then(|s| ron::from_str::<Card>(s).ok());
+    match (card, score_from_index(score)) {
+        (Some(card), Some(score)) => {
+            let coefficients = IntervalCoefficients::new(pass_coef, easy_coef, fail_coef);
+            to_ron(&card.transform(score, &coefficients))
+        }
+        _ => ptr::null_mut(),
+    }
+}
+
+/// # Safety
+/// `state` must be null or a pointer returned by `vultan_state_from_ron`
+/// that has not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn vultan_state_free(state: *mut State) {
+    if !state.is_null() {
+        drop(Box::from_raw(state));
+    }
+}
+
+/// # Safety
+/// `value` must be null or a string returned by this module that has not
+/// already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn vultan_string_free(value: *mut c_char) {
+    if !value.is_null() {
+        drop(CString::from_raw(value));
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use crate::state::card::RevisionSettings;
+    use crate::state::deck::Deck;
+    use chrono::{Duration, Utc};
+
+    fn make_card(path: &str, due_in_days: i64) -> Card {
+        Card::new(
+            path.to_string(),
+            vec!["a".to_string()],
+            "q".to_string(),
+            "a".to_string(),
+            RevisionSettings::new(Utc::now() + Duration::days(due_in_days), 1.0, 1300.0),
+        )
+    }
+
+    fn make_state_ron() -> CString {
+        let cards = vec![make_card("due", -1), make_card("not_due", 1)];
+        let deck = Deck::new("a", vec!["due", "not_due"], IntervalCoefficients::default());
+        let state = State::new(Default::default(), cards, vec![deck]);
+        CString::new(ron::to_string(&state).unwrap()).unwrap()
+    }
+
+    unsafe fn take_string(value: *mut c_char) -> String {
+        assert!(!value.is_null());
+        let owned = CStr::from_ptr(value).to_str().unwrap().to_string();
+        vultan_string_free(value);
+        owned
+    }
+
+    #[test]
+    fn state_round_trips_through_ron() {
+        unsafe {
+            let state_ron = make_state_ron();
+            let state = vultan_state_from_ron(state_ron.as_ptr());
+            let actual = take_string(vultan_state_to_ron(state));
+            let expected: State = ron::from_str(state_ron.to_str().unwrap()).unwrap();
+            assert_eq!(expected, ron::from_str::<State>(&actual).unwrap());
+            vultan_state_free(state);
+        }
+    }
+
+    #[test]
+    fn invalid_arguments_return_null() {
+        unsafe {
+            let garbage = CString::new("garbage").unwrap();
+            assert!(vultan_state_from_ron(garbage.as_ptr()).is_null());
+            assert!(vultan_state_from_ron(ptr::null()).is_null());
+            assert!(vultan_state_to_ron(ptr::null()).is_null());
+            assert!(vultan_deal(ptr::null(), garbage.as_ptr()).is_null());
+            assert!(vultan_card_transform(garbage.as_ptr(), 0, 1.0, 1.3, 0.0).is_null());
+            assert!(!vultan_state_override_card(
+                ptr::null_mut(),
+                garbage.as_ptr()
+            ));
+        }
+    }
+
+    #[test]
+    fn deal() {
+        unsafe {
+            let state = vultan_state_from_ron(make_state_ron().as_ptr());
+            let deck_name = CString::new("a").unwrap();
+            let actual = take_string(vultan_deal(state, deck_name.as_ptr()));
+            let actual: Vec<Card> = ron::from_str(&actual).unwrap();
+            assert_eq!(1, actual.len());
+            assert_eq!("due", actual[0].path);
+            let missing = CString::new("b").unwrap();
+            assert!(vultan_deal(state, missing.as_ptr()).is_null());
+            vultan_state_free(state);
+        }
+    }
+
+    #[test]
+    fn card_transform_and_override() {
+        unsafe {
+            let card = make_card("due", -1);
+            let card_ron = CString::new(ron::to_string(&card).unwrap()).unwrap();
+            assert!(vultan_card_transform(card_ron.as_ptr(), 4, 1.0, 1.3, 0.0).is_null());
+            let actual = take_string(vultan_card_transform(card_ron.as_ptr(), 3, 1.0, 1.3, 0.0));
+            let coefficients = IntervalCoefficients::new(1.0, 1.3, 0.0);
+            let expected = card.transform(Score::Easy, &coefficients);
+            let transformed: Card = ron::from_str(&actual).unwrap();
+            assert_eq!(
+                expected.revision_settings.interval,
+                transformed.revision_settings.interval
+            );
+
+            let state = vultan_state_from_ron(make_state_ron().as_ptr());
+            let transformed_ron = CString::new(actual).unwrap();
+            assert!(vultan_state_override_card(state, transformed_ron.as_ptr()));
+            let actual = (*state).cards()["due"].revision_settings.interval;
+            assert_eq!(expected.revision_settings.interval, actual);
+            vultan_state_free(state);
+        }
+    }
+}
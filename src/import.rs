@@ -0,0 +1,53 @@
+pub mod anki;
+pub mod mochi;
+pub mod remnote;
+pub mod supermemo;
+pub mod template;
+
+use crate::state::card::RevisionSettings;
+use snafu::prelude::*;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImportedCard {
+    pub decks: Vec<String>,
+    pub question: String,
+    pub answer: String,
+    pub revision_settings: RevisionSettings,
+}
+
+// Why an export, or a data file and template, couldn't be imported. A record that can't be is
+// reported in a Record error, which says which one it was.
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub enum ImportError {
+    #[snafu(display("Unable to import {}: {}", record, source))]
+    Record {
+        record: String,
+        #[snafu(source(from(ImportError, Box::new)))]
+        source: Box<ImportError>,
+    },
+    // A side of a card, or a line that should set it apart from the other.
+    #[snafu(display("Missing {}", what))]
+    Missing { what: String },
+    #[snafu(display("Unable to parse {} \"{}\"", what, value))]
+    InvalidValue { what: String, value: String },
+    #[snafu(display("Unknown column \"{}\"", name))]
+    UnknownColumn { name: String },
+    #[snafu(display("Column mapping \"{}\" must name {} exactly once", spec, column))]
+    InvalidColumnMapping { spec: String, column: String },
+    #[snafu(display("Unknown separator \"{}\"", separator))]
+    UnknownSeparator { separator: String },
+    #[snafu(display("Unable to import line {}: a flashcard needs both sides", line))]
+    OneSided { line: usize },
+    #[snafu(display("Expected {}: {}", expected, source))]
+    MalformedJson {
+        expected: String,
+        source: serde_json::Error,
+    },
+    #[snafu(display("Unable to tell the format of \"{}\": expected .csv or .json", path))]
+    UnknownFormat { path: String },
+    #[snafu(display("The CSV file has no header"))]
+    MissingHeader,
+    #[snafu(display("No field \"{}\" to fill in the template with", name))]
+    UnknownField { name: String },
+}
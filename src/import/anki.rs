@@ -0,0 +1,322 @@
+use super::{
+    ImportError, ImportedCard, InvalidColumnMappingSnafu, InvalidValueSnafu, MissingSnafu,
+    RecordSnafu, UnknownColumnSnafu, UnknownSeparatorSnafu,
+};
+use crate::state::card::RevisionSettings;
+use chrono::{DateTime, NaiveDate, Utc};
+use snafu::prelude::*;
+
+const DEFAULT_SEPARATOR: char = '\t';
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Column {
+    Question,
+    Answer,
+    Deck,
+    Due,
+    Interval,
+    Ease,
+    Ignored,
+}
+
+impl Column {
+    fn parse(name: &str) -> Result<Self, ImportError> {
+        Ok(match name.trim().to_lowercase().as_str() {
+            "question" | "front" => Column::Question,
+            "answer" | "back" => Column::Answer,
+            "deck" => Column::Deck,
+            "due" => Column::Due,
+            "interval" | "ivl" => Column::Interval,
+            "ease" | "factor" => Column::Ease,
+            "_" | "" => Column::Ignored,
+            other => return UnknownColumnSnafu { name: other }.fail(),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnMapping {
+    columns: Vec<Column>,
+}
+
+impl ColumnMapping {
+    pub fn parse(spec: &str) -> Result<Self, ImportError> {
+        let columns = spec
+            .split(',')
+            .map(Column::parse)
+            .collect::<Result<Vec<Column>, ImportError>>()?;
+        for required in [Column::Question, Column::Answer] {
+            let count = columns.iter().filter(|c| **c == required).count();
+            ensure!(
+                count == 1,
+                InvalidColumnMappingSnafu {
+                    spec,
+                    column: format!("{:?}", required),
+                }
+            );
+        }
+        Ok(Self { columns })
+    }
+
+    fn index_of(&self, column: Column) -> Option<usize> {
+        self.columns.iter().position(|c| *c == column)
+    }
+}
+
+impl Default for ColumnMapping {
+    fn default() -> Self {
+        Self {
+            columns: vec![Column::Question, Column::Answer],
+        }
+    }
+}
+
+pub fn parse(
+    content: &str,
+    mapping: &ColumnMapping,
+    default_deck: &str,
+) -> Result<Vec<ImportedCard>, ImportError> {
+    let (separator, body) = split_headers(content)?;
+    split_records(body, separator)
+        .into_iter()
+        .enumerate()
+        .map(|(i, record)| {
+            to_imported_card(&record, mapping, default_deck).with_context(|_| RecordSnafu {
+                record: format!("record {}", i + 1),
+            })
+        })
+        .collect()
+}
+
+fn split_headers(content: &str) -> Result<(char, &str), ImportError> {
+    let mut separator = DEFAULT_SEPARATOR;
+    let mut body = content;
+    while body.starts_with('#') {
+        let (line, rest) = body.split_once('\n').unwrap_or((body, ""));
+        if let Some(value) = line.trim_end().strip_prefix("#separator:") {
+            separator = parse_separator(value)?;
+        }
+        body = rest;
+    }
+    Ok((separator, body))
+}
+
+fn parse_separator(value: &str) -> Result<char, ImportError> {
+    Ok(match value.to_lowercase().as_str() {
+        "tab" => '\t',
+        "comma" => ',',
+        "semicolon" => ';',
+        "pipe" => '|',
+        "space" => ' ',
+        "colon" => ':',
+        other => match other.chars().collect::<Vec<char>>()[..] {
+            [c] => c,
+            _ => return UnknownSeparatorSnafu { separator: value }.fail(),
+        },
+    })
+}
+
+// Quoted fields may contain separators, newlines and doubled quotes, as Anki writes them.
+pub(super) fn split_records(body: &str, separator: char) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => in_quotes = false,
+            ('"', false) if field.is_empty() => in_quotes = true,
+            ('\r', false) => {}
+            ('\n', false) => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            (c, false) if c == separator => record.push(std::mem::take(&mut field)),
+            (c, _) => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+        .into_iter()
+        .filter(|r| r.iter().any(|f| !f.trim().is_empty()))
+        .collect()
+}
+
+fn to_imported_card(
+    record: &[String],
+    mapping: &ColumnMapping,
+    default_deck: &str,
+) -> Result<ImportedCard, ImportError> {
+    let field = |column: Column| -> Option<&str> {
+        mapping
+            .index_of(column)
+            .and_then(|i| record.get(i))
+            .map(|f| f.trim())
+            .filter(|f| !f.is_empty())
+    };
+    let defaults = RevisionSettings::default();
+    let question = field(Column::Question).context(MissingSnafu { what: "question" })?;
+    let answer = field(Column::Answer).context(MissingSnafu { what: "answer" })?;
+    let due = match field(Column::Due) {
+        Some(due) => parse_due(due)?,
+        None => defaults.due,
+    };
+    let interval = match field(Column::Interval) {
+        Some(interval) => parse_interval(interval)?,
+        None => defaults.interval,
+    };
+    let memorisation_factor = match field(Column::Ease) {
+        Some(ease) => parse_ease(ease)?,
+        None => defaults.memorisation_factor,
+    };
+    Ok(ImportedCard {
+        decks: vec![field(Column::Deck).unwrap_or(default_deck).to_string()],
+        question: question.to_string(),
+        answer: answer.to_string(),
+        revision_settings: RevisionSettings::new(due, interval, memorisation_factor),
+    })
+}
+
+pub(super) fn parse_due(value: &str) -> Result<DateTime<Utc>, ImportError> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let midnight = date.and_hms_opt(0, 0, 0).expect("midnight is a valid time");
+        return Ok(DateTime::from_naive_utc_and_offset(midnight, Utc));
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|due| due.with_timezone(&Utc))
+        .ok()
+        .context(InvalidValueSnafu {
+            what: "due date",
+            value,
+        })
+}
+
+// Anki stores learning intervals as negative seconds; those cards start over.
+fn parse_interval(value: &str) -> Result<f64, ImportError> {
+    let interval: f64 = value.parse().ok().context(InvalidValueSnafu {
+        what: "interval",
+        value,
+    })?;
+    Ok(interval.max(0.0))
+}
+
+// Anki's ease is stored in permille (2500), but shown as a percentage (250%) or ratio (2.5).
+pub(super) fn parse_ease(value: &str) -> Result<f64, ImportError> {
+    let ease: f64 = value
+        .trim_end_matches('%')
+        .parse()
+        .ok()
+        .context(InvalidValueSnafu {
+            what: "ease",
+            value,
+        })?;
+    Ok(match ease {
+        e if e < 10.0 => e * 1000.0,
+        e if e < 1000.0 => e * 10.0,
+        e => e,
+    })
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use chrono::TimeZone;
+    use rstest::*;
+
+    #[rstest]
+    #[case::default_order("question,answer", vec![Column::Question, Column::Answer])]
+    #[case::anki_names(
+        "front, back, _, deck, due, ivl, factor",
+        vec![
+            Column::Question,
+            Column::Answer,
+            Column::Ignored,
+            Column::Deck,
+            Column::Due,
+            Column::Interval,
+            Column::Ease,
+        ]
+    )]
+    fn column_mapping_parse(#[case] spec: &str, #[case] expected: Vec<Column>) {
+        let actual = ColumnMapping::parse(spec).unwrap();
+        assert_eq!(ColumnMapping { columns: expected }, actual);
+    }
+
+    #[rstest]
+    #[case::unknown_column("question,answer,colour", "Unknown column \"colour\"")]
+    #[case::missing_answer("question,deck", "must name Answer exactly once")]
+    #[case::repeated_question("question,question,answer", "must name Question exactly once")]
+    fn column_mapping_parse_fails(#[case] spec: &str, #[case] expected: &str) {
+        let actual = ColumnMapping::parse(spec).unwrap_err();
+        assert!(actual.to_string().contains(expected));
+    }
+
+    #[rstest]
+    #[case::ratio("2.5", 2500.0)]
+    #[case::percentage("250%", 2500.0)]
+    #[case::permille("2500", 2500.0)]
+    fn parse_ease(#[case] value: &str, #[case] expected: f64) {
+        assert_eq!(expected, super::parse_ease(value).unwrap());
+    }
+
+    #[rstest]
+    #[case::date("2030-01-02", Utc.with_ymd_and_hms(2030, 1, 2, 0, 0, 0).unwrap())]
+    #[case::rfc3339("2030-01-02T03:04:05+01:00", Utc.with_ymd_and_hms(2030, 1, 2, 2, 4, 5).unwrap())]
+    fn parse_due(#[case] value: &str, #[case] expected: DateTime<Utc>) {
+        assert_eq!(expected, super::parse_due(value).unwrap());
+    }
+
+    #[test]
+    fn split_records_handles_quoted_fields() {
+        let body = "a\t\"b\tc\"\n\"multi\nline\"\t\"say \"\"hi\"\"\"\r\n\n";
+        let expected = vec![
+            vec!["a".to_string(), "b\tc".to_string()],
+            vec!["multi\nline".to_string(), "say \"hi\"".to_string()],
+        ];
+        assert_eq!(expected, split_records(body, '\t'));
+    }
+
+    #[test]
+    fn parse_maps_scheduling_columns() {
+        let content =
+            "#separator:comma\n#html:false\nwhat?,that,,biology,2030-01-02,12,250%\nwho?,me,,,,,\n";
+        let mapping = ColumnMapping::parse("question,answer,_,deck,due,interval,ease").unwrap();
+        let actual = parse(content, &mapping, "imported").unwrap();
+        assert_eq!(2, actual.len());
+        assert_eq!(vec!["biology".to_string()], actual[0].decks);
+        assert_eq!("what?", actual[0].question);
+        assert_eq!("that", actual[0].answer);
+        let expected_due = Utc.with_ymd_and_hms(2030, 1, 2, 0, 0, 0).unwrap();
+        assert_eq!(
+            RevisionSettings::new(expected_due, 12.0, 2500.0),
+            actual[0].revision_settings
+        );
+        assert_eq!(vec!["imported".to_string()], actual[1].decks);
+        assert_eq!(0.0, actual[1].revision_settings.interval);
+        assert_eq!(1300.0, actual[1].revision_settings.memorisation_factor);
+    }
+
+    #[test]
+    fn parse_reports_the_failing_record() {
+        let content = "what?\tthat\t5\nwho?\tme\tsoon\n";
+        let mapping = ColumnMapping::parse("question,answer,interval").unwrap();
+        let actual = parse(content, &mapping, "imported").unwrap_err();
+        assert!(actual.to_string().contains("Unable to import record 2"));
+        match actual {
+            ImportError::Record { source, .. } => assert!(matches!(
+                *source,
+                ImportError::InvalidValue { ref value, .. } if value == "soon"
+            )),
+            other => panic!("expected a Record error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use super::anki::parse_due;
+use super::{ImportError, ImportedCard, MalformedJsonSnafu, MissingSnafu, RecordSnafu};
+use crate::state::card::RevisionSettings;
+use crate::state::deck::HIERARCHY_SEPARATOR;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use snafu::prelude::*;
+use std::collections::HashMap;
+
+// A line of its own that separates a Mochi card's sides.
+const SIDE_SEPARATOR: &str = "---";
+const SECONDS_PER_DAY: f64 = 86400.0;
+
+// The data.json of a .mochi archive, which is a zip file.
+#[derive(Debug, Deserialize)]
+struct Export {
+    decks: Vec<MochiDeck>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MochiDeck {
+    id: String,
+    name: String,
+    #[serde(default, rename = "parent-id")]
+    parent_id: Option<String>,
+    #[serde(default)]
+    cards: Vec<MochiCard>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MochiCard {
+    content: String,
+    #[serde(default)]
+    reviews: Vec<Review>,
+    #[serde(default, rename = "archived?")]
+    archived: bool,
+    #[serde(default, rename = "trashed?")]
+    trashed: Option<MochiDate>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Review {
+    date: MochiDate,
+    due: MochiDate,
+}
+
+// Dates are written either as strings or wrapped in {"date": ...}.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum MochiDate {
+    Plain(String),
+    Wrapped { date: String },
+}
+
+impl MochiDate {
+    fn parse(&self) -> Result<DateTime<Utc>, ImportError> {
+        match self {
+            MochiDate::Plain(date) | MochiDate::Wrapped { date } => parse_due(date),
+        }
+    }
+}
+
+// Sub-decks are nested with "::" under their parents. Archived and trashed cards are left out,
+// and a card's schedule is taken from its last review.
+pub fn parse(content: &str) -> Result<Vec<ImportedCard>, ImportError> {
+    let export: Export = serde_json::from_str(content).context(MalformedJsonSnafu {
+        expected: "a Mochi export",
+    })?;
+    let decks_by_id: HashMap<&str, &MochiDeck> = export
+        .decks
+        .iter()
+        .map(|deck| (deck.id.as_str(), deck))
+        .collect();
+    let mut cards = Vec::new();
+    for deck in export.decks.iter() {
+        let deck_name = qualified_name(deck, &decks_by_id);
+        for (i, card) in deck.cards.iter().enumerate() {
+            if card.archived || card.trashed.is_some() {
+                continue;
+            }
+            let card = to_imported_card(card, &deck_name).with_context(|_| RecordSnafu {
+                record: format!("card {} of deck \"{}\"", i + 1, deck_name),
+            })?;
+            cards.push(card);
+        }
+    }
+    Ok(cards)
+}
+
+fn qualified_name(deck: &MochiDeck, decks_by_id: &HashMap<&str, &MochiDeck>) -> String {
+    let mut names = vec![deck.name.trim()];
+    let mut parent_id = deck.parent_id.as_deref();
+    // Bounded, in case of a cycle.
+    while let Some(parent) = parent_id.and_then(|id| decks_by_id.get(id)) {
+        if names.len() > decks_by_id.len() {
+            break;
+        }
+        names.push(parent.name.trim());
+        parent_id = parent.parent_id.as_deref();
+    }
+    names.reverse();
+    names.join(HIERARCHY_SEPARATOR)
+}
+
+fn to_imported_card(card: &MochiCard, deck_name: &str) -> Result<ImportedCard, ImportError> {
+    let (question, answer) = split_sides(&card.content).context(MissingSnafu {
+        what: "a \"---\" line before the answer",
+    })?;
+    let defaults = RevisionSettings::default();
+    let revision_settings = match card.reviews.last() {
+        Some(review) => {
+            let (reviewed, due) = (review.date.parse()?, review.due.parse()?);
+            let interval = (due - reviewed).num_seconds() as f64 / SECONDS_PER_DAY;
+            RevisionSettings::new(due, interval.max(0.0), defaults.memorisation_factor)
+        }
+        None => defaults,
+    };
+    Ok(ImportedCard {
+        decks: vec![deck_name.to_string()],
+        question: question.to_string(),
+        answer: answer.to_string(),
+        revision_settings,
+    })
+}
+
+// Only the first separator splits, so that the answer may hold horizontal rules of its own.
+fn split_sides(content: &str) -> Option<(&str, &str)> {
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        if line.trim() == SIDE_SEPARATOR {
+            let question = content[..offset].trim();
+            let answer = content[offset + line.len()..].trim();
+            return (!question.is_empty() && !answer.is_empty()).then_some((question, answer));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use chrono::TimeZone;
+    use rstest::*;
+
+    #[rstest]
+    #[case::two_sides("what?\n---\nthat", Some(("what?", "that")))]
+    #[case::rule_in_the_answer("what?\n---\nthis\n\n---\nthat\n", Some(("what?", "this\n\n---\nthat")))]
+    #[case::one_side("what?", None)]
+    #[case::empty_answer("what?\n---\n", None)]
+    fn split_sides(#[case] content: &str, #[case] expected: Option<(&str, &str)>) {
+        assert_eq!(expected, super::split_sides(content));
+    }
+
+    #[test]
+    fn parse() {
+        let content = r#"{
+            "version": 2,
+            "decks": [
+                {"id": "a1", "name": "Spanish", "cards": []},
+                {"id": "b2", "name": "Verbs", "parent-id": "a1", "cards": [
+                    {"content": "hablar?\n---\nto speak", "reviews": [
+                        {"date": {"date": "2030-01-02T00:00:00Z"}, "due": "2030-01-12T12:00:00Z"}
+                    ]},
+                    {"content": "comer?\n---\nto eat"},
+                    {"content": "gone?\n---\nyes", "archived?": true}
+                ]}
+            ]
+        }"#;
+        let actual = super::parse(content).unwrap();
+        assert_eq!(2, actual.len());
+        assert_eq!(vec!["Spanish::Verbs".to_string()], actual[0].decks);
+        assert_eq!("hablar?", actual[0].question);
+        assert_eq!("to speak", actual[0].answer);
+        let expected_due = Utc.with_ymd_and_hms(2030, 1, 12, 12, 0, 0).unwrap();
+        assert_eq!(
+            RevisionSettings::new(expected_due, 10.5, 1300.0),
+            actual[0].revision_settings
+        );
+        assert_eq!(0.0, actual[1].revision_settings.interval);
+    }
+
+    #[test]
+    fn parse_reports_the_failing_card() {
+        let content = r#"{"decks": [{"id": "a", "name": "Bio", "cards": [{"content": "what?"}]}]}"#;
+        let actual = super::parse(content).unwrap_err();
+        assert!(actual
+            .to_string()
+            .contains("Unable to import card 1 of deck \"Bio\""));
+    }
+}
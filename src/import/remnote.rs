@@ -0,0 +1,166 @@
+use super::{ImportError, ImportedCard, OneSidedSnafu};
+use crate::state::card::RevisionSettings;
+use snafu::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Direction {
+    Forward,
+    Backward,
+    Both,
+}
+
+// RemNote's flashcard delimiters, longest first so that ">>>" isn't read as ">>".
+const MULTI_LINE_DELIMITERS: [(&str, Direction); 3] = [
+    (">>>", Direction::Forward),
+    (";;;", Direction::Forward),
+    (":::", Direction::Both),
+];
+const DELIMITERS: [(&str, Direction); 5] = [
+    (">>", Direction::Forward),
+    (";;", Direction::Forward),
+    ("<<", Direction::Backward),
+    ("<>", Direction::Both),
+    ("::", Direction::Both),
+];
+
+// Reads the flashcards of a RemNote markdown export, such as "- Question >> Answer", or
+// "- Question >>>" with its answer in the nested bullets below it. Concepts ("::") and two-way
+// cards ("<>") become a card each way. RemNote doesn't export schedules, so every card is new.
+pub fn parse(content: &str, deck_name: &str) -> Result<Vec<ImportedCard>, ImportError> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut cards = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let (depth, text) = split_bullet(lines[i]);
+        let line_number = i + 1;
+        i += 1;
+        let (question, answer, direction) = match multi_line_question(text) {
+            Some((question, direction)) => {
+                let mut answer_lines = Vec::new();
+                while i < lines.len() && is_nested(lines[i], depth) {
+                    answer_lines.push(lines[i]);
+                    i += 1;
+                }
+                (question, dedent(&answer_lines), direction)
+            }
+            None => match split_inline(text) {
+                Some((question, answer, direction)) => (question, answer.to_string(), direction),
+                None => continue,
+            },
+        };
+        ensure!(
+            !question.is_empty() && !answer.trim().is_empty(),
+            OneSidedSnafu { line: line_number }
+        );
+        let make_card = |question: &str, answer: &str| ImportedCard {
+            decks: vec![deck_name.to_string()],
+            question: question.trim().to_string(),
+            answer: answer.trim().to_string(),
+            revision_settings: RevisionSettings::default(),
+        };
+        match direction {
+            Direction::Forward => cards.push(make_card(question, &answer)),
+            Direction::Backward => cards.push(make_card(&answer, question)),
+            Direction::Both => {
+                cards.push(make_card(question, &answer));
+                cards.push(make_card(&answer, question));
+            }
+        }
+    }
+    Ok(cards)
+}
+
+// The indentation, in characters, and the text after any bullet.
+fn split_bullet(line: &str) -> (usize, &str) {
+    let text = line.trim_start();
+    let depth = line.len() - text.len();
+    let text = ["- ", "* "]
+        .iter()
+        .find_map(|bullet| text.strip_prefix(bullet))
+        .unwrap_or(text);
+    (depth, text.trim_end())
+}
+
+fn is_nested(line: &str, depth: usize) -> bool {
+    line.trim().is_empty() || split_bullet(line).0 > depth
+}
+
+fn dedent(lines: &[&str]) -> String {
+    let depth = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| split_bullet(line).0)
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|line| line.get(depth..).unwrap_or("").trim_end())
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+fn multi_line_question(text: &str) -> Option<(&str, Direction)> {
+    MULTI_LINE_DELIMITERS
+        .iter()
+        .find_map(|(delimiter, direction)| {
+            text.strip_suffix(delimiter)
+                .map(|question| (question.trim(), *direction))
+        })
+}
+
+// Splits at the earliest delimiter in the line.
+fn split_inline(text: &str) -> Option<(&str, &str, Direction)> {
+    DELIMITERS
+        .iter()
+        .filter_map(|(delimiter, direction)| {
+            text.find(delimiter)
+                .map(|start| (start, start + delimiter.len(), *direction))
+        })
+        .min_by_key(|(start, _, _)| *start)
+        .map(|(start, end, direction)| (text[..start].trim(), text[end..].trim(), direction))
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use rstest::*;
+
+    fn sides(cards: &[ImportedCard]) -> Vec<(&str, &str)> {
+        cards
+            .iter()
+            .map(|card| (card.question.as_str(), card.answer.as_str()))
+            .collect()
+    }
+
+    #[rstest]
+    #[case::forward("- hola >> hello", vec![("hola", "hello")])]
+    #[case::descriptor("- hola ;; hello", vec![("hola", "hello")])]
+    #[case::backward("- hola << hello", vec![("hello", "hola")])]
+    #[case::both("- hola <> hello", vec![("hola", "hello"), ("hello", "hola")])]
+    #[case::concept("* Mitochondria :: powerhouse", vec![("Mitochondria", "powerhouse"), ("powerhouse", "Mitochondria")])]
+    #[case::earliest_delimiter("- a >> b :: c", vec![("a", "b :: c")])]
+    #[case::plain_notes("# Biology\n- just a note\n- see https://example.com", vec![])]
+    fn parse(#[case] content: &str, #[case] expected: Vec<(&str, &str)>) {
+        let actual = super::parse(content, "remnote").unwrap();
+        assert_eq!(expected, sides(&actual));
+    }
+
+    #[test]
+    fn parse_multi_line_cards() {
+        let content = "- Cells\n  - Organelles >>>\n    - mitochondria\n      - makes ATP\n    - ribosome\n  - Nucleus >> DNA\n";
+        let actual = super::parse(content, "bio").unwrap();
+        let expected = vec![
+            ("Organelles", "- mitochondria\n  - makes ATP\n- ribosome"),
+            ("Nucleus", "DNA"),
+        ];
+        assert_eq!(expected, sides(&actual));
+        assert_eq!(vec!["bio".to_string()], actual[0].decks);
+    }
+
+    #[test]
+    fn parse_fails_on_a_one_sided_card() {
+        let actual = super::parse("- a >> b\n- what? >>>\n- next", "bio").unwrap_err();
+        assert!(actual.to_string().contains("line 2"));
+    }
+}
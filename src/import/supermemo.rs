@@ -0,0 +1,132 @@
+use super::anki::{parse_due, parse_ease};
+use super::{ImportError, ImportedCard, InvalidValueSnafu, MissingSnafu, RecordSnafu};
+use crate::state::card::RevisionSettings;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use snafu::prelude::*;
+
+// Reads SuperMemo's Q&A text, items of "Q:" and "A:" lines separated by blank lines. Lines that
+// continue a side repeat its prefix. Items exported with their parameters, such as
+// "Interval=12", "LastRepetition=02.01.2030" and "AFactor=2.5", keep their schedules.
+pub fn parse(content: &str, deck_name: &str) -> Result<Vec<ImportedCard>, ImportError> {
+    split_items(content)
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            to_imported_card(item, deck_name).with_context(|_| RecordSnafu {
+                record: format!("item {}", i + 1),
+            })
+        })
+        .collect()
+}
+
+fn split_items(content: &str) -> Vec<Vec<&str>> {
+    let mut items = Vec::new();
+    let mut item = Vec::new();
+    for line in content.lines() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() {
+            if !item.is_empty() {
+                items.push(std::mem::take(&mut item));
+            }
+        } else {
+            item.push(line);
+        }
+    }
+    if !item.is_empty() {
+        items.push(item);
+    }
+    items
+}
+
+fn to_imported_card(item: &[&str], deck_name: &str) -> Result<ImportedCard, ImportError> {
+    let (mut question, mut answer) = (Vec::new(), Vec::new());
+    let (mut interval, mut last_repetition, mut memorisation_factor) = (None, None, None);
+    for line in item.iter() {
+        if let Some(text) = line.strip_prefix("Q:") {
+            question.push(text.trim());
+        } else if let Some(text) = line.strip_prefix("A:") {
+            answer.push(text.trim());
+        } else if let Some((key, value)) = line.split_once('=') {
+            let value = value.trim();
+            match key.trim() {
+                "Interval" => interval = Some(parse_interval(value)?),
+                "LastRepetition" => last_repetition = Some(parse_date(value)?),
+                "AFactor" => memorisation_factor = Some(parse_ease(value)?),
+                _ => {}
+            }
+        }
+    }
+    let question = question.join("\n");
+    let answer = answer.join("\n");
+    ensure!(
+        !question.trim().is_empty(),
+        MissingSnafu { what: "question" }
+    );
+    ensure!(!answer.trim().is_empty(), MissingSnafu { what: "answer" });
+    let defaults = RevisionSettings::default();
+    let interval = interval.unwrap_or(defaults.interval);
+    let due = match last_repetition {
+        Some(last_repetition) => last_repetition + Duration::days(interval as i64),
+        None => defaults.due,
+    };
+    Ok(ImportedCard {
+        decks: vec![deck_name.to_string()],
+        question: question.trim().to_string(),
+        answer: answer.trim().to_string(),
+        revision_settings: RevisionSettings::new(
+            due,
+            interval,
+            memorisation_factor.unwrap_or(defaults.memorisation_factor),
+        ),
+    })
+}
+
+fn parse_interval(value: &str) -> Result<f64, ImportError> {
+    let interval: f64 = value.parse().ok().context(InvalidValueSnafu {
+        what: "interval",
+        value,
+    })?;
+    Ok(interval.max(0.0))
+}
+
+// SuperMemo writes dates as dd.mm.yyyy.
+fn parse_date(value: &str) -> Result<DateTime<Utc>, ImportError> {
+    match NaiveDate::parse_from_str(value, "%d.%m.%Y") {
+        Ok(date) => {
+            let midnight = date.and_hms_opt(0, 0, 0).expect("midnight is a valid time");
+            Ok(DateTime::from_naive_utc_and_offset(midnight, Utc))
+        }
+        Err(_) => parse_due(value),
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn parse() {
+        let content = "Q: What is the powerhouse\nQ: of the cell?\nA: Mitochondria\r\n\r\n\
+                       Q: hola?\nA: hello\nInterval=12\nLastRepetition=02.01.2030\nAFactor=2.5\n\n\n";
+        let actual = super::parse(content, "supermemo").unwrap();
+        assert_eq!(2, actual.len());
+        assert_eq!("What is the powerhouse\nof the cell?", actual[0].question);
+        assert_eq!("Mitochondria", actual[0].answer);
+        assert_eq!(0.0, actual[0].revision_settings.interval);
+        assert_eq!(vec!["supermemo".to_string()], actual[1].decks);
+        let expected_due = Utc.with_ymd_and_hms(2030, 1, 14, 0, 0, 0).unwrap();
+        assert_eq!(
+            RevisionSettings::new(expected_due, 12.0, 2500.0),
+            actual[1].revision_settings
+        );
+    }
+
+    #[test]
+    fn parse_reports_the_failing_item() {
+        let content = "Q: a\nA: b\n\nQ: c\n";
+        let actual = super::parse(content, "supermemo").unwrap_err();
+        assert!(actual.to_string().contains("Unable to import item 2"));
+    }
+}
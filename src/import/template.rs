@@ -0,0 +1,166 @@
+use super::anki::split_records;
+use super::{
+    ImportError, MalformedJsonSnafu, MissingHeaderSnafu, UnknownFieldSnafu, UnknownFormatSnafu,
+};
+use regex::{Captures, Regex};
+use snafu::prelude::*;
+use std::path::Path;
+
+// A record's fields, in the order the data file gives them.
+pub type Row = Vec<(String, String)>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DataFormat {
+    Csv,
+    Json,
+}
+
+impl DataFormat {
+    pub fn from_path(path: &str) -> Result<Self, ImportError> {
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("csv") => Ok(DataFormat::Csv),
+            Some("json") => Ok(DataFormat::Json),
+            _ => UnknownFormatSnafu { path }.fail(),
+        }
+    }
+}
+
+// A CSV file's first line names its columns; a JSON file holds an array of flat objects, whose
+// non-string values are written as JSON.
+pub fn read_rows(content: &str, format: DataFormat) -> Result<Vec<Row>, ImportError> {
+    match format {
+        DataFormat::Csv => {
+            let mut records = split_records(content, ',').into_iter();
+            let header: Vec<String> = records
+                .next()
+                .context(MissingHeaderSnafu)?
+                .into_iter()
+                .map(|name| name.trim().to_string())
+                .collect();
+            Ok(records
+                .map(|record| header.iter().cloned().zip(record).collect())
+                .collect())
+        }
+        DataFormat::Json => {
+            let objects: Vec<serde_json::Map<String, serde_json::Value>> =
+                serde_json::from_str(content).context(MalformedJsonSnafu {
+                    expected: "a JSON array of objects",
+                })?;
+            Ok(objects
+                .into_iter()
+                .map(|object| {
+                    object
+                        .into_iter()
+                        .map(|(name, value)| match value {
+                            serde_json::Value::String(text) => (name, text),
+                            serde_json::Value::Null => (name, String::new()),
+                            value => (name, value.to_string()),
+                        })
+                        .collect()
+                })
+                .collect())
+        }
+    }
+}
+
+// Note text with "{{field}}" placeholders, filled in from a row.
+#[derive(Clone, Debug)]
+pub struct Template {
+    text: String,
+}
+
+impl Template {
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+        }
+    }
+
+    pub fn render(&self, row: &Row) -> Result<String, ImportError> {
+        let mut missing = None;
+        let rendered = placeholder_expression().replace_all(&self.text, |c: &Captures| {
+            let name = &c[1];
+            match row.iter().find(|(field, _)| field == name) {
+                Some((_, value)) => value.clone(),
+                None => {
+                    missing.get_or_insert_with(|| name.to_string());
+                    String::new()
+                }
+            }
+        });
+        if let Some(name) = missing {
+            return UnknownFieldSnafu { name }.fail();
+        }
+        Ok(rendered.to_string())
+    }
+}
+
+fn placeholder_expression() -> Regex {
+    Regex::new(r"\{\{\s*([^{}\s]+)\s*\}\}").expect("the placeholder expression is valid")
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use rstest::*;
+
+    fn row(fields: &[(&str, &str)]) -> Row {
+        fields
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[rstest]
+    #[case::csv(
+        "word,meaning\nhablar,\"to speak, to talk\"\ncomer,to eat\n",
+        DataFormat::Csv
+    )]
+    #[case::json(
+        r#"[{"word": "hablar", "meaning": "to speak, to talk"}, {"word": "comer", "meaning": "to eat"}]"#,
+        DataFormat::Json
+    )]
+    fn read_rows(#[case] content: &str, #[case] format: DataFormat) {
+        let expected = vec![
+            row(&[("word", "hablar"), ("meaning", "to speak, to talk")]),
+            row(&[("word", "comer"), ("meaning", "to eat")]),
+        ];
+        assert_eq!(expected, super::read_rows(content, format).unwrap());
+    }
+
+    #[test]
+    fn read_rows_writes_other_json_values_as_json() {
+        let actual = super::read_rows(r#"[{"n": 2, "tags": ["a"], "x": null}]"#, DataFormat::Json);
+        assert_eq!(
+            vec![row(&[("n", "2"), ("tags", "[\"a\"]"), ("x", "")])],
+            actual.unwrap()
+        );
+    }
+
+    #[test]
+    fn render() {
+        let template =
+            Template::new("tags: spanish\n# Question\n{{word}}\n# Answer\n{{ meaning }}\n");
+        let actual = template.render(&row(&[("word", "comer"), ("meaning", "to eat")]));
+        assert_eq!(
+            "tags: spanish\n# Question\ncomer\n# Answer\nto eat\n",
+            actual.unwrap()
+        );
+        let actual = template.render(&row(&[("word", "comer")])).unwrap_err();
+        assert_eq!(
+            "No field \"meaning\" to fill in the template with",
+            actual.to_string()
+        );
+    }
+
+    #[test]
+    fn data_format_from_path() {
+        assert_eq!(DataFormat::Csv, DataFormat::from_path("words.csv").unwrap());
+        assert_eq!(
+            DataFormat::Json,
+            DataFormat::from_path("a/words.json").unwrap()
+        );
+        assert!(DataFormat::from_path("words.txt").is_err());
+    }
+}
@@ -1,3 +1,7 @@
 #![allow(dead_code)] // TODO remove
 #![allow(unused_variables)] // TODO remove
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod import;
 pub mod state;
@@ -1,32 +1,71 @@
+pub mod audit;
+pub mod builder;
 pub mod card;
+pub mod codec;
+pub mod config;
 pub mod deck;
+pub mod diff;
+pub mod doctor;
+pub mod error;
 pub mod file;
+pub mod filter;
 pub mod hand;
+pub mod id;
+pub mod link_index;
+pub mod postpone;
+pub mod rebalance;
+pub mod review_log;
+pub mod search;
+pub mod shards;
+pub mod simulate;
+pub mod streak;
 mod tools;
 
-use card::{parser::ParsingConfig, Card};
-use deck::Deck;
-use hand::Hand;
+use builder::StateBuilder;
+#[cfg(feature = "scripting")]
+use card::script::ScriptScheduler;
+use card::{
+    parser::{registry::ParserRegistry, ParsingConfig},
+    Card, LoadFailure,
+};
+use chrono::{DateTime, Utc};
+use codec::{StateCodec, StateFormat};
+use config::Config;
+use deck::{DailyGoal, Deck, DeckOptions, DeckStats, Tuning};
+use error::{NoSuchDeckSnafu, StateError, StorageError, UnreadableSnafu, UnwritableSnafu};
+use hand::{Checkpoint, DealError, DealOptions, Hand, Session};
+use id::{CardId, DeckName};
+use postpone::Postponement;
+use rebalance::RebalanceOptions;
+use review_log::ReviewEvent;
 use serde::{Deserialize, Serialize};
-use snafu::{prelude::*, Whatever};
+use simulate::{DailyLoad, SimulationOptions};
+use snafu::prelude::*;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use tools::{Merge, UID};
+use tracing::{debug, instrument};
 
 #[cfg_attr(test, double)]
 use file::FileHandle;
+use file::IO;
 #[cfg(test)]
 use mockall_double::double;
 
-#[cfg(test)]
-use mocks::to_string_pretty as serialise;
-#[cfg(not(test))]
-use ron::ser::to_string_pretty as serialise;
-
-#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 pub struct State {
     card_parsing_config: ParsingConfig,
-    cards: HashMap<String, Card>,
-    decks: HashMap<String, Deck>,
+    cards: HashMap<CardId, Card>,
+    decks: HashMap<DeckName, Deck>,
+    // Keeps only the cards' paths and schedules in the state file. Questions and answers are
+    // read from the notes as cards are dealt.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    metadata_only: bool,
+    // Layered over the settings above from the user's vultan.toml, and never written back.
+    #[serde(skip)]
+    config: Config,
+    #[serde(skip)]
+    configured_parsing_config: Option<ParsingConfig>,
 }
 
 impl State {
@@ -35,25 +74,106 @@ impl State {
             card_parsing_config,
             cards: HashMap::from_iter(Self::uid_value_pairs(cards).into_iter()),
             decks: HashMap::from_iter(Self::uid_value_pairs(decks).into_iter()),
+            ..Self::default()
+        }
+    }
+
+    pub fn builder() -> StateBuilder {
+        StateBuilder::default()
+    }
+
+    // With the vultan.toml beside the state file, if there's one, layered over it.
+    pub fn read(file_handle: FileHandle) -> Result<Self, StorageError> {
+        let state = Self::read_from(&file_handle)?;
+        let config_path = Config::path_beside(file_handle.path());
+        match Config::read_if_present(&config_path)? {
+            Some(config) => state.with_config(config, &config_path),
+            None => Ok(state),
         }
     }
 
-    pub fn read(file_handle: FileHandle) -> Result<Self, Whatever> {
+    pub fn write(&self, file_handle: FileHandle) -> Result<(), StorageError> {
+        self.write_to(&file_handle)
+    }
+
+    // As read, through any IO, such as an EncryptedFileHandle.
+    pub fn read_from(io: &impl IO) -> Result<Self, StorageError> {
+        Self::read_with(io, StateFormat::from_path(io.path()).codec().as_ref())
+    }
+
+    pub fn write_to(&self, io: &impl IO) -> Result<(), StorageError> {
+        self.write_with(io, StateFormat::from_path(io.path()).codec().as_ref())
+    }
+
+    // As read_from, in a format other than the one the file's extension implies.
+    #[instrument(skip_all, fields(path = io.path()))]
+    pub fn read_with(io: &impl IO, codec: &dyn StateCodec) -> Result<Self, StorageError> {
+        let file_path = io.path();
+        let content = io.read().context(UnreadableSnafu { path: file_path })?;
+        let state = Self::deserialise(file_path, &content, codec)?;
+        debug!(
+            bytes = content.len(),
+            cards = state.cards.len(),
+            decks = state.decks.len(),
+            "read State"
+        );
+        Ok(state)
+    }
+
+    pub fn write_with(&self, io: &impl IO, codec: &dyn StateCodec) -> Result<(), StorageError> {
+        let file_path = io.path();
+        let content = self.serialise(file_path, codec)?;
+        io.write(content)
+            .context(UnwritableSnafu { path: file_path })
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn read_async(file_handle: FileHandle) -> Result<Self, StorageError> {
         let file_path = file_handle.path();
         let content = file_handle
-            .read()
-            .with_whatever_context(|_| format!("Unable to read State from {}", file_path))?;
-        ron::from_str(&content)
-            .with_whatever_context(|_| format!("Unable to parse State from {}", file_path))
+            .read_async()
+            .await
+            .context(UnreadableSnafu { path: file_path })?;
+        Self::deserialise(
+            file_path,
+            &content,
+            StateFormat::from_path(file_path).codec().as_ref(),
+        )
     }
 
-    pub fn write(&self, file_handle: FileHandle) -> Result<(), Whatever> {
+    #[cfg(feature = "async")]
+    pub async fn write_async(&self, file_handle: FileHandle) -> Result<(), StorageError> {
         let file_path = file_handle.path();
-        let content = serialise(&self, ron::ser::PrettyConfig::default())
-            .with_whatever_context(|_| format!("Unable to serialise State to {}", file_path))?;
+        let content = self.serialise(
+            file_path,
+            StateFormat::from_path(file_path).codec().as_ref(),
+        )?;
         file_handle
-            .write(content)
-            .with_whatever_context(|_| format!("Unable to write State to {}", file_path))
+            .write_async(content)
+            .await
+            .context(UnwritableSnafu { path: file_path })
+    }
+
+    fn deserialise(
+        file_path: &str,
+        content: &str,
+        codec: &dyn StateCodec,
+    ) -> Result<Self, StorageError> {
+        codec
+            .decode(content)
+            .map_err(|message| StorageError::Malformed {
+                path: file_path.to_string(),
+                message,
+            })
+    }
+
+    fn serialise(&self, file_path: &str, codec: &dyn StateCodec) -> Result<String, StorageError> {
+        codec
+            .encode(&self.stored())
+            .map_err(|message| StorageError::Unserialisable {
+                path: file_path.to_string(),
+                message,
+            })
     }
 
     pub fn with_overriden_cards(self, cards: Vec<Card>) -> Self {
@@ -70,6 +190,91 @@ impl State {
         }
     }
 
+    pub fn with_loaded_cards(self, cards: Vec<Card>) -> Self {
+        let decks = Deck::many_from_cards(&cards);
+        self.with_merged_cards(cards).with_merged_decks(decks)
+    }
+
+    // Moves cards between decks, rebuilding every deck's cards. Decks keep their coefficients and
+    // options, even once empty.
+    pub fn with_redecked_cards(self, cards: Vec<Card>) -> Self {
+        let mut state = self.with_overriden_cards(cards);
+        for deck in state.decks.values_mut() {
+            deck.card_paths.clear();
+        }
+        let cards: Vec<Card> = state.cards.values().cloned().collect();
+        state.with_merged_decks(Deck::many_from_cards(&cards))
+    }
+
+    // Renames a deck and its sub-decks, keeping their coefficients and options. Their cards are
+    // moved with with_redecked_cards.
+    pub fn with_renamed_deck(self, old_name: &str, new_name: &str) -> Self {
+        let decks = self
+            .decks
+            .into_values()
+            .map(|deck| match deck::renamed(&deck.name, old_name, new_name) {
+                Some(name) => Deck {
+                    name: DeckName::from(name),
+                    ..deck
+                },
+                None => deck,
+            })
+            .map(|deck| (deck.uid(), deck))
+            .collect();
+        Self { decks, ..self }
+    }
+
+    // The cards whose notes no longer exist, by path.
+    pub fn missing_card_paths(&self, exists: impl Fn(&str) -> bool) -> Vec<CardId> {
+        let mut paths: Vec<CardId> = self
+            .cards
+            .keys()
+            .filter(|path| !exists(path))
+            .cloned()
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    // Drops the cards from the state and from their decks' card lists. Decks are kept.
+    pub fn with_pruned_cards(self, paths: &[CardId]) -> Self {
+        let mut cards = self.cards;
+        let mut decks = self.decks;
+        for path in paths.iter() {
+            cards.remove(path);
+        }
+        for deck in decks.values_mut() {
+            deck.card_paths.retain(|path| !paths.contains(path));
+        }
+        Self {
+            cards,
+            decks,
+            ..self
+        }
+    }
+
+    pub fn with_review_events(self, events: Vec<ReviewEvent>) -> Self {
+        let mut cards = self.cards;
+        for (path, answer_time) in review_log::answer_times_by_card(&events).into_iter() {
+            if let Some(card) = cards.get_mut(&path) {
+                card.answer_time = answer_time;
+            }
+        }
+        // A schedule read from the note, or changed by hand since the review, is kept.
+        for (path, event) in review_log::latest_by_card(events).into_iter() {
+            if let Some(card) = cards.get_mut(&path) {
+                if !card.scheduled_in_note
+                    && card
+                        .rescheduled_at
+                        .is_none_or(|rescheduled_at| event.reviewed_at > rescheduled_at)
+                {
+                    card.revision_settings = event.revision_settings;
+                }
+            }
+        }
+        Self { cards, ..self }
+    }
+
     pub fn with_card_parsing_config(self, card_parsing_config: ParsingConfig) -> Self {
         Self {
             card_parsing_config,
@@ -77,12 +282,356 @@ impl State {
         }
     }
 
-    pub fn deal(&self, deck_name: &str) -> Result<Hand, String> {
+    pub fn with_metadata_only(self, metadata_only: bool) -> Self {
+        Self {
+            metadata_only,
+            ..self
+        }
+    }
+
+    pub fn metadata_only(&self) -> bool {
+        self.metadata_only
+    }
+
+    // What the config sets wins over the state file's parsing config and deck options; the path
+    // is only used to report a [parsing] key that doesn't fit.
+    pub fn with_config(self, config: Config, path: &str) -> Result<Self, StorageError> {
+        let configured_parsing_config = match config.parsing.is_empty() {
+            true => None,
+            false => Some(config.parsing_config(&self.card_parsing_config, path)?),
+        };
+        Ok(Self {
+            config,
+            configured_parsing_config,
+            ..self
+        })
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    // As written to the state file.
+    pub(crate) fn stored(&self) -> Cow<'_, Self> {
+        if !self.metadata_only {
+            return Cow::Borrowed(self);
+        }
+        let mut stored = self.clone();
+        for card in stored.cards.values_mut() {
+            card.question.clear();
+            card.answer.clear();
+        }
+        Cow::Owned(stored)
+    }
+
+    // As layered by the config, if it sets any of it.
+    pub fn card_parsing_config(&self) -> &ParsingConfig {
+        self.configured_parsing_config
+            .as_ref()
+            .unwrap_or(&self.card_parsing_config)
+    }
+
+    pub fn cards(&self) -> &HashMap<CardId, Card> {
+        &self.cards
+    }
+
+    pub fn decks(&self) -> &HashMap<DeckName, Deck> {
+        &self.decks
+    }
+
+    // Re-parses a single note, e.g. one edited mid-session, keeping the card's schedule.
+    pub fn reload_card(&self, file_handle: FileHandle) -> Result<Card, StateError> {
+        let parser = ParserRegistry::parser_for(self.card_parsing_config().clone())?;
+        let path = file_handle.path().to_string();
+        let card =
+            Card::from(file_handle, &parser).map_err(|error| StateError::UnloadableCard {
+                source: LoadFailure::from(&path, &error),
+            })?;
+        Ok(match self.cards.get(&card.path) {
+            Some(existing) => card.merge(existing),
+            None => card,
+        })
+    }
+
+    pub fn cards_in_deck(&self, deck_name: &str) -> Vec<&Card> {
+        self.cards
+            .values()
+            .filter(|c| c.in_deck(deck_name))
+            .collect()
+    }
+
+    pub fn deck_stats(&self, deck_name: &str) -> Result<DeckStats, StateError> {
+        ensure!(
+            self.decks.contains_key(deck_name),
+            NoSuchDeckSnafu { deck_name }
+        );
+        Ok(DeckStats::from(deck_name, &self.cards_in_deck(deck_name)))
+    }
+
+    // Counts the deck's reviews made since the start of the day, given as since, toward its
+    // daily_goal option, or default_goal when no deck in its chain sets one.
+    pub fn daily_goal(
+        &self,
+        deck_name: &str,
+        default_goal: Option<usize>,
+        events: &[ReviewEvent],
+        since: DateTime<Utc>,
+    ) -> Result<DailyGoal, StateError> {
         let deck = self
             .decks
             .get(deck_name)
-            .ok_or(format!("No deck named '{}' exists.", deck_name))?;
-        Hand::from(deck, self.cards.values().collect())
+            .context(NoSuchDeckSnafu { deck_name })?;
+        let reviewed = events
+            .iter()
+            .filter(|e| e.reviewed_at >= since)
+            .filter(|e| {
+                self.cards
+                    .get(&e.card_path)
+                    .is_some_and(|c| c.in_deck(deck_name))
+            })
+            .count();
+        Ok(DailyGoal {
+            name: deck_name.to_string(),
+            goal: self.resolved_options(deck).daily_goal.or(default_goal),
+            reviewed,
+            due: self.deck_stats(deck_name)?.due,
+        })
+    }
+
+    // Proposes coefficients for the deck from the events of its cards, whose paths must match
+    // this state's.
+    pub fn tune_coefficients(
+        &self,
+        deck_name: &str,
+        events: &[ReviewEvent],
+        target_retention: f64,
+    ) -> Result<Tuning, StateError> {
+        let deck = self
+            .decks
+            .get(deck_name)
+            .context(NoSuchDeckSnafu { deck_name })?;
+        let in_deck: Vec<ReviewEvent> = events
+            .iter()
+            .filter(|e| {
+                self.cards
+                    .get(&e.card_path)
+                    .is_some_and(|c| c.in_deck(deck_name))
+            })
+            .cloned()
+            .collect();
+        let current = self
+            .resolved_options(deck)
+            .interval_coefficients
+            .unwrap_or_default();
+        deck::tuning::tune(&in_deck, &current, target_retention)
+            .map_err(|message| StateError::InvalidOption { message })
+    }
+
+    pub fn deal(&self, deck_name: &str) -> Result<Hand<'_>, DealError> {
+        self.deal_with_options(deck_name, &DealOptions::default())
+    }
+
+    #[instrument(skip(self, deal_options))]
+    pub fn deal_with_options(
+        &self,
+        deck_name: &str,
+        deal_options: &DealOptions,
+    ) -> Result<Hand<'_>, DealError> {
+        let deck = self.get_deck(deck_name)?;
+        let options = self.resolved_options(deck);
+        let deal_options = deal_options
+            .clone()
+            .with_order(deal_options.order.or(options.order))
+            .with_prerequisite_interval(
+                deal_options
+                    .prerequisite_interval
+                    .or(options.prerequisite_interval),
+            )
+            .with_bury_siblings(deal_options.bury_siblings.or(options.bury_siblings));
+        let hand = Hand::from_with_options(deck, self.cards.values().collect(), &deal_options)?
+            .with_limits(options.max_new_cards, options.max_reviews)
+            .with_interleaving(options.interleaving);
+        let hand = self.with_card_content(hand);
+        if hand.is_empty() {
+            return Err(DealError::NoDueCards {
+                deck_name: deck_name.to_string(),
+                next_due: None,
+            });
+        }
+        debug!(cards = hand.len(), "dealt Hand");
+        Self::with_deck_options(hand, deck_name, options)
+    }
+
+    pub fn cram(&self, deck_name: &str) -> Result<Hand<'_>, DealError> {
+        let deck = self.get_deck(deck_name)?;
+        let options = self.resolved_options(deck);
+        let hand = self.with_card_content(Hand::cram(deck, self.cards.values().collect())?);
+        Self::with_deck_options(hand, deck_name, options)
+    }
+
+    // Picks an interrupted session up where its checkpoint left it, with the deck's current
+    // options.
+    pub fn resume(&self, checkpoint: &Checkpoint) -> Result<Session<'_>, DealError> {
+        let deck_name = &checkpoint.deck_name;
+        let deck = self.get_deck(deck_name)?;
+        let options = self.resolved_options(deck);
+        let hand = Hand::resume(deck, self.cards.values().collect(), checkpoint)?;
+        let hand = Self::with_deck_options(self.with_card_content(hand), deck_name, options)?;
+        let revised = checkpoint
+            .revised
+            .iter()
+            .filter_map(|path| self.cards.get(path).cloned())
+            .collect();
+        Ok(Session::from(hand).with_revised(revised, checkpoint.skipped))
+    }
+
+    fn with_deck_options<'h>(
+        hand: Hand<'h>,
+        deck_name: &str,
+        options: DeckOptions,
+    ) -> Result<Hand<'h>, DealError> {
+        Ok(Self::with_scheduler(hand, deck_name, &options)?
+            .with_relearn_delay(options.relearn_delay())
+            .with_score_suggestions(options.score_suggestions.unwrap_or_default())
+            .with_typed_answers(options.typed_answers)
+            .with_grading(options.grading.unwrap_or_default())
+            .with_interval_coefficients(options.interval_coefficients.unwrap_or_default()))
+    }
+
+    #[cfg(feature = "scripting")]
+    fn with_scheduler<'h>(
+        hand: Hand<'h>,
+        deck_name: &str,
+        options: &DeckOptions,
+    ) -> Result<Hand<'h>, DealError> {
+        let Some(path) = &options.scheduling_script else {
+            return Ok(hand);
+        };
+        let scheduler = ScriptScheduler::read(path).map_err(|error| {
+            let messages: Vec<String> =
+                std::iter::successors(Some(&error as &dyn std::error::Error), |e| e.source())
+                    .map(|e| e.to_string())
+                    .collect();
+            DealError::InvalidSchedulingScript {
+                deck_name: deck_name.to_string(),
+                message: messages.join(": "),
+            }
+        })?;
+        Ok(hand.with_scheduler(std::rc::Rc::new(scheduler)))
+    }
+
+    // Rather than silently scheduling the deck some other way.
+    #[cfg(not(feature = "scripting"))]
+    fn with_scheduler<'h>(
+        hand: Hand<'h>,
+        deck_name: &str,
+        options: &DeckOptions,
+    ) -> Result<Hand<'h>, DealError> {
+        match options.scheduling_script {
+            Some(_) => Err(DealError::InvalidSchedulingScript {
+                deck_name: deck_name.to_string(),
+                message: "vultan was built without the scripting feature".to_string(),
+            }),
+            None => Ok(hand),
+        }
+    }
+
+    // Cards whose notes can no longer be parsed are left out of the hand.
+    fn with_card_content<'h>(&self, hand: Hand<'h>) -> Hand<'h> {
+        if !self.metadata_only {
+            return hand;
+        }
+        let Ok(parser) = ParserRegistry::parser_for(self.card_parsing_config().clone()) else {
+            return hand;
+        };
+        hand.with_card_content(|card| {
+            let loaded = Card::from(FileHandle::from(card.path.to_string()), &parser).ok()?;
+            Some((loaded.question, loaded.answer))
+        })
+    }
+
+    // Projects the workload of a deck, or of every deck, from today. Suspended cards are left out
+    // and each card schedules with the coefficients of its first deck.
+    pub fn simulate(
+        &self,
+        deck_name: Option<&str>,
+        options: &SimulationOptions,
+    ) -> Result<Vec<DailyLoad>, StateError> {
+        if let Some(name) = deck_name.filter(|name| !self.decks.contains_key(*name)) {
+            return NoSuchDeckSnafu { deck_name: name }.fail();
+        }
+        // Sorted so that a seeded simulation is repeatable.
+        let mut cards: Vec<&Card> = self
+            .cards
+            .values()
+            .filter(|c| !c.suspended && deck_name.is_none_or(|name| c.in_deck(name)))
+            .collect();
+        cards.sort_by(|a, b| a.path.cmp(&b.path));
+        let cards = cards
+            .into_iter()
+            .map(|c| {
+                let coefficients = c
+                    .decks
+                    .first()
+                    .and_then(|name| self.decks.get(name))
+                    .and_then(|deck| self.resolved_options(deck).interval_coefficients)
+                    .unwrap_or_default();
+                (c.clone(), coefficients)
+            })
+            .collect();
+        simulate::simulate(cards, options, Utc::now())
+            .map_err(|message| StateError::InvalidOption { message })
+    }
+
+    // The deck's cards, or every card, with their new due dates; suspended cards are left alone.
+    pub fn postponed(
+        &self,
+        deck_name: Option<&str>,
+        postponement: &Postponement,
+    ) -> Result<Vec<Card>, StateError> {
+        if let Some(name) = deck_name.filter(|name| !self.decks.contains_key(*name)) {
+            return NoSuchDeckSnafu { deck_name: name }.fail();
+        }
+        let cards = self
+            .cards
+            .values()
+            .filter(|c| !c.suspended && deck_name.is_none_or(|name| c.in_deck(name)))
+            .cloned()
+            .collect();
+        Ok(postpone::postpone(cards, postponement, Utc::now()))
+    }
+
+    // The cards moved to even out the deck's, or every deck's, daily reviews. New and suspended
+    // cards are left alone, as they aren't part of the reviews.
+    pub fn rebalanced(
+        &self,
+        deck_name: Option<&str>,
+        options: &RebalanceOptions,
+    ) -> Result<Vec<Card>, StateError> {
+        if let Some(name) = deck_name.filter(|name| !self.decks.contains_key(*name)) {
+            return NoSuchDeckSnafu { deck_name: name }.fail();
+        }
+        let cards = self
+            .cards
+            .values()
+            .filter(|c| !c.suspended && !c.is_new() && deck_name.is_none_or(|name| c.in_deck(name)))
+            .cloned()
+            .collect();
+        Ok(rebalance::rebalance(cards, options, Utc::now()))
+    }
+
+    pub fn deck_options(&self, deck_name: &str) -> Result<DeckOptions, DealError> {
+        Ok(self.resolved_options(self.get_deck(deck_name)?))
+    }
+
+    fn resolved_options(&self, deck: &Deck) -> DeckOptions {
+        deck::options::resolve(deck, &self.decks, &self.config)
+    }
+
+    fn get_deck(&self, deck_name: &str) -> Result<&Deck, DealError> {
+        self.decks.get(deck_name).ok_or(DealError::NoSuchDeck {
+            deck_name: deck_name.to_string(),
+        })
     }
 
     fn with_merged_cards(self, cards: Vec<Card>) -> Self {
@@ -100,21 +649,21 @@ impl State {
     }
 
     fn override_matching_values<T: UID>(
-        map: HashMap<String, T>,
+        map: HashMap<T::Id, T>,
         items: Vec<T>,
-    ) -> HashMap<String, T> {
+    ) -> HashMap<T::Id, T> {
         let mut m = map;
-        m.extend(items.into_iter().map(|i| (i.uid().to_string(), i)));
+        m.extend(items.into_iter().map(|i| (i.uid(), i)));
         m
     }
 
     fn merge_matching_values<T: Merge<T> + UID>(
-        map: HashMap<String, T>,
+        map: HashMap<T::Id, T>,
         items: Vec<T>,
-    ) -> HashMap<String, T> {
+    ) -> HashMap<T::Id, T> {
         let overriding: Vec<T> = items
             .into_iter()
-            .map(|i| match map.get(i.uid()) {
+            .map(|i| match map.get(&i.uid()) {
                 Some(item) => i.merge(&item),
                 None => i,
             })
@@ -122,11 +671,8 @@ impl State {
         State::override_matching_values(map, overriding)
     }
 
-    fn uid_value_pairs<T: UID>(items: Vec<T>) -> Vec<(String, T)> {
-        items
-            .into_iter()
-            .map(|i| (i.uid().to_string(), i))
-            .collect()
+    fn uid_value_pairs<T: UID>(items: Vec<T>) -> Vec<(T::Id, T)> {
+        items.into_iter().map(|i| (i.uid(), i)).collect()
     }
 }
 
@@ -172,8 +718,10 @@ mod assertions {
 mod unit_tests {
 
     use super::card::revision_settings::RevisionSettings;
+    use super::card::Score;
     use super::deck::interval_coefficients::IntervalCoefficients;
     use super::hand::assertions::assert_hand_contains;
+    use super::hand::QueueOrder;
     use super::tools::test_tools::Expect;
     use super::*;
     use chrono::{DateTime, Duration, Utc};
@@ -186,7 +734,7 @@ mod unit_tests {
 
     fn fake_card_with_path_and_decks(path: &str, decks: Vec<&str>) -> Card {
         let mut card = Card::default();
-        card.path = path.to_string();
+        card.path = path.into();
         card.decks = decks.into_iter().map(|d| d.to_string()).collect();
         card
     }
@@ -203,7 +751,7 @@ mod unit_tests {
 
     fn fake_deck_with_name(name: &str) -> Deck {
         let mut deck = Deck::default();
-        deck.name = name.to_string();
+        deck.name = name.into();
         deck
     }
 
@@ -214,8 +762,11 @@ mod unit_tests {
         let deck = fake_deck_with_name(deck_name);
         let state = State {
             card_parsing_config: card_parsing_config.clone(),
-            cards: HashMap::from([(card.path.clone(), card.clone())]),
-            decks: HashMap::from([(deck.name.clone(), deck.clone())]),
+            cards: HashMap::from([(card.uid(), card.clone())]),
+            decks: HashMap::from([(deck.uid(), deck.clone())]),
+            metadata_only: false,
+            config: Config::default(),
+            configured_parsing_config: None,
         };
         (card_parsing_config, card, deck, state)
     }
@@ -226,6 +777,9 @@ mod unit_tests {
             card_parsing_config: ParsingConfig::default(),
             cards: HashMap::new(),
             decks: HashMap::new(),
+            metadata_only: false,
+            config: Config::default(),
+            configured_parsing_config: None,
         };
         let actual = State::default();
         assert_eq!(expected, actual);
@@ -283,10 +837,63 @@ mod unit_tests {
         );
     }
 
+    #[test]
+    fn with_redecked_cards() {
+        let (_, old_card, deck, state) = fake_state_with_single_card_and_deck();
+        let moved = fake_card_with_path_and_decks(&old_card.path, vec!["another_deck"]);
+        let actual = state.with_redecked_cards(vec![moved.clone()]);
+        assert_eq!(moved, actual.cards()[&moved.path]);
+        assert!(actual.decks()[&deck.name].card_paths.is_empty());
+        assert_eq!(
+            vec![moved.path.clone()],
+            actual.decks()["another_deck"].card_paths
+        );
+    }
+
+    #[test]
+    fn with_renamed_deck() {
+        let (_, _, deck, state) = fake_state_with_single_card_and_deck();
+        let mut sub_deck = fake_deck_with_name("a_deck::sub");
+        sub_deck.interval_coefficients = IntervalCoefficients::new(2.0, 3.0, 0.5);
+        let state = state.with_overriden_decks(vec![sub_deck.clone()]);
+        let actual = state.with_renamed_deck("a_deck", "b_deck");
+        assert!(!actual.decks().contains_key("a_deck"));
+        assert_eq!(
+            Deck {
+                name: "b_deck".into(),
+                ..deck
+            },
+            actual.decks()["b_deck"]
+        );
+        assert_eq!(
+            sub_deck.interval_coefficients,
+            actual.decks()["b_deck::sub"].interval_coefficients
+        );
+    }
+
+    #[test]
+    fn with_pruned_cards() {
+        let (_, card, deck, state) = fake_state_with_single_card_and_deck();
+        let kept = fake_card_with_path_and_decks("kept/path", vec![&deck.name]);
+        let deck = Deck::new(
+            &deck.name,
+            vec![&card.path, &kept.path],
+            IntervalCoefficients::default(),
+        );
+        let state = state
+            .with_overriden_cards(vec![kept.clone()])
+            .with_overriden_decks(vec![deck.clone()]);
+        let missing = state.missing_card_paths(|path| path == kept.path);
+        assert_eq!(vec![card.path.clone()], missing);
+        let actual = state.with_pruned_cards(&missing);
+        assert_eq!(vec![&kept.path], actual.cards().keys().collect::<Vec<_>>());
+        assert_eq!(vec![kept.path], actual.decks()[&deck.name].card_paths);
+    }
+
     #[test]
     fn with_merged_cards_when_new_card_has_same_path_as_old_card() {
         let (parsing_config, old_card, deck, state) = fake_state_with_single_card_and_deck();
-        let mut expected_card = fake_card_with_path_and_decks(old_card.uid(), vec!["another_deck"]);
+        let mut expected_card = fake_card_with_path_and_decks(&old_card.path, vec!["another_deck"]);
         expected_card.revision_settings = old_card.revision_settings.clone();
         let mut new_card = expected_card.clone();
         new_card.revision_settings = RevisionSettings::new(Utc::now(), 9000.0, 1234567.5);
@@ -350,7 +957,7 @@ mod unit_tests {
     fn with_merged_decks_when_new_deck_has_same_name_as_old_deck() {
         let (parsing_config, card, old_deck, state) = fake_state_with_single_card_and_deck();
         let mut expected_deck = old_deck.clone();
-        expected_deck.card_paths = vec!["a/new/path".to_string(), "another/new/path".to_string()];
+        expected_deck.card_paths = vec!["a/new/path".into(), "another/new/path".into()];
         let mut new_deck = expected_deck.clone();
         new_deck.interval_coefficients = IntervalCoefficients::new(31.0, 32.0, 33.0);
         let actual = state.with_merged_decks(vec![new_deck.clone()]);
@@ -380,13 +987,238 @@ mod unit_tests {
         );
     }
 
+    #[test]
+    fn with_config_layers_over_what_is_stored() {
+        let (card_parsing_config, _, deck, state) = fake_state_with_single_card_and_deck();
+        let config: Config = toml::from_str(&format!(
+            "[parsing]\nstrict_loading = true\n\n[decks.{}]\nmax_reviews = 7\n",
+            deck.name
+        ))
+        .unwrap();
+        let actual = state.clone().with_config(config, "vultan.toml").unwrap();
+        assert!(actual.card_parsing_config().strict_loading);
+        assert_eq!(
+            card_parsing_config.deck_delimiter,
+            actual.card_parsing_config().deck_delimiter
+        );
+        assert_eq!(
+            Some(7),
+            actual.deck_options(&deck.name).unwrap().max_reviews
+        );
+        assert_eq!(
+            ron::to_string(&state).unwrap(),
+            ron::to_string(&actual).unwrap()
+        );
+    }
+
+    #[test]
+    fn with_loaded_cards() {
+        let (parsing_config, old_card, old_deck, state) = fake_state_with_single_card_and_deck();
+        let mut old_deck = old_deck;
+        old_deck.interval_coefficients = IntervalCoefficients::new(31.0, 32.0, 33.0);
+        let state = state.with_overriden_decks(vec![old_deck.clone()]);
+        let mut reloaded_card = old_card.clone();
+        reloaded_card.question = "a new question?".to_string();
+        reloaded_card.revision_settings = RevisionSettings::new(Utc::now(), 9000.0, 1234567.5);
+        let new_card = fake_card_with_path_and_decks("some/other/path", vec!["another_deck"]);
+        let mut expected_card = reloaded_card.clone();
+        expected_card.revision_settings = old_card.revision_settings.clone();
+        let mut expected_old_deck = old_deck.clone();
+        expected_old_deck.card_paths = vec![old_card.uid()];
+        let mut expected_new_deck = fake_deck_with_name("another_deck");
+        expected_new_deck.card_paths = vec![new_card.uid()];
+        let actual = state.with_loaded_cards(vec![reloaded_card, new_card.clone()]);
+        assertions::assert_state_eq(
+            &actual,
+            &parsing_config,
+            vec![Expect::DoesContain(expected_card), Expect::DoesContain(new_card)],
+            vec![
+                Expect::DoesContain(expected_old_deck),
+                Expect::DoesContain(expected_new_deck),
+            ],
+        );
+    }
+
+    #[test]
+    fn simulate_leaves_out_suspended_cards_and_other_decks() {
+        let (_, card, deck, state) = fake_state_with_single_card_and_deck();
+        let due = |card: Card| {
+            card.with_revision_settings(RevisionSettings::new(Utc::now(), 1.0, 1300.0))
+        };
+        let suspended = fake_card_with_path_and_decks("some/suspended/path", vec![&deck.name]);
+        let other_card = fake_card_with_path_and_decks("some/other/path", vec!["another_deck"]);
+        let state = state.with_overriden_cards(vec![
+            due(card),
+            due(suspended).with_suspended(true),
+            due(other_card),
+        ]);
+        let options = simulate::SimulationOptions::default()
+            .with_days(1)
+            .with_new_cards_per_day(0);
+        let reviews = |deck_name| state.simulate(deck_name, &options).unwrap()[0].reviews;
+        assert_eq!(1, reviews(Some(&deck.name)));
+        assert_eq!(2, reviews(None));
+        assert!(state.simulate(Some("does not exist"), &options).is_err());
+    }
+
+    #[test]
+    fn postponed_leaves_out_suspended_cards_and_other_decks() {
+        let (_, card, deck, state) = fake_state_with_single_card_and_deck();
+        let suspended = fake_card_with_path_and_decks("some/suspended/path", vec![&deck.name]);
+        let other_card = fake_card_with_path_and_decks("some/other/path", vec!["another_deck"]);
+        let state = state.with_overriden_cards(vec![suspended.with_suspended(true), other_card]);
+        let postponement = Postponement::default().with_days(7);
+        let paths = |deck_name| -> Vec<String> {
+            let mut paths: Vec<String> = state
+                .postponed(deck_name, &postponement)
+                .unwrap()
+                .into_iter()
+                .map(|c| c.path.into_string())
+                .collect();
+            paths.sort();
+            paths
+        };
+        assert_eq!(vec![card.path.clone()], paths(Some(&deck.name)));
+        assert_eq!(
+            vec!["some/other/path".to_string(), card.path.to_string()],
+            paths(None)
+        );
+        assert!(state
+            .postponed(Some("does not exist"), &postponement)
+            .is_err());
+    }
+
+    #[test]
+    fn tune_coefficients_only_uses_the_decks_cards() {
+        let (_, card, deck, state) = fake_state_with_single_card_and_deck();
+        let other_card = fake_card_with_path_and_decks("some/other/path", vec!["another_deck"]);
+        let state = state.with_overriden_cards(vec![other_card.clone()]);
+        let now = Utc::now();
+        let events: Vec<ReviewEvent> = [&card, &other_card]
+            .iter()
+            .flat_map(|c| {
+                let reviewed = (*c)
+                    .clone()
+                    .with_revision_settings(RevisionSettings::new(now, 1.0, 1300.0));
+                vec![
+                    ReviewEvent::new(&reviewed, Score::Pass, "laptop", now - Duration::days(1)),
+                    ReviewEvent::new(&reviewed, Score::Pass, "laptop", now),
+                ]
+            })
+            .collect();
+        let actual = state.tune_coefficients(&deck.name, &events, 0.9);
+        assert!(matches!(
+            actual.unwrap_err(),
+            StateError::InvalidOption { message } if message.starts_with("Only 1 review(s)")
+        ));
+        let actual = state.tune_coefficients("does not exist", &events, 0.9);
+        assert!(matches!(
+            actual.unwrap_err(),
+            StateError::NoSuchDeck { deck_name } if deck_name == "does not exist"
+        ));
+    }
+
+    #[test]
+    fn cards_in_deck() {
+        let (_, card, deck, state) = fake_state_with_single_card_and_deck();
+        let other_card = fake_card_with_path_and_decks("some/other/path", vec!["another_deck"]);
+        let state = state.with_overriden_cards(vec![other_card]);
+        assert_eq!(vec![&card], state.cards_in_deck(&deck.name));
+        assert!(state.cards_in_deck("does not exist").is_empty());
+    }
+
+    #[test]
+    fn deck_stats() {
+        let (_, card, deck, state) = fake_state_with_single_card_and_deck();
+        let expected = DeckStats::from(&deck.name, &[&card]);
+        assert_eq!(expected, state.deck_stats(&deck.name).unwrap());
+    }
+
+    #[test]
+    fn daily_goal() {
+        let (_, card, deck, state) = fake_state_with_single_card_and_deck();
+        let now = Utc::now();
+        let other_card = fake_card_with_path_and_decks("some/other/path", vec!["another_deck"]);
+        let events = [
+            ReviewEvent::new(&card, Score::Pass, "laptop", now - Duration::days(1)),
+            ReviewEvent::new(&card, Score::Pass, "laptop", now),
+            ReviewEvent::new(&other_card, Score::Pass, "laptop", now),
+        ];
+        let since = now - Duration::hours(1);
+        let expected = DailyGoal {
+            name: deck.name.to_string(),
+            goal: Some(20),
+            reviewed: 1,
+            due: state.deck_stats(&deck.name).unwrap().due,
+        };
+        let actual = state.daily_goal(&deck.name, Some(20), &events, since);
+        assert_eq!(expected, actual.unwrap());
+        let mut deck = deck;
+        deck.options = DeckOptions::default().with_daily_goal(Some(5));
+        let state = state.with_overriden_decks(vec![deck.clone()]);
+        let actual = state.daily_goal(&deck.name, Some(20), &events, since);
+        assert_eq!(Some(5), actual.unwrap().goal);
+        assert!(state.daily_goal("nope", None, &events, since).is_err());
+    }
+
+    #[test]
+    fn deck_stats_when_deck_does_not_exist() {
+        let deck_name = "Does not exist";
+        let actual = State::default().deck_stats(deck_name);
+        assert_eq!(
+            "No deck named 'Does not exist' exists.",
+            actual.unwrap_err().to_string()
+        );
+    }
+
     #[test]
     fn deal_when_deck_does_not_exist() {
         let state = State::default();
         let deck_name = "Does not exist";
         let actual = state.deal(deck_name);
         assert!(actual.is_err());
-        assert!(actual.unwrap_err().contains(deck_name));
+        assert!(actual.unwrap_err().to_string().contains(deck_name));
+    }
+
+    #[test]
+    fn cram_when_deck_does_not_exist() {
+        let deck_name = "Does not exist";
+        let expected = DealError::NoSuchDeck {
+            deck_name: deck_name.to_string(),
+        };
+        assert_eq!(expected, State::default().cram(deck_name).unwrap_err());
+    }
+
+    #[test]
+    fn deal_with_an_unusable_scheduling_script() {
+        let deck_name = "a";
+        let card = fake_card_with_path_decks_and_due_date("a/some", vec![deck_name], Utc::now());
+        let deck = fake_deck_with_name(deck_name).with_options(
+            DeckOptions::default()
+                .with_scheduling_script(Some("/nonexistent/vultan/script.rhai".to_string())),
+        );
+        let state = State::new(ParsingConfig::default(), vec![card], vec![deck]);
+        let actual = state.deal(deck_name).unwrap_err();
+        assert!(matches!(actual, DealError::InvalidSchedulingScript { .. }));
+    }
+
+    #[test]
+    fn cram() {
+        let future = Utc::now() + Duration::days(10);
+        let deck_name = "a";
+        let card = fake_card_with_path_decks_and_due_date("a/some", vec![deck_name], future);
+        let deck = fake_deck_with_name(deck_name);
+        let state = State::new(
+            ParsingConfig::default(),
+            vec![card.clone()],
+            vec![deck.clone()],
+        );
+        let actual = state.cram(deck_name).unwrap();
+        assert_hand_contains(
+            &actual,
+            &deck.interval_coefficients,
+            &[Expect::DoesContain(card)],
+        );
     }
 
     #[test]
@@ -410,15 +1242,18 @@ mod unit_tests {
         let state = State {
             card_parsing_config: card_parsing_config.clone(),
             cards: HashMap::from([
-                (deck_a_due_card.path.clone(), deck_a_due_card.clone()),
-                (deck_a_other_card.path.clone(), deck_a_other_card.clone()),
-                (deck_b_due_card.path.clone(), deck_b_due_card.clone()),
-                (deck_b_other_card.path.clone(), deck_b_other_card.clone()),
+                (deck_a_due_card.uid(), deck_a_due_card.clone()),
+                (deck_a_other_card.uid(), deck_a_other_card.clone()),
+                (deck_b_due_card.uid(), deck_b_due_card.clone()),
+                (deck_b_other_card.uid(), deck_b_other_card.clone()),
             ]),
             decks: HashMap::from([
-                (deck_a.name.clone(), deck_a.clone()),
-                (deck_b.name.clone(), deck_b.clone()),
+                (deck_a.uid(), deck_a.clone()),
+                (deck_b.uid(), deck_b.clone()),
             ]),
+            metadata_only: false,
+            config: Config::default(),
+            configured_parsing_config: None,
         };
         let expected_queued_items = vec![Expect::DoesContain(deck_b_due_card)];
         let actual = state.deal(deck_name_b).unwrap();
@@ -429,6 +1264,195 @@ mod unit_tests {
         );
     }
 
+    #[test]
+    fn deal_applies_options_inherited_from_parent_decks() {
+        let past = Utc::now() - Duration::days(10);
+        let cards = vec![
+            fake_card_with_path_decks_and_due_date("a", vec!["lang::spanish"], past),
+            fake_card_with_path_decks_and_due_date("b", vec!["lang::spanish"], past),
+        ];
+        let coefficients = IntervalCoefficients::new(2.0, 3.0, 0.5);
+        let state = State::default().with_loaded_cards(cards);
+        let parent = state.decks()["lang"].clone().with_options(
+            DeckOptions::default()
+                .with_interval_coefficients(coefficients.clone())
+                .with_max_new_cards(Some(1)),
+        );
+        let state = state.with_overriden_decks(vec![parent]);
+        let hand = state.deal("lang::spanish").unwrap();
+        assert_eq!(1, hand.cards().count());
+        assert_eq!(
+            Some(coefficients.clone()),
+            state
+                .deck_options("lang::spanish")
+                .unwrap()
+                .interval_coefficients
+        );
+        let card = hand.cards().next().unwrap().clone();
+        let expected = card.transform(Score::Easy, &coefficients);
+        let actual = hand.revise_until_none_fail(|_| Score::Easy);
+        assert_eq!(
+            expected.revision_settings.interval,
+            actual[0].revision_settings.interval
+        );
+    }
+
+    #[test]
+    fn deal_with_options_skips_excluded_tags() {
+        let past = Utc::now() - Duration::days(10);
+        let keep = fake_card_with_path_decks_and_due_date("keep", vec!["a"], past);
+        let skip = fake_card_with_path_decks_and_due_date("skip", vec!["a"], past)
+            .with_tags(vec!["needs-rewrite".to_string()]);
+        let state = State::default().with_loaded_cards(vec![keep.clone(), skip]);
+        let options = DealOptions::default().with_exclude_tags(vec!["needs-rewrite".to_string()]);
+        let actual = state.deal_with_options("a", &options).unwrap();
+        assert_hand_contains(
+            &actual,
+            &IntervalCoefficients::default(),
+            &[Expect::DoesContain(keep)],
+        );
+        let options = DealOptions::default().with_include_tags(vec!["missing".to_string()]);
+        let actual = state.deal_with_options("a", &options).unwrap_err();
+        assert!(matches!(actual, DealError::NoDueCards { .. }));
+    }
+
+    #[test]
+    fn deal_orders_cards_by_the_deck_option_unless_overridden() {
+        let now = Utc::now();
+        let cards = vec![
+            fake_card_with_path_decks_and_due_date("a", vec!["a"], now - Duration::days(1)),
+            fake_card_with_path_decks_and_due_date("b", vec!["a"], now - Duration::days(5)),
+            fake_card_with_path_decks_and_due_date("c", vec!["a"], now - Duration::days(3)),
+        ];
+        let state = State::default().with_loaded_cards(cards);
+        let deck = state.decks()["a"]
+            .clone()
+            .with_options(DeckOptions::default().with_order(Some(QueueOrder::OldestDueFirst)));
+        let state = state.with_overriden_decks(vec![deck]);
+        let paths =
+            |hand: Hand| -> Vec<String> { hand.cards().map(|c| c.path.to_string()).collect() };
+        assert_eq!(vec!["b", "c", "a"], paths(state.deal("a").unwrap()));
+        // Equally easy cards fall back to path order.
+        let options = DealOptions::default().with_order(Some(QueueOrder::EaseAscending));
+        let actual = paths(state.deal_with_options("a", &options).unwrap());
+        assert_eq!(vec!["a", "b", "c"], actual);
+    }
+
+    #[test]
+    fn deal_buries_siblings_by_the_deck_option_unless_overridden() {
+        let now = Utc::now();
+        let cards = vec![
+            fake_card_with_path_decks_and_due_date("n.md#1", vec!["a"], now),
+            fake_card_with_path_decks_and_due_date("n.md#2", vec!["a"], now),
+        ];
+        let state = State::default().with_loaded_cards(cards);
+        let deck = state.decks()["a"]
+            .clone()
+            .with_options(DeckOptions::default().with_bury_siblings(Some(true)));
+        let state = state.with_overriden_decks(vec![deck]);
+        assert_eq!(1, state.deal("a").unwrap().len());
+        let options = DealOptions::default().with_bury_siblings(Some(false));
+        assert_eq!(2, state.deal_with_options("a", &options).unwrap().len());
+    }
+
+    #[test]
+    fn deal_with_max_cards_keeps_the_oldest_due() {
+        let now = Utc::now();
+        let cards = vec![
+            fake_card_with_path_decks_and_due_date("a", vec!["a"], now - Duration::days(1)),
+            fake_card_with_path_decks_and_due_date("b", vec!["a"], now - Duration::days(5)),
+            fake_card_with_path_decks_and_due_date("c", vec!["a"], now - Duration::days(3)),
+        ];
+        let state = State::default().with_loaded_cards(cards);
+        let options = DealOptions::default()
+            .with_order(Some(QueueOrder::EaseAscending))
+            .with_max_cards(Some(2));
+        let paths =
+            |hand: Hand| -> Vec<String> { hand.cards().map(|c| c.path.to_string()).collect() };
+        let actual = paths(state.deal_with_options("a", &options).unwrap());
+        assert_eq!(vec!["b", "c"], actual);
+    }
+
+    #[test]
+    fn deal_with_review_ahead_includes_cards_due_soon() {
+        let now = Utc::now();
+        let cards = vec![
+            fake_card_with_path_decks_and_due_date("due", vec!["a"], now - Duration::days(1)),
+            fake_card_with_path_decks_and_due_date("soon", vec!["a"], now + Duration::days(2)),
+            fake_card_with_path_decks_and_due_date("later", vec!["a"], now + Duration::days(5)),
+        ];
+        let state = State::default().with_loaded_cards(cards);
+        let options = DealOptions::default()
+            .with_order(Some(QueueOrder::OldestDueFirst))
+            .with_review_ahead(Some(Duration::days(3)));
+        let paths =
+            |hand: Hand| -> Vec<String> { hand.cards().map(|c| c.path.to_string()).collect() };
+        let actual = paths(state.deal_with_options("a", &options).unwrap());
+        assert_eq!(vec!["due", "soon"], actual);
+    }
+
+    #[test]
+    fn resume_deals_the_checkpointed_queue() {
+        let past = Utc::now() - Duration::days(10);
+        let cards = ["a", "b", "c"]
+            .map(|path| fake_card_with_path_decks_and_due_date(path, vec!["a"], past))
+            .to_vec();
+        let state = State::default().with_loaded_cards(cards);
+        let checkpoint = Checkpoint {
+            deck_name: "a".to_string(),
+            seed: Some(3),
+            queue: vec!["c".to_string(), "gone".to_string(), "a".to_string()],
+            revised: vec!["b".to_string()],
+            skipped: 0,
+        };
+        let session = state.resume(&checkpoint).unwrap();
+        assert_eq!(Some("c"), session.next_card().map(|c| c.path.as_str()));
+        assert_eq!(2, session.remaining());
+        assert_eq!(1, session.progress().revised);
+        let missing_deck = Checkpoint {
+            deck_name: "missing".to_string(),
+            ..checkpoint
+        };
+        assert!(state.resume(&missing_deck).is_err());
+    }
+
+    #[test]
+    fn deal_with_a_seed_repeats_the_order() {
+        let past = Utc::now() - Duration::days(10);
+        let cards = (0..20)
+            .map(|i| fake_card_with_path_decks_and_due_date(&i.to_string(), vec!["a"], past))
+            .collect();
+        let state = State::default().with_loaded_cards(cards);
+        let options = DealOptions::default().with_seed(Some(3));
+        let paths =
+            |hand: Hand| -> Vec<String> { hand.cards().map(|c| c.path.to_string()).collect() };
+        let first = paths(state.deal_with_options("a", &options).unwrap());
+        let second = paths(state.deal_with_options("a", &options).unwrap());
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn deal_from_a_parent_deck_pulls_from_its_children() {
+        let past = Utc::now() - Duration::days(10);
+        let verb =
+            fake_card_with_path_decks_and_due_date("verb", vec!["lang::spanish::verbs"], past);
+        let noun = fake_card_with_path_decks_and_due_date("noun", vec!["lang::french"], past);
+        let other = fake_card_with_path_decks_and_due_date("other", vec!["language"], past);
+        let state = State::default().with_loaded_cards(vec![verb.clone(), noun.clone(), other]);
+        let deck = &state.decks()["lang"];
+        assert_eq!(2, state.deck_stats("lang").unwrap().cards);
+        assert_hand_contains(
+            &state.deal("lang").unwrap(),
+            &deck.interval_coefficients,
+            &[Expect::DoesContain(verb.clone()), Expect::DoesContain(noun)],
+        );
+        assert_hand_contains(
+            &state.deal("lang::spanish").unwrap(),
+            &deck.interval_coefficients,
+            &[Expect::DoesContain(verb)],
+        );
+    }
+
     #[test]
     fn read() {
         let expected_due_date = Utc::now();
@@ -469,6 +1493,42 @@ mod unit_tests {
         );
     }
 
+    #[test]
+    fn serialise_leaves_out_card_content_when_metadata_only() {
+        let (_, card, _, state) = fake_state_with_single_card_and_deck();
+        let mut card = card.clone();
+        card.question = "a question".to_string();
+        let state = state
+            .with_overriden_cards(vec![card])
+            .with_metadata_only(true);
+        let codec = codec::JsonCodec;
+        let content = state.serialise("state.json", &codec).unwrap();
+        assert!(!content.contains("a question"));
+        let actual = State::deserialise("state.json", &content, &codec).unwrap();
+        assert!(actual.metadata_only());
+        assert_eq!(state.stored().into_owned(), actual);
+    }
+
+    #[test]
+    fn reload_card_keeps_the_schedule_of_a_known_card() {
+        let (_, card, _, state) = fake_state_with_single_card_and_deck();
+        let mut known = card.with_suspended(true);
+        known.revision_settings = RevisionSettings::new(Utc::now(), 3.0, 1500.0);
+        let state = state.with_overriden_cards(vec![known.clone()]);
+        let mut mock_file_handle = FileHandle::new();
+        mock_file_handle.expect_read().returning(|| {
+            Ok("tags: a_deck///b_deck\n# Question\nnew q\n# Answer\nnew a\n----\n".to_string())
+        });
+        mock_file_handle
+            .expect_path()
+            .return_const(known.path.to_string());
+        let actual = state.reload_card(mock_file_handle).unwrap();
+        assert_eq!("new q", actual.question);
+        assert_eq!(vec!["a_deck", "b_deck"], actual.decks);
+        assert_eq!(known.revision_settings, actual.revision_settings);
+        assert!(actual.suspended);
+    }
+
     #[test]
     fn read_when_file_handle_read_fails() {
         let state_str = "oh dear";
@@ -497,14 +1557,75 @@ mod unit_tests {
             .expect_read()
             .returning(move || Ok(state_content.clone()));
         mock_file_handle.expect_path().return_const(state_path);
-        let actual = State::read(mock_file_handle);
-        assert!(actual.is_err());
+        let actual = State::read(mock_file_handle).unwrap_err();
+        assert!(matches!(&actual, StorageError::Malformed { path, .. } if *path == state_str));
         assert!(actual
-            .unwrap_err()
             .to_string()
             .contains(&format!("Unable to parse State from {}", state_str)));
     }
 
+    #[test]
+    fn with_review_events_applies_the_latest_event_to_known_cards() {
+        let (_, card, _, state) = fake_state_with_single_card_and_deck();
+        let earlier = Utc::now() - Duration::days(1);
+        let later = Utc::now();
+        let mut reviewed = card.clone();
+        reviewed.revision_settings = RevisionSettings::new(later, 3.0, 1500.0);
+        let mut stale = card.clone();
+        stale.revision_settings = RevisionSettings::new(earlier, 1.0, 1300.0);
+        let mut unknown = card.clone();
+        unknown.path = "unknown".into();
+        let events = vec![
+            ReviewEvent::new(&reviewed, Score::Pass, "phone", later),
+            ReviewEvent::new(&stale, Score::Pass, "laptop", earlier),
+            ReviewEvent::new(&unknown, Score::Pass, "laptop", later),
+        ];
+        let actual = state.with_review_events(events);
+        assert_eq!(1, actual.cards.len());
+        assert_eq!(reviewed, actual.cards[&card.path]);
+    }
+
+    #[test]
+    fn with_review_events_keeps_schedules_changed_since_or_read_from_the_note() {
+        let (_, card, _, _) = fake_state_with_single_card_and_deck();
+        let reviewed_at = Utc::now() - Duration::days(1);
+        let mut reviewed = card.clone();
+        reviewed.revision_settings = RevisionSettings::new(reviewed_at, 3.0, 1500.0);
+        let events = vec![ReviewEvent::new(
+            &reviewed,
+            Score::Pass,
+            "phone",
+            reviewed_at,
+        )];
+        let rescheduled =
+            card.clone()
+                .with_revision_settings(RevisionSettings::new(Utc::now(), 0.0, 1300.0));
+        let state = State::default().with_loaded_cards(vec![rescheduled
+            .clone()
+            .with_rescheduled_at(Some(Utc::now()))]);
+        let actual = state.with_review_events(events.clone());
+        assert_eq!(
+            rescheduled.revision_settings,
+            actual.cards[&card.path].revision_settings
+        );
+        let state = State::default().with_loaded_cards(vec![rescheduled
+            .clone()
+            .with_rescheduled_at(Some(reviewed_at - Duration::days(1)))]);
+        let actual = state.with_review_events(events.clone());
+        assert_eq!(
+            reviewed.revision_settings,
+            actual.cards[&card.path].revision_settings
+        );
+        let state = State::default().with_loaded_cards(vec![rescheduled
+            .clone()
+            .with_note_schedule(rescheduled.revision_settings.clone())]);
+        let actual = state.with_review_events(events);
+        assert_eq!(
+            rescheduled.revision_settings,
+            actual.cards[&card.path].revision_settings
+        );
+    }
+
     #[test]
     fn write() {
         let due_date = Utc::now();
@@ -572,4 +1693,32 @@ mod unit_tests {
             .to_string()
             .contains(&format!("Unable to serialise State to {}", state_path)));
     }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn read_async_and_write_async() {
+        let card = fake_card_with_path_decks_and_due_date("a_card", vec!["a"], Utc::now());
+        let state = State::new(
+            ParsingConfig::default(),
+            vec![card],
+            vec![fake_deck_with_name("a")],
+        );
+        let content =
+            ron::ser::to_string_pretty(&state, ron::ser::PrettyConfig::default()).unwrap();
+        let written = content.clone();
+        let mut mock_file_handle = FileHandle::new();
+        mock_file_handle.expect_path().return_const("".to_string());
+        mock_file_handle
+            .expect_write_async()
+            .with(mockall::predicate::eq(written))
+            .returning(move |_| Ok(()));
+        state.write_async(mock_file_handle).await.unwrap();
+        let mut mock_file_handle = FileHandle::new();
+        mock_file_handle.expect_path().return_const("".to_string());
+        mock_file_handle
+            .expect_read_async()
+            .returning(move || Ok(content.clone()));
+        let actual = State::read_async(mock_file_handle).await.unwrap();
+        assert_eq!(state, actual);
+    }
 }
@@ -0,0 +1,192 @@
+use super::card::Card;
+use super::diff::{self, ScheduleChange};
+use super::file::IO;
+use super::id::{CardId, DeckName};
+use super::State;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use snafu::{prelude::*, Whatever};
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum Mutation {
+    CardAdded { path: String },
+    CardRemoved { path: String },
+    CardRescheduled(ScheduleChange),
+    // Anything but the schedule, such as the content, decks or flags.
+    CardChanged { path: String },
+    DeckAdded { name: String },
+    DeckRemoved { name: String },
+    DeckChanged { name: String },
+    ConfigChanged,
+}
+
+// A mutation saved to the state file, and the command line that made it.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct AuditEntry {
+    pub at: DateTime<Utc>,
+    pub command: String,
+    pub mutation: Mutation,
+}
+
+// What saving the second state over the first changes, cards then decks, each sorted by key.
+pub fn mutations(before: &State, after: &State) -> Vec<Mutation> {
+    let mut mutations = Vec::new();
+    if before.card_parsing_config() != after.card_parsing_config() {
+        mutations.push(Mutation::ConfigChanged);
+    }
+    let mut card_paths: Vec<&CardId> = before.cards().keys().chain(after.cards().keys()).collect();
+    card_paths.sort();
+    card_paths.dedup();
+    let rescheduled = diff::schedule_changes(before, after);
+    for path in card_paths {
+        match (before.cards().get(path), after.cards().get(path)) {
+            (None, Some(_)) => mutations.push(Mutation::CardAdded {
+                path: path.to_string(),
+            }),
+            (Some(_), None) => mutations.push(Mutation::CardRemoved {
+                path: path.to_string(),
+            }),
+            (Some(old), Some(new)) if old != new => {
+                if let Some(change) = rescheduled.iter().find(|c| &c.path == path) {
+                    mutations.push(Mutation::CardRescheduled(change.clone()));
+                }
+                // What a review or reschedule updates along with the schedule isn't a change of
+                // its own.
+                let unscheduled = Card {
+                    revision_settings: old.revision_settings.clone(),
+                    answer_time: old.answer_time,
+                    rescheduled_at: old.rescheduled_at,
+                    scheduled_in_note: old.scheduled_in_note,
+                    ..new.clone()
+                };
+                if &unscheduled != old {
+                    mutations.push(Mutation::CardChanged {
+                        path: path.to_string(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    let mut deck_names: Vec<&DeckName> =
+        before.decks().keys().chain(after.decks().keys()).collect();
+    deck_names.sort();
+    deck_names.dedup();
+    for name in deck_names.into_iter().map(DeckName::to_string) {
+        match (before.decks().get(&name), after.decks().get(&name)) {
+            (None, Some(_)) => mutations.push(Mutation::DeckAdded { name }),
+            (Some(_), None) => mutations.push(Mutation::DeckRemoved { name }),
+            (Some(old), Some(new)) if old != new => mutations.push(Mutation::DeckChanged { name }),
+            _ => {}
+        }
+    }
+    mutations
+}
+
+pub fn append(file_handle: impl IO, entries: &[AuditEntry]) -> Result<(), Whatever> {
+    let file_path = file_handle.path();
+    let mut lines = String::new();
+    for entry in entries {
+        let line = ron::to_string(entry).with_whatever_context(|_| {
+            format!("Unable to serialise AuditEntry to {}", file_path)
+        })?;
+        lines.push_str(&line);
+        lines.push('\n');
+    }
+    file_handle
+        .append(lines)
+        .with_whatever_context(|_| format!("Unable to append AuditEntry to {}", file_path))
+}
+
+// As with review logs, lines that fail to parse are skipped.
+pub fn read(file_handle: impl IO) -> Result<Vec<AuditEntry>, Whatever> {
+    let file_path = file_handle.path();
+    let content = file_handle
+        .read()
+        .with_whatever_context(|_| format!("Unable to read audit log from {}", file_path))?;
+    Ok(content
+        .lines()
+        .filter_map(|line| ron::from_str(line).ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use crate::state::card::{AnswerTime, RevisionSettings};
+    use crate::state::deck::Deck;
+    use std::time::Duration;
+
+    fn make_card(path: &str, interval: f64, flagged: bool) -> Card {
+        Card {
+            path: path.into(),
+            decks: vec!["deck".to_string()],
+            flagged,
+            ..Card::default()
+        }
+        .with_revision_settings(RevisionSettings::new(
+            Default::default(),
+            interval,
+            1300.0,
+        ))
+    }
+
+    #[test]
+    fn mutations_between_states() {
+        let before = State::default().with_loaded_cards(vec![
+            make_card("flagged", 1.0, false),
+            make_card("removed", 1.0, false),
+            make_card("reviewed", 1.0, false),
+            make_card("timed", 1.0, false),
+            make_card("unchanged", 1.0, false),
+        ]);
+        let after = State::default()
+            .with_loaded_cards(vec![
+                make_card("added", 1.0, false),
+                make_card("flagged", 1.0, true),
+                make_card("reviewed", 3.0, true),
+                make_card("timed", 3.0, false)
+                    .with_answer_time(AnswerTime::default().record(Duration::from_secs(4)))
+                    .with_rescheduled_at(Some(Utc::now())),
+                make_card("unchanged", 1.0, false),
+            ])
+            .with_merged_decks(vec![Deck {
+                name: "other".into(),
+                ..Deck::default()
+            }]);
+        let actual = mutations(&before, &after);
+        let path = |path: &str| path.to_string();
+        assert_eq!(
+            vec![
+                Mutation::CardAdded {
+                    path: path("added")
+                },
+                Mutation::CardChanged {
+                    path: path("flagged")
+                },
+                Mutation::CardRemoved {
+                    path: path("removed")
+                },
+                Mutation::CardRescheduled(ScheduleChange {
+                    path: path("reviewed"),
+                    before: make_card("", 1.0, false).revision_settings,
+                    after: make_card("", 3.0, false).revision_settings,
+                }),
+                Mutation::CardChanged {
+                    path: path("reviewed")
+                },
+                Mutation::CardRescheduled(ScheduleChange {
+                    path: path("timed"),
+                    before: make_card("", 1.0, false).revision_settings,
+                    after: make_card("", 3.0, false).revision_settings,
+                }),
+                Mutation::DeckChanged { name: path("deck") },
+                Mutation::DeckAdded {
+                    name: path("other")
+                },
+            ],
+            actual
+        );
+    }
+}
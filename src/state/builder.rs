@@ -0,0 +1,111 @@
+use super::card::parser::ParsingConfig;
+use super::card::Card;
+use super::deck::Deck;
+use super::State;
+
+// What the state file keeps of each card.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum CardStorage {
+    #[default]
+    Full,
+    // Only the paths and schedules, with questions and answers read from the notes.
+    MetadataOnly,
+}
+
+// Assembles a State in code, as importers, generators and tests do, without reading notes.
+#[derive(Clone, Debug, Default)]
+pub struct StateBuilder {
+    parsing_config: ParsingConfig,
+    cards: Vec<Card>,
+    decks: Vec<Deck>,
+    storage: CardStorage,
+}
+
+impl StateBuilder {
+    pub fn parsing_config(self, parsing_config: ParsingConfig) -> Self {
+        Self {
+            parsing_config,
+            ..self
+        }
+    }
+
+    // The card is listed in its decks and their ancestors, which are added as needed. A card
+    // added again with the same path replaces the first.
+    pub fn add_card(mut self, card: Card) -> Self {
+        self.cards.retain(|c| c.path != card.path);
+        self.cards.push(card);
+        self
+    }
+
+    // Sets a deck's coefficients and options. Its cards are those added that list it, plus any
+    // in its card_paths.
+    pub fn add_deck(mut self, deck: Deck) -> Self {
+        self.decks.retain(|d| d.name != deck.name);
+        self.decks.push(deck);
+        self
+    }
+
+    pub fn storage(self, storage: CardStorage) -> Self {
+        Self { storage, ..self }
+    }
+
+    pub fn build(self) -> State {
+        State::default()
+            .with_card_parsing_config(self.parsing_config)
+            .with_overriden_decks(self.decks)
+            .with_loaded_cards(self.cards)
+            .with_metadata_only(self.storage == CardStorage::MetadataOnly)
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use crate::state::deck::IntervalCoefficients;
+
+    fn make_card(path: &str, decks: &[&str], question: &str) -> Card {
+        Card {
+            path: path.into(),
+            decks: decks.iter().map(|d| d.to_string()).collect(),
+            question: question.to_string(),
+            ..Card::default()
+        }
+    }
+
+    #[test]
+    fn build_lists_cards_in_their_decks() {
+        let coefficients = IntervalCoefficients::new(2.0, 3.0, 0.5);
+        let state = State::builder()
+            .add_card(make_card("verbs/ser", &["lang::spanish"], "old"))
+            .add_card(make_card("verbs/ser", &["lang::spanish"], "ser"))
+            .add_card(make_card("krebs", &["bio"], "krebs"))
+            .add_deck(Deck::new("lang", vec![], IntervalCoefficients::default()))
+            .add_deck(Deck::new("lang", vec![], coefficients.clone()))
+            .build();
+        assert_eq!(2, state.cards().len());
+        assert_eq!("ser", state.cards()["verbs/ser"].question);
+        let mut deck_names: Vec<&str> = state.decks().keys().map(|name| name.as_str()).collect();
+        deck_names.sort();
+        assert_eq!(vec!["bio", "lang", "lang::spanish"], deck_names);
+        let lang = &state.decks()["lang"];
+        assert_eq!(coefficients, lang.interval_coefficients);
+        assert!(lang.lists("verbs/ser"));
+        assert!(!lang.lists("krebs"));
+    }
+
+    #[test]
+    fn build_with_parsing_config_and_storage() {
+        let parsing_config = ParsingConfig {
+            strict_loading: true,
+            ..ParsingConfig::default()
+        };
+        let state = State::builder()
+            .parsing_config(parsing_config.clone())
+            .storage(CardStorage::MetadataOnly)
+            .build();
+        assert_eq!(&parsing_config, state.card_parsing_config());
+        assert!(state.metadata_only());
+        assert!(state.cards().is_empty());
+    }
+}
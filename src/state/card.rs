@@ -1,14 +1,37 @@
+pub mod answer_time;
+pub mod cache;
+pub mod frontmatter;
+pub mod load_failure;
+pub mod occlusion;
+pub mod overrides;
 pub mod parser; // TODO only ParsingConfig & ParsingPattern should be exposed publically
+pub mod prerequisites;
+pub mod priority;
 pub mod revision_settings; // Shouldn't need to be exposed publically
+pub mod scheduler;
 pub mod score;
+#[cfg(feature = "scripting")]
+pub mod script;
+pub mod sidecar;
+pub mod typed_answer;
 
-use super::deck::IntervalCoefficients;
+use super::deck::{self, IntervalCoefficients};
+use super::id::CardId;
 use super::tools::{Merge, UID};
-use chrono::Utc;
+pub use answer_time::{AnswerTime, ScoreSuggestions};
+use chrono::{DateTime, Utc};
+pub use load_failure::{LoadFailure, LoadFailureKind};
+pub use overrides::CardOverrides;
 use parser::Parse;
+pub use priority::Priority;
 pub use revision_settings::RevisionSettings; // Shouldn't need to be exposed publically
-pub use score::Score;
+use scheduler::Review;
+pub use scheduler::Scheduler;
+pub use score::{Grade, Grading, Score};
 use snafu::{prelude::*, Whatever};
+use std::path::Path;
+use tracing::{debug, debug_span, trace, trace_span};
+pub use typed_answer::TypedAnswers;
 
 #[cfg_attr(test, double)]
 use super::file::FileHandle;
@@ -16,13 +39,42 @@ use super::file::FileHandle;
 use mockall_double::double;
 use serde::{Deserialize, Serialize};
 
+pub const NOTE_FRAGMENT_SEPARATOR: char = '#';
+
 #[derive(Clone, Default, Debug, Deserialize, PartialEq, PartialOrd, Serialize)]
 pub struct Card {
-    pub path: String,
+    pub path: CardId,
     pub decks: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // Empty in metadata-only state files.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub question: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub answer: String,
     pub revision_settings: RevisionSettings,
+    #[serde(default)]
+    pub overrides: CardOverrides,
+    #[serde(default)]
+    pub suspended: bool,
+    #[serde(default)]
+    pub flagged: bool,
+    #[serde(default)]
+    pub answer_time: AnswerTime,
+    #[serde(default)]
+    pub priority: Priority,
+    // The paths of the notes whose cards are learnt before this one, relative to the notes
+    // directory.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub requires: Vec<String>,
+    // Whether the revision settings were read from the note, which then takes precedence over the
+    // state file.
+    #[serde(skip)]
+    pub scheduled_in_note: bool,
+    // When the schedule was last changed other than by a review, e.g. by set-due or postpone, so
+    // that replaying the reviews logged before then doesn't undo it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rescheduled_at: Option<DateTime<Utc>>,
 }
 
 impl Card {
@@ -34,11 +86,20 @@ impl Card {
         revision_settings: RevisionSettings,
     ) -> Self {
         Self {
-            path,
+            path: CardId::from(path),
             decks,
+            tags: Vec::new(),
             question,
             answer,
             revision_settings,
+            overrides: CardOverrides::default(),
+            suspended: false,
+            flagged: false,
+            answer_time: AnswerTime::default(),
+            priority: Priority::default(),
+            requires: Vec::new(),
+            scheduled_in_note: false,
+            rescheduled_at: None,
         }
     }
 
@@ -47,26 +108,158 @@ impl Card {
         let file_content = file_handle
             .read()
             .with_whatever_context(|_| format!("Unable to read Card from \"{}\"", file_path))?;
+        Self::parse(file_path, &file_content, parser)
+    }
+
+    #[cfg(feature = "async")]
+    pub async fn from_async(
+        file_handle: FileHandle,
+        parser: &impl Parse,
+    ) -> Result<Self, Whatever> {
+        let file_path = file_handle.path();
+        let file_content = file_handle
+            .read_async()
+            .await
+            .with_whatever_context(|_| format!("Unable to read Card from \"{}\"", file_path))?;
+        Self::parse(file_path, &file_content, parser)
+    }
+
+    fn parse(file_path: &str, file_content: &str, parser: &impl Parse) -> Result<Self, Whatever> {
         let parsed_fields = parser
-            .parse(&file_content)
+            .parse(file_content)
             .with_whatever_context(|_| format!("Unable to parse Card from \"{}\"", file_path))?;
         Ok(Self {
-            path: file_path.to_string(),
+            path: CardId::from(file_path),
             decks: parsed_fields.decks.iter().map(|s| s.to_string()).collect(),
+            tags: parsed_fields.tags.iter().map(|s| s.to_string()).collect(),
             question: parsed_fields.question.to_string(),
             answer: parsed_fields.answer.to_string(),
-            revision_settings: RevisionSettings::default(),
+            revision_settings: parsed_fields.schedule.clone().unwrap_or_default(),
+            overrides: parsed_fields.overrides,
+            suspended: false,
+            flagged: false,
+            answer_time: AnswerTime::default(),
+            priority: parsed_fields.priority,
+            requires: parsed_fields
+                .requires
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            scheduled_in_note: parsed_fields.schedule.is_some(),
+            rescheduled_at: None,
         })
     }
 
+    // Coefficients set in the card's own note take precedence over its deck's.
     pub fn transform(self, score: Score, interval_coefficients: &IntervalCoefficients) -> Self {
-        let revision_settings = self
-            .revision_settings
+        self.transform_at(score, interval_coefficients, Utc::now())
+    }
+
+    pub fn transform_at(
+        self,
+        score: Score,
+        interval_coefficients: &IntervalCoefficients,
+        reviewed_at: DateTime<Utc>,
+    ) -> Self {
+        let revision_settings = self.revision_settings.clone().transform_at(
+            score,
+            &self.overrides.apply_to(interval_coefficients),
+            self.overrides.max_interval,
+            reviewed_at,
+        );
+        self.with_revision_settings(revision_settings)
+    }
+
+    // The note's max interval still applies to what the scheduler decides.
+    pub fn transform_with_scheduler(
+        self,
+        score: Score,
+        interval_coefficients: &IntervalCoefficients,
+        scheduler: &dyn Scheduler,
+    ) -> Self {
+        self.transform_with_scheduler_at(score, interval_coefficients, scheduler, Utc::now())
+    }
+
+    pub fn transform_with_scheduler_at(
+        self,
+        score: Score,
+        interval_coefficients: &IntervalCoefficients,
+        scheduler: &dyn Scheduler,
+        reviewed_at: DateTime<Utc>,
+    ) -> Self {
+        let before = self.revision_settings.clone();
+        let coefficients = self.overrides.apply_to(interval_coefficients);
+        let max_interval = self.overrides.max_interval;
+        let built_in = before
             .clone()
-            .transform(score, interval_coefficients);
+            .transform_at(score, &coefficients, max_interval, reviewed_at);
+        let scheduled = scheduler.schedule(&Review {
+            before: &before,
+            score,
+            coefficients: &coefficients,
+            reviewed_at,
+            built_in: &built_in,
+        });
+        let revision_settings = match max_interval {
+            Some(max) if scheduled.interval > max => {
+                before.rescheduled(max, scheduled.memorisation_factor)
+            }
+            _ => scheduled,
+        };
         self.with_revision_settings(revision_settings)
     }
 
+    pub fn with_overrides(self, overrides: CardOverrides) -> Self {
+        Self { overrides, ..self }
+    }
+
+    pub fn with_priority(self, priority: Priority) -> Self {
+        Self { priority, ..self }
+    }
+
+    pub fn with_requires(self, requires: Vec<String>) -> Self {
+        Self { requires, ..self }
+    }
+
+    pub fn with_tags(self, tags: Vec<String>) -> Self {
+        Self { tags, ..self }
+    }
+
+    pub fn with_suspended(self, suspended: bool) -> Self {
+        Self { suspended, ..self }
+    }
+
+    pub fn with_flagged(self, flagged: bool) -> Self {
+        Self { flagged, ..self }
+    }
+
+    pub fn with_answer_time(self, answer_time: AnswerTime) -> Self {
+        Self {
+            answer_time,
+            ..self
+        }
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    // A schedule kept with the note, which takes precedence over the state file's when merged.
+    pub fn with_note_schedule(self, revision_settings: RevisionSettings) -> Self {
+        Self {
+            revision_settings,
+            scheduled_in_note: true,
+            ..self
+        }
+    }
+
+    pub fn with_rescheduled_at(self, rescheduled_at: Option<DateTime<Utc>>) -> Self {
+        Self {
+            rescheduled_at,
+            ..self
+        }
+    }
+
     pub fn with_revision_settings(self, revision_settings: RevisionSettings) -> Self {
         Self {
             revision_settings,
@@ -74,24 +267,182 @@ impl Card {
         }
     }
 
+    // Never passed, i.e. not yet learnt.
+    pub fn is_new(&self) -> bool {
+        self.revision_settings.interval == 0.0
+    }
+
     pub fn is_due(&self) -> bool {
-        Utc::now() >= self.revision_settings.due
+        self.is_due_at(Utc::now())
+    }
+
+    pub fn is_due_at(&self, at: DateTime<Utc>) -> bool {
+        at >= self.revision_settings.due
     }
 
     pub fn in_deck(&self, deck_id: &str) -> bool {
-        self.decks.iter().any(|d| d == deck_id)
+        self.decks.iter().any(|d| deck::is_within(d, deck_id))
+    }
+
+    // Cards made from the same note are told apart by a fragment after the note's file name and
+    // extension, e.g. "bio/krebs.md#2", which is left out here. A "#" anywhere else, as in
+    // "lang/C# generics.md", is part of the note's path, as is the whole path of a card alone in
+    // its note.
+    pub fn note_path(&self) -> &str {
+        match self.path.rsplit_once(NOTE_FRAGMENT_SEPARATOR) {
+            Some((note_path, fragment))
+                if !fragment.is_empty()
+                    && !fragment.contains(['.', '/'])
+                    && Path::new(note_path).extension().is_some() =>
+            {
+                note_path
+            }
+            _ => &self.path,
+        }
+    }
+}
+
+// The outcome of loading every card at once, collected from try_load_many.
+#[derive(Debug, Default)]
+pub struct LoadedCards {
+    pub loaded: Vec<Card>,
+    pub failed: Vec<LoadFailure>,
+}
+
+impl FromIterator<Result<Card, LoadFailure>> for LoadedCards {
+    fn from_iter<I: IntoIterator<Item = Result<Card, LoadFailure>>>(results: I) -> Self {
+        let mut output = LoadedCards::default();
+        for result in results {
+            match result {
+                Ok(card) => output.loaded.push(card),
+                Err(failure) => output.failed.push(failure),
+            }
+        }
+        output
+    }
+}
+
+impl LoadedCards {
+    // The loaded cards, or an error reporting every failure if there were any.
+    pub fn strict(self) -> Result<Vec<Card>, Whatever> {
+        if !self.failed.is_empty() {
+            let report: Vec<String> = self
+                .failed
+                .iter()
+                .map(|failure| format!("{}: {}", failure.path, failure.message))
+                .collect();
+            whatever!(
+                "{} note(s) couldn't be read as cards:\n{}",
+                self.failed.len(),
+                report.join("\n")
+            );
+        }
+        Ok(self.loaded)
     }
 }
 
+// Each note is read and parsed as the iterator reaches it, so only one is held in memory at a
+// time unless the caller collects them.
+pub fn try_load_many<'p, I>(
+    file_handles: I,
+    parser: &'p impl Parse,
+) -> impl Iterator<Item = Result<Card, LoadFailure>> + 'p
+where
+    I: IntoIterator<Item = FileHandle>,
+    I::IntoIter: 'p,
+{
+    // Entered for each note, as the notes are loaded whenever the caller iterates.
+    let span = debug_span!("try_load_many");
+    file_handles.into_iter().map(move |file_handle| {
+        let _entered = span.enter();
+        let path = file_handle.path().to_string();
+        let _note = trace_span!("load_card", path).entered();
+        Card::from(file_handle, parser)
+            .inspect(|card| trace!(decks = ?card.decks, "loaded Card"))
+            .map_err(|error| {
+                let failure = LoadFailure::from(&path, &error);
+                debug!(kind = ?failure.kind, error = failure.message, "failed to load Card");
+                failure
+            })
+    })
+}
+
+// How many notes there are to load, and how many have loaded as cards or failed to so far.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LoadProgress {
+    pub scanned: usize,
+    pub parsed: usize,
+    pub failed: usize,
+}
+
+impl LoadProgress {
+    pub fn done(&self) -> usize {
+        self.parsed + self.failed
+    }
+}
+
+// As try_load_many, calling on_progress once with the notes counted, then after each note.
+pub fn try_load_many_with_progress<'p, I>(
+    file_handles: I,
+    parser: &'p impl Parse,
+    mut on_progress: impl FnMut(&LoadProgress) + 'p,
+) -> impl Iterator<Item = Result<Card, LoadFailure>> + 'p
+where
+    I: IntoIterator<Item = FileHandle>,
+    I::IntoIter: ExactSizeIterator + 'p,
+{
+    let file_handles = file_handles.into_iter();
+    let mut progress = LoadProgress {
+        scanned: file_handles.len(),
+        ..LoadProgress::default()
+    };
+    on_progress(&progress);
+    try_load_many(file_handles, parser).inspect(move |result| {
+        match result {
+            Ok(_) => progress.parsed += 1,
+            Err(_) => progress.failed += 1,
+        }
+        on_progress(&progress);
+    })
+}
+
+#[cfg(feature = "async")]
+pub fn try_load_many_async<'p, I>(
+    file_handles: I,
+    parser: &'p impl Parse,
+) -> impl futures::Stream<Item = Result<Card, LoadFailure>> + 'p
+where
+    I: IntoIterator<Item = FileHandle>,
+    I::IntoIter: 'p,
+{
+    use futures::StreamExt;
+    futures::stream::iter(file_handles).then(move |file_handle| async move {
+        let path = file_handle.path().to_string();
+        Card::from_async(file_handle, parser)
+            .await
+            .map_err(|error| LoadFailure::from(&path, &error))
+    })
+}
+
 impl UID for Card {
-    fn uid(&self) -> &str {
-        &self.path[..]
+    type Id = CardId;
+
+    fn uid(&self) -> CardId {
+        self.path.clone()
     }
 }
 
 impl Merge<Card> for Card {
     fn merge(self, other: &Card) -> Self {
-        self.with_revision_settings(other.revision_settings.clone())
+        let revision_settings = match self.scheduled_in_note {
+            true => self.revision_settings.clone(),
+            false => other.revision_settings.clone(),
+        };
+        self.with_revision_settings(revision_settings)
+            .with_suspended(other.suspended)
+            .with_flagged(other.flagged)
+            .with_answer_time(other.answer_time)
+            .with_rescheduled_at(other.rescheduled_at)
     }
 }
 
@@ -119,6 +470,7 @@ mod unit_tests {
     use chrono::{Duration, Utc};
     use mockall::predicate::eq;
     use parser::MockParser;
+    use parser::ParseError;
     use parser::ParsedCardFields;
     use rstest::*;
 
@@ -146,8 +498,13 @@ mod unit_tests {
     ) -> ParsedCardFields<'static> {
         ParsedCardFields {
             decks,
+            tags: vec![],
             question,
             answer,
+            overrides: CardOverrides::default(),
+            priority: Priority::default(),
+            requires: vec![],
+            schedule: None,
         }
     }
 
@@ -175,7 +532,7 @@ mod unit_tests {
 
     fn make_mock_parser(
         expected_filepath_arg: &'static str,
-        expected_return_value: Result<ParsedCardFields<'static>, String>,
+        expected_return_value: Result<ParsedCardFields<'static>, ParseError>,
     ) -> MockParser {
         let mut mock_parser = MockParser::new();
         mock_parser
@@ -195,6 +552,10 @@ mod unit_tests {
         mock_file_handle
             .expect_read()
             .returning(move || Ok(content.clone()));
+        #[cfg(feature = "async")]
+        mock_file_handle
+            .expect_read_async()
+            .returning(move || Ok(FAKE_PATH.to_string()));
         mock_file_handle
     }
 
@@ -204,6 +565,10 @@ mod unit_tests {
         mock_file_handle
             .expect_read()
             .returning(move || Err(std::io::Error::from(std::io::ErrorKind::NotFound)));
+        #[cfg(feature = "async")]
+        mock_file_handle
+            .expect_read_async()
+            .returning(move || Err(std::io::Error::from(std::io::ErrorKind::NotFound)));
         mock_file_handle
             .expect_path()
             .return_const(FAKE_PATH.to_string());
@@ -213,11 +578,20 @@ mod unit_tests {
     #[test]
     fn default() {
         let expected = Card {
-            path: String::from(""),
+            path: CardId::default(),
             decks: Vec::new(),
+            tags: Vec::new(),
             question: String::from(""),
             answer: String::from(""),
             revision_settings: RevisionSettings::default(),
+            overrides: CardOverrides::default(),
+            suspended: false,
+            flagged: false,
+            answer_time: AnswerTime::default(),
+            priority: Priority::default(),
+            requires: vec![],
+            scheduled_in_note: false,
+            rescheduled_at: None,
         };
         let actual = Card::default();
         assertions::assert_cards_near(&expected, &actual);
@@ -234,7 +608,7 @@ mod unit_tests {
 
     #[rstest]
     fn from_where_parser_fails(successful_file_handle: MockFileHandle) {
-        let parser_error = Result::Err(FAKE_PATH.to_string());
+        let parser_error = Result::Err(ParseError::InvalidValue(FAKE_PATH.to_string()));
         let mock_parser = make_mock_parser(FAKE_PATH, parser_error);
         let actual = Card::from(successful_file_handle, &mock_parser);
         assert!(actual.is_err());
@@ -247,7 +621,10 @@ mod unit_tests {
     #[rstest]
     fn from_where_file_read_fails(failing_file_handle: MockFileHandle) {
         let unexpected_message = "UNEXPECTED";
-        let mock_parser = make_mock_parser(FAKE_PATH, Result::Err(unexpected_message.to_string()));
+        let mock_parser = make_mock_parser(
+            FAKE_PATH,
+            Result::Err(ParseError::InvalidValue(unexpected_message.to_string())),
+        );
         let expected_message = format!("Unable to read Card from \"{}\"", FAKE_PATH);
         let actual = Card::from(failing_file_handle, &mock_parser);
         assert!(actual.is_err());
@@ -264,11 +641,20 @@ mod unit_tests {
         let answer = String::from("an answer.");
         let revision_settings = make_fake_revision_settings(2.0, 3.0);
         let expected = Card {
-            path: path.clone(),
+            path: CardId::from(&path),
             decks: decks.clone(),
+            tags: Vec::new(),
             question: question.clone(),
             answer: answer.clone(),
             revision_settings: revision_settings.clone(),
+            overrides: CardOverrides::default(),
+            suspended: false,
+            flagged: false,
+            answer_time: AnswerTime::default(),
+            priority: Priority::default(),
+            requires: vec![],
+            scheduled_in_note: false,
+            rescheduled_at: None,
         };
         let actual = Card::new(path, decks, question, answer, revision_settings);
         assert_eq!(expected, actual);
@@ -303,6 +689,40 @@ mod unit_tests {
         assert_eq!(expected, actual)
     }
 
+    #[test]
+    fn transform_with_overrides() {
+        let in_due_date = Utc::now() - Duration::days(4);
+        let revision_settings = RevisionSettings::new(in_due_date, 1.0, 2000.0);
+        let input = Card::new(
+            String::from("p"),
+            vec![String::from("d")],
+            String::from("q"),
+            String::from("a"),
+            revision_settings,
+        );
+        let deck_coefficients = IntervalCoefficients::new(1.0, 2.0, 0.0);
+        let card_coefficients = IntervalCoefficients::new(1.0, 1.5, 0.0);
+        let overrides = CardOverrides {
+            easy_coef: Some(1.5),
+            ..CardOverrides::default()
+        };
+        let expected = input.clone().transform(Score::Easy, &card_coefficients);
+        let actual = input
+            .clone()
+            .with_overrides(overrides.clone())
+            .transform(Score::Easy, &deck_coefficients);
+        assert_eq!(expected.revision_settings, actual.revision_settings);
+        let capped = CardOverrides {
+            max_interval: Some(5.0),
+            ..overrides
+        };
+        let actual = input
+            .with_overrides(capped)
+            .transform(Score::Easy, &deck_coefficients);
+        let expected = make_expected_revision_settings(&in_due_date, 5.0, 2150.0);
+        assert_eq!(expected, actual.revision_settings);
+    }
+
     #[rstest]
     #[case::when_due_date_in_past(Utc::now() - Duration::days(100), Expect::Truthy)]
     #[case::when_due_date_in_present(Utc::now(), Expect::Truthy)]
@@ -321,6 +741,8 @@ mod unit_tests {
     #[rstest]
     #[case::when_decks_contains_id(vec!["deck", "THIS"], "THIS", Expect::Truthy)]
     #[case::when_decks_do_not_contain_id(vec![], "THIS", Expect::Falsy)]
+    #[case::when_decks_contain_a_descendant(vec!["THIS::child"], "THIS", Expect::Truthy)]
+    #[case::when_decks_contain_an_ancestor(vec!["THIS"], "THIS::child", Expect::Falsy)]
     fn in_deck(
         #[case] decks: Vec<&'static str>,
         #[case] input: &'static str,
@@ -332,6 +754,110 @@ mod unit_tests {
         assert_truthy(expectation, card.in_deck(input));
     }
 
+    #[rstest]
+    #[case::alone_in_its_note("bio/krebs.md", "bio/krebs.md")]
+    #[case::one_of_many("bio/krebs.md#2", "bio/krebs.md")]
+    #[case::hash_in_the_file_name("lang/C# generics.md", "lang/C# generics.md")]
+    #[case::hash_in_a_directory_name("lang/C#/generics.md", "lang/C#/generics.md")]
+    #[case::hash_without_an_extension_before_it("lang/C#2", "lang/C#2")]
+    #[case::fragment_after_a_hash_in_the_file_name("lang/C# linq.md#1", "lang/C# linq.md")]
+    fn note_path(#[case] path: &str, #[case] expected: &str) {
+        let card = Card {
+            path: path.into(),
+            ..Card::default()
+        };
+        assert_eq!(expected, card.note_path());
+    }
+
+    #[test]
+    fn try_load_many() {
+        let parsed_fields = make_fake_parsed_fields(vec!["tag"], "what?", "that");
+        let mock_parser = make_mock_parser(FAKE_PATH, Result::Ok(parsed_fields.clone()));
+        let file_handles = vec![successful_file_handle(), failing_file_handle()];
+        let expected = make_expected_card(FAKE_PATH, &parsed_fields, RevisionSettings::default());
+        let actual: LoadedCards = super::try_load_many(file_handles, &mock_parser).collect();
+        assert_eq!(1, actual.loaded.len());
+        assertions::assert_cards_near(&expected, &actual.loaded[0]);
+        assert_eq!(1, actual.failed.len());
+        assert_eq!(FAKE_PATH, actual.failed[0].path);
+        assert_eq!(LoadFailureKind::Unreadable, actual.failed[0].kind);
+        assert!(actual.failed[0]
+            .message
+            .contains("Unable to read Card from \"a_path\""));
+    }
+
+    #[test]
+    fn strict_loaded_cards() {
+        let card = make_expected_card(
+            FAKE_PATH,
+            &make_fake_parsed_fields(vec!["tag"], "what?", "that"),
+            RevisionSettings::default(),
+        );
+        let loaded = LoadedCards {
+            loaded: vec![card.clone()],
+            failed: vec![],
+        };
+        assert_eq!(vec![card.clone()], loaded.strict().unwrap());
+        let failure = LoadFailure {
+            path: "b.md".to_string(),
+            kind: LoadFailureKind::InvalidValue,
+            message: "Unable to parse Card from \"b.md\"".to_string(),
+        };
+        let loaded = LoadedCards {
+            loaded: vec![card],
+            failed: vec![failure],
+        };
+        assert_eq!(
+            "1 note(s) couldn't be read as cards:\nb.md: Unable to parse Card from \"b.md\"",
+            loaded.strict().unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn try_load_many_loads_lazily() {
+        let parsed_fields = make_fake_parsed_fields(vec!["tag"], "what?", "that");
+        let mock_parser = make_mock_parser(FAKE_PATH, Result::Ok(parsed_fields));
+        let file_handles = vec![successful_file_handle(), failing_file_handle()];
+        let mut actual = super::try_load_many(file_handles, &mock_parser);
+        assert!(actual.next().unwrap().is_ok());
+        assert!(actual.next().unwrap().is_err());
+        assert!(actual.next().is_none());
+    }
+
+    #[test]
+    fn try_load_many_with_progress() {
+        let parsed_fields = make_fake_parsed_fields(vec!["tag"], "what?", "that");
+        let mock_parser = make_mock_parser(FAKE_PATH, Result::Ok(parsed_fields));
+        let file_handles = vec![successful_file_handle(), failing_file_handle()];
+        let mut reported = Vec::new();
+        let actual: LoadedCards =
+            super::try_load_many_with_progress(file_handles, &mock_parser, |progress| {
+                reported.push((progress.scanned, progress.parsed, progress.failed))
+            })
+            .collect();
+        assert_eq!(1, actual.loaded.len());
+        assert_eq!(vec![(2, 0, 0), (2, 1, 0), (2, 1, 1)], reported);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn try_load_many_async() {
+        let parsed_fields = make_fake_parsed_fields(vec!["tag"], "what?", "that");
+        let mock_parser = make_mock_parser(FAKE_PATH, Result::Ok(parsed_fields.clone()));
+        let file_handles = vec![successful_file_handle(), failing_file_handle()];
+        let expected = make_expected_card(FAKE_PATH, &parsed_fields, RevisionSettings::default());
+        let results: Vec<Result<Card, LoadFailure>> =
+            futures::StreamExt::collect(super::try_load_many_async(file_handles, &mock_parser))
+                .await;
+        let actual: LoadedCards = results.into_iter().collect();
+        assert_eq!(1, actual.loaded.len());
+        assertions::assert_cards_near(&expected, &actual.loaded[0]);
+        assert_eq!(1, actual.failed.len());
+        assert!(actual.failed[0]
+            .message
+            .contains("Unable to read Card from \"a_path\""));
+    }
+
     #[test]
     fn uid() {
         let path = "the/path";
@@ -341,6 +867,38 @@ mod unit_tests {
         assert_eq!(path, card.uid());
     }
 
+    #[derive(Debug)]
+    struct FixedScheduler(f64);
+
+    impl Scheduler for FixedScheduler {
+        fn schedule(&self, review: &Review) -> RevisionSettings {
+            review
+                .before
+                .rescheduled(self.0, review.built_in.memorisation_factor)
+        }
+    }
+
+    #[rstest]
+    #[case::scheduled(None, 40.0)]
+    #[case::capped_by_the_note(Some(30.0), 30.0)]
+    fn transform_with_scheduler(#[case] max_interval: Option<f64>, #[case] expected: f64) {
+        let card = Card::default().with_overrides(CardOverrides {
+            max_interval,
+            ..CardOverrides::default()
+        });
+        let due = card.revision_settings.due;
+        let actual = card.transform_with_scheduler(
+            Score::Pass,
+            &IntervalCoefficients::default(),
+            &FixedScheduler(40.0),
+        );
+        assert_eq!(expected, actual.revision_settings.interval);
+        assert_eq!(
+            due + chrono::Duration::days(expected as i64),
+            actual.revision_settings.due
+        );
+    }
+
     #[test]
     fn merge() {
         let question = "huh?".to_string();
@@ -354,10 +912,15 @@ mod unit_tests {
             revision_settings_a,
         );
         let mut b = a.clone();
-        b.path = "b".to_string();
+        b.path = "b".into();
         b.revision_settings = RevisionSettings::new(Utc::now(), 654.25, 9876.5);
         let mut expected = a.clone();
         expected.revision_settings = b.revision_settings.clone();
-        assert_eq!(expected, a.merge(&b));
+        assert_eq!(expected, a.clone().merge(&b));
+        let scheduled_in_note = Card {
+            scheduled_in_note: true,
+            ..a
+        };
+        assert_eq!(scheduled_in_note, scheduled_in_note.clone().merge(&b));
     }
 }
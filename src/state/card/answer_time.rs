@@ -0,0 +1,113 @@
+use super::Score;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+// A running mean of how long a card takes to answer, over every timed review of it.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, PartialOrd, Serialize)]
+pub struct AnswerTime {
+    pub reviews: u32,
+    pub mean: Duration,
+}
+
+impl AnswerTime {
+    pub fn record(self, answer_time: Duration) -> Self {
+        let reviews = self.reviews.saturating_add(1);
+        let total = self.mean.as_secs_f64() * f64::from(self.reviews) + answer_time.as_secs_f64();
+        Self {
+            reviews,
+            mean: Duration::from_secs_f64(total / f64::from(reviews)),
+        }
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        (self.reviews > 0).then_some(self.mean)
+    }
+}
+
+// How a card's answer time maps onto a suggested score. The suggestion is only ever a hint: the
+// reviewer still picks the score.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, PartialOrd, Serialize)]
+#[serde(default)]
+pub struct ScoreSuggestions {
+    pub enabled: bool,
+    pub hard_after_seconds: u32,
+    pub easy_within_seconds: u32,
+}
+
+impl Default for ScoreSuggestions {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            hard_after_seconds: 30,
+            easy_within_seconds: 5,
+        }
+    }
+}
+
+impl ScoreSuggestions {
+    // Only passing scores are suggested, since only the reviewer knows whether they were right.
+    // An answer is slow past the threshold, or when it takes over twice the card's usual time.
+    pub fn suggest(&self, answer_time: Duration, history: &AnswerTime) -> Option<Score> {
+        if !self.enabled {
+            return None;
+        }
+        let hard_after = Duration::from_secs(u64::from(self.hard_after_seconds));
+        let easy_within = Duration::from_secs(u64::from(self.easy_within_seconds));
+        let slow_for_card = history.mean().is_some_and(|usual| answer_time > usual * 2);
+        Some(match answer_time {
+            t if t >= hard_after || (slow_for_card && t > easy_within) => Score::Hard,
+            t if t <= easy_within => Score::Easy,
+            _ => Score::Pass,
+        })
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use rstest::*;
+
+    #[test]
+    fn record() {
+        let actual = AnswerTime::default()
+            .record(Duration::from_secs(4))
+            .record(Duration::from_secs(8))
+            .record(Duration::from_secs(12));
+        assert_eq!(3, actual.reviews);
+        assert_eq!(Some(Duration::from_secs(8)), actual.mean());
+    }
+
+    #[test]
+    fn mean_without_timed_reviews() {
+        assert_eq!(None, AnswerTime::default().mean());
+    }
+
+    #[rstest]
+    #[case::quick(3, None, Some(Score::Easy))]
+    #[case::steady(12, None, Some(Score::Pass))]
+    #[case::slow(31, None, Some(Score::Hard))]
+    #[case::slow_for_the_card(12, Some(4), Some(Score::Hard))]
+    #[case::quick_though_slow_for_the_card(4, Some(1), Some(Score::Easy))]
+    fn suggest(
+        #[case] seconds: u64,
+        #[case] usual_seconds: Option<u64>,
+        #[case] expected: Option<Score>,
+    ) {
+        let history = usual_seconds.map_or(AnswerTime::default(), |usual| {
+            AnswerTime::default().record(Duration::from_secs(usual))
+        });
+        let actual = ScoreSuggestions::default().suggest(Duration::from_secs(seconds), &history);
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn suggest_when_disabled() {
+        let suggestions = ScoreSuggestions {
+            enabled: false,
+            ..ScoreSuggestions::default()
+        };
+        let actual = suggestions.suggest(Duration::from_secs(60), &AnswerTime::default());
+        assert_eq!(None, actual);
+    }
+}
@@ -0,0 +1,263 @@
+use super::overrides::CardOverrides;
+use super::parser::{Parse, ParseError, ParsedCardFields, ParsingConfig};
+use super::priority::Priority;
+use super::revision_settings::RevisionSettings;
+use crate::state::file::IO;
+use serde::{Deserialize, Serialize};
+use snafu::{prelude::*, Whatever};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+
+// Bumped whenever CachedFields changes, as bincode can't tell one layout from another.
+const FORMAT_VERSION: u32 = 2;
+
+// Where each field was found in the note, as byte offsets, so that a note with the same content
+// is read back without parsing it again.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+struct CachedFields {
+    decks: Vec<(usize, usize)>,
+    tags: Vec<(usize, usize)>,
+    question: (usize, usize),
+    answer: (usize, usize),
+    overrides: CardOverrides,
+    priority: Priority,
+    requires: Vec<(usize, usize)>,
+    schedule: Option<RevisionSettings>,
+}
+
+impl CachedFields {
+    // None when a field isn't part of the input, e.g. a literal a custom parser returns.
+    fn from(input: &str, fields: &ParsedCardFields) -> Option<Self> {
+        Some(Self {
+            decks: offsets_of_all(input, &fields.decks)?,
+            tags: offsets_of_all(input, &fields.tags)?,
+            question: offsets(input, fields.question)?,
+            answer: offsets(input, fields.answer)?,
+            overrides: fields.overrides.clone(),
+            priority: fields.priority,
+            requires: offsets_of_all(input, &fields.requires)?,
+            schedule: fields.schedule.clone(),
+        })
+    }
+
+    fn fields<'a>(&self, input: &'a str) -> Option<ParsedCardFields<'a>> {
+        let slice = |(start, end): (usize, usize)| input.get(start..end);
+        Some(ParsedCardFields {
+            decks: self
+                .decks
+                .iter()
+                .map(|o| slice(*o))
+                .collect::<Option<_>>()?,
+            tags: self.tags.iter().map(|o| slice(*o)).collect::<Option<_>>()?,
+            question: slice(self.question)?,
+            answer: slice(self.answer)?,
+            overrides: self.overrides.clone(),
+            priority: self.priority,
+            requires: self
+                .requires
+                .iter()
+                .map(|o| slice(*o))
+                .collect::<Option<_>>()?,
+            schedule: self.schedule.clone(),
+        })
+    }
+}
+
+fn offsets(input: &str, field: &str) -> Option<(usize, usize)> {
+    if field.is_empty() {
+        return Some((0, 0));
+    }
+    let start = (field.as_ptr() as usize).checked_sub(input.as_ptr() as usize)?;
+    let end = start + field.len();
+    (end <= input.len()).then_some((start, end))
+}
+
+fn offsets_of_all(input: &str, fields: &[&str]) -> Option<Vec<(usize, usize)>> {
+    fields.iter().map(|field| offsets(input, field)).collect()
+}
+
+fn content_hash(input: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    input.hash(&mut hasher);
+    hasher.finish()
+}
+
+// The fields parsed from notes, by a hash of each note's content. Only valid for the parsing
+// config they were parsed with, which is kept alongside them with the format's version.
+#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct CardCache {
+    config: String,
+    entries: HashMap<u64, CachedFields>,
+}
+
+impl CardCache {
+    pub fn new(config: &ParsingConfig) -> Self {
+        Self {
+            config: format!(
+                "{} {}",
+                FORMAT_VERSION,
+                ron::to_string(config).unwrap_or_default()
+            ),
+            entries: HashMap::new(),
+        }
+    }
+
+    // A missing or unreadable cache, or one made with another config, starts out empty.
+    pub fn read(file: &impl IO, config: &ParsingConfig) -> Self {
+        let empty = Self::new(config);
+        file.read_bytes()
+            .ok()
+            .and_then(|bytes| bincode::deserialize::<Self>(&bytes).ok())
+            .filter(|cache| cache.config == empty.config)
+            .unwrap_or(empty)
+    }
+
+    pub fn write(&self, file: &impl IO) -> Result<(), Whatever> {
+        let bytes = bincode::serialize(self).with_whatever_context(|_| {
+            format!("Unable to serialise CardCache to {}", file.path())
+        })?;
+        file.write_bytes(bytes)
+            .with_whatever_context(|_| format!("Unable to write CardCache to {}", file.path()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+// Parses through the cache, only running the parser on notes whose content it hasn't seen.
+// Notes that fail to parse aren't cached, and are parsed again each time.
+pub struct CachingParser<P> {
+    parser: P,
+    cached: Mutex<CardCache>,
+    // The entries of the notes parsed through this parser, which make up the cache to keep.
+    used: Mutex<CardCache>,
+}
+
+impl<P: Parse> CachingParser<P> {
+    pub fn new(parser: P, cache: CardCache) -> Self {
+        let used = CardCache {
+            config: cache.config.clone(),
+            entries: HashMap::new(),
+        };
+        Self {
+            parser,
+            cached: Mutex::new(cache),
+            used: Mutex::new(used),
+        }
+    }
+
+    // Entries of notes since edited, or no longer loaded, are dropped.
+    pub fn into_cache(self) -> CardCache {
+        self.used.into_inner().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<P: Parse> Parse for CachingParser<P> {
+    fn parse<'a>(&self, input: &'a str) -> Result<ParsedCardFields<'a>, ParseError> {
+        let hash = content_hash(input);
+        let mut used = self.used.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = {
+            let mut cached = self.cached.lock().unwrap_or_else(|e| e.into_inner());
+            cached.entries.remove(&hash)
+        }
+        .or_else(|| used.entries.get(&hash).cloned());
+        if let Some(entry) = entry {
+            if let Some(fields) = entry.fields(input) {
+                used.entries.insert(hash, entry);
+                return Ok(fields);
+            }
+        }
+        let fields = self.parser.parse(input)?;
+        if let Some(entry) = CachedFields::from(input, &fields) {
+            used.entries.insert(hash, entry);
+        }
+        Ok(fields)
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use crate::state::file::mocks::MemoryFile;
+    use std::cell::Cell;
+
+    // Counts its calls, so that cache hits can be told from parses.
+    struct CountingParser {
+        calls: Cell<usize>,
+    }
+
+    impl Parse for CountingParser {
+        fn parse<'a>(&self, input: &'a str) -> Result<ParsedCardFields<'a>, ParseError> {
+            self.calls.set(self.calls.get() + 1);
+            let (question, answer) = input
+                .split_once('\n')
+                .ok_or_else(|| ParseError::InvalidValue("no answer".to_string()))?;
+            Ok(ParsedCardFields {
+                decks: vec![&question[..1]],
+                tags: vec![],
+                question,
+                answer,
+                overrides: CardOverrides::default(),
+                priority: Priority::High,
+                requires: vec![],
+                schedule: None,
+            })
+        }
+    }
+
+    fn counting_parser() -> CountingParser {
+        CountingParser {
+            calls: Cell::new(0),
+        }
+    }
+
+    #[test]
+    fn parse_reads_seen_notes_from_the_cache() {
+        let parser = CachingParser::new(counting_parser(), CardCache::default());
+        let first = String::from("bio question\nanswer");
+        let second = first.clone();
+        let parsed = parser.parse(&first).unwrap();
+        let cached = parser.parse(&second).unwrap();
+        assert_eq!(parsed, cached);
+        assert_eq!("b", cached.decks[0]);
+        assert_eq!(Priority::High, cached.priority);
+        assert!(parser.parse("unanswered").is_err());
+        assert_eq!(2, parser.parser.calls.get());
+        assert_eq!(1, parser.into_cache().len());
+    }
+
+    #[test]
+    fn cache_keeps_only_the_notes_parsed_since_read() {
+        let parser = CachingParser::new(counting_parser(), CardCache::default());
+        parser.parse("old\nnote").unwrap();
+        parser.parse("kept\nnote").unwrap();
+        let parser = CachingParser::new(counting_parser(), parser.into_cache());
+        parser.parse("kept\nnote").unwrap();
+        assert_eq!(0, parser.parser.calls.get());
+        assert_eq!(1, parser.into_cache().len());
+    }
+
+    #[test]
+    fn cache_is_written_and_read_for_the_same_config() {
+        let file = MemoryFile::default();
+        let config = ParsingConfig::default();
+        let parser = CachingParser::new(counting_parser(), CardCache::new(&config));
+        parser.parse("question\nanswer").unwrap();
+        let cache = parser.into_cache();
+        cache.write(&file).unwrap();
+        assert_eq!(cache, CardCache::read(&file, &config));
+        let other_config = ParsingConfig {
+            deck_delimiter: ";".to_string(),
+            ..ParsingConfig::default()
+        };
+        assert!(CardCache::read(&file, &other_config).is_empty());
+    }
+}
@@ -0,0 +1,157 @@
+use super::RevisionSettings;
+use chrono::{DateTime, SecondsFormat, Utc};
+
+const DELIMITER: &str = "---";
+const DUE_KEY: &str = "due";
+const INTERVAL_KEY: &str = "interval";
+const MEMORISATION_FACTOR_KEY: &str = "memorisation_factor";
+const KEYS: [&str; 3] = [DUE_KEY, INTERVAL_KEY, MEMORISATION_FACTOR_KEY];
+
+// The lines of a note's frontmatter, the block between a first line of "---" and the next one.
+fn frontmatter_lines(input: &str) -> Option<Vec<&str>> {
+    let mut lines = input.lines();
+    if lines.next()?.trim_end() != DELIMITER {
+        return None;
+    }
+    let mut frontmatter = Vec::new();
+    for line in lines {
+        if line.trim_end() == DELIMITER {
+            return Some(frontmatter);
+        }
+        frontmatter.push(line);
+    }
+    None
+}
+
+fn value_of<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let (name, value) = line.split_once(':')?;
+    (name.trim() == key).then(|| value.trim())
+}
+
+// Reads a schedule written by write_schedule. Notes without a due date have none, and the
+// interval and memorisation factor default as a new card's do.
+pub fn read_schedule(input: &str) -> Result<Option<RevisionSettings>, String> {
+    let Some(lines) = frontmatter_lines(input) else {
+        return Ok(None);
+    };
+    let find = |key: &str| lines.iter().find_map(|line| value_of(line, key));
+    let Some(due) = find(DUE_KEY) else {
+        return Ok(None);
+    };
+    let defaults = RevisionSettings::default();
+    let due = DateTime::parse_from_rfc3339(due)
+        .map(|due| due.with_timezone(&Utc))
+        .map_err(|_| format!("Could not parse frontmatter due value \"{}\"", due))?;
+    let number = |key: &str, default: f64| match find(key) {
+        None => Ok(default),
+        Some(value) => value
+            .parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite() && *v >= 0.0)
+            .ok_or(format!(
+                "Could not parse frontmatter {} value \"{}\"",
+                key, value
+            )),
+    };
+    Ok(Some(RevisionSettings::new(
+        due,
+        number(INTERVAL_KEY, defaults.interval)?,
+        number(MEMORISATION_FACTOR_KEY, defaults.memorisation_factor)?,
+    )))
+}
+
+// Replaces the schedule's lines in a note's frontmatter, adding them, or the frontmatter itself,
+// where they're missing. The rest of the note is kept as it is.
+pub fn write_schedule(input: &str, revision_settings: &RevisionSettings) -> String {
+    let values = [
+        revision_settings
+            .due
+            .to_rfc3339_opts(SecondsFormat::Secs, true),
+        revision_settings.interval.to_string(),
+        revision_settings.memorisation_factor.to_string(),
+    ];
+    let schedule_lines: Vec<String> = KEYS
+        .iter()
+        .zip(values)
+        .map(|(key, value)| format!("{}: {}", key, value))
+        .collect();
+    let Some(lines) = frontmatter_lines(input) else {
+        return format!(
+            "{}\n{}\n{}\n{}",
+            DELIMITER,
+            schedule_lines.join("\n"),
+            DELIMITER,
+            input
+        );
+    };
+    let mut rewritten: Vec<&str> = vec![DELIMITER];
+    rewritten.extend(
+        lines
+            .iter()
+            .filter(|line| !KEYS.iter().any(|key| value_of(line, key).is_some())),
+    );
+    rewritten.extend(schedule_lines.iter().map(String::as_str));
+    rewritten.push(DELIMITER);
+    // The frontmatter's lines, and its delimiters, are replaced whole.
+    let body = input
+        .split_inclusive('\n')
+        .skip(lines.len() + 2)
+        .collect::<String>();
+    format!("{}\n{}", rewritten.join("\n"), body)
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use chrono::TimeZone;
+    use rstest::*;
+
+    fn make_revision_settings() -> RevisionSettings {
+        let due = Utc.with_ymd_and_hms(2030, 1, 2, 3, 4, 5).unwrap();
+        RevisionSettings::new(due, 12.5, 2500.0)
+    }
+
+    #[rstest]
+    #[case::no_frontmatter("tags: a\n# Question\nq\n")]
+    #[case::frontmatter_without_a_schedule("---\ntags: a\n---\n# Question\nq\n")]
+    #[case::unclosed_frontmatter("---\ndue: 2030-01-02T03:04:05Z\n")]
+    fn read_schedule_of_a_note_without_one(#[case] input: &str) {
+        assert_eq!(Ok(None), super::read_schedule(input));
+    }
+
+    #[test]
+    fn read_schedule() {
+        let input = "---\ntags: a\ndue: 2030-01-02T03:04:05Z\ninterval: 12.5\n---\n# Question\n";
+        let actual = super::read_schedule(input).unwrap().unwrap();
+        assert_eq!(make_revision_settings().due, actual.due);
+        assert_eq!(12.5, actual.interval);
+        assert_eq!(1300.0, actual.memorisation_factor);
+        let input = "---\ndue: 2030-01-02T03:04:05Z\ninterval: soon\n---\n";
+        assert!(super::read_schedule(input)
+            .unwrap_err()
+            .contains("interval value \"soon\""));
+    }
+
+    #[rstest]
+    #[case::no_frontmatter(
+        "tags: a\n# Question\nq\n",
+        "---\ndue: 2030-01-02T03:04:05Z\ninterval: 12.5\nmemorisation_factor: 2500\n---\ntags: a\n# Question\nq\n"
+    )]
+    #[case::frontmatter(
+        "---\ntags: a\n---\n# Question\nq\n",
+        "---\ntags: a\ndue: 2030-01-02T03:04:05Z\ninterval: 12.5\nmemorisation_factor: 2500\n---\n# Question\nq\n"
+    )]
+    #[case::earlier_schedule(
+        "---\ndue: 2020-01-01T00:00:00Z\ntags: a\ninterval: 1\nmemorisation_factor: 1300\n---\n# Question\nq\n",
+        "---\ntags: a\ndue: 2030-01-02T03:04:05Z\ninterval: 12.5\nmemorisation_factor: 2500\n---\n# Question\nq\n"
+    )]
+    fn write_schedule(#[case] input: &str, #[case] expected: &str) {
+        let actual = super::write_schedule(input, &make_revision_settings());
+        assert_eq!(expected, actual);
+        assert_eq!(
+            Ok(Some(make_revision_settings())),
+            super::read_schedule(&actual)
+        );
+    }
+}
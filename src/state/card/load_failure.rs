@@ -0,0 +1,117 @@
+use super::parser::ParseError;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum LoadFailureKind {
+    Unreadable,
+    Unmatched { field: String, pattern: String },
+    InvalidValue,
+}
+
+// Why a note couldn't be loaded as a card.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct LoadFailure {
+    pub path: String,
+    pub kind: LoadFailureKind,
+    pub message: String,
+}
+
+impl LoadFailure {
+    // The kind is taken from the ParseError behind the error, if there is one.
+    pub fn from(path: &str, error: &(dyn Error + 'static)) -> Self {
+        let mut kind = LoadFailureKind::Unreadable;
+        let mut messages = vec![error.to_string()];
+        let mut source = error.source();
+        while let Some(error) = source {
+            messages.push(error.to_string());
+            kind = match error.downcast_ref::<ParseError>() {
+                Some(ParseError::Unmatched { field, pattern }) => LoadFailureKind::Unmatched {
+                    field: field.clone(),
+                    pattern: pattern.clone(),
+                },
+                Some(ParseError::InvalidValue(_)) => LoadFailureKind::InvalidValue,
+                Some(ParseError::MalformedPattern { .. } | ParseError::UnknownParser { .. })
+                | None => kind,
+            };
+            source = error.source();
+        }
+        Self {
+            path: path.to_string(),
+            kind,
+            message: messages.join(": "),
+        }
+    }
+
+    pub fn suggestion(&self) -> String {
+        match &self.kind {
+            LoadFailureKind::Unreadable => {
+                "Check that the note still exists and is readable UTF-8 text.".to_string()
+            }
+            LoadFailureKind::Unmatched { field, pattern } => format!(
+                "Write the note's {} so that the pattern {} matches it, or list the note in \
+                 .vultanignore if it isn't a card.",
+                field.to_lowercase(),
+                pattern
+            ),
+            LoadFailureKind::InvalidValue => {
+                "Correct or remove the line the error names.".to_string()
+            }
+        }
+    }
+}
+
+impl fmt::Display for LoadFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for LoadFailure {}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use snafu::{prelude::*, Whatever};
+
+    fn make_error(source: Result<(), impl Error + 'static>) -> Whatever {
+        source
+            .with_whatever_context(|_| "Unable to parse Card from \"a.md\"".to_string())
+            .unwrap_err()
+    }
+
+    #[test]
+    fn from_an_unmatched_pattern() {
+        let error = make_error(Err(ParseError::Unmatched {
+            field: "QUESTION".to_string(),
+            pattern: "# Question(.*)".to_string(),
+        }));
+        let actual = LoadFailure::from("a.md", &error);
+        let expected_kind = LoadFailureKind::Unmatched {
+            field: "QUESTION".to_string(),
+            pattern: "# Question(.*)".to_string(),
+        };
+        assert_eq!(expected_kind, actual.kind);
+        assert_eq!(
+            "Unable to parse Card from \"a.md\": Could not match QUESTION against pattern(\"# Question(.*)\")",
+            actual.message
+        );
+        assert!(actual.suggestion().contains("question"));
+    }
+
+    #[test]
+    fn from_other_errors() {
+        let error = make_error(Err(ParseError::InvalidValue("bad".to_string())));
+        assert_eq!(
+            LoadFailureKind::InvalidValue,
+            LoadFailure::from("a.md", &error).kind
+        );
+        let error = make_error(Err(std::io::Error::from(std::io::ErrorKind::NotFound)));
+        assert_eq!(
+            LoadFailureKind::Unreadable,
+            LoadFailure::from("a.md", &error).kind
+        );
+    }
+}
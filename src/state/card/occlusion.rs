@@ -0,0 +1,108 @@
+use regex::Regex;
+
+const SENTINEL: &str = "vultan-hide";
+// The sentinel with the comment around it, in the line comment syntaxes of most languages.
+const SENTINEL_PATTERN: &str = r"\s*(?://|#|--|;|%|/\*|<!--)\s*vultan-hide\s*(?:\*/|-->)?\s*$";
+const FENCES: [&str; 2] = ["```", "~~~"];
+const MASK: char = '▒';
+const MIN_MASK_WIDTH: usize = 3;
+
+// Lines of a fenced code block ending with a "vultan-hide" comment, such as
+// "    return a + b  # vultan-hide", are hidden in the question and revealed in the answer.
+pub fn has_occlusions(text: &str) -> bool {
+    text.contains(SENTINEL) && lines(text).any(|(_, hidden)| hidden.is_some())
+}
+
+// Each hidden line is masked, keeping its indentation so that the code's shape still shows.
+pub fn question(text: &str) -> String {
+    map_lines(text, |line, code| {
+        let indentation = &line[..line.len() - line.trim_start().len()];
+        let width = code.trim().chars().count().max(MIN_MASK_WIDTH);
+        format!("{}{}", indentation, MASK.to_string().repeat(width))
+    })
+}
+
+// The question with its hidden lines revealed, without their sentinels, followed by any answer.
+// Cards without occlusions keep their answer as it is.
+pub fn answer(question: &str, answer: &str) -> String {
+    if !has_occlusions(question) {
+        return answer.to_string();
+    }
+    let revealed = map_lines(question, |_, code| code.to_string());
+    match answer.trim().is_empty() {
+        true => revealed,
+        false => format!("{}\n\n{}", revealed, answer),
+    }
+}
+
+fn map_lines(text: &str, hide: impl Fn(&str, &str) -> String) -> String {
+    if !text.contains(SENTINEL) {
+        return text.to_string();
+    }
+    lines(text)
+        .map(|(line, hidden)| match hidden {
+            Some(code) => hide(line, code),
+            None => line.to_string(),
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// Every line, with the code of those to hide: those within a fence that end with the sentinel.
+fn lines(text: &str) -> impl Iterator<Item = (&str, Option<&str>)> {
+    let sentinel = Regex::new(SENTINEL_PATTERN).expect("sentinel pattern is a valid regex");
+    let mut fenced = false;
+    text.split('\n').map(move |line| {
+        if FENCES
+            .iter()
+            .any(|fence| line.trim_start().starts_with(fence))
+        {
+            fenced = !fenced;
+            return (line, None);
+        }
+        let hidden = match fenced {
+            true => sentinel.find(line).map(|m| &line[..m.start()]),
+            false => None,
+        };
+        (line, hidden)
+    })
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+
+    const QUESTION: &str = "What completes add?\n```python\ndef add(a, b):\n    return a + b  # vultan-hide\n```\nNot code # vultan-hide";
+
+    #[test]
+    fn question_masks_hidden_lines_within_fences() {
+        let expected = "What completes add?\n```python\ndef add(a, b):\n    ▒▒▒▒▒▒▒▒▒▒▒▒\n```\nNot code # vultan-hide";
+        assert_eq!(expected, question(QUESTION));
+        assert!(has_occlusions(QUESTION));
+    }
+
+    #[test]
+    fn answer_reveals_hidden_lines_above_the_answer() {
+        let revealed = "What completes add?\n```python\ndef add(a, b):\n    return a + b\n```\nNot code # vultan-hide";
+        assert_eq!(revealed, answer(QUESTION, ""));
+        assert_eq!(
+            format!("{}\n\nThe sum", revealed),
+            answer(QUESTION, "The sum")
+        );
+    }
+
+    #[test]
+    fn cards_without_occlusions_are_left_as_they_are() {
+        let text = "```rust\nlet x = 1; // a comment\n```\nvultan-hide outside a fence";
+        assert!(!has_occlusions(text));
+        assert_eq!(text, question(text));
+        assert_eq!("answer", answer(text, "answer"));
+    }
+
+    #[test]
+    fn sentinels_in_other_comment_syntaxes() {
+        let text = "~~~\nSELECT 1; -- vultan-hide\n<b>x</b> <!-- vultan-hide -->\nint y; /* vultan-hide */\n~~~";
+        assert_eq!("~~~\n▒▒▒▒▒▒▒▒▒\n▒▒▒▒▒▒▒▒\n▒▒▒▒▒▒\n~~~", question(text));
+    }
+}
@@ -0,0 +1,94 @@
+use crate::state::deck::IntervalCoefficients;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+const OVERRIDE_PATTERN: &str = r"(?m)^\s*vultan-([a-z-]+):[ \t]*(\S*)[ \t]*$";
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, PartialOrd, Serialize)]
+pub struct CardOverrides {
+    #[serde(default)]
+    pub pass_coef: Option<f64>,
+    #[serde(default)]
+    pub easy_coef: Option<f64>,
+    #[serde(default)]
+    pub fail_coef: Option<f64>,
+    #[serde(default)]
+    pub max_interval: Option<f64>,
+}
+
+impl CardOverrides {
+    // Reads "vultan-<name>: <value>" lines, such as "vultan-easy-coef: 2.0", from a note.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let expression = Regex::new(OVERRIDE_PATTERN).expect("override pattern is a valid regex");
+        let mut overrides = Self::default();
+        for captures in expression.captures_iter(input) {
+            let (name, value) = (&captures[1], &captures[2]);
+            let field = match name {
+                "pass-coef" => &mut overrides.pass_coef,
+                "easy-coef" => &mut overrides.easy_coef,
+                "fail-coef" => &mut overrides.fail_coef,
+                "max-interval" => &mut overrides.max_interval,
+                _ => continue,
+            };
+            let value = value
+                .parse::<f64>()
+                .ok()
+                .filter(|v| v.is_finite() && *v >= 0.0)
+                .ok_or(format!(
+                    "Could not parse vultan-{} value \"{}\"",
+                    name, value
+                ))?;
+            *field = Some(value);
+        }
+        Ok(overrides)
+    }
+
+    pub fn apply_to(&self, coefficients: &IntervalCoefficients) -> IntervalCoefficients {
+        IntervalCoefficients::new(
+            self.pass_coef.unwrap_or(coefficients.pass_coef),
+            self.easy_coef.unwrap_or(coefficients.easy_coef),
+            self.fail_coef.unwrap_or(coefficients.fail_coef),
+        )
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use rstest::*;
+
+    #[test]
+    fn parse() {
+        let input = "---\nvultan-easy-coef: 2.0\nvultan-max-interval: 90\nvultan-colour: red\ntags: a\n---\nvultan-pass-coef in prose: 3\n";
+        let expected = CardOverrides {
+            pass_coef: None,
+            easy_coef: Some(2.0),
+            fail_coef: None,
+            max_interval: Some(90.0),
+        };
+        assert_eq!(expected, CardOverrides::parse(input).unwrap());
+    }
+
+    #[rstest]
+    #[case::not_a_number("vultan-easy-coef: fast")]
+    #[case::negative("vultan-max-interval: -1")]
+    #[case::missing("vultan-fail-coef:")]
+    fn parse_fails_for_invalid_values(#[case] input: &str) {
+        let actual = CardOverrides::parse(input).unwrap_err();
+        assert!(actual.contains("Could not parse vultan-"));
+    }
+
+    #[test]
+    fn apply_to() {
+        let overrides = CardOverrides {
+            easy_coef: Some(2.0),
+            ..CardOverrides::default()
+        };
+        let expected = IntervalCoefficients::new(1.0, 2.0, 0.0);
+        assert_eq!(
+            expected,
+            overrides.apply_to(&IntervalCoefficients::default())
+        );
+    }
+}
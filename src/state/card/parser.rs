@@ -1,5 +1,11 @@
-use regex::Regex;
+use super::{frontmatter, prerequisites, CardOverrides, Priority, RevisionSettings};
+use crate::state::deck::HIERARCHY_SEPARATOR;
+use regex::{Captures, Match, Regex};
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+
+pub mod registry;
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct ParsingConfig {
@@ -7,6 +13,57 @@ pub struct ParsingConfig {
     pub deck_delimiter: String,
     pub question_pattern: ParsingPattern,
     pub answer_pattern: ParsingPattern,
+    #[serde(default = "default_tags_pattern")]
+    pub tags_pattern: Option<ParsingPattern>,
+    // Globs, relative to the notes directory, of the notes to read cards from.
+    #[serde(default = "default_note_globs")]
+    pub note_globs: Vec<String>,
+    // Also leave out the notes the notes directory's .gitignore ignores, as well as those its
+    // .vultanignore does.
+    #[serde(default)]
+    pub use_gitignore: bool,
+    #[serde(default = "default_true")]
+    pub follow_symlinks: bool,
+    // List a note reached through several symlinks once.
+    #[serde(default = "default_true")]
+    pub deduplicate_symlinked_notes: bool,
+    // Refuse to load any cards while a note fails to parse, rather than skipping it.
+    #[serde(default)]
+    pub strict_loading: bool,
+    // Warn when loading of cards with the same question, as doctor reports them.
+    #[serde(default)]
+    pub warn_duplicates: bool,
+    // The name of a parser in the ParserRegistry to read notes with, instead of the patterns.
+    #[serde(default)]
+    pub parser: Option<String>,
+    #[serde(default)]
+    pub schedule_location: ScheduleLocation,
+}
+
+// Where cards' schedules are written after reviews, besides the state file. Schedules found there
+// when loading take precedence over the state file's.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub enum ScheduleLocation {
+    #[default]
+    StateFile,
+    // The note's frontmatter.
+    Frontmatter,
+    // A "<note>.vultan" file next to the note, so that it's copied along with it.
+    Sidecar,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_note_globs() -> Vec<String> {
+    vec!["**/*.md".to_string()]
+}
+
+fn default_tags_pattern() -> Option<ParsingPattern> {
+    Some(ParsingPattern::InlineTags {
+        prefix: "#".to_string(),
+    })
 }
 
 impl Default for ParsingConfig {
@@ -24,6 +81,72 @@ impl Default for ParsingConfig {
                 opening_tag: "# Answer".to_string(),
                 closing_tag: "----\n".to_string(),
             },
+            tags_pattern: default_tags_pattern(),
+            note_globs: default_note_globs(),
+            use_gitignore: false,
+            follow_symlinks: true,
+            deduplicate_symlinked_notes: true,
+            strict_loading: false,
+            warn_duplicates: false,
+            parser: None,
+            schedule_location: ScheduleLocation::StateFile,
+        }
+    }
+}
+
+impl ParsingConfig {
+    // Tags are written verbatim, so this only round-trips through the Parser for
+    // tags without regex syntax, such as the defaults. Custom patterns' fields are written bare.
+    // Notes are rendered in the first of a chain's patterns.
+    pub fn render(&self, decks: &[String], question: &str, answer: &str) -> String {
+        let decks = match self.decks_pattern.preferred() {
+            ParsingPattern::InlineTags { prefix } => decks
+                .iter()
+                .map(|deck| format!("{}{}", prefix, deck))
+                .collect::<Vec<String>>()
+                .join(" "),
+            _ => decks.join(&self.deck_delimiter),
+        };
+        let fields = [
+            (self.decks_pattern.preferred(), decks.as_str()),
+            (self.question_pattern.preferred(), question),
+            (self.answer_pattern.preferred(), answer),
+        ];
+        let mut rendered = String::new();
+        let mut pending_closing_tag: Option<&str> = None;
+        for (pattern, content) in fields.into_iter() {
+            let opening_tag = pattern.opening_tag();
+            match pending_closing_tag.take() {
+                Some(closing_tag) if closing_tag != opening_tag => {
+                    Self::push_line(&mut rendered, closing_tag)
+                }
+                _ => {}
+            }
+            rendered.push_str(opening_tag);
+            match pattern {
+                ParsingPattern::TaggedLine { .. } => {
+                    rendered.push_str(&format!(" {}\n", content.replace('\n', " ")))
+                }
+                ParsingPattern::WrappedMultiLine { closing_tag, .. } => {
+                    rendered.push_str(&format!("\n{}\n", content));
+                    pending_closing_tag = Some(closing_tag);
+                }
+                ParsingPattern::InlineTags { .. } | ParsingPattern::Custom { .. } => {
+                    Self::push_line(&mut rendered, content)
+                }
+                ParsingPattern::FirstOf(_) => {}
+            }
+        }
+        if let Some(closing_tag) = pending_closing_tag {
+            Self::push_line(&mut rendered, closing_tag);
+        }
+        rendered
+    }
+
+    fn push_line(rendered: &mut String, line: &str) {
+        rendered.push_str(line);
+        if !line.ends_with('\n') {
+            rendered.push('\n');
         }
     }
 }
@@ -37,41 +160,198 @@ pub enum ParsingPattern {
     TaggedLine {
         tag: String,
     },
+    // Collects every "<prefix>name" in the note, such as "#biology/cells", rather than the first.
+    InlineTags {
+        prefix: String,
+    },
+    // Tries each pattern in turn, using the first that matches, so that notes written in
+    // different formats can be read with one config.
+    FirstOf(Vec<ParsingPattern>),
+    // A whole regex, whose group named after the field ("decks", "tags", "question" or "answer"),
+    // or else its first group, is the field's content. The same regex can serve every field.
+    Custom {
+        regex: String,
+    },
+}
+
+impl ParsingPattern {
+    fn opening_tag(&self) -> &str {
+        match self {
+            ParsingPattern::WrappedMultiLine { opening_tag, .. } => opening_tag,
+            ParsingPattern::TaggedLine { tag } => tag,
+            ParsingPattern::InlineTags { .. }
+            | ParsingPattern::FirstOf(_)
+            | ParsingPattern::Custom { .. } => "",
+        }
+    }
+
+    fn preferred(&self) -> &ParsingPattern {
+        match self {
+            ParsingPattern::FirstOf(patterns) if !patterns.is_empty() => patterns[0].preferred(),
+            pattern => pattern,
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct ParsedCardFields<'a> {
     pub decks: Vec<&'a str>,
+    pub tags: Vec<&'a str>,
     pub question: &'a str,
     pub answer: &'a str,
+    pub overrides: CardOverrides,
+    pub priority: Priority,
+    pub requires: Vec<&'a str>,
+    pub schedule: Option<RevisionSettings>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseError {
+    // A field's pattern matched nothing in the note.
+    Unmatched {
+        field: String,
+        pattern: String,
+    },
+    // A line, such as "vultan-max-interval: never", with a value that can't be used.
+    InvalidValue(String),
+    // A field's pattern that isn't a valid regex, or an empty FirstOf chain.
+    MalformedPattern {
+        field: String,
+        pattern: String,
+        message: String,
+    },
+    UnknownParser {
+        name: String,
+        registered: Vec<String>,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Unmatched { field, pattern } => {
+                write!(
+                    f,
+                    "Could not match {} against pattern(\"{}\")",
+                    field, pattern
+                )
+            }
+            ParseError::InvalidValue(message) => write!(f, "{}", message),
+            ParseError::MalformedPattern {
+                field,
+                pattern,
+                message,
+            } => write!(
+                f,
+                "Couldn't make Parser for the {} pattern(\"{}\") -> {}",
+                field, pattern, message
+            ),
+            ParseError::UnknownParser { name, registered } => write!(
+                f,
+                "No parser registered as \"{}\" (registered: {})",
+                name,
+                registered.join(", ")
+            ),
+        }
+    }
 }
 
+impl std::error::Error for ParseError {}
+
 pub trait Parse {
-    fn parse<'a>(&self, input: &'a str) -> Result<ParsedCardFields<'a>, String>;
+    fn parse<'a>(&self, input: &'a str) -> Result<ParsedCardFields<'a>, ParseError>;
+}
+
+impl<P: Parse + ?Sized> Parse for Arc<P> {
+    fn parse<'a>(&self, input: &'a str) -> Result<ParsedCardFields<'a>, ParseError> {
+        (**self).parse(input)
+    }
+}
+
+// A field's patterns, in the order they're tried.
+#[derive(Debug)]
+struct Expression {
+    regex: Regex,
+    collect_all: bool,
+    group: Option<String>,
+}
+
+impl Expression {
+    fn capture<'a>(&self, captures: &Captures<'a>) -> Option<Match<'a>> {
+        match &self.group {
+            Some(group) => captures.name(group),
+            None => captures.get(1),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct Parser {
-    decks_expression: Regex,
+    decks_expressions: Vec<Expression>,
     deck_delimiter: String,
-    question_expression: Regex,
-    answer_expression: Regex,
+    tags_expressions: Vec<Expression>,
+    question_expressions: Vec<Expression>,
+    answer_expressions: Vec<Expression>,
+    read_frontmatter_schedule: bool,
 }
 
 impl Parser {
-    pub fn from(user_config: ParsingConfig) -> Result<Self, String> {
-        let partial_error = format!("Couldn't make Parser for {:?}", &user_config);
+    pub fn from(user_config: ParsingConfig) -> Result<Self, ParseError> {
         Ok(Self {
             deck_delimiter: user_config.deck_delimiter,
-            decks_expression: Self::make_regex(&user_config.decks_pattern, &partial_error)?,
-            question_expression: Self::make_regex(&user_config.question_pattern, &partial_error)?,
-            answer_expression: Self::make_regex(&user_config.answer_pattern, &partial_error)?,
+            decks_expressions: Self::make_expressions(&user_config.decks_pattern, "decks")?,
+            tags_expressions: match &user_config.tags_pattern {
+                Some(pattern) => Self::make_expressions(pattern, "tags")?,
+                None => Vec::new(),
+            },
+            question_expressions: Self::make_expressions(
+                &user_config.question_pattern,
+                "question",
+            )?,
+            answer_expressions: Self::make_expressions(&user_config.answer_pattern, "answer")?,
+            read_frontmatter_schedule: user_config.schedule_location
+                == ScheduleLocation::Frontmatter,
         })
     }
 
-    fn make_regex(pattern: &ParsingPattern, error_formatter: &str) -> Result<Regex, String> {
-        let error_formatter = |e| format!("{} -> {}", error_formatter, e);
-        Regex::new(&Self::make_regex_expression(&pattern)).map_err(error_formatter)
+    fn make_expressions(
+        pattern: &ParsingPattern,
+        field: &str,
+    ) -> Result<Vec<Expression>, ParseError> {
+        match pattern {
+            ParsingPattern::FirstOf(patterns) if patterns.is_empty() => {
+                Err(ParseError::MalformedPattern {
+                    field: field.to_string(),
+                    pattern: String::new(),
+                    message: "FirstOf needs at least one pattern".to_string(),
+                })
+            }
+            ParsingPattern::FirstOf(patterns) => {
+                let mut expressions = Vec::new();
+                for pattern in patterns.iter() {
+                    expressions.extend(Self::make_expressions(pattern, field)?);
+                }
+                Ok(expressions)
+            }
+            pattern => {
+                let regex = Self::make_regex(pattern, field)?;
+                let named = regex.capture_names().flatten().any(|name| name == field);
+                Ok(vec![Expression {
+                    regex,
+                    collect_all: matches!(pattern, ParsingPattern::InlineTags { .. }),
+                    group: named.then(|| field.to_string()),
+                }])
+            }
+        }
+    }
+
+    fn make_regex(pattern: &ParsingPattern, field: &str) -> Result<Regex, ParseError> {
+        let expression = Self::make_regex_expression(pattern);
+        Regex::new(&expression).map_err(|e| ParseError::MalformedPattern {
+            field: field.to_string(),
+            pattern: expression.clone(),
+            message: e.to_string(),
+        })
     }
 
     fn make_regex_expression(pattern: &ParsingPattern) -> String {
@@ -82,45 +362,232 @@ impl Parser {
                 opening_tag,
                 closing_tag,
             } => format!(r"{}((?s).*){}", opening_tag, closing_tag),
+            InlineTags { prefix } => format!(r"(?m)(?:^|\s){}((?:[\w/-]|::)+)", prefix),
+            FirstOf(patterns) => patterns
+                .iter()
+                .map(Self::make_regex_expression)
+                .collect::<Vec<_>>()
+                .join(" | "),
+            Custom { regex } => regex.clone(),
+        }
+    }
+
+    // Writes the decks where the decks pattern found them, leaving the rest of the note as it is.
+    // Inline tags that are no longer decks are removed, and new ones are added to the line of the
+    // first tag.
+    pub fn rewrite_decks(&self, input: &str, decks: &[String]) -> Result<String, ParseError> {
+        self.decks_expressions
+            .iter()
+            .find_map(|expression| match expression.collect_all {
+                true => self.rewrite_inline_decks(expression, input, decks),
+                false => self.rewrite_listed_decks(expression, input, decks),
+            })
+            .ok_or_else(|| ParseError::Unmatched {
+                field: "DECKS".to_string(),
+                pattern: describe(&self.decks_expressions),
+            })
+    }
+
+    // Keeps any delimiters wrapping the list, as in "tags: :a:b:".
+    fn rewrite_listed_decks(
+        &self,
+        expression: &Expression,
+        input: &str,
+        decks: &[String],
+    ) -> Option<String> {
+        let captures = expression.regex.captures(input)?;
+        let content = expression.capture(&captures)?;
+        let listed = content.as_str().trim();
+        let start = content.start() + content.as_str().len() - content.as_str().trim_start().len();
+        let end = start + listed.len();
+        let delimiter = self.deck_delimiter.as_str();
+        let mut rewritten = decks.join(delimiter);
+        if !delimiter.is_empty() && listed.len() > delimiter.len() {
+            if listed.starts_with(delimiter) {
+                rewritten.insert_str(0, delimiter);
+            }
+            if listed.ends_with(delimiter) {
+                rewritten.push_str(delimiter);
+            }
+        }
+        if content.as_str().is_empty() {
+            rewritten.insert(0, ' ');
         }
+        Some(format!("{}{}{}", &input[..start], rewritten, &input[end..]))
     }
 
-    fn parse_string<'a>(&self, expression: &Regex, input: &'a str) -> Option<&'a str> {
-        Some(expression.captures(input)?.get(1)?.as_str().trim())
+    fn rewrite_inline_decks(
+        &self,
+        expression: &Expression,
+        input: &str,
+        decks: &[String],
+    ) -> Option<String> {
+        let tags: Vec<(usize, Match)> = expression
+            .regex
+            .captures_iter(input)
+            .filter_map(|captures| {
+                let tag = captures.get(1)?;
+                let prefix_length = input[captures.get(0)?.start()..tag.start()]
+                    .trim_start()
+                    .len();
+                Some((tag.start() - prefix_length, tag))
+            })
+            .filter(|(_, tag)| !tag.as_str().chars().all(|c| c.is_ascii_digit()))
+            .collect();
+        let (first_start, first_tag) = tags.first()?;
+        let prefix = &input[*first_start..first_tag.start()];
+        let mut edits: Vec<(usize, usize, String)> = Vec::new();
+        for (start, tag) in tags.iter() {
+            if decks.iter().any(|deck| deck == tag.as_str()) {
+                continue;
+            }
+            match input[..*start].ends_with(' ') {
+                true => edits.push((start - 1, tag.end(), String::new())),
+                false if input[tag.end()..].starts_with(' ') => {
+                    edits.push((*start, tag.end() + 1, String::new()))
+                }
+                false => edits.push((*start, tag.end(), String::new())),
+            }
+        }
+        let added: String = decks
+            .iter()
+            .filter(|deck| !tags.iter().any(|(_, tag)| tag.as_str() == deck.as_str()))
+            .map(|deck| format!(" {}{}", prefix, deck))
+            .collect();
+        let line_end = input[first_tag.end()..]
+            .find('\n')
+            .map_or(input.len(), |i| first_tag.end() + i);
+        edits.push((line_end, line_end, added));
+        edits.sort_by_key(|(start, _, _)| *start);
+        let mut rewritten = String::new();
+        let mut cursor = 0;
+        for (start, end, replacement) in edits.into_iter() {
+            rewritten.push_str(&input[cursor..start]);
+            rewritten.push_str(&replacement);
+            cursor = end;
+        }
+        rewritten.push_str(&input[cursor..]);
+        Some(rewritten)
+    }
+
+    fn parse_string<'a>(&self, expressions: &[Expression], input: &'a str) -> Option<&'a str> {
+        expressions
+            .iter()
+            .find_map(|expression| self.parse_first(expression, input))
+    }
+
+    fn parse_first<'a>(&self, expression: &Expression, input: &'a str) -> Option<&'a str> {
+        let captures = expression.regex.captures(input)?;
+        Some(expression.capture(&captures)?.as_str().trim())
     }
 
     fn parse_decks<'a>(&self, input: &'a str) -> Option<Vec<&'a str>> {
+        self.parse_list(&self.decks_expressions, input)
+    }
+
+    // Unlike decks, tags are optional.
+    fn parse_tags<'a>(&self, input: &'a str) -> Vec<&'a str> {
+        self.parse_list(&self.tags_expressions, input)
+            .unwrap_or_default()
+    }
+
+    fn parse_list<'a>(&self, expressions: &[Expression], input: &'a str) -> Option<Vec<&'a str>> {
+        expressions
+            .iter()
+            .find_map(|expression| self.parse_list_with(expression, input))
+    }
+
+    fn parse_list_with<'a>(&self, expression: &Expression, input: &'a str) -> Option<Vec<&'a str>> {
+        if expression.collect_all {
+            return self.parse_all(&expression.regex, input);
+        }
+        let line = self.parse_first(expression, input)?;
         Some(
-            self.parse_string(&self.decks_expression, input)?
-                .split(&self.deck_delimiter)
+            split_keeping_hierarchies(line, &self.deck_delimiter)
+                .into_iter()
                 .filter(|tag| !tag.is_empty())
                 .collect(),
         )
     }
 
+    // Purely numeric matches, such as "#1", are not tags.
+    fn parse_all<'a>(&self, expression: &Regex, input: &'a str) -> Option<Vec<&'a str>> {
+        let mut matches: Vec<&'a str> = Vec::new();
+        let all_matches = expression
+            .captures_iter(input)
+            .filter_map(|captures| Some(captures.get(1)?.as_str()))
+            .filter(|tag| !tag.chars().all(|c| c.is_ascii_digit()));
+        for tag in all_matches {
+            if !matches.contains(&tag) {
+                matches.push(tag);
+            }
+        }
+        Some(matches).filter(|matches| !matches.is_empty())
+    }
+
     fn error_if_none<T>(
         &self,
         parsed_field: Option<T>,
         field_id: &str,
-        expression: &Regex,
-    ) -> Result<T, String> {
-        parsed_field.ok_or(format!(
-            "Could not match {} against pattern(\"{}\")",
-            field_id,
-            expression.as_str()
-        ))
+        expressions: &[Expression],
+    ) -> Result<T, ParseError> {
+        parsed_field.ok_or_else(|| ParseError::Unmatched {
+            field: field_id.to_string(),
+            pattern: describe(expressions),
+        })
+    }
+}
+
+// A chain of patterns reads as alternatives, "a | b".
+fn describe(expressions: &[Expression]) -> String {
+    expressions
+        .iter()
+        .map(|expression| expression.regex.as_str())
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+// A delimiter such as ":" must not split nested deck names such as "lang::spanish".
+fn split_keeping_hierarchies<'a>(line: &'a str, delimiter: &str) -> Vec<&'a str> {
+    if delimiter.is_empty() || delimiter == HIERARCHY_SEPARATOR {
+        return line.split(delimiter).collect();
     }
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i < line.len() {
+        let rest = &line[i..];
+        if rest.starts_with(HIERARCHY_SEPARATOR) {
+            i += HIERARCHY_SEPARATOR.len();
+        } else if rest.starts_with(delimiter) {
+            parts.push(&line[start..i]);
+            i += delimiter.len();
+            start = i;
+        } else {
+            i += rest.chars().next().map_or(1, |c| c.len_utf8());
+        }
+    }
+    parts.push(&line[start..]);
+    parts
 }
 
 impl Parse for Parser {
-    fn parse<'a>(&self, input: &'a str) -> Result<ParsedCardFields<'a>, String> {
+    fn parse<'a>(&self, input: &'a str) -> Result<ParsedCardFields<'a>, ParseError> {
         let maybe_decks = self.parse_decks(input);
-        let maybe_question = self.parse_string(&self.question_expression, input);
-        let maybe_answer = self.parse_string(&self.answer_expression, input);
+        let maybe_question = self.parse_string(&self.question_expressions, input);
+        let maybe_answer = self.parse_string(&self.answer_expressions, input);
         Ok(ParsedCardFields {
-            decks: self.error_if_none(maybe_decks, "DECKS", &self.decks_expression)?,
-            question: self.error_if_none(maybe_question, "QUESTION", &self.question_expression)?,
-            answer: self.error_if_none(maybe_answer, "ANSWER", &self.answer_expression)?,
+            decks: self.error_if_none(maybe_decks, "DECKS", &self.decks_expressions)?,
+            tags: self.parse_tags(input),
+            question: self.error_if_none(maybe_question, "QUESTION", &self.question_expressions)?,
+            answer: self.error_if_none(maybe_answer, "ANSWER", &self.answer_expressions)?,
+            overrides: CardOverrides::parse(input).map_err(ParseError::InvalidValue)?,
+            priority: Priority::parse(input).map_err(ParseError::InvalidValue)?,
+            requires: prerequisites::parse(input),
+            schedule: match self.read_frontmatter_schedule {
+                true => frontmatter::read_schedule(input).map_err(ParseError::InvalidValue)?,
+                false => None,
+            },
         })
     }
 }
@@ -132,7 +599,7 @@ use mockall::*;
 mock! {
     pub Parser{}
     impl Parse for Parser {
-        fn parse(&self, input: &str) -> Result<ParsedCardFields<'static>, String>;
+        fn parse(&self, input: &str) -> Result<ParsedCardFields<'static>, ParseError>;
     }
 }
 
@@ -164,6 +631,89 @@ mod unit_tests {
             assert_eq!(expected_tag_delimiter, actual.deck_delimiter);
             assert_eq!(expected_question_pattern, actual.question_pattern);
             assert_eq!(expected_answer_pattern, actual.answer_pattern);
+            assert_eq!(
+                Some(ParsingPattern::InlineTags {
+                    prefix: String::from("#")
+                }),
+                actual.tags_pattern
+            );
+        }
+
+        #[test]
+        fn render_with_the_first_of_a_chain() {
+            let config = ParsingConfig {
+                question_pattern: ParsingPattern::FirstOf(vec![
+                    ParsingPattern::TaggedLine {
+                        tag: "Q:".to_string(),
+                    },
+                    ParsingConfig::default().question_pattern,
+                ]),
+                answer_pattern: ParsingPattern::FirstOf(vec![ParsingPattern::TaggedLine {
+                    tag: "A:".to_string(),
+                }]),
+                ..ParsingConfig::default()
+            };
+            let actual = config.render(&["a".to_string()], "who?", "me");
+            assert_eq!("tags: a\nQ: who?\nA: me\n", actual);
+        }
+
+        #[test]
+        fn render() {
+            let decks = vec!["a".to_string(), "b".to_string()];
+            let expected = "tags: a:b\n# Question\nwho\ndis?\n# Answer\nme\n----\n";
+            let actual = ParsingConfig::default().render(&decks, "who\ndis?", "me");
+            assert_eq!(expected, actual);
+        }
+
+        #[test]
+        fn render_inline_tags() {
+            let config = ParsingConfig {
+                decks_pattern: ParsingPattern::InlineTags {
+                    prefix: "#".to_string(),
+                },
+                ..ParsingConfig::default()
+            };
+            let decks = vec!["biology/cells".to_string(), "exam".to_string()];
+            let rendered = config.render(&decks, "what?", "that");
+            assert_eq!(
+                "#biology/cells #exam\n# Question\nwhat?\n# Answer\nthat\n----\n",
+                rendered
+            );
+            let actual = Parser::from(config).unwrap().parse(&rendered).unwrap();
+            assert_eq!(vec!["biology/cells", "exam"], actual.decks);
+        }
+
+        #[test]
+        fn render_round_trips_through_parser() {
+            let config = ParsingConfig {
+                decks_pattern: ParsingPattern::WrappedMultiLine {
+                    opening_tag: "Decks:".to_string(),
+                    closing_tag: "Question:".to_string(),
+                },
+                deck_delimiter: "\n - ".to_string(),
+                question_pattern: ParsingPattern::TaggedLine {
+                    tag: "Question:".to_string(),
+                },
+                answer_pattern: ParsingPattern::TaggedLine {
+                    tag: "Answer:".to_string(),
+                },
+                tags_pattern: None,
+                note_globs: default_note_globs(),
+                use_gitignore: false,
+                follow_symlinks: true,
+                deduplicate_symlinked_notes: true,
+                strict_loading: false,
+                warn_duplicates: false,
+                parser: None,
+                schedule_location: ScheduleLocation::StateFile,
+            };
+            let decks = vec!["a".to_string(), "b".to_string()];
+            let rendered = config.render(&decks, "what?", "thing");
+            let parser = Parser::from(config).unwrap();
+            let actual = parser.parse(&rendered).unwrap();
+            assert_eq!(vec!["a", "b"], actual.decks);
+            assert_eq!("what?", actual.question);
+            assert_eq!("thing", actual.answer);
         }
     }
 
@@ -183,6 +733,15 @@ mod unit_tests {
                 deck_delimiter,
                 question_pattern,
                 answer_pattern,
+                tags_pattern: None,
+                note_globs: default_note_globs(),
+                use_gitignore: false,
+                follow_symlinks: true,
+                deduplicate_symlinked_notes: true,
+                strict_loading: false,
+                warn_duplicates: false,
+                parser: None,
+                schedule_location: ScheduleLocation::StateFile,
             }
         }
 
@@ -202,6 +761,17 @@ mod unit_tests {
             }
         }
 
+        fn fake_inline_tags_user_config() -> ParsingConfig {
+            fake_parsing_config(
+                ParsingPattern::InlineTags {
+                    prefix: "#".to_string(),
+                },
+                ":".to_string(),
+                fake_wrapped_multi_line_parsing_pattern("# Question", "# Answer"),
+                fake_wrapped_multi_line_parsing_pattern("# Answer", "----\n"),
+            )
+        }
+
         fn fake_custom_user_config() -> ParsingConfig {
             fake_parsing_config(
                 fake_wrapped_multi_line_parsing_pattern("Decks:", "Question:"),
@@ -211,6 +781,33 @@ mod unit_tests {
             )
         }
 
+        fn fake_chained_user_config() -> ParsingConfig {
+            ParsingConfig {
+                question_pattern: ParsingPattern::FirstOf(vec![
+                    fake_tagged_line_parsing_pattern("Q:"),
+                    fake_wrapped_multi_line_parsing_pattern("# Question", "# Answer"),
+                ]),
+                answer_pattern: ParsingPattern::FirstOf(vec![
+                    fake_tagged_line_parsing_pattern("A:"),
+                    fake_wrapped_multi_line_parsing_pattern("# Answer", "----\n"),
+                ]),
+                ..ParsingConfig::default()
+            }
+        }
+
+        fn fake_custom_regex_user_config() -> ParsingConfig {
+            let pattern = ParsingPattern::Custom {
+                regex: r"Front: (?P<question>.*)\nBack: (?P<answer>.*)\nDeck: (?P<decks>.*)"
+                    .to_string(),
+            };
+            ParsingConfig {
+                decks_pattern: pattern.clone(),
+                question_pattern: pattern.clone(),
+                answer_pattern: pattern,
+                ..ParsingConfig::default()
+            }
+        }
+
         fn make_fake_config(field: &str, value: &str) -> ParsingConfig {
             let mut user_config = ParsingConfig::default();
             match field.to_lowercase().as_str() {
@@ -218,10 +815,10 @@ mod unit_tests {
                     user_config.decks_pattern = fake_tagged_line_parsing_pattern(value);
                 }
                 "question" => {
-                    user_config.decks_pattern = fake_tagged_line_parsing_pattern(value);
+                    user_config.question_pattern = fake_tagged_line_parsing_pattern(value);
                 }
                 "answer" => {
-                    user_config.decks_pattern = fake_tagged_line_parsing_pattern(value);
+                    user_config.answer_pattern = fake_tagged_line_parsing_pattern(value);
                 }
                 _ => panic!("BAD TEST"),
             };
@@ -233,35 +830,34 @@ mod unit_tests {
             ParsingConfig::default(),
             Ok((r"tags:(.*)", r"# Question((?s).*)# Answer", "# Answer((?s).*)----\n"))
         )]
-        #[case::fails_for_malformed_decks_pattern(
-            make_fake_config("decks", "(("),
-            Err("Couldn't make Parser for ParsingConfig")
-        )]
+        #[case::fails_for_malformed_decks_pattern(make_fake_config("decks", "(("), Err("decks"))]
         #[case::fails_for_malformed_question_pattern(
             make_fake_config("question", "(("),
-            Err("Couldn't make Parser for ParsingConfig")
+            Err("question")
         )]
-        #[case::fails_for_malformed_answer_pattern(
-            make_fake_config("answer", "(("),
-            Err("Couldn't make Parser for ParsingConfig")
+        #[case::fails_for_an_empty_chain(
+            ParsingConfig {
+                answer_pattern: ParsingPattern::FirstOf(vec![]),
+                ..ParsingConfig::default()
+            },
+            Err("answer")
         )]
+        #[case::fails_for_malformed_answer_pattern(make_fake_config("answer", "(("), Err("answer"))]
         fn from(#[case] config: ParsingConfig, #[case] expected: Result<(&str, &str, &str), &str>) {
             let expected_delimiter = config.deck_delimiter.to_string();
             let actual = Parser::from(config);
             match expected {
                 Ok((expected_decks, expected_question, expected_answer)) => {
                     let actual = actual.unwrap();
-                    assert_eq!(expected_decks, actual.decks_expression.as_str());
+                    assert_eq!(expected_decks, describe(&actual.decks_expressions));
                     assert_eq!(expected_delimiter, actual.deck_delimiter);
-                    assert_eq!(expected_question, actual.question_expression.as_str());
-                    assert_eq!(expected_answer, actual.answer_expression.as_str());
-                }
-                Err(expected_message) => {
-                    assert!(actual.is_err());
-                    assert!(actual
-                        .unwrap_err()
-                        .contains("Couldn't make Parser for ParsingConfig"));
+                    assert_eq!(expected_question, describe(&actual.question_expressions));
+                    assert_eq!(expected_answer, describe(&actual.answer_expressions));
                 }
+                Err(expected_field) => match actual.unwrap_err() {
+                    ParseError::MalformedPattern { field, .. } => assert_eq!(expected_field, field),
+                    error => panic!("Unexpected error: {}", error),
+                },
             }
         }
 
@@ -276,6 +872,26 @@ mod unit_tests {
             "some noise\nDecks:\n a\n - b\n - c\nQuestion: what?\nAnswer: thing\nsome noise",
             Ok((vec!["a","b","c"], "what?", "thing"))
         )]
+        #[case::with_inline_tags(
+            fake_inline_tags_user_config(),
+            "#biology/cells\n# Question\nwhat makes #ATP? see page#2 and #1\n# Answer\nthe #biology/cells mitochondria\n----\n",
+            Ok((vec!["biology/cells", "ATP"], "what makes #ATP? see page#2 and #1", "the #biology/cells mitochondria"))
+        )]
+        #[case::with_hierarchical_decks(
+            ParsingConfig::default(),
+            "tags: :lang::spanish::verbs:exam:\n# Question\nhablar?\n# Answer\nto speak\n----\n",
+            Ok((vec!["lang::spanish::verbs", "exam"], "hablar?", "to speak"))
+        )]
+        #[case::with_hierarchical_inline_tags(
+            fake_inline_tags_user_config(),
+            "#lang::spanish: verbs\n# Question\nhablar?\n# Answer\nto speak\n----\n",
+            Ok((vec!["lang::spanish"], "hablar?", "to speak"))
+        )]
+        #[case::with_inline_tags_that_have_no_matches(
+            fake_inline_tags_user_config(),
+            "# Question\nwhat?\n# Answer\nthat\n----\n",
+            Err("Could not match DECKS against pattern")
+        )]
         #[case::with_decks_expression_that_have_no_captures(
             ParsingConfig::default(),
             "---\nk1: v1\n---\n# Question\nwhat?\n# Answer \nthing\n\n----\nBacklink: SOMELINK\n",
@@ -286,6 +902,33 @@ mod unit_tests {
             "---\nk1: v1\ntags: :a:\n---\n# A Q\nwhat?\n# Answer \nthing\n\n----\n",
             Err("Could not match QUESTION against pattern")
         )]
+        #[case::with_a_chain_falling_back_to_its_second_pattern(
+            fake_chained_user_config(),
+            "tags: a\n# Question\nwhat?\n# Answer\nthat\n----\n",
+            Ok((vec!["a"], "what?", "that"))
+        )]
+        #[case::with_a_chain_matching_its_first_pattern(
+            fake_chained_user_config(),
+            "tags: a\nQ: what?\nA: that\n# Question\nignored\n# Answer\nignored\n----\n",
+            Ok((vec!["a"], "what?", "that"))
+        )]
+        #[case::with_a_chain_that_has_no_captures(
+            fake_chained_user_config(),
+            "tags: a\n# A Q\nwhat?\nA: that\n",
+            Err(
+                "Could not match QUESTION against pattern(\"Q:(.*) | # Question((?s).*)# Answer\")"
+            )
+        )]
+        #[case::with_a_custom_regex_of_named_groups(
+            fake_custom_regex_user_config(),
+            "Front: what?\nBack: that\nDeck: a:b\n",
+            Ok((vec!["a", "b"], "what?", "that"))
+        )]
+        #[case::with_a_custom_regex_that_has_no_match(
+            fake_custom_regex_user_config(),
+            "Front: what?\nDeck: a:b\n",
+            Err("Could not match DECKS against pattern")
+        )]
         #[case::with_answer_expression_that_have_no_captures(
             ParsingConfig::default(),
             "---\ntags: :a:\n---\n# Question\nwho?\n# Answer \ntme\n\n--_--\n",
@@ -306,9 +949,116 @@ mod unit_tests {
                     assert_eq!(expected_answer, actual.answer);
                 }
                 Err(expected_message) => {
-                    assert!(actual.unwrap_err().contains(expected_message));
+                    assert!(actual.unwrap_err().to_string().contains(expected_message));
                 }
             }
         }
+
+        #[rstest]
+        #[case::inline_tags(
+            ParsingConfig::default(),
+            "tags: deck\n# Question\nwhat? #needs-rewrite\n# Answer\nthat #exam #1\n----\n",
+            vec!["needs-rewrite", "exam"]
+        )]
+        #[case::tagged_line(
+            ParsingConfig {
+                tags_pattern: Some(ParsingPattern::TaggedLine { tag: "labels:".to_string() }),
+                ..ParsingConfig::default()
+            },
+            "tags: deck\nlabels: :a:b:\n# Question\nwhat? #c\n# Answer\nthat\n----\n",
+            vec!["a", "b"]
+        )]
+        #[case::no_tags(
+            ParsingConfig::default(),
+            "tags: deck\n# Question\nwhat?\n# Answer\nthat\n----\n",
+            vec![]
+        )]
+        #[case::custom_regex_without_a_named_group(
+            ParsingConfig {
+                tags_pattern: Some(ParsingPattern::Custom { regex: r"labels=\[(.*)\]".to_string() }),
+                ..ParsingConfig::default()
+            },
+            "tags: deck\nlabels=[x:y]\n# Question\nwhat?\n# Answer\nthat\n----\n",
+            vec!["x", "y"]
+        )]
+        #[case::tags_disabled(
+            ParsingConfig {
+                tags_pattern: None,
+                ..ParsingConfig::default()
+            },
+            "tags: deck\n# Question\nwhat? #exam\n# Answer\nthat\n----\n",
+            vec![]
+        )]
+        fn parse_tags(
+            #[case] user_config: ParsingConfig,
+            #[case] input: &str,
+            #[case] expected: Vec<&str>,
+        ) {
+            let parser = Parser::from(user_config).unwrap();
+            let actual = parser.parse(input).unwrap();
+            assert_eq!(vec!["deck"], actual.decks);
+            assert_eq!(expected, actual.tags);
+        }
+
+        #[rstest]
+        #[case::tagged_line(
+            ParsingConfig::default(),
+            "---\ntags: :old:keep:\n---\n# Question\nq\n# Answer\na\n----\n",
+            "---\ntags: :keep:new:\n---\n# Question\nq\n# Answer\na\n----\n"
+        )]
+        #[case::tagged_line_without_wrapping_delimiters(
+            ParsingConfig::default(),
+            "tags: old\n# Question\nq\n# Answer\na\n----\n",
+            "tags: keep:new\n# Question\nq\n# Answer\na\n----\n"
+        )]
+        #[case::wrapped_multi_line(
+            fake_custom_user_config(),
+            "Decks:\n old\n - keep\nQuestion: q\nAnswer: a\n",
+            "Decks:\n keep\n - new\nQuestion: q\nAnswer: a\n"
+        )]
+        #[case::inline_tags(
+            fake_inline_tags_user_config(),
+            "#old #keep #1\n# Question\nq #old\n# Answer\na\n----\n",
+            "#keep #1 #new\n# Question\nq\n# Answer\na\n----\n"
+        )]
+        fn rewrite_decks(
+            #[case] user_config: ParsingConfig,
+            #[case] input: &str,
+            #[case] expected: &str,
+        ) {
+            let parser = Parser::from(user_config).unwrap();
+            let decks = ["keep", "new"].map(String::from);
+            let actual = parser.rewrite_decks(input, &decks).unwrap();
+            assert_eq!(expected, actual);
+            assert_eq!(vec!["keep", "new"], parser.parse(&actual).unwrap().decks);
+        }
+
+        #[test]
+        fn rewrite_decks_of_a_note_without_them() {
+            let parser = Parser::from(ParsingConfig::default()).unwrap();
+            let actual = parser.rewrite_decks("# Question\nq\n", &["a".to_string()]);
+            assert!(actual.is_err());
+        }
+
+        #[test]
+        fn parse_surfaces_card_overrides() {
+            let parser = Parser::from(ParsingConfig::default()).unwrap();
+            let input =
+                "---\ntags: a\nvultan-max-interval: 90\n---\n# Question\nq\n# Answer\na\n----\n";
+            let actual = parser.parse(input).unwrap();
+            assert_eq!(Some(90.0), actual.overrides.max_interval);
+            let input = input.replace("90", "never");
+            let actual = parser.parse(&input).unwrap_err();
+            assert!(actual
+                .to_string()
+                .contains("Could not parse vultan-max-interval"));
+        }
+
+        #[test]
+        fn parse_surfaces_priority() {
+            let parser = Parser::from(ParsingConfig::default()).unwrap();
+            let input = "---\ntags: a\npriority: high\n---\n# Question\nq\n# Answer\na\n----\n";
+            assert_eq!(Priority::High, parser.parse(input).unwrap().priority);
+        }
     }
 }
@@ -0,0 +1,100 @@
+use super::{Parse, ParseError, Parser, ParsingConfig};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+pub type SharedParser = Arc<dyn Parse + Send + Sync>;
+
+// Parsers registered by name, e.g. by a downstream crate for a note format of its own, for a
+// ParsingConfig to pick with its parser field. The registry is shared by the whole process.
+pub struct ParserRegistry;
+
+fn parsers() -> &'static RwLock<HashMap<String, SharedParser>> {
+    static PARSERS: OnceLock<RwLock<HashMap<String, SharedParser>>> = OnceLock::new();
+    PARSERS.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+impl ParserRegistry {
+    // Replaces any parser already registered under the name.
+    pub fn register(name: &str, parser: Box<dyn Parse + Send + Sync>) {
+        let mut parsers = parsers().write().unwrap_or_else(|e| e.into_inner());
+        parsers.insert(name.to_string(), Arc::from(parser));
+    }
+
+    pub fn get(name: &str) -> Option<SharedParser> {
+        let parsers = parsers().read().unwrap_or_else(|e| e.into_inner());
+        parsers.get(name).cloned()
+    }
+
+    pub fn names() -> Vec<String> {
+        let parsers = parsers().read().unwrap_or_else(|e| e.into_inner());
+        let mut names: Vec<String> = parsers.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    // The parser the config names, or else one made from its patterns.
+    pub fn parser_for(config: ParsingConfig) -> Result<SharedParser, ParseError> {
+        match &config.parser {
+            Some(name) => Self::get(name).ok_or_else(|| ParseError::UnknownParser {
+                name: name.clone(),
+                registered: Self::names(),
+            }),
+            None => Ok(Arc::new(Parser::from(config)?)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::super::{ParseError, ParsedCardFields};
+    use super::*;
+    use crate::state::card::{CardOverrides, Priority};
+
+    struct FirstLineParser;
+
+    impl Parse for FirstLineParser {
+        fn parse<'a>(&self, input: &'a str) -> Result<ParsedCardFields<'a>, ParseError> {
+            let (question, answer) = input.split_once('\n').unwrap_or((input, ""));
+            Ok(ParsedCardFields {
+                decks: vec!["plugged-in"],
+                tags: vec![],
+                question,
+                answer,
+                overrides: CardOverrides::default(),
+                priority: Priority::default(),
+                requires: vec![],
+                schedule: None,
+            })
+        }
+    }
+
+    #[test]
+    fn parser_for_a_registered_name() {
+        ParserRegistry::register("registry-test-first-line", Box::new(FirstLineParser));
+        let config = ParsingConfig {
+            parser: Some("registry-test-first-line".to_string()),
+            ..ParsingConfig::default()
+        };
+        let parser = ParserRegistry::parser_for(config).unwrap();
+        let actual = parser.parse("what?\nthat").unwrap();
+        assert_eq!(vec!["plugged-in"], actual.decks);
+        assert_eq!(("what?", "that"), (actual.question, actual.answer));
+        assert!(ParserRegistry::names().contains(&"registry-test-first-line".to_string()));
+    }
+
+    #[test]
+    fn parser_for_patterns_or_an_unknown_name() {
+        let parser = ParserRegistry::parser_for(ParsingConfig::default()).unwrap();
+        let actual = parser.parse("tags: a\n# Question\nq\n# Answer\na\n----\n");
+        assert_eq!(vec!["a"], actual.unwrap().decks);
+        let config = ParsingConfig {
+            parser: Some("registry-test-unknown".to_string()),
+            ..ParsingConfig::default()
+        };
+        let actual = ParserRegistry::parser_for(config).err().unwrap();
+        assert!(
+            matches!(actual, ParseError::UnknownParser { name, .. } if name == "registry-test-unknown")
+        );
+    }
+}
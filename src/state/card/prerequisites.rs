@@ -0,0 +1,39 @@
+use regex::Regex;
+
+const REQUIRES_PATTERN: &str = r"(?m)^\s*requires:[ \t]*(.*?)[ \t]*$";
+
+// Reads a "requires: [other/card.md, basics.md]" line from a note, with or without the brackets,
+// into the paths of the notes whose cards should be learnt first.
+pub fn parse(input: &str) -> Vec<&str> {
+    let expression = Regex::new(REQUIRES_PATTERN).expect("requires pattern is a valid regex");
+    let Some(captures) = expression.captures(input) else {
+        return Vec::new();
+    };
+    let list = captures.get(1).map_or("", |m| m.as_str());
+    let list = list
+        .strip_prefix('[')
+        .and_then(|list| list.strip_suffix(']'))
+        .unwrap_or(list);
+    list.split(',')
+        .map(|path| path.trim().trim_matches(|c| c == '"' || c == '\''))
+        .filter(|path| !path.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use rstest::*;
+
+    #[rstest]
+    #[case::list(
+        "---\ntags: a\nrequires: [bio/cells.md, \"bio/atp.md\"]\n---\n",
+        vec!["bio/cells.md", "bio/atp.md"]
+    )]
+    #[case::bare("requires: basics.md \n# Question\n", vec!["basics.md"])]
+    #[case::empty("requires: []\n", vec![])]
+    #[case::unset("# Question\nwhat requires: nothing?\n", vec![])]
+    fn parse(#[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expected, super::parse(input));
+    }
+}
@@ -0,0 +1,68 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+const PRIORITY_PATTERN: &str = r"(?m)^\s*priority:[ \t]*(\S*)[ \t]*$";
+
+// Due cards are reviewed a tier at a time, most important first.
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize,
+)]
+pub enum Priority {
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+impl Priority {
+    // Reads a "priority: high|normal|low" line from a note; notes without one are normal.
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let expression = Regex::new(PRIORITY_PATTERN).expect("priority pattern is a valid regex");
+        match expression.captures(input) {
+            None => Ok(Self::default()),
+            Some(captures) => match &captures[1] {
+                "high" => Ok(Priority::High),
+                "normal" => Ok(Priority::Normal),
+                "low" => Ok(Priority::Low),
+                value => Err(format!(
+                    "Could not parse priority \"{}\": expected high, normal or low",
+                    value
+                )),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case::high("---\ntags: a\npriority: high\n---\n", Priority::High)]
+    #[case::low("priority: low \n# Question\n", Priority::Low)]
+    #[case::unset(
+        "---\ntags: a\n---\n# Question\nwhat is the priority: here?\n",
+        Priority::Normal
+    )]
+    fn parse(#[case] input: &str, #[case] expected: Priority) {
+        assert_eq!(Ok(expected), Priority::parse(input));
+    }
+
+    #[test]
+    fn parse_fails_for_unknown_priorities() {
+        let actual = Priority::parse("priority: urgent").unwrap_err();
+        assert!(actual.contains("\"urgent\""));
+    }
+
+    #[test]
+    fn high_priorities_sort_first() {
+        let mut priorities = vec![Priority::Low, Priority::High, Priority::Normal];
+        priorities.sort();
+        assert_eq!(
+            vec![Priority::High, Priority::Normal, Priority::Low],
+            priorities
+        );
+    }
+}
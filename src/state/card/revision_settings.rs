@@ -29,7 +29,33 @@ impl RevisionSettings {
     }
 
     pub fn transform(self, score: Score, coefficients: &IntervalCoefficients) -> Self {
-        let new_interval = self.calculate_new_interval(&score, &coefficients);
+        self.transform_with_max_interval(score, coefficients, None)
+    }
+
+    pub fn transform_with_max_interval(
+        self,
+        score: Score,
+        coefficients: &IntervalCoefficients,
+        max_interval: Option<f64>,
+    ) -> Self {
+        self.transform_at(score, coefficients, max_interval, Utc::now())
+    }
+
+    // What the schedule would become with the score, leaving this one as it is.
+    pub fn preview(&self, score: Score, coefficients: &IntervalCoefficients) -> Self {
+        self.clone().transform(score, coefficients)
+    }
+
+    // As if reviewed at the given time rather than now, e.g. to project future reviews.
+    pub fn transform_at(
+        self,
+        score: Score,
+        coefficients: &IntervalCoefficients,
+        max_interval: Option<f64>,
+        reviewed_at: DateTime<Utc>,
+    ) -> Self {
+        let new_interval = self.calculate_new_interval(&score, &coefficients, reviewed_at);
+        let new_interval = max_interval.map_or(new_interval, |max| new_interval.min(max));
         Self {
             due: self.calculate_new_due_date(new_interval),
             interval: new_interval,
@@ -37,6 +63,15 @@ impl RevisionSettings {
         }
     }
 
+    // With a new interval, counted from the current due date as the built-in scheduling does.
+    pub fn rescheduled(&self, interval: f64, memorisation_factor: f64) -> Self {
+        Self {
+            due: self.calculate_new_due_date(interval),
+            interval,
+            memorisation_factor,
+        }
+    }
+
     fn calculate_new_due_date(&self, new_interval: f64) -> DateTime<Utc> {
         let seconds_in_minute = 60.0;
         let minutes_in_hour = 60.0;
@@ -55,9 +90,14 @@ impl RevisionSettings {
         }
     }
 
-    fn calculate_new_interval(&self, score: &Score, coefficients: &IntervalCoefficients) -> f64 {
+    fn calculate_new_interval(
+        &self,
+        score: &Score,
+        coefficients: &IntervalCoefficients,
+        reviewed_at: DateTime<Utc>,
+    ) -> f64 {
         let PossibleIntervals(fail_interval, hard_interval, pass_interval, easy_interval) =
-            self.calculate_possible_intervals(coefficients);
+            self.calculate_possible_intervals(coefficients, reviewed_at);
         match score {
             Score::Fail => fail_interval,
             Score::Hard => hard_interval,
@@ -69,8 +109,10 @@ impl RevisionSettings {
     fn calculate_possible_intervals(
         &self,
         coefficients: &IntervalCoefficients,
+        reviewed_at: DateTime<Utc>,
     ) -> PossibleIntervals {
-        let calculation_settings = self.create_interval_calculation_settings(coefficients);
+        let calculation_settings =
+            self.create_interval_calculation_settings(coefficients, reviewed_at);
         let fail_interval = self.calculate_fail_interval(&calculation_settings);
         let hard_interval = self.calculate_hard_interval(&calculation_settings);
         let pass_interval = self.calculate_pass_interval(&calculation_settings, hard_interval);
@@ -81,8 +123,9 @@ impl RevisionSettings {
     fn create_interval_calculation_settings<'a>(
         &self,
         coefficients: &'a IntervalCoefficients,
+        reviewed_at: DateTime<Utc>,
     ) -> IntervalCalculationSettings<'a> {
-        let present = Utc::now();
+        let present = reviewed_at;
         let past = self.due;
         let days_overdue_quantised_by_hour =
             (present.signed_duration_since(past).num_hours() as f64) / 24.0;
@@ -229,7 +272,8 @@ mod unit_tests {
         let coefficients = IntervalCoefficients::new(pass_coef, easy_coef, fail_coef);
         let expected = make_interval_calculation_settings(&coefficients, n_days_overdue);
         let revision_settings = RevisionSettings::new(due, interval, memorisation_factor);
-        let actual = revision_settings.create_interval_calculation_settings(&coefficients);
+        let actual =
+            revision_settings.create_interval_calculation_settings(&coefficients, Utc::now());
         assert_eq!(expected, actual);
     }
 
@@ -304,6 +348,17 @@ mod unit_tests {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn transform_at_counts_days_overdue_from_the_review_time() {
+        let due = Utc::now() + Duration::days(30);
+        let revision_settings = RevisionSettings::new(due, 1.0, 2000.0);
+        let coefficients = IntervalCoefficients::new(1.0, 2.0, 0.0);
+        let reviewed_at = due + Duration::days(4);
+        let actual = revision_settings.transform_at(Score::Pass, &coefficients, None, reviewed_at);
+        assert_eq!(6.0, actual.interval);
+        assert_eq!(due + Duration::days(6), actual.due);
+    }
+
     #[test]
     fn calculate_possible_intervals() {
         let interval = 1.0;
@@ -316,7 +371,7 @@ mod unit_tests {
         let revision_settings = RevisionSettings::new(due, interval, factor);
         let coefficients = IntervalCoefficients::new(pass_coef, easy_coef, fail_coef);
         let expected = PossibleIntervals(0.0, 2.4, 6.0, 20.0);
-        let actual = revision_settings.calculate_possible_intervals(&coefficients);
+        let actual = revision_settings.calculate_possible_intervals(&coefficients, Utc::now());
         assert_eq!(expected, actual);
     }
 
@@ -329,7 +384,7 @@ mod unit_tests {
         let due = Utc::now() - Duration::days(4);
         let revision_settings = RevisionSettings::new(due, 1.0, 2000.0);
         let coefficients = IntervalCoefficients::new(1.0, 2.0, 0.0);
-        let actual = revision_settings.calculate_new_interval(&score, &coefficients);
+        let actual = revision_settings.calculate_new_interval(&score, &coefficients, Utc::now());
         assert_eq!(expected, actual);
     }
 
@@ -385,4 +440,13 @@ mod unit_tests {
         let actual = revision_settings.transform(score, &coefficients);
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn preview_leaves_the_settings_as_they_are() {
+        let revision_settings = RevisionSettings::new(Utc::now() - Duration::days(4), 1.0, 2000.0);
+        let coefficients = IntervalCoefficients::new(1.0, 2.0, 0.0);
+        let actual = revision_settings.preview(Score::Pass, &coefficients);
+        assert_eq!(6.0, actual.interval);
+        assert_eq!(1.0, revision_settings.interval);
+    }
 }
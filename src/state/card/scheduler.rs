@@ -0,0 +1,20 @@
+use super::{RevisionSettings, Score};
+use crate::state::deck::IntervalCoefficients;
+use chrono::{DateTime, Utc};
+use std::fmt;
+
+// A review, as a Scheduler is asked to schedule it.
+#[derive(Debug)]
+pub struct Review<'r> {
+    pub before: &'r RevisionSettings,
+    pub score: Score,
+    pub coefficients: &'r IntervalCoefficients,
+    pub reviewed_at: DateTime<Utc>,
+    // What the built-in scheduling makes of the review.
+    pub built_in: &'r RevisionSettings,
+}
+
+// Takes over scheduling a deck's reviews from the built-in scheduling, e.g. with a user script.
+pub trait Scheduler: fmt::Debug {
+    fn schedule(&self, review: &Review) -> RevisionSettings;
+}
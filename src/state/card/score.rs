@@ -1,7 +1,106 @@
-#[derive(Clone, Copy, Debug)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, PartialOrd, Serialize)]
 pub enum Score {
     Fail,
     Hard,
     Pass,
     Easy,
 }
+
+// A score as offered to the user: its label, the score whose curve schedules it, and a multiplier
+// for the interval that curve gives, e.g. for a grade between Pass and Easy.
+#[derive(Clone, Debug, Deserialize, PartialEq, PartialOrd, Serialize)]
+pub struct Grade {
+    pub label: String,
+    pub score: Score,
+    #[serde(default = "Grade::default_interval_multiplier")]
+    pub interval_multiplier: f64,
+}
+
+impl Grade {
+    pub fn of(score: Score) -> Self {
+        Self {
+            label: format!("{:?}", score),
+            score,
+            interval_multiplier: Self::default_interval_multiplier(),
+        }
+    }
+
+    fn default_interval_multiplier() -> f64 {
+        1.0
+    }
+}
+
+// The grades reviews are scored with. Pass/fail grading only offers Fail and Pass, for those who
+// find four-way grading noisy, and Custom grades can have any labels and count, such as
+// SuperMemo's six.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, PartialOrd, Serialize)]
+pub enum Grading {
+    #[default]
+    FourWay,
+    PassFail,
+    Custom(Vec<Grade>),
+}
+
+impl Grading {
+    pub fn grades(&self) -> Vec<Grade> {
+        match self {
+            Grading::FourWay => [Score::Fail, Score::Hard, Score::Pass, Score::Easy]
+                .map(Grade::of)
+                .to_vec(),
+            Grading::PassFail => [Score::Fail, Score::Pass].map(Grade::of).to_vec(),
+            Grading::Custom(grades) => grades.clone(),
+        }
+    }
+
+    pub fn offers(&self, score: Score) -> bool {
+        self.grades().iter().any(|grade| grade.score == score)
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+
+    #[test]
+    fn grades_of_the_built_in_gradings() {
+        let labels = |grading: Grading| -> Vec<String> {
+            grading
+                .grades()
+                .into_iter()
+                .map(|grade| grade.label)
+                .collect()
+        };
+        assert_eq!(
+            ["Fail", "Hard", "Pass", "Easy"],
+            labels(Grading::FourWay)[..]
+        );
+        assert_eq!(["Fail", "Pass"], labels(Grading::PassFail)[..]);
+        assert!(!Grading::PassFail.offers(Score::Easy));
+    }
+
+    #[test]
+    fn custom_grades_from_ron() {
+        let actual: Grading = ron::from_str(
+            "Custom([(label: \"Blackout\", score: Fail), \
+             (label: \"Bright\", score: Easy, interval_multiplier: 1.3)])",
+        )
+        .unwrap();
+        let expected = Grading::Custom(vec![
+            Grade {
+                label: "Blackout".to_string(),
+                ..Grade::of(Score::Fail)
+            },
+            Grade {
+                label: "Bright".to_string(),
+                score: Score::Easy,
+                interval_multiplier: 1.3,
+            },
+        ]);
+        assert_eq!(expected, actual);
+        assert!(actual.offers(Score::Easy));
+        assert!(!actual.offers(Score::Pass));
+    }
+}
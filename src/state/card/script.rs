@@ -0,0 +1,233 @@
+use super::scheduler::{Review, Scheduler};
+use super::{RevisionSettings, Score};
+use crate::state::deck::IntervalCoefficients;
+use chrono::Utc;
+use rhai::{Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+use snafu::prelude::*;
+use std::fmt;
+
+const ENTRY_POINT: &str = "schedule";
+// Enough for any reasonable scheduling, but stops a script that loops forever.
+const MAX_OPERATIONS: u64 = 100_000;
+
+// A rhai script scheduling a deck's reviews. It defines `fn schedule(review)`, which is given a map
+// of the review (score, interval, memorisation_factor, days_overdue, pass_coef, easy_coef,
+// fail_coef, and the built-in scheduling's built_in_interval and built_in_memorisation_factor),
+// and returns either the new interval or a map of the new interval and memorisation_factor; any
+// left out are the built-in scheduling's.
+pub struct ScriptScheduler {
+    engine: Engine,
+    ast: AST,
+}
+
+// Why a scheduling script couldn't be used, or failed on a review.
+#[derive(Debug, Snafu)]
+pub enum ScriptError {
+    #[snafu(display("Unable to read scheduling script {}", path))]
+    Unreadable {
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("Invalid scheduling script {}", path))]
+    Invalid {
+        path: String,
+        #[snafu(source(from(ScriptError, Box::new)))]
+        source: Box<ScriptError>,
+    },
+    #[snafu(display("Unable to compile the scheduling script"))]
+    Uncompilable { source: rhai::ParseError },
+    #[snafu(display("The scheduling script failed"))]
+    Failed { source: Box<EvalAltResult> },
+    #[snafu(display("The scheduling script returned a {} for a number", type_name))]
+    NotANumber { type_name: String },
+    #[snafu(display("The scheduling script returned an interval of {}", interval))]
+    InvalidInterval { interval: f64 },
+    #[snafu(display(
+        "The scheduling script returned a memorisation factor of {}",
+        memorisation_factor
+    ))]
+    InvalidMemorisationFactor { memorisation_factor: f64 },
+}
+
+impl fmt::Debug for ScriptScheduler {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ScriptScheduler").finish_non_exhaustive()
+    }
+}
+
+impl ScriptScheduler {
+    // The script is tried on a sample review, so that it fails here rather than mid-session.
+    pub fn compile(source: &str) -> Result<Self, ScriptError> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        let ast = engine.compile(source).context(UncompilableSnafu)?;
+        let scheduler = Self { engine, ast };
+        let before = RevisionSettings::default();
+        let built_in = before
+            .clone()
+            .transform(Score::Pass, &IntervalCoefficients::default());
+        scheduler.try_schedule(&Review {
+            before: &before,
+            score: Score::Pass,
+            coefficients: &IntervalCoefficients::default(),
+            reviewed_at: Utc::now(),
+            built_in: &built_in,
+        })?;
+        Ok(scheduler)
+    }
+
+    pub fn read(path: &str) -> Result<Self, ScriptError> {
+        let source = std::fs::read_to_string(path).context(UnreadableSnafu { path })?;
+        Self::compile(&source).context(InvalidSnafu { path })
+    }
+
+    fn try_schedule(&self, review: &Review) -> Result<RevisionSettings, ScriptError> {
+        let result: Dynamic = self
+            .engine
+            .call_fn(&mut Scope::new(), &self.ast, ENTRY_POINT, (to_map(review),))
+            .context(FailedSnafu)?;
+        let built_in = review.built_in;
+        let (interval, memorisation_factor) = match result.clone().try_cast::<Map>() {
+            Some(map) => (
+                number(map.get("interval"), built_in.interval)?,
+                number(map.get("memorisation_factor"), built_in.memorisation_factor)?,
+            ),
+            None => (
+                number(Some(&result), built_in.interval)?,
+                built_in.memorisation_factor,
+            ),
+        };
+        ensure!(
+            interval.is_finite() && interval >= 0.0,
+            InvalidIntervalSnafu { interval }
+        );
+        ensure!(
+            memorisation_factor.is_finite() && memorisation_factor > 0.0,
+            InvalidMemorisationFactorSnafu {
+                memorisation_factor
+            }
+        );
+        Ok(review.before.rescheduled(interval, memorisation_factor))
+    }
+}
+
+// A script that fails on a review leaves it to the built-in scheduling.
+impl Scheduler for ScriptScheduler {
+    fn schedule(&self, review: &Review) -> RevisionSettings {
+        self.try_schedule(review)
+            .unwrap_or_else(|_| review.built_in.clone())
+    }
+}
+
+fn to_map(review: &Review) -> Map {
+    let days_overdue = (review.reviewed_at - review.before.due).num_hours() as f64 / 24.0;
+    let fields: [(&str, Dynamic); 10] = [
+        ("score", format!("{:?}", review.score).to_lowercase().into()),
+        ("interval", review.before.interval.into()),
+        (
+            "memorisation_factor",
+            review.before.memorisation_factor.into(),
+        ),
+        ("days_overdue", days_overdue.into()),
+        ("pass_coef", review.coefficients.pass_coef.into()),
+        ("easy_coef", review.coefficients.easy_coef.into()),
+        ("fail_coef", review.coefficients.fail_coef.into()),
+        ("built_in_interval", review.built_in.interval.into()),
+        (
+            "built_in_memorisation_factor",
+            review.built_in.memorisation_factor.into(),
+        ),
+        ("reviewed_at", review.reviewed_at.to_rfc3339().into()),
+    ];
+    fields
+        .into_iter()
+        .map(|(name, value)| (name.into(), value))
+        .collect()
+}
+
+// Scripts may return whole numbers as integers.
+fn number(value: Option<&Dynamic>, default: f64) -> Result<f64, ScriptError> {
+    match value {
+        None => Ok(default),
+        Some(value) => value
+            .as_float()
+            .or_else(|_| value.as_int().map(|i| i as f64))
+            .map_err(|type_name| ScriptError::NotANumber {
+                type_name: type_name.to_string(),
+            }),
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use rstest::*;
+
+    fn schedule(source: &str, score: Score) -> RevisionSettings {
+        let scheduler = ScriptScheduler::compile(source).unwrap();
+        let before = RevisionSettings::new(Utc::now(), 10.0, 2000.0);
+        let built_in = before
+            .clone()
+            .transform(score, &IntervalCoefficients::default());
+        scheduler.schedule(&Review {
+            before: &before,
+            score,
+            coefficients: &IntervalCoefficients::default(),
+            reviewed_at: Utc::now(),
+            built_in: &built_in,
+        })
+    }
+
+    #[rstest]
+    #[case::an_interval("fn schedule(review) { review.interval * 2.0 }", 20.0, 2150.0)]
+    #[case::an_integer("fn schedule(review) { 3 }", 3.0, 2150.0)]
+    #[case::a_map(
+        "fn schedule(review) { #{ interval: 5.0, memorisation_factor: 2500.0 } }",
+        5.0,
+        2500.0
+    )]
+    #[case::by_score(
+        "fn schedule(review) { if review.score == \"easy\" { 30.0 } else { 1.0 } }",
+        30.0,
+        2150.0
+    )]
+    fn schedule_with(
+        #[case] source: &str,
+        #[case] expected_interval: f64,
+        #[case] expected_memorisation_factor: f64,
+    ) {
+        let actual = schedule(source, Score::Easy);
+        assert_eq!(expected_interval, actual.interval);
+        // Otherwise, the built-in memorisation factor, which easy raises.
+        assert_eq!(expected_memorisation_factor, actual.memorisation_factor);
+    }
+
+    #[test]
+    fn schedule_falls_back_on_the_built_in_scheduling() {
+        let source = "fn schedule(review) { if review.score == \"fail\" { -1.0 } else { 2.0 } }";
+        let before = RevisionSettings::new(Utc::now(), 10.0, 2000.0);
+        let built_in = before
+            .clone()
+            .transform(Score::Fail, &IntervalCoefficients::default());
+        let actual = schedule(source, Score::Fail);
+        assert_eq!(built_in.interval, actual.interval);
+    }
+
+    #[rstest]
+    #[case::syntax_error("fn schedule(review) {")]
+    #[case::no_entry_point("fn other(review) { 1.0 }")]
+    #[case::wrong_type("fn schedule(review) { \"soon\" }")]
+    #[case::endless_loop("fn schedule(review) { loop {} }")]
+    fn compile_fails(#[case] source: &str) {
+        assert!(ScriptScheduler::compile(source).is_err());
+    }
+
+    #[test]
+    fn read_reports_the_script_path() {
+        let actual = ScriptScheduler::read("no/such/script.rhai").unwrap_err();
+        assert!(
+            matches!(actual, ScriptError::Unreadable { ref path, .. } if path == "no/such/script.rhai")
+        );
+    }
+}
@@ -0,0 +1,53 @@
+use super::RevisionSettings;
+use snafu::{prelude::*, Whatever};
+use std::fs;
+use std::path::Path;
+
+pub const EXTENSION: &str = "vultan";
+
+// "bio/cells.md" keeps its schedule in "bio/cells.md.vultan".
+pub fn path_of(note_path: &str) -> String {
+    format!("{}.{}", note_path, EXTENSION)
+}
+
+// Notes without a sidecar have no schedule of their own.
+pub fn read(note_path: &str) -> Result<Option<RevisionSettings>, Whatever> {
+    let path = path_of(note_path);
+    if !Path::new(&path).exists() {
+        return Ok(None);
+    }
+    let content =
+        fs::read_to_string(&path).with_whatever_context(|_| format!("Unable to read {}", path))?;
+    ron::from_str(&content)
+        .map(Some)
+        .with_whatever_context(|_| format!("Unable to parse the schedule in {}", path))
+}
+
+pub fn write(note_path: &str, revision_settings: &RevisionSettings) -> Result<(), Whatever> {
+    let path = path_of(note_path);
+    let content = ron::ser::to_string_pretty(revision_settings, ron::ser::PrettyConfig::default())
+        .with_whatever_context(|_| format!("Unable to serialise the schedule of {}", note_path))?;
+    fs::write(&path, content).with_whatever_context(|_| format!("Unable to write {}", path))
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+
+    #[test]
+    fn write_then_read() {
+        let dir = std::env::temp_dir().join(format!("vultan_sidecar_{}", std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        let note_path = dir.join("a.md").to_string_lossy().to_string();
+        assert!(read(&note_path).unwrap().is_none());
+        let revision_settings = RevisionSettings::new(chrono::Utc::now(), 12.5, 2500.0);
+        write(&note_path, &revision_settings).unwrap();
+        assert!(dir.join("a.md.vultan").exists());
+        assert_eq!(Some(revision_settings), read(&note_path).unwrap());
+        fs::write(path_of(&note_path), "not a schedule").unwrap();
+        assert!(read(&note_path).is_err());
+        fs::remove_dir_all(dir).ok();
+    }
+}
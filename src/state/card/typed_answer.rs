@@ -0,0 +1,90 @@
+use super::Score;
+use serde::{Deserialize, Serialize};
+
+// Has the answer typed in before it's shown, and a score suggested from how far the typed answer
+// is from the card's. Distances run from 0, for the same words, to 1, for nothing in common.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, PartialOrd, Serialize)]
+#[serde(default)]
+pub struct TypedAnswers {
+    pub easy_within: f64,
+    pub pass_within: f64,
+}
+
+impl Default for TypedAnswers {
+    fn default() -> Self {
+        Self {
+            easy_within: 0.0,
+            pass_within: 0.25,
+        }
+    }
+}
+
+impl TypedAnswers {
+    pub fn suggest(&self, typed: &str, answer: &str) -> Score {
+        match distance(typed, answer) {
+            d if d <= self.easy_within => Score::Easy,
+            d if d <= self.pass_within => Score::Pass,
+            _ => Score::Fail,
+        }
+    }
+}
+
+// The edit distance between the answers, in characters, over the length of the longer, once
+// case, punctuation and spacing are set aside.
+pub fn distance(typed: &str, answer: &str) -> f64 {
+    let typed: Vec<char> = normalise(typed).chars().collect();
+    let answer: Vec<char> = normalise(answer).chars().collect();
+    match typed.len().max(answer.len()) {
+        0 => 0.0,
+        longest => levenshtein(&typed, &answer) as f64 / longest as f64,
+    }
+}
+
+fn normalise(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// Keeps a single row of the table, as answers can run to a few hundred characters.
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, x) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(x != y);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case::same("Mitochondria", "mitochondria.", 0.0)]
+    #[case::one_typo("mitocondria", "Mitochondria", 1.0 / 12.0)]
+    #[case::unrelated("abc", "xyz", 1.0)]
+    #[case::nothing_typed("", "ATP", 1.0)]
+    #[case::both_empty("", "", 0.0)]
+    fn distance(#[case] typed: &str, #[case] answer: &str, #[case] expected: f64) {
+        assert!((expected - super::distance(typed, answer)).abs() < 1e-9);
+    }
+
+    #[rstest]
+    #[case::exact("The Krebs cycle", Score::Easy)]
+    #[case::close("the krebbs cycle", Score::Pass)]
+    #[case::far("glycolysis", Score::Fail)]
+    fn suggest(#[case] typed: &str, #[case] expected: Score) {
+        let actual = TypedAnswers::default().suggest(typed, "The Krebs cycle!");
+        assert_eq!(expected, actual);
+    }
+}
@@ -0,0 +1,164 @@
+use super::State;
+use std::path::Path;
+use std::str::FromStr;
+
+#[cfg(test)]
+use super::mocks::to_string_pretty as serialise;
+#[cfg(not(test))]
+use ron::ser::to_string_pretty as serialise;
+
+// Turns the state into the text of a state file and back.
+pub trait StateCodec {
+    fn encode(&self, state: &State) -> Result<String, String>;
+    fn decode(&self, content: &str) -> Result<State, String>;
+}
+
+pub struct RonCodec;
+
+impl StateCodec for RonCodec {
+    fn encode(&self, state: &State) -> Result<String, String> {
+        serialise(state, ron::ser::PrettyConfig::default()).map_err(|e| e.to_string())
+    }
+
+    fn decode(&self, content: &str) -> Result<State, String> {
+        ron::from_str(content).map_err(|e| e.to_string())
+    }
+}
+
+pub struct JsonCodec;
+
+impl StateCodec for JsonCodec {
+    fn encode(&self, state: &State) -> Result<String, String> {
+        serde_json::to_string_pretty(state).map_err(|e| e.to_string())
+    }
+
+    fn decode(&self, content: &str) -> Result<State, String> {
+        serde_json::from_str(content).map_err(|e| e.to_string())
+    }
+}
+
+pub struct YamlCodec;
+
+impl StateCodec for YamlCodec {
+    fn encode(&self, state: &State) -> Result<String, String> {
+        serde_yaml::to_string(state).map_err(|e| e.to_string())
+    }
+
+    fn decode(&self, content: &str) -> Result<State, String> {
+        serde_yaml::from_str(content).map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum StateFormat {
+    #[default]
+    Ron,
+    Json,
+    Yaml,
+}
+
+impl StateFormat {
+    // ".json" and ".yaml" or ".yml" files; anything else is RON.
+    pub fn from_path(path: &str) -> Self {
+        let extension = Path::new(path)
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("json") => StateFormat::Json,
+            Some("yaml" | "yml") => StateFormat::Yaml,
+            _ => StateFormat::Ron,
+        }
+    }
+
+    pub fn codec(&self) -> Box<dyn StateCodec> {
+        match self {
+            StateFormat::Ron => Box::new(RonCodec),
+            StateFormat::Json => Box::new(JsonCodec),
+            StateFormat::Yaml => Box::new(YamlCodec),
+        }
+    }
+}
+
+impl FromStr for StateFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "ron" => Ok(StateFormat::Ron),
+            "json" => Ok(StateFormat::Json),
+            "yaml" => Ok(StateFormat::Yaml),
+            _ => Err(format!(
+                "Unknown state format \"{}\": expected ron, json or yaml",
+                name
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use crate::state::card::{Card, Priority, RevisionSettings};
+    use crate::state::deck::DeckOptions;
+    use crate::state::hand::{Interleaving, QueueOrder};
+    use chrono::{TimeZone, Utc};
+    use rstest::*;
+
+    fn make_state() -> State {
+        let due = Utc.with_ymd_and_hms(2024, 3, 1, 9, 0, 0).unwrap();
+        let card = Card::new(
+            "notes/a.md".to_string(),
+            vec!["lang::spanish".to_string()],
+            "hablar?".to_string(),
+            "to speak".to_string(),
+            RevisionSettings::new(due, 3.5, 2500.0),
+        )
+        .with_priority(Priority::High)
+        .with_tags(vec!["exam".to_string()]);
+        let state = State::default().with_loaded_cards(vec![card]);
+        let deck = state.decks()["lang"].clone().with_options(
+            DeckOptions::default()
+                .with_order(Some(QueueOrder::OldestDueFirst))
+                .with_interleaving(Some(Interleaving::Mixed { new: 1, reviews: 4 })),
+        );
+        state.with_overriden_decks(vec![deck])
+    }
+
+    #[rstest]
+    #[case::ron(StateFormat::Ron)]
+    #[case::json(StateFormat::Json)]
+    #[case::yaml(StateFormat::Yaml)]
+    fn round_trip(#[case] format: StateFormat) {
+        let state = make_state();
+        let codec = format.codec();
+        let encoded = codec.encode(&state).unwrap();
+        assert_eq!(state, codec.decode(&encoded).unwrap());
+    }
+
+    #[test]
+    fn json_is_plain_json() {
+        let encoded = JsonCodec.encode(&make_state()).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(
+            "to speak",
+            value["cards"]["notes/a.md"]["answer"].as_str().unwrap()
+        );
+    }
+
+    #[rstest]
+    #[case::ron("notes/.vultan.ron", StateFormat::Ron)]
+    #[case::no_extension("state", StateFormat::Ron)]
+    #[case::json("notes/state.json", StateFormat::Json)]
+    #[case::yaml("notes/state.yaml", StateFormat::Yaml)]
+    #[case::yml("notes/STATE.YML", StateFormat::Yaml)]
+    fn from_path(#[case] path: &str, #[case] expected: StateFormat) {
+        assert_eq!(expected, StateFormat::from_path(path));
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!(Ok(StateFormat::Yaml), "yaml".parse());
+        assert!("toml".parse::<StateFormat>().is_err());
+    }
+}
@@ -0,0 +1,181 @@
+use super::card::parser::ParsingConfig;
+use super::deck::DeckOptions;
+use super::error::{StorageError, UnreadableConfigSnafu};
+use super::id::DeckName;
+use super::tools::Merge;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use snafu::prelude::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+pub const CONFIG_FILE_NAME: &str = "vultan.toml";
+
+// The preferences a user edits, kept in a vultan.toml apart from the state file, which is left
+// to the scheduling data written on every save. What it sets wins over the state file's own
+// settings, and is never written back to it. Tables it doesn't know, such as a front end's
+// [theme], are left to whatever reads them.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Config {
+    // Keys of the state file's card_parsing_config to replace, one by one, e.g.
+    // deck_delimiter = "/".
+    pub parsing: Map<String, Value>,
+    // Options of decks by name, which win over those in the state file option by option.
+    pub decks: HashMap<DeckName, DeckOptions>,
+}
+
+impl Config {
+    pub fn read(path: &str) -> Result<Self, StorageError> {
+        let content = fs::read_to_string(path).context(UnreadableConfigSnafu { path })?;
+        toml::from_str(&content).map_err(|e| StorageError::MalformedConfig {
+            path: path.to_string(),
+            message: e.to_string(),
+        })
+    }
+
+    // None when there's no file at the path.
+    pub fn read_if_present(path: &str) -> Result<Option<Self>, StorageError> {
+        match Path::new(path).exists() {
+            true => Self::read(path).map(Some),
+            false => Ok(None),
+        }
+    }
+
+    // Where State::read looks for the config of a state file: in the same directory.
+    pub fn path_beside(state_file_path: &str) -> String {
+        Path::new(state_file_path)
+            .with_file_name(CONFIG_FILE_NAME)
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    // The state file's parsing config, with the keys set under [parsing] replaced. The path is
+    // only used to report a key that doesn't fit.
+    pub fn parsing_config(
+        &self,
+        stored: &ParsingConfig,
+        path: &str,
+    ) -> Result<ParsingConfig, StorageError> {
+        if self.parsing.is_empty() {
+            return Ok(stored.clone());
+        }
+        let malformed = |e: serde_json::Error| StorageError::MalformedConfig {
+            path: path.to_string(),
+            message: format!("[parsing] {}", e),
+        };
+        let mut layered = match serde_json::to_value(stored).map_err(malformed)? {
+            Value::Object(fields) => fields,
+            _ => Map::new(),
+        };
+        layered.extend(self.parsing.clone());
+        serde_json::from_value(Value::Object(layered)).map_err(malformed)
+    }
+
+    // The deck's options from the state file, under any the config sets for it.
+    pub fn deck_options(&self, deck_name: &str, stored: &DeckOptions) -> DeckOptions {
+        match self.decks.get(deck_name) {
+            Some(options) => options.clone().merge(stored),
+            None => stored.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use crate::state::card::parser::ScheduleLocation;
+    use crate::state::hand::QueueOrder;
+    use std::time::SystemTime;
+
+    const CONFIG: &str = r#"
+[parsing]
+deck_delimiter = "/"
+schedule_location = "Frontmatter"
+
+[decks."lang::spanish"]
+max_new_cards = 5
+order = "OldestDueFirst"
+
+[theme]
+border = "="
+"#;
+
+    #[test]
+    fn read_if_present() {
+        let nanos = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!("vultan-config-{}.toml", nanos));
+        let path = path.to_str().unwrap();
+        assert_eq!(None, Config::read_if_present(path).unwrap());
+        fs::write(path, CONFIG).unwrap();
+        let config = Config::read_if_present(path).unwrap().unwrap();
+        assert_eq!(
+            Some(&Value::from("/")),
+            config.parsing.get("deck_delimiter")
+        );
+        assert_eq!(Some(5), config.decks["lang::spanish"].max_new_cards);
+        fs::write(path, "decks = 3").unwrap();
+        assert!(matches!(
+            Config::read_if_present(path),
+            Err(StorageError::MalformedConfig { .. })
+        ));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn path_beside() {
+        assert_eq!(
+            "notes/vultan.toml",
+            Config::path_beside("notes/.vultan.ron")
+        );
+        assert_eq!("vultan.toml", Config::path_beside(".vultan.ron"));
+    }
+
+    #[test]
+    fn parsing_config_replaces_the_keys_set() {
+        let config: Config = toml::from_str(CONFIG).unwrap();
+        let stored = ParsingConfig {
+            strict_loading: true,
+            ..ParsingConfig::default()
+        };
+        let expected = ParsingConfig {
+            deck_delimiter: "/".to_string(),
+            schedule_location: ScheduleLocation::Frontmatter,
+            ..stored.clone()
+        };
+        assert_eq!(
+            expected,
+            config.parsing_config(&stored, "vultan.toml").unwrap()
+        );
+        assert_eq!(
+            stored,
+            Config::default()
+                .parsing_config(&stored, "vultan.toml")
+                .unwrap()
+        );
+        let config: Config = toml::from_str("[parsing]\ndeck_delimiter = 3").unwrap();
+        let error = config.parsing_config(&stored, "vultan.toml").unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Unable to parse config from vultan.toml"));
+    }
+
+    #[test]
+    fn deck_options_win_option_by_option() {
+        let config: Config = toml::from_str(CONFIG).unwrap();
+        let stored = DeckOptions::default()
+            .with_max_new_cards(Some(20))
+            .with_max_reviews(Some(100));
+        let expected = DeckOptions::default()
+            .with_max_new_cards(Some(5))
+            .with_max_reviews(Some(100))
+            .with_order(Some(QueueOrder::OldestDueFirst));
+        assert_eq!(expected, config.deck_options("lang::spanish", &stored));
+        assert_eq!(stored, config.deck_options("lang", &stored));
+    }
+}
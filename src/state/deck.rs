@@ -1,14 +1,29 @@
+pub mod goal;
 pub mod interval_coefficients;
+pub mod options;
+pub mod stats;
+pub mod tuning;
 
+use super::card::Card;
+use super::id::{CardId, DeckName};
 use super::tools::{Merge, UID};
+pub use goal::DailyGoal;
 pub use interval_coefficients::IntervalCoefficients;
+pub use options::DeckOptions;
+pub use stats::DeckStats;
+pub use tuning::Tuning;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const HIERARCHY_SEPARATOR: &str = "::";
 
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, PartialOrd, Serialize)]
 pub struct Deck {
-    pub name: String,
-    pub card_paths: Vec<String>,
+    pub name: DeckName,
+    pub card_paths: Vec<CardId>,
     pub interval_coefficients: IntervalCoefficients,
+    #[serde(default)]
+    pub options: DeckOptions,
 }
 
 impl Deck {
@@ -18,29 +33,115 @@ impl Deck {
         interval_coefficients: IntervalCoefficients,
     ) -> Self {
         Self {
-            name: name.to_string(),
-            card_paths: card_paths.iter().map(|s| s.to_string()).collect(),
+            name: DeckName::from(name),
+            card_paths: card_paths.into_iter().map(CardId::from).collect(),
             interval_coefficients,
+            options: DeckOptions::default(),
+        }
+    }
+
+    pub fn many_from_cards(cards: &[Card]) -> Vec<Self> {
+        let mut card_paths_by_deck: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for card in cards.iter() {
+            for deck_name in card.decks.iter() {
+                for name in ancestor_names(deck_name).chain([deck_name.as_str()]) {
+                    let card_paths = card_paths_by_deck.entry(name).or_default();
+                    if !card_paths.contains(&card.path.as_str()) {
+                        card_paths.push(&card.path);
+                    }
+                }
+            }
         }
+        card_paths_by_deck
+            .into_iter()
+            .map(|(name, card_paths)| Self::new(name, card_paths, IntervalCoefficients::default()))
+            .collect()
     }
 
     pub fn with_interval_coefficients(self, interval_coefficients: IntervalCoefficients) -> Self {
         Self {
+            options: self
+                .options
+                .with_interval_coefficients(interval_coefficients.clone()),
             interval_coefficients,
             ..self
         }
     }
+
+    pub fn with_options(self, options: DeckOptions) -> Self {
+        Self { options, ..self }
+    }
+
+    // The card paths as the plain strings card_paths held before ids were typed.
+    #[deprecated(note = "use card_paths, which holds CardIds")]
+    pub fn card_path_strings(&self) -> Vec<String> {
+        self.card_paths
+            .iter()
+            .map(|path| path.to_string())
+            .collect()
+    }
+
+    #[deprecated(note = "set card_paths, which holds CardIds")]
+    pub fn with_card_path_strings(self, card_paths: Vec<String>) -> Self {
+        Self {
+            card_paths: card_paths.into_iter().map(CardId::from).collect(),
+            ..self
+        }
+    }
+}
+
+// "lang::spanish::verbs" yields "lang" then "lang::spanish".
+pub fn ancestor_names(deck_name: &str) -> impl Iterator<Item = &str> {
+    deck_name
+        .match_indices(HIERARCHY_SEPARATOR)
+        .map(move |(i, _)| &deck_name[..i])
+        .filter(|name| !name.is_empty())
+}
+
+pub fn is_within(deck_name: &str, ancestor_name: &str) -> bool {
+    match deck_name.strip_prefix(ancestor_name) {
+        Some(rest) => rest.is_empty() || rest.starts_with(HIERARCHY_SEPARATOR),
+        None => false,
+    }
+}
+
+// The name a deck takes when it, or one of its ancestors, is renamed.
+pub fn renamed(deck_name: &str, old_name: &str, new_name: &str) -> Option<String> {
+    is_within(deck_name, old_name).then(|| format!("{}{}", new_name, &deck_name[old_name.len()..]))
+}
+
+pub fn depth(deck_name: &str) -> usize {
+    ancestor_names(deck_name).count()
+}
+
+pub fn leaf_name(deck_name: &str) -> &str {
+    deck_name
+        .rsplit(HIERARCHY_SEPARATOR)
+        .next()
+        .unwrap_or(deck_name)
+}
+
+impl Deck {
+    pub fn lists(&self, card_path: &str) -> bool {
+        self.card_paths.iter().any(|path| path == card_path)
+    }
 }
 
 impl UID for Deck {
-    fn uid(&self) -> &str {
-        &self.name[..]
+    type Id = DeckName;
+
+    fn uid(&self) -> DeckName {
+        self.name.clone()
     }
 }
 
 impl Merge<Deck> for Deck {
     fn merge(self, other: &Deck) -> Self {
-        self.with_interval_coefficients(other.interval_coefficients.clone())
+        Self {
+            interval_coefficients: other.interval_coefficients.clone(),
+            options: other.options.clone(),
+            ..self
+        }
     }
 }
 
@@ -48,16 +149,17 @@ impl Merge<Deck> for Deck {
 mod unit_tests {
 
     use super::*;
+    use rstest::*;
 
     #[test]
     fn new() {
         let name = "cephelapoda";
         let card_paths = vec!["octopus", "squid", "cuttlefish", "nautilus"];
         let expected_card_paths = vec![
-            String::from("octopus"),
-            String::from("squid"),
-            String::from("cuttlefish"),
-            String::from("nautilus"),
+            CardId::from("octopus"),
+            CardId::from("squid"),
+            CardId::from("cuttlefish"),
+            CardId::from("nautilus"),
         ];
         let interval_coefficients = IntervalCoefficients {
             pass_coef: 8.0,
@@ -65,14 +167,105 @@ mod unit_tests {
             fail_coef: 10.0,
         };
         let expected = Deck {
-            name: name.to_string(),
+            name: name.into(),
             card_paths: expected_card_paths,
             interval_coefficients: interval_coefficients.clone(),
+            options: DeckOptions::default(),
         };
         let actual = Deck::new(name, card_paths, interval_coefficients);
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    #[allow(deprecated)]
+    fn string_shims_of_the_typed_ids() {
+        let deck = Deck::new("cephelapoda", vec![], IntervalCoefficients::default())
+            .with_card_path_strings(vec!["octopus".to_string()]);
+        assert_eq!(vec![CardId::from("octopus")], deck.card_paths);
+        assert_eq!(vec!["octopus".to_string()], deck.card_path_strings());
+        assert_eq!("cephelapoda".to_string(), deck.uid_string());
+    }
+
+    #[test]
+    fn many_from_cards() {
+        let make_card = |path: &str, decks: Vec<&str>| {
+            let mut card = Card::default();
+            card.path = path.into();
+            card.decks = decks.into_iter().map(|d| d.to_string()).collect();
+            card
+        };
+        let cards = vec![
+            make_card("octopus", vec!["cephelapoda", "molluska"]),
+            make_card("clam", vec!["bivalvia", "molluska"]),
+            make_card("squid", vec!["cephelapoda"]),
+            make_card("rock", vec![]),
+        ];
+        let coefficients = IntervalCoefficients::default();
+        let expected = vec![
+            Deck::new("bivalvia", vec!["clam"], coefficients.clone()),
+            Deck::new("cephelapoda", vec!["octopus", "squid"], coefficients.clone()),
+            Deck::new("molluska", vec!["octopus", "clam"], coefficients),
+        ];
+        assert_eq!(expected, Deck::many_from_cards(&cards));
+    }
+
+    #[test]
+    fn many_from_cards_adds_cards_to_ancestor_decks() {
+        let make_card = |path: &str, decks: Vec<&str>| {
+            let mut card = Card::default();
+            card.path = path.into();
+            card.decks = decks.into_iter().map(|d| d.to_string()).collect();
+            card
+        };
+        let cards = vec![
+            make_card("hablar", vec!["lang::spanish::verbs", "lang::spanish"]),
+            make_card("bonjour", vec!["lang::french"]),
+        ];
+        let coefficients = IntervalCoefficients::default();
+        let expected = vec![
+            Deck::new("lang", vec!["hablar", "bonjour"], coefficients.clone()),
+            Deck::new("lang::french", vec!["bonjour"], coefficients.clone()),
+            Deck::new("lang::spanish", vec!["hablar"], coefficients.clone()),
+            Deck::new("lang::spanish::verbs", vec!["hablar"], coefficients),
+        ];
+        assert_eq!(expected, Deck::many_from_cards(&cards));
+    }
+
+    #[test]
+    fn ancestor_names() {
+        let actual: Vec<&str> = super::ancestor_names("lang::spanish::verbs").collect();
+        assert_eq!(vec!["lang", "lang::spanish"], actual);
+        assert_eq!(0, super::ancestor_names("lang").count());
+    }
+
+    #[rstest]
+    #[case::itself("lang::spanish", "lang::spanish", true)]
+    #[case::descendant("lang::spanish::verbs", "lang", true)]
+    #[case::sibling("lang::french", "lang::spanish", false)]
+    #[case::shared_prefix("language", "lang", false)]
+    #[case::ancestor("lang", "lang::spanish", false)]
+    fn is_within(#[case] deck_name: &str, #[case] ancestor_name: &str, #[case] expected: bool) {
+        assert_eq!(expected, super::is_within(deck_name, ancestor_name));
+    }
+
+    #[rstest]
+    #[case::itself("lang::spanish", Some("espanol"))]
+    #[case::descendant("lang::spanish::verbs", Some("espanol::verbs"))]
+    #[case::shared_prefix("lang::spanish2", None)]
+    #[case::ancestor("lang", None)]
+    fn renamed(#[case] deck_name: &str, #[case] expected: Option<&str>) {
+        let actual = super::renamed(deck_name, "lang::spanish", "espanol");
+        assert_eq!(expected.map(String::from), actual);
+    }
+
+    #[test]
+    fn depth_and_leaf_name() {
+        assert_eq!(0, depth("lang"));
+        assert_eq!(2, depth("lang::spanish::verbs"));
+        assert_eq!("verbs", leaf_name("lang::spanish::verbs"));
+        assert_eq!("lang", leaf_name("lang"));
+    }
+
     #[test]
     fn with_interval_coefficients() {
         let name = "deck";
@@ -81,6 +274,7 @@ mod unit_tests {
         let deck = Deck::new(name, vec!["a"], old_interval_coefficients);
         let mut expected = deck.clone();
         expected.interval_coefficients = new_interval_coefficients.clone();
+        expected.options.interval_coefficients = Some(new_interval_coefficients.clone());
         let actual = deck.with_interval_coefficients(new_interval_coefficients);
         assert_eq!(expected, actual);
     }
@@ -95,9 +289,11 @@ mod unit_tests {
     #[test]
     fn merge() {
         let a = Deck::new("a", vec![], IntervalCoefficients::default());
-        let b = Deck::new("b", vec![], IntervalCoefficients::new(8.0, 9.0, 10.0));
+        let b = Deck::new("b", vec![], IntervalCoefficients::new(8.0, 9.0, 10.0))
+            .with_options(DeckOptions::default().with_max_reviews(Some(10)));
         let mut expected = a.clone();
         expected.interval_coefficients = b.interval_coefficients.clone();
+        expected.options = b.options.clone();
         assert_eq!(expected, a.merge(&b));
     }
 }
@@ -0,0 +1,76 @@
+use serde::Serialize;
+
+// Progress toward a deck's daily review target, counting the reviews already made today and the
+// cards still due.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DailyGoal {
+    pub name: String,
+    pub goal: Option<usize>,
+    pub reviewed: usize,
+    pub due: usize,
+}
+
+impl DailyGoal {
+    pub fn with_review(self) -> Self {
+        Self {
+            reviewed: self.reviewed + 1,
+            due: self.due.saturating_sub(1),
+            ..self
+        }
+    }
+
+    // Reviews still needed to meet the goal, or 0 without one.
+    pub fn remaining(&self) -> usize {
+        self.goal
+            .map_or(0, |goal| goal.saturating_sub(self.reviewed))
+    }
+
+    pub fn is_met(&self) -> bool {
+        self.goal.is_some() && self.remaining() == 0
+    }
+
+    // Whether enough cards are due to meet the goal without reviewing ahead.
+    pub fn is_reachable(&self) -> bool {
+        self.due >= self.remaining()
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use rstest::*;
+
+    fn make_goal(goal: Option<usize>, reviewed: usize, due: usize) -> DailyGoal {
+        DailyGoal {
+            name: "deck".to_string(),
+            goal,
+            reviewed,
+            due,
+        }
+    }
+
+    #[rstest]
+    #[case::under_way(make_goal(Some(50), 12, 40), 38, false, true)]
+    #[case::out_of_reach(make_goal(Some(50), 12, 20), 38, false, false)]
+    #[case::met(make_goal(Some(50), 60, 0), 0, true, true)]
+    #[case::without_a_goal(make_goal(None, 3, 4), 0, false, true)]
+    fn progress(
+        #[case] goal: DailyGoal,
+        #[case] remaining: usize,
+        #[case] is_met: bool,
+        #[case] is_reachable: bool,
+    ) {
+        assert_eq!(remaining, goal.remaining());
+        assert_eq!(is_met, goal.is_met());
+        assert_eq!(is_reachable, goal.is_reachable());
+    }
+
+    #[test]
+    fn with_review() {
+        assert_eq!(
+            make_goal(Some(10), 5, 0),
+            make_goal(Some(10), 3, 1).with_review().with_review()
+        );
+    }
+}
@@ -0,0 +1,309 @@
+use super::{ancestor_names, Deck, IntervalCoefficients};
+use crate::state::card::{Grading, ScoreSuggestions, TypedAnswers};
+use crate::state::config::Config;
+use crate::state::hand::{Interleaving, QueueOrder};
+use crate::state::id::DeckName;
+use crate::state::tools::Merge;
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, PartialOrd, Serialize)]
+pub struct DeckOptions {
+    #[serde(default)]
+    pub interval_coefficients: Option<IntervalCoefficients>,
+    #[serde(default)]
+    pub max_new_cards: Option<usize>,
+    #[serde(default)]
+    pub max_reviews: Option<usize>,
+    #[serde(default)]
+    pub relearn_delay_minutes: Option<u32>,
+    #[serde(default)]
+    pub score_suggestions: Option<ScoreSuggestions>,
+    #[serde(default)]
+    pub order: Option<QueueOrder>,
+    #[serde(default)]
+    pub interleaving: Option<Interleaving>,
+    // A rhai script scheduling the deck's reviews in place of the built-in scheduling, with the
+    // scripting feature.
+    #[serde(default)]
+    pub scheduling_script: Option<String>,
+    #[serde(default)]
+    pub grading: Option<Grading>,
+    #[serde(default)]
+    pub daily_goal: Option<usize>,
+    // The interval, in days, a card's prerequisites need before it's dealt as a new card.
+    #[serde(default)]
+    pub prerequisite_interval: Option<f64>,
+    // Cards are answered by typing the answer in, when set.
+    #[serde(default)]
+    pub typed_answers: Option<TypedAnswers>,
+    // Only one card of a note is dealt in a hand when set, its siblings waiting for a later one.
+    #[serde(default)]
+    pub bury_siblings: Option<bool>,
+}
+
+impl DeckOptions {
+    pub fn with_interval_coefficients(self, interval_coefficients: IntervalCoefficients) -> Self {
+        Self {
+            interval_coefficients: Some(interval_coefficients),
+            ..self
+        }
+    }
+
+    pub fn with_max_new_cards(self, max_new_cards: Option<usize>) -> Self {
+        Self {
+            max_new_cards,
+            ..self
+        }
+    }
+
+    pub fn with_max_reviews(self, max_reviews: Option<usize>) -> Self {
+        Self {
+            max_reviews,
+            ..self
+        }
+    }
+
+    pub fn with_relearn_delay_minutes(self, relearn_delay_minutes: Option<u32>) -> Self {
+        Self {
+            relearn_delay_minutes,
+            ..self
+        }
+    }
+
+    pub fn with_score_suggestions(self, score_suggestions: Option<ScoreSuggestions>) -> Self {
+        Self {
+            score_suggestions,
+            ..self
+        }
+    }
+
+    pub fn with_order(self, order: Option<QueueOrder>) -> Self {
+        Self { order, ..self }
+    }
+
+    pub fn with_interleaving(self, interleaving: Option<Interleaving>) -> Self {
+        Self {
+            interleaving,
+            ..self
+        }
+    }
+
+    pub fn with_scheduling_script(self, scheduling_script: Option<String>) -> Self {
+        Self {
+            scheduling_script,
+            ..self
+        }
+    }
+
+    pub fn with_grading(self, grading: Option<Grading>) -> Self {
+        Self { grading, ..self }
+    }
+
+    pub fn with_daily_goal(self, daily_goal: Option<usize>) -> Self {
+        Self { daily_goal, ..self }
+    }
+
+    pub fn with_typed_answers(self, typed_answers: Option<TypedAnswers>) -> Self {
+        Self {
+            typed_answers,
+            ..self
+        }
+    }
+
+    pub fn with_prerequisite_interval(self, prerequisite_interval: Option<f64>) -> Self {
+        Self {
+            prerequisite_interval,
+            ..self
+        }
+    }
+
+    pub fn with_bury_siblings(self, bury_siblings: Option<bool>) -> Self {
+        Self {
+            bury_siblings,
+            ..self
+        }
+    }
+
+    pub fn relearn_delay(&self) -> Duration {
+        Duration::minutes(i64::from(self.relearn_delay_minutes.unwrap_or_default()))
+    }
+}
+
+// Options set on self win; anything left unset is inherited from other.
+impl Merge<DeckOptions> for DeckOptions {
+    fn merge(self, other: &DeckOptions) -> Self {
+        Self {
+            interval_coefficients: self
+                .interval_coefficients
+                .or_else(|| other.interval_coefficients.clone()),
+            max_new_cards: self.max_new_cards.or(other.max_new_cards),
+            max_reviews: self.max_reviews.or(other.max_reviews),
+            relearn_delay_minutes: self.relearn_delay_minutes.or(other.relearn_delay_minutes),
+            score_suggestions: self.score_suggestions.or(other.score_suggestions),
+            order: self.order.or(other.order),
+            interleaving: self.interleaving.or(other.interleaving),
+            scheduling_script: self
+                .scheduling_script
+                .or_else(|| other.scheduling_script.clone()),
+            grading: self.grading.or_else(|| other.grading.clone()),
+            daily_goal: self.daily_goal.or(other.daily_goal),
+            prerequisite_interval: self.prerequisite_interval.or(other.prerequisite_interval),
+            typed_answers: self.typed_answers.or(other.typed_answers),
+            bury_siblings: self.bury_siblings.or(other.bury_siblings),
+        }
+    }
+}
+
+// Walks from the deck up to the root, taking each deck's options from the config before the
+// state file. Decks saved before options existed only have their own interval coefficients,
+// which are used when no deck in the chain sets any.
+pub fn resolve(deck: &Deck, decks: &HashMap<DeckName, Deck>, config: &Config) -> DeckOptions {
+    let mut names: Vec<&str> = ancestor_names(&deck.name).collect();
+    names.reverse();
+    let resolved = names.into_iter().filter_map(|name| decks.get(name)).fold(
+        config.deck_options(&deck.name, &deck.options),
+        |options, ancestor| options.merge(&config.deck_options(&ancestor.name, &ancestor.options)),
+    );
+    DeckOptions {
+        interval_coefficients: resolved
+            .interval_coefficients
+            .or_else(|| Some(deck.interval_coefficients.clone())),
+        ..resolved
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+
+    fn make_decks(decks: Vec<Deck>) -> HashMap<DeckName, Deck> {
+        decks.into_iter().map(|d| (d.name.clone(), d)).collect()
+    }
+
+    fn make_deck(name: &str, options: DeckOptions) -> Deck {
+        let mut deck = Deck::new(name, vec![], IntervalCoefficients::default());
+        deck.options = options;
+        deck
+    }
+
+    #[test]
+    fn merge_keeps_set_options_and_inherits_the_rest() {
+        let own = DeckOptions::default().with_max_new_cards(Some(5));
+        let parent = DeckOptions::default()
+            .with_interval_coefficients(IntervalCoefficients::new(2.0, 3.0, 0.5))
+            .with_max_new_cards(Some(20))
+            .with_max_reviews(Some(100))
+            .with_relearn_delay_minutes(Some(10))
+            .with_score_suggestions(Some(ScoreSuggestions::default()))
+            .with_order(Some(QueueOrder::OldestDueFirst))
+            .with_interleaving(Some(Interleaving::ReviewFirst))
+            .with_scheduling_script(Some("parent.rhai".to_string()))
+            .with_grading(Some(Grading::PassFail))
+            .with_daily_goal(Some(50))
+            .with_prerequisite_interval(Some(3.0))
+            .with_typed_answers(Some(TypedAnswers::default()))
+            .with_bury_siblings(Some(true));
+        let expected = DeckOptions {
+            interval_coefficients: Some(IntervalCoefficients::new(2.0, 3.0, 0.5)),
+            max_new_cards: Some(5),
+            max_reviews: Some(100),
+            relearn_delay_minutes: Some(10),
+            score_suggestions: Some(ScoreSuggestions::default()),
+            order: Some(QueueOrder::OldestDueFirst),
+            interleaving: Some(Interleaving::ReviewFirst),
+            scheduling_script: Some("parent.rhai".to_string()),
+            grading: Some(Grading::PassFail),
+            daily_goal: Some(50),
+            prerequisite_interval: Some(3.0),
+            typed_answers: Some(TypedAnswers::default()),
+            bury_siblings: Some(true),
+        };
+        assert_eq!(expected, own.merge(&parent));
+    }
+
+    #[test]
+    fn resolve_inherits_from_the_nearest_ancestor() {
+        let root_coefficients = IntervalCoefficients::new(2.0, 3.0, 0.5);
+        let middle_coefficients = IntervalCoefficients::new(1.5, 2.0, 0.2);
+        let decks = make_decks(vec![
+            make_deck(
+                "lang",
+                DeckOptions::default()
+                    .with_interval_coefficients(root_coefficients)
+                    .with_max_reviews(Some(50)),
+            ),
+            make_deck(
+                "lang::spanish",
+                DeckOptions::default().with_interval_coefficients(middle_coefficients.clone()),
+            ),
+            make_deck(
+                "lang::spanish::verbs",
+                DeckOptions::default().with_max_new_cards(Some(3)),
+            ),
+        ]);
+        let expected = DeckOptions {
+            interval_coefficients: Some(middle_coefficients),
+            max_new_cards: Some(3),
+            max_reviews: Some(50),
+            relearn_delay_minutes: None,
+            score_suggestions: None,
+            order: None,
+            interleaving: None,
+            scheduling_script: None,
+            grading: None,
+            daily_goal: None,
+            prerequisite_interval: None,
+            typed_answers: None,
+            bury_siblings: None,
+        };
+        assert_eq!(
+            expected,
+            resolve(&decks["lang::spanish::verbs"], &decks, &Config::default())
+        );
+    }
+
+    #[test]
+    fn resolve_falls_back_to_the_decks_own_coefficients() {
+        let own_coefficients = IntervalCoefficients::new(4.0, 5.0, 0.0);
+        let deck = Deck::new("lang::spanish", vec![], own_coefficients.clone());
+        let decks = make_decks(vec![
+            make_deck("lang", DeckOptions::default().with_max_reviews(Some(10))),
+            deck.clone(),
+        ]);
+        let actual = resolve(&deck, &decks, &Config::default());
+        assert_eq!(Some(own_coefficients), actual.interval_coefficients);
+        assert_eq!(Some(10), actual.max_reviews);
+    }
+
+    #[test]
+    fn resolve_takes_options_from_the_config_first() {
+        let deck = make_deck(
+            "lang::spanish",
+            DeckOptions::default().with_max_new_cards(Some(3)),
+        );
+        let decks = make_decks(vec![
+            make_deck("lang", DeckOptions::default().with_max_reviews(Some(10))),
+            deck.clone(),
+        ]);
+        let config = Config {
+            decks: HashMap::from([
+                (
+                    DeckName::from("lang"),
+                    DeckOptions::default().with_max_reviews(Some(40)),
+                ),
+                (
+                    DeckName::from("lang::spanish"),
+                    DeckOptions::default().with_daily_goal(Some(20)),
+                ),
+            ]),
+            ..Config::default()
+        };
+        let actual = resolve(&deck, &decks, &config);
+        assert_eq!(Some(3), actual.max_new_cards);
+        assert_eq!(Some(40), actual.max_reviews);
+        assert_eq!(Some(20), actual.daily_goal);
+    }
+}
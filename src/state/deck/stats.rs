@@ -0,0 +1,122 @@
+use crate::state::card::Card;
+use serde::Serialize;
+
+// Cards whose interval has reached this many days count as mature, as in Anki.
+pub const MATURE_INTERVAL: f64 = 21.0;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DeckStats {
+    pub name: String,
+    pub cards: usize,
+    pub due: usize,
+    pub new: usize,
+    pub mean_interval: f64,
+    pub mean_memorisation_factor: f64,
+    // From 0 to 1, see mastery.
+    pub mastery: f64,
+}
+
+impl DeckStats {
+    pub fn from(name: &str, cards: &[&Card]) -> Self {
+        Self {
+            name: name.to_string(),
+            cards: cards.len(),
+            due: cards.iter().filter(|c| c.is_due()).count(),
+            new: cards
+                .iter()
+                .filter(|c| c.revision_settings.interval == 0.0)
+                .count(),
+            mean_interval: Self::mean(cards, |c| c.revision_settings.interval),
+            mean_memorisation_factor: Self::mean(cards, |c| {
+                c.revision_settings.memorisation_factor
+            }),
+            mastery: Self::mastery(cards),
+        }
+    }
+
+    // The share of the cards that are mature, with each card weighted by its memorisation factor,
+    // so that mature cards that are easily remembered count for more than those that aren't.
+    pub fn mastery(cards: &[&Card]) -> f64 {
+        let weight = |c: &&Card| c.revision_settings.memorisation_factor.max(0.0);
+        // Folded from 0.0, as an empty sum of floats is -0.0.
+        let total = cards.iter().map(weight).fold(0.0, |sum, w| sum + w);
+        let mature = cards
+            .iter()
+            .filter(|c| c.revision_settings.interval >= MATURE_INTERVAL)
+            .map(weight)
+            .fold(0.0, |sum, w| sum + w);
+        match total {
+            total if total > 0.0 => mature / total,
+            _ => 0.0,
+        }
+    }
+
+    fn mean(cards: &[&Card], value: fn(&Card) -> f64) -> f64 {
+        match cards.len() {
+            0 => 0.0,
+            n => cards.iter().map(|c| value(c)).sum::<f64>() / n as f64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use crate::state::card::RevisionSettings;
+    use chrono::{Duration, Utc};
+
+    fn make_card(due: chrono::DateTime<Utc>, interval: f64, memorisation_factor: f64) -> Card {
+        let mut card = Card::default();
+        card.revision_settings = RevisionSettings::new(due, interval, memorisation_factor);
+        card
+    }
+
+    #[test]
+    fn from() {
+        let past = Utc::now() - Duration::days(1);
+        let future = Utc::now() + Duration::days(1);
+        let cards = vec![
+            make_card(past, 0.0, 1300.0),
+            make_card(past, 4.0, 2000.0),
+            make_card(future, 8.0, 2700.0),
+        ];
+        let expected = DeckStats {
+            name: "deck".to_string(),
+            cards: 3,
+            due: 2,
+            new: 1,
+            mean_interval: 4.0,
+            mean_memorisation_factor: 2000.0,
+            mastery: 0.0,
+        };
+        let actual = DeckStats::from("deck", &cards.iter().collect::<Vec<&Card>>());
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn from_when_deck_is_empty() {
+        let expected = DeckStats {
+            name: "empty".to_string(),
+            cards: 0,
+            due: 0,
+            new: 0,
+            mean_interval: 0.0,
+            mean_memorisation_factor: 0.0,
+            mastery: 0.0,
+        };
+        assert_eq!(expected, DeckStats::from("empty", &[]));
+    }
+
+    #[test]
+    fn mastery_weighs_mature_cards_by_memorisation_factor() {
+        let now = Utc::now();
+        let cards = [
+            make_card(now, 0.0, 1300.0),
+            make_card(now, 30.0, 2600.0),
+            make_card(now, 20.0, 1300.0),
+        ];
+        let actual = DeckStats::mastery(&cards.iter().collect::<Vec<&Card>>());
+        assert_eq!(0.5, actual);
+    }
+}
@@ -0,0 +1,260 @@
+use super::IntervalCoefficients;
+use crate::state::card::Score;
+use crate::state::review_log::ReviewEvent;
+use serde::Serialize;
+use std::collections::HashMap;
+
+const MIN_REVIEWS: usize = 20;
+const SECONDS_IN_DAY: f64 = 86400.0;
+const PASS_COEF_GRID: (f64, f64) = (0.5, 4.0);
+const EASY_COEF_GRID: (f64, f64) = (1.0, 4.0);
+const GRID_STEP: f64 = 0.05;
+
+// A review that followed an earlier one, with how far into the scheduled interval it came.
+#[derive(Clone, Debug, PartialEq)]
+struct Observation {
+    elapsed_ratio: f64,
+    recalled: bool,
+    after_easy: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Tuning {
+    pub reviews: usize,
+    pub observed_retention: f64,
+    pub predicted_retention: f64,
+    pub current: IntervalCoefficients,
+    pub proposed: IntervalCoefficients,
+}
+
+// Fits an exponential forgetting curve, recall = exp(-decay * elapsed / interval), to reviews
+// after easy scores and to the rest, then grid-searches the coefficients whose intervals would
+// bring recall closest to the target. Intervals are taken to scale with pass_coef, and easy
+// intervals with pass_coef * easy_coef, which holds for the formula away from its one-day
+// fallbacks. fail_coef is kept.
+pub fn tune(
+    events: &[ReviewEvent],
+    current: &IntervalCoefficients,
+    target_retention: f64,
+) -> Result<Tuning, String> {
+    if !(target_retention > 0.0 && target_retention < 1.0) {
+        return Err(format!(
+            "Target retention must be between 0 and 1, not {}",
+            target_retention
+        ));
+    }
+    if current.pass_coef <= 0.0 || current.easy_coef <= 0.0 {
+        return Err("Current pass and easy coefficients must be positive".to_string());
+    }
+    let observations = observations(events);
+    let (after_easy, after_pass): (Vec<Observation>, Vec<Observation>) =
+        observations.iter().cloned().partition(|o| o.after_easy);
+    if after_pass.len() < MIN_REVIEWS {
+        return Err(format!(
+            "Only {} review(s) follow a non-easy review; at least {} are needed to tune",
+            after_pass.len(),
+            MIN_REVIEWS
+        ));
+    }
+    let pass_decay = fit_decay(&after_pass);
+    let easy_decay = fit_decay(&after_easy);
+    let pass_coef = closest(
+        PASS_COEF_GRID,
+        |pass_coef| predict(&after_pass, pass_decay, pass_coef / current.pass_coef),
+        target_retention,
+    );
+    let current_easy_scale = current.pass_coef * current.easy_coef;
+    let easy_coef = match after_easy.len() >= MIN_REVIEWS {
+        true => closest(
+            EASY_COEF_GRID,
+            |easy_coef| {
+                predict(
+                    &after_easy,
+                    easy_decay,
+                    pass_coef * easy_coef / current_easy_scale,
+                )
+            },
+            target_retention,
+        ),
+        false => current.easy_coef,
+    };
+    let proposed = IntervalCoefficients::new(pass_coef, easy_coef, current.fail_coef);
+    let recalled = observations.iter().filter(|o| o.recalled).count();
+    let predicted_total = predict(&after_pass, pass_decay, pass_coef / current.pass_coef)
+        * after_pass.len() as f64
+        + predict(
+            &after_easy,
+            easy_decay,
+            pass_coef * easy_coef / current_easy_scale,
+        ) * after_easy.len() as f64;
+    Ok(Tuning {
+        reviews: observations.len(),
+        observed_retention: recalled as f64 / observations.len() as f64,
+        predicted_retention: predicted_total / observations.len() as f64,
+        current: current.clone(),
+        proposed,
+    })
+}
+
+// Consecutive reviews of each card. Reviews after an interval of 0, i.e. relearning a failed
+// card, say nothing about the interval formula and are left out.
+fn observations(events: &[ReviewEvent]) -> Vec<Observation> {
+    let mut by_card: HashMap<&str, Vec<&ReviewEvent>> = HashMap::new();
+    for event in events.iter() {
+        by_card.entry(&event.card_path).or_default().push(event);
+    }
+    let mut observations = Vec::new();
+    for reviews in by_card.values_mut() {
+        reviews.sort_by(|a, b| (a.reviewed_at, &a.device).cmp(&(b.reviewed_at, &b.device)));
+        for pair in reviews.windows(2) {
+            let (previous, next) = (pair[0], pair[1]);
+            let interval = previous.revision_settings.interval;
+            if interval <= 0.0 {
+                continue;
+            }
+            let elapsed = (next.reviewed_at - previous.reviewed_at).num_seconds() as f64;
+            observations.push(Observation {
+                elapsed_ratio: elapsed / SECONDS_IN_DAY / interval,
+                recalled: next.score != Score::Fail,
+                after_easy: previous.score == Score::Easy,
+            });
+        }
+    }
+    observations
+}
+
+// The maximum-likelihood decay, searched over a geometric grid.
+fn fit_decay(observations: &[Observation]) -> f64 {
+    let log_likelihood = |decay: f64| -> f64 {
+        observations
+            .iter()
+            .map(|o| {
+                let recall = (-decay * o.elapsed_ratio).exp().clamp(1e-9, 1.0 - 1e-9);
+                match o.recalled {
+                    true => recall.ln(),
+                    false => (1.0 - recall).ln(),
+                }
+            })
+            .sum()
+    };
+    (0..300)
+        .map(|step| 0.001 * 1.04_f64.powi(step))
+        .map(|decay| (decay, log_likelihood(decay)))
+        .fold((0.0, f64::NEG_INFINITY), |best, candidate| {
+            match candidate.1 > best.1 {
+                true => candidate,
+                false => best,
+            }
+        })
+        .0
+}
+
+// Expected recall if every interval were scaled by the given factor.
+fn predict(observations: &[Observation], decay: f64, scale: f64) -> f64 {
+    match observations.len() {
+        0 => 0.0,
+        n => {
+            observations
+                .iter()
+                .map(|o| (-decay * o.elapsed_ratio * scale).exp())
+                .sum::<f64>()
+                / n as f64
+        }
+    }
+}
+
+fn closest(grid: (f64, f64), predict: impl Fn(f64) -> f64, target: f64) -> f64 {
+    let steps = ((grid.1 - grid.0) / GRID_STEP).round() as usize;
+    (0..=steps)
+        .map(|step| grid.0 + step as f64 * GRID_STEP)
+        .map(|candidate| (candidate, (predict(candidate) - target).abs()))
+        .fold((grid.0, f64::INFINITY), |best, candidate| {
+            match candidate.1 < best.1 {
+                true => candidate,
+                false => best,
+            }
+        })
+        .0
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use crate::state::card::{Card, RevisionSettings};
+    use chrono::{Duration, TimeZone, Utc};
+
+    // Each card is reviewed once, then again when its 10 day interval is up.
+    fn make_history(previous_score: Score, recalled: usize, forgotten: usize) -> Vec<ReviewEvent> {
+        let first = Utc.with_ymd_and_hms(2024, 1, 1, 9, 0, 0).unwrap();
+        let second = first + Duration::days(10);
+        (0..recalled + forgotten)
+            .flat_map(|i| {
+                let card = Card::default()
+                    .with_revision_settings(RevisionSettings::new(second, 10.0, 2500.0));
+                let card = Card {
+                    path: format!("{:?}-{}", previous_score, i).into(),
+                    ..card
+                };
+                let score = match i < recalled {
+                    true => Score::Pass,
+                    false => Score::Fail,
+                };
+                vec![
+                    ReviewEvent::new(&card, previous_score, "laptop", first),
+                    ReviewEvent::new(&card, score, "laptop", second),
+                ]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn observations_skip_relearning_reviews() {
+        let mut events = make_history(Score::Pass, 1, 0);
+        events[0].revision_settings.interval = 0.0;
+        assert!(observations(&events).is_empty());
+        let events = make_history(Score::Easy, 0, 1);
+        let expected = vec![Observation {
+            elapsed_ratio: 1.0,
+            recalled: false,
+            after_easy: true,
+        }];
+        assert_eq!(expected, observations(&events));
+    }
+
+    #[test]
+    fn tune_shortens_intervals_when_recall_is_below_target() {
+        // 75% recall at the full interval: reaching 80% means intervals of ln 0.8 / ln 0.75.
+        let events = make_history(Score::Pass, 30, 10);
+        let current = IntervalCoefficients::default();
+        let actual = tune(&events, &current, 0.8).unwrap();
+        assert_eq!(40, actual.reviews);
+        assert!((actual.observed_retention - 0.75).abs() < 1e-9);
+        assert!((actual.proposed.pass_coef - 0.78).abs() <= GRID_STEP);
+        assert!((actual.predicted_retention - 0.8).abs() < 0.01);
+        assert_eq!(current.easy_coef, actual.proposed.easy_coef);
+        assert_eq!(current.fail_coef, actual.proposed.fail_coef);
+    }
+
+    #[test]
+    fn tune_lengthens_easy_intervals_when_easy_cards_are_always_recalled() {
+        let mut events = make_history(Score::Pass, 18, 2);
+        events.extend(make_history(Score::Easy, 39, 1));
+        let current = IntervalCoefficients::default();
+        let actual = tune(&events, &current, 0.9).unwrap();
+        assert!(actual.proposed.easy_coef > current.easy_coef);
+    }
+
+    #[test]
+    fn tune_needs_enough_history() {
+        let events = make_history(Score::Pass, 5, 5);
+        let actual = tune(&events, &IntervalCoefficients::default(), 0.9);
+        assert!(actual.unwrap_err().contains("Only 10 review(s)"));
+    }
+
+    #[test]
+    fn tune_rejects_unreachable_targets() {
+        let events = make_history(Score::Pass, 30, 10);
+        assert!(tune(&events, &IntervalCoefficients::default(), 1.0).is_err());
+    }
+}
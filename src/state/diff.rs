@@ -0,0 +1,88 @@
+use crate::state::card::RevisionSettings;
+use crate::state::State;
+use serde::{Deserialize, Serialize};
+
+// A card whose schedule differs between two snapshots of the state.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ScheduleChange {
+    pub path: String,
+    pub before: RevisionSettings,
+    pub after: RevisionSettings,
+}
+
+impl ScheduleChange {
+    pub fn due_days(&self) -> i64 {
+        (self.after.due.date_naive() - self.before.due.date_naive()).num_days()
+    }
+
+    pub fn interval_days(&self) -> f64 {
+        self.after.interval - self.before.interval
+    }
+
+    pub fn memorisation_factor(&self) -> f64 {
+        self.after.memorisation_factor - self.before.memorisation_factor
+    }
+}
+
+// Cards added or removed in between have no schedule to compare, and are left out. Sorted by path.
+pub fn schedule_changes(before: &State, after: &State) -> Vec<ScheduleChange> {
+    let mut changes: Vec<ScheduleChange> = after
+        .cards()
+        .values()
+        .filter_map(|card| {
+            let previous = before.cards().get(&card.path)?;
+            (previous.revision_settings != card.revision_settings).then(|| ScheduleChange {
+                path: card.path.to_string(),
+                before: previous.revision_settings.clone(),
+                after: card.revision_settings.clone(),
+            })
+        })
+        .collect();
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    changes
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use crate::state::card::Card;
+    use chrono::{Duration, TimeZone, Utc};
+
+    fn make_card(path: &str, revision_settings: RevisionSettings) -> Card {
+        Card {
+            path: path.into(),
+            ..Card::default()
+        }
+        .with_revision_settings(revision_settings)
+    }
+
+    #[test]
+    fn schedule_changes_of_cards_in_both_states() {
+        let due = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+        let unchanged = RevisionSettings::new(due, 4.0, 1300.0);
+        let before = State::default().with_loaded_cards(vec![
+            make_card("b", RevisionSettings::new(due, 2.0, 1300.0)),
+            make_card("a", unchanged.clone()),
+            make_card("deleted", unchanged.clone()),
+        ]);
+        let reviewed = RevisionSettings::new(due + Duration::days(5), 5.2, 1450.0);
+        let after = State::default().with_loaded_cards(vec![
+            make_card("b", reviewed.clone()),
+            make_card("a", unchanged.clone()),
+            make_card("added", reviewed.clone()),
+        ]);
+        let actual = schedule_changes(&before, &after);
+        assert_eq!(
+            vec![ScheduleChange {
+                path: "b".to_string(),
+                before: RevisionSettings::new(due, 2.0, 1300.0),
+                after: reviewed,
+            }],
+            actual
+        );
+        assert_eq!(5, actual[0].due_days());
+        assert!((actual[0].interval_days() - 3.2).abs() < 1e-9);
+        assert_eq!(150.0, actual[0].memorisation_factor());
+    }
+}
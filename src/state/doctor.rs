@@ -0,0 +1,355 @@
+use super::card::{Card, RevisionSettings};
+use super::deck::{Deck, IntervalCoefficients};
+use super::State;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+
+// An inconsistency in a state, such as a hand-edited or half-synced state file may have.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum Problem {
+    // A deck lists a card the state doesn't have.
+    MissingCard {
+        deck: String,
+        path: String,
+    },
+    // A card is in a deck that doesn't list it.
+    UnlistedCard {
+        deck: String,
+        path: String,
+    },
+    // A card is in a deck the state doesn't have.
+    UnknownDeck {
+        path: String,
+        deck: String,
+    },
+    // A card or deck is kept under a key other than its path or name.
+    MisfiledCard {
+        key: String,
+        path: String,
+    },
+    MisfiledDeck {
+        key: String,
+        name: String,
+    },
+    // NaN, infinite or negative.
+    InvalidInterval {
+        path: String,
+        interval: f64,
+    },
+    // NaN, infinite, zero or negative.
+    InvalidMemorisationFactor {
+        path: String,
+        memorisation_factor: f64,
+    },
+    InvalidCoefficients {
+        deck: String,
+    },
+    // Cards whose questions are the same once case, punctuation and spacing are set aside, which
+    // splits the review history of what is one card between them.
+    DuplicateQuestion {
+        paths: Vec<String>,
+    },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::MissingCard { deck, path } => {
+                write!(f, "Deck \"{}\" lists {}, which has no card", deck, path)
+            }
+            Problem::UnlistedCard { deck, path } => {
+                write!(f, "{} is in deck \"{}\", which doesn't list it", path, deck)
+            }
+            Problem::UnknownDeck { path, deck } => {
+                write!(f, "{} is in deck \"{}\", which doesn't exist", path, deck)
+            }
+            Problem::MisfiledCard { key, path } => {
+                write!(f, "The card of {} is kept under \"{}\"", path, key)
+            }
+            Problem::MisfiledDeck { key, name } => {
+                write!(f, "Deck \"{}\" is kept under \"{}\"", name, key)
+            }
+            Problem::InvalidInterval { path, interval } => {
+                write!(f, "{} has an interval of {}", path, interval)
+            }
+            Problem::InvalidMemorisationFactor {
+                path,
+                memorisation_factor,
+            } => write!(
+                f,
+                "{} has a memorisation factor of {}",
+                path, memorisation_factor
+            ),
+            Problem::InvalidCoefficients { deck } => {
+                write!(f, "Deck \"{}\" has invalid interval coefficients", deck)
+            }
+            Problem::DuplicateQuestion { paths } => {
+                write!(f, "{} have the same question", paths.join(", "))
+            }
+        }
+    }
+}
+
+impl State {
+    // Sorted by what they concern, so that the same state always reports the same way.
+    pub fn diagnose(&self) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        for (key, card) in sorted(&self.cards) {
+            if *key != card.path {
+                problems.push(Problem::MisfiledCard {
+                    key: key.to_string(),
+                    path: card.path.to_string(),
+                });
+            }
+            for name in card.decks.iter() {
+                match self.decks.get(name) {
+                    Some(deck) if !deck.lists(&card.path) => problems.push(Problem::UnlistedCard {
+                        deck: name.clone(),
+                        path: card.path.to_string(),
+                    }),
+                    Some(_) => {}
+                    None => problems.push(Problem::UnknownDeck {
+                        path: card.path.to_string(),
+                        deck: name.clone(),
+                    }),
+                }
+            }
+            let settings = &card.revision_settings;
+            if !settings.interval.is_finite() || settings.interval < 0.0 {
+                problems.push(Problem::InvalidInterval {
+                    path: card.path.to_string(),
+                    interval: settings.interval,
+                });
+            }
+            if !settings.memorisation_factor.is_finite() || settings.memorisation_factor <= 0.0 {
+                problems.push(Problem::InvalidMemorisationFactor {
+                    path: card.path.to_string(),
+                    memorisation_factor: settings.memorisation_factor,
+                });
+            }
+        }
+        for (key, deck) in sorted(&self.decks) {
+            if *key != deck.name {
+                problems.push(Problem::MisfiledDeck {
+                    key: key.to_string(),
+                    name: deck.name.to_string(),
+                });
+            }
+            for path in deck.card_paths.iter() {
+                if !self.cards.contains_key(path) {
+                    problems.push(Problem::MissingCard {
+                        deck: deck.name.to_string(),
+                        path: path.to_string(),
+                    });
+                }
+            }
+            let options_coefficients = deck.options.interval_coefficients.as_ref();
+            if !valid_coefficients(&deck.interval_coefficients)
+                || options_coefficients.is_some_and(|c| !valid_coefficients(c))
+            {
+                problems.push(Problem::InvalidCoefficients {
+                    deck: deck.name.to_string(),
+                });
+            }
+        }
+        for paths in duplicate_questions(self.cards.values()) {
+            problems.push(Problem::DuplicateQuestion {
+                paths: paths.into_iter().map(String::from).collect(),
+            });
+        }
+        problems
+    }
+
+    // Safe repairs only: cards and decks are re-keyed, decks' card lists are rebuilt from the
+    // cards, and invalid numbers are reset to their defaults, which has a card relearned.
+    pub fn with_repairs(self) -> Self {
+        let defaults = RevisionSettings::default();
+        let cards: Vec<Card> = self
+            .cards
+            .into_values()
+            .map(|mut card| {
+                let settings = &mut card.revision_settings;
+                if !settings.interval.is_finite() || settings.interval < 0.0 {
+                    settings.interval = defaults.interval;
+                }
+                if !settings.memorisation_factor.is_finite() || settings.memorisation_factor <= 0.0
+                {
+                    settings.memorisation_factor = defaults.memorisation_factor;
+                }
+                card
+            })
+            .collect();
+        let decks: Vec<Deck> = self
+            .decks
+            .into_values()
+            .map(|mut deck| {
+                if !valid_coefficients(&deck.interval_coefficients) {
+                    deck.interval_coefficients = IntervalCoefficients::default();
+                }
+                if let Some(coefficients) = &deck.options.interval_coefficients {
+                    if !valid_coefficients(coefficients) {
+                        deck.options.interval_coefficients = None;
+                    }
+                }
+                deck
+            })
+            .collect();
+        let state = State {
+            cards: HashMap::from_iter(Self::uid_value_pairs(cards)),
+            decks: HashMap::from_iter(Self::uid_value_pairs(decks)),
+            ..self
+        };
+        state.with_redecked_cards(Vec::new())
+    }
+}
+
+// The paths of each set of cards with the same normalised question, sorted. Cards without a
+// question, as in a metadata only state, are left out.
+pub fn duplicate_questions<'c>(cards: impl IntoIterator<Item = &'c Card>) -> Vec<Vec<&'c str>> {
+    let mut paths_by_question: HashMap<String, Vec<&str>> = HashMap::new();
+    for card in cards {
+        let question = normalise_question(&card.question);
+        if !question.is_empty() {
+            paths_by_question
+                .entry(question)
+                .or_default()
+                .push(&card.path);
+        }
+    }
+    let mut duplicates: Vec<Vec<&str>> = paths_by_question
+        .into_values()
+        .filter(|paths| paths.len() > 1)
+        .map(|mut paths| {
+            paths.sort();
+            paths
+        })
+        .collect();
+    duplicates.sort();
+    duplicates
+}
+
+// Lowercase words of letters and digits, so that "What is ATP?" and "what is  ATP" match.
+fn normalise_question(question: &str) -> String {
+    question
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn valid_coefficients(coefficients: &IntervalCoefficients) -> bool {
+    [
+        coefficients.pass_coef,
+        coefficients.easy_coef,
+        coefficients.fail_coef,
+    ]
+    .iter()
+    .all(|c| c.is_finite() && *c >= 0.0)
+}
+
+fn sorted<K: Ord, T>(map: &HashMap<K, T>) -> Vec<(&K, &T)> {
+    let mut entries: Vec<(&K, &T)> = map.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    entries
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+
+    fn make_card(path: &str, decks: &[&str], interval: f64) -> Card {
+        Card {
+            path: path.into(),
+            decks: decks.iter().map(|d| d.to_string()).collect(),
+            revision_settings: RevisionSettings {
+                interval,
+                ..RevisionSettings::default()
+            },
+            ..Card::default()
+        }
+    }
+
+    fn make_unhealthy_state() -> State {
+        let mut cards = HashMap::new();
+        cards.insert("a".into(), make_card("a", &["bio"], 2.0));
+        cards.insert("old/b".into(), make_card("b", &["chem"], f64::NAN));
+        let mut decks = HashMap::new();
+        let deck = Deck::new(
+            "bio",
+            vec!["gone"],
+            IntervalCoefficients::new(1.0, -1.0, 0.0),
+        );
+        decks.insert("bio".into(), deck);
+        State {
+            cards,
+            decks,
+            ..State::default()
+        }
+    }
+
+    #[test]
+    fn diagnose() {
+        let actual: Vec<String> = make_unhealthy_state()
+            .diagnose()
+            .iter()
+            .map(|problem| problem.to_string())
+            .collect();
+        let expected = vec![
+            "a is in deck \"bio\", which doesn't list it",
+            "The card of b is kept under \"old/b\"",
+            "b is in deck \"chem\", which doesn't exist",
+            "b has an interval of NaN",
+            "Deck \"bio\" lists gone, which has no card",
+            "Deck \"bio\" has invalid interval coefficients",
+        ];
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn with_repairs() {
+        let actual = make_unhealthy_state().with_repairs();
+        assert!(actual.diagnose().is_empty());
+        assert_eq!(0.0, actual.cards()["b"].revision_settings.interval);
+        assert_eq!(2.0, actual.cards()["a"].revision_settings.interval);
+        assert_eq!(vec!["a".to_string()], actual.decks()["bio"].card_paths);
+        assert_eq!(
+            IntervalCoefficients::default(),
+            actual.decks()["bio"].interval_coefficients
+        );
+        assert!(State::default().diagnose().is_empty());
+    }
+
+    #[test]
+    fn diagnose_duplicate_questions() {
+        let mut cards = [
+            make_card("bio/atp", &[], 0.0),
+            make_card("chem/atp", &[], 0.0),
+            make_card("bio/krebs", &[], 0.0),
+            make_card("unparsed", &[], 0.0),
+            make_card("old/atp", &[], 0.0),
+        ];
+        cards[0].question = "What is ATP?".to_string();
+        cards[1].question = "what is\n  **ATP**".to_string();
+        cards[2].question = "What is the Krebs cycle?".to_string();
+        cards[4].question = "What is ATP".to_string();
+        assert_eq!(
+            vec![vec!["bio/atp", "chem/atp", "old/atp"]],
+            duplicate_questions(cards.iter())
+        );
+        let state = State::builder()
+            .add_card(cards[0].clone())
+            .add_card(cards[1].clone())
+            .build();
+        assert_eq!(
+            vec!["bio/atp, chem/atp have the same question"],
+            state
+                .diagnose()
+                .iter()
+                .map(|problem| problem.to_string())
+                .collect::<Vec<String>>()
+        );
+    }
+}
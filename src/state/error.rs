@@ -0,0 +1,47 @@
+use super::card::load_failure::LoadFailure;
+use super::card::parser::ParseError;
+use snafu::prelude::*;
+
+// Why the state file, one of its shards, or the config layered over it, couldn't be read or
+// written.
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub enum StorageError {
+    #[snafu(display("Unable to read State from {}", path))]
+    Unreadable {
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("Unable to write State to {}", path))]
+    Unwritable {
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("Unable to parse State from {}: {}", path, message))]
+    Malformed { path: String, message: String },
+    #[snafu(display("Unable to serialise State to {}: {}", path, message))]
+    Unserialisable { path: String, message: String },
+    #[snafu(display("Unable to read config from {}", path))]
+    UnreadableConfig {
+        path: String,
+        source: std::io::Error,
+    },
+    #[snafu(display("Unable to parse config from {}: {}", path, message))]
+    MalformedConfig { path: String, message: String },
+}
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub enum StateError {
+    #[snafu(display("No deck named '{}' exists.", deck_name))]
+    NoSuchDeck { deck_name: String },
+    // An argument out of range, such as a retention above 1.
+    #[snafu(display("{}", message))]
+    InvalidOption { message: String },
+    #[snafu(display("Unable to load Card: {}", source))]
+    UnloadableCard { source: LoadFailure },
+    #[snafu(context(false), display("{}", source))]
+    Parse { source: ParseError },
+    #[snafu(context(false), display("{}", source))]
+    Storage { source: StorageError },
+}
@@ -1,14 +1,70 @@
 #[cfg(test)]
 use mockall::automock;
 #[cfg(test)]
+use mocks::mock_append_file as append_file;
+#[cfg(test)]
 use mocks::mock_read_file as read_file;
 #[cfg(test)]
+use mocks::mock_read_file_bytes as read_file_bytes;
+#[cfg(test)]
 use mocks::mock_write_file as write_file;
+#[cfg(test)]
+use mocks::mock_write_file_bytes as write_file_bytes;
 
+#[cfg(not(test))]
+use std::fs::read as read_file_bytes;
 #[cfg(not(test))]
 use std::fs::read_to_string as read_file;
 #[cfg(not(test))]
 use std::fs::write as write_file;
+#[cfg(not(test))]
+use std::fs::write as write_file_bytes;
+
+#[cfg(all(feature = "async", test))]
+use mocks::mock_read_file_async as read_file_async;
+#[cfg(all(feature = "async", test))]
+use mocks::mock_write_file_async as write_file_async;
+#[cfg(all(feature = "async", not(test)))]
+use tokio::fs::read_to_string as read_file_async;
+#[cfg(all(feature = "async", not(test)))]
+use tokio::fs::write as write_file_async;
+
+mod compressed;
+mod discovery;
+mod encrypted;
+mod ignored;
+
+pub use compressed::{CompressedFileHandle, Compression};
+pub use discovery::Discovery;
+pub use encrypted::{is_encrypted, EncryptedFileHandle};
+pub use ignored::{IgnoreRules, IGNORE_FILE_NAME};
+
+// Whole-file reads and writes, so that layers such as encryption and compression can wrap a
+// FileHandle, or each other.
+pub trait IO {
+    fn path(&self) -> &str;
+    fn read_bytes(&self) -> Result<Vec<u8>, std::io::Error>;
+    fn write_bytes(&self, content: Vec<u8>) -> Result<(), std::io::Error>;
+
+    fn read(&self) -> Result<String, std::io::Error> {
+        String::from_utf8(self.read_bytes()?)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    fn write(&self, content: String) -> Result<(), std::io::Error> {
+        self.write_bytes(content.into_bytes())
+    }
+
+    // Layers such as encryption can only append by writing the whole file again.
+    fn append(&self, content: String) -> Result<(), std::io::Error> {
+        let mut appended = match self.read() {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            read => read?,
+        };
+        appended.push_str(&content);
+        self.write(appended)
+    }
+}
 
 #[derive(Debug)]
 pub struct FileHandle {
@@ -29,11 +85,124 @@ impl FileHandle {
     pub fn write<'a>(&'a self, content: String) -> Result<(), std::io::Error> {
         write_file(&self.path, content)
     }
+    pub fn append(&self, content: String) -> Result<(), std::io::Error> {
+        append_file(&self.path, content)
+    }
+    pub fn read_bytes(&self) -> Result<Vec<u8>, std::io::Error> {
+        read_file_bytes(&self.path)
+    }
+    pub fn write_bytes(&self, content: Vec<u8>) -> Result<(), std::io::Error> {
+        write_file_bytes(&self.path, content)
+    }
+    #[cfg(feature = "async")]
+    pub async fn read_async(&self) -> Result<String, std::io::Error> {
+        read_file_async(&self.path).await
+    }
+    #[cfg(feature = "async")]
+    pub async fn write_async(&self, content: String) -> Result<(), std::io::Error> {
+        write_file_async(&self.path, content).await
+    }
+}
+
+impl IO for FileHandle {
+    fn path(&self) -> &str {
+        FileHandle::path(self)
+    }
+    fn read_bytes(&self) -> Result<Vec<u8>, std::io::Error> {
+        FileHandle::read_bytes(self)
+    }
+    fn write_bytes(&self, content: Vec<u8>) -> Result<(), std::io::Error> {
+        FileHandle::write_bytes(self, content)
+    }
+    fn read(&self) -> Result<String, std::io::Error> {
+        FileHandle::read(self)
+    }
+    fn write(&self, content: String) -> Result<(), std::io::Error> {
+        FileHandle::write(self, content)
+    }
+    fn append(&self, content: String) -> Result<(), std::io::Error> {
+        FileHandle::append(self, content)
+    }
+}
+
+impl<T: IO + ?Sized> IO for Box<T> {
+    fn path(&self) -> &str {
+        (**self).path()
+    }
+    fn read_bytes(&self) -> Result<Vec<u8>, std::io::Error> {
+        (**self).read_bytes()
+    }
+    fn write_bytes(&self, content: Vec<u8>) -> Result<(), std::io::Error> {
+        (**self).write_bytes(content)
+    }
+    fn read(&self) -> Result<String, std::io::Error> {
+        (**self).read()
+    }
+    fn write(&self, content: String) -> Result<(), std::io::Error> {
+        (**self).write(content)
+    }
+    fn append(&self, content: String) -> Result<(), std::io::Error> {
+        (**self).append(content)
+    }
 }
 
 #[cfg(test)]
-mod mocks {
+impl IO for MockFileHandle {
+    fn path(&self) -> &str {
+        MockFileHandle::path(self)
+    }
+    fn read_bytes(&self) -> Result<Vec<u8>, std::io::Error> {
+        MockFileHandle::read_bytes(self)
+    }
+    fn write_bytes(&self, content: Vec<u8>) -> Result<(), std::io::Error> {
+        MockFileHandle::write_bytes(self, content)
+    }
+    fn read(&self) -> Result<String, std::io::Error> {
+        MockFileHandle::read(self)
+    }
+    fn write(&self, content: String) -> Result<(), std::io::Error> {
+        MockFileHandle::write(self, content)
+    }
+    fn append(&self, content: String) -> Result<(), std::io::Error> {
+        MockFileHandle::append(self, content)
+    }
+}
+
+#[cfg(not(test))]
+fn append_file(path: &str, content: String) -> Result<(), std::io::Error> {
+    use std::io::Write;
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(content.as_bytes())
+}
+
+#[cfg(test)]
+pub(crate) mod mocks {
+    use super::IO;
+    use std::cell::RefCell;
+
     pub const ERRONEOUS_PATH: &str = "error this path is garbage";
+
+    #[derive(Default)]
+    pub struct MemoryFile {
+        pub content: RefCell<Vec<u8>>,
+    }
+
+    impl IO for MemoryFile {
+        fn path(&self) -> &str {
+            "memory"
+        }
+        fn read_bytes(&self) -> Result<Vec<u8>, std::io::Error> {
+            Ok(self.content.borrow().clone())
+        }
+        fn write_bytes(&self, content: Vec<u8>) -> Result<(), std::io::Error> {
+            self.content.replace(content);
+            Ok(())
+        }
+    }
+
     pub fn mock_read_file(path: &str) -> Result<String, std::io::Error> {
         if path == ERRONEOUS_PATH {
             Err(std::io::Error::new(
@@ -54,6 +223,23 @@ mod mocks {
             Ok(())
         }
     }
+    pub fn mock_append_file(path: &str, content: String) -> Result<(), std::io::Error> {
+        mock_write_file(path, content)
+    }
+    pub fn mock_read_file_bytes(path: &str) -> Result<Vec<u8>, std::io::Error> {
+        mock_read_file(path).map(String::into_bytes)
+    }
+    pub fn mock_write_file_bytes(path: &str, content: Vec<u8>) -> Result<(), std::io::Error> {
+        mock_write_file(path, String::from_utf8_lossy(&content).to_string())
+    }
+    #[cfg(feature = "async")]
+    pub async fn mock_read_file_async(path: &str) -> Result<String, std::io::Error> {
+        mock_read_file(path)
+    }
+    #[cfg(feature = "async")]
+    pub async fn mock_write_file_async(path: &str, content: String) -> Result<(), std::io::Error> {
+        mock_write_file(path, content)
+    }
 }
 
 #[cfg(test)]
@@ -101,4 +287,30 @@ mod unit_tests {
         let handle = FileHandle::from(path.to_string());
         assert_result(expected, handle.write(content.to_string()));
     }
+
+    #[rstest]
+    #[case::should_call_append_file("hello", "world", Ok(()))]
+    #[case::should_propagate_error(mocks::ERRONEOUS_PATH, "", Err(()))]
+    fn append(#[case] path: &str, #[case] content: &str, #[case] expected: Result<(), ()>) {
+        let handle = FileHandle::from(path.to_string());
+        assert_result(expected, handle.append(content.to_string()));
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn read_async() {
+        let handle = FileHandle::from("hello".to_string());
+        assert_eq!("hello", handle.read_async().await.unwrap());
+        let handle = FileHandle::from(mocks::ERRONEOUS_PATH.to_string());
+        assert!(handle.read_async().await.is_err());
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn write_async() {
+        let handle = FileHandle::from("hello".to_string());
+        assert!(handle.write_async("world".to_string()).await.is_ok());
+        let handle = FileHandle::from(mocks::ERRONEOUS_PATH.to_string());
+        assert!(handle.write_async("".to_string()).await.is_err());
+    }
 }
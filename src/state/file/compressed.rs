@@ -0,0 +1,124 @@
+use super::{FileHandle, IO};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read, Write};
+use std::str::FromStr;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const ZSTD_LEVEL: i32 = 3;
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl FromStr for Compression {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "gzip" => Ok(Compression::Gzip),
+            "zstd" => Ok(Compression::Zstd),
+            _ => Err(format!(
+                "Unknown compression \"{}\": expected gzip or zstd",
+                name
+            )),
+        }
+    }
+}
+
+// Compressed files are recognised by their magic bytes whatever the setting, which only decides
+// how files are written: None writes them uncompressed.
+#[derive(Debug)]
+pub struct CompressedFileHandle<F: IO = FileHandle> {
+    inner: F,
+    compression: Option<Compression>,
+}
+
+impl<F: IO> CompressedFileHandle<F> {
+    pub fn new(inner: F, compression: Option<Compression>) -> Self {
+        Self { inner, compression }
+    }
+}
+
+impl<F: IO> IO for CompressedFileHandle<F> {
+    fn path(&self) -> &str {
+        self.inner.path()
+    }
+
+    fn read_bytes(&self) -> Result<Vec<u8>, io::Error> {
+        let content = self.inner.read_bytes()?;
+        let mut decompressed = Vec::new();
+        if content.starts_with(GZIP_MAGIC) {
+            GzDecoder::new(content.as_slice()).read_to_end(&mut decompressed)?;
+        } else if content.starts_with(ZSTD_MAGIC) {
+            decompressed = zstd::decode_all(content.as_slice())?;
+        } else {
+            return Ok(content);
+        }
+        Ok(decompressed)
+    }
+
+    fn write_bytes(&self, content: Vec<u8>) -> Result<(), io::Error> {
+        let compressed = match self.compression {
+            None => content,
+            Some(Compression::Gzip) => {
+                let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(&content)?;
+                encoder.finish()?
+            }
+            Some(Compression::Zstd) => zstd::encode_all(content.as_slice(), ZSTD_LEVEL)?,
+        };
+        self.inner.write_bytes(compressed)
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::super::mocks::MemoryFile;
+    use super::super::EncryptedFileHandle;
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case::gzip(Some(Compression::Gzip), GZIP_MAGIC)]
+    #[case::zstd(Some(Compression::Zstd), ZSTD_MAGIC)]
+    #[case::uncompressed(None, b"(cards")]
+    fn round_trip(#[case] compression: Option<Compression>, #[case] magic: &[u8]) {
+        let content = format!("(cards: {{{}}})", "\"a.md\": (),".repeat(100));
+        let handle = CompressedFileHandle::new(MemoryFile::default(), compression);
+        handle.write(content.clone()).unwrap();
+        let stored = handle.inner.content.borrow().clone();
+        assert!(stored.starts_with(magic));
+        if compression.is_some() {
+            assert!(stored.len() < content.len() / 10);
+        }
+        assert_eq!(content, handle.read().unwrap());
+    }
+
+    #[test]
+    fn read_detects_compression_whatever_the_setting() {
+        let handle = CompressedFileHandle::new(MemoryFile::default(), Some(Compression::Zstd));
+        handle.write("(cards: {})".to_string()).unwrap();
+        let handle = CompressedFileHandle::new(handle.inner, None);
+        assert_eq!("(cards: {})", handle.read().unwrap());
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!(Ok(Compression::Zstd), "zstd".parse());
+        assert!("lz4".parse::<Compression>().is_err());
+    }
+
+    #[test]
+    fn compresses_before_encrypting() {
+        let encrypted = EncryptedFileHandle::new(MemoryFile::default(), "hunter2");
+        let handle = CompressedFileHandle::new(encrypted, Some(Compression::Gzip));
+        handle.write("(cards: {})".to_string()).unwrap();
+        assert_eq!("(cards: {})", handle.read().unwrap());
+    }
+}
@@ -0,0 +1,204 @@
+use super::IgnoreRules;
+use glob::{MatchOptions, Pattern};
+use snafu::{prelude::*, Whatever};
+use std::collections::HashSet;
+use std::path::Path;
+use walkdir::{DirEntry, WalkDir};
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+// Finds the notes in a directory matching any of a list of globs, relative to it. Symlinked
+// folders are followed by default, and a note reached through several links is only listed once,
+// under the first path found to it. Links back to a folder being walked are skipped.
+#[derive(Debug)]
+pub struct Discovery {
+    note_globs: Vec<Pattern>,
+    ignore_rules: IgnoreRules,
+    follow_symlinks: bool,
+    deduplicate: bool,
+}
+
+impl Discovery {
+    pub fn new(note_globs: &[String]) -> Result<Self, Whatever> {
+        let note_globs = note_globs
+            .iter()
+            .map(|note_glob| {
+                Pattern::new(note_glob)
+                    .with_whatever_context(|_| format!("Invalid note glob \"{}\"", note_glob))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            note_globs,
+            ignore_rules: IgnoreRules::default(),
+            follow_symlinks: true,
+            deduplicate: true,
+        })
+    }
+
+    pub fn with_ignore_rules(self, ignore_rules: IgnoreRules) -> Self {
+        Self {
+            ignore_rules,
+            ..self
+        }
+    }
+
+    pub fn with_follow_symlinks(self, follow_symlinks: bool) -> Self {
+        Self {
+            follow_symlinks,
+            ..self
+        }
+    }
+
+    // By the notes' canonical paths.
+    pub fn with_deduplication(self, deduplicate: bool) -> Self {
+        Self {
+            deduplicate,
+            ..self
+        }
+    }
+
+    // Paths are spelled as under the notes directory, without a leading "./".
+    pub fn find_paths(&self, notes_dir: &str) -> Result<Vec<String>, Whatever> {
+        let root = notes_dir.trim_end_matches('/');
+        let root = match root.trim_start_matches("./") {
+            "" => "/",
+            root => root,
+        };
+        let mut canonical_paths = HashSet::new();
+        let walk = WalkDir::new(root)
+            .follow_links(self.follow_symlinks)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|entry| !self.is_ignored(root, entry));
+        Ok(walk
+            // Symlink cycles, and unreadable folders, are reported as errors and skipped.
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .filter(|entry| self.matches(root, entry))
+            .filter(|entry| {
+                !self.deduplicate
+                    || match entry.path().canonicalize() {
+                        Ok(path) => canonical_paths.insert(path),
+                        Err(_) => true,
+                    }
+            })
+            .map(|entry| spell(root, entry.path()))
+            .collect())
+    }
+
+    fn matches(&self, root: &str, entry: &DirEntry) -> bool {
+        let Ok(relative_path) = entry.path().strip_prefix(root) else {
+            return false;
+        };
+        self.note_globs
+            .iter()
+            .any(|pattern| pattern.matches_path_with(relative_path, MATCH_OPTIONS))
+    }
+
+    fn is_ignored(&self, root: &str, entry: &DirEntry) -> bool {
+        entry.depth() > 0 && self.ignore_rules.is_ignored(&spell(root, entry.path()))
+    }
+}
+
+fn spell(root: &str, path: &Path) -> String {
+    let path = path.to_string_lossy();
+    match root {
+        "." => path.trim_start_matches("./").to_string(),
+        _ => path.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::super::IGNORE_FILE_NAME;
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn make_notes_dir(name: &str, files: &[&str]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        for file in files {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        dir
+    }
+
+    fn find_paths(discovery: &Discovery, notes_dir: &Path) -> Vec<String> {
+        let notes_dir = notes_dir.to_string_lossy();
+        discovery
+            .find_paths(&notes_dir)
+            .unwrap()
+            .into_iter()
+            .map(|path| {
+                path.trim_start_matches(&format!("{}/", notes_dir))
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn find_paths_by_globs() {
+        let dir = make_notes_dir(
+            "vultan_discovery_globs",
+            &["a.md", "nested/b.md", "nested/c.txt", "flashcards/d.org"],
+        );
+        let discovery = Discovery::new(&["**/*.md".to_string()]).unwrap();
+        assert_eq!(["a.md", "nested/b.md"], find_paths(&discovery, &dir)[..]);
+        let discovery = Discovery::new(&["*.md", "flashcards/**/*.org"].map(String::from)).unwrap();
+        assert_eq!(
+            ["a.md", "flashcards/d.org"],
+            find_paths(&discovery, &dir)[..]
+        );
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn find_paths_leaves_out_ignored_notes() {
+        let dir = make_notes_dir("vultan_discovery_ignored", &["a.md", "templates/b.md"]);
+        fs::write(dir.join(IGNORE_FILE_NAME), "templates/\n").unwrap();
+        let ignore_rules = IgnoreRules::read(&dir.to_string_lossy(), false).unwrap();
+        let discovery = Discovery::new(&["**/*.md".to_string()])
+            .unwrap()
+            .with_ignore_rules(ignore_rules);
+        assert_eq!(["a.md"], find_paths(&discovery, &dir)[..]);
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn find_paths_through_symlinks() {
+        let dir = make_notes_dir("vultan_discovery_symlinks", &["shared/a.md"]);
+        std::os::unix::fs::symlink(dir.join("shared"), dir.join("linked")).unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("shared/cycle")).unwrap();
+        let discovery = Discovery::new(&["**/*.md".to_string()]).unwrap();
+        assert_eq!(["linked/a.md"], find_paths(&discovery, &dir)[..]);
+        let discovery = discovery.with_deduplication(false);
+        assert_eq!(
+            ["linked/a.md", "shared/a.md"],
+            find_paths(&discovery, &dir)[..]
+        );
+        let discovery = discovery.with_follow_symlinks(false);
+        assert_eq!(["shared/a.md"], find_paths(&discovery, &dir)[..]);
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn find_paths_in_a_missing_dir() {
+        let discovery = Discovery::new(&["**/*.md".to_string()]).unwrap();
+        let actual = discovery.find_paths("/nonexistent/vultan/notes").unwrap();
+        assert!(actual.is_empty());
+    }
+
+    #[test]
+    fn new_with_an_invalid_glob() {
+        assert!(Discovery::new(&["***".to_string()]).is_err());
+    }
+}
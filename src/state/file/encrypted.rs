@@ -0,0 +1,153 @@
+use super::{FileHandle, IO};
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use std::io;
+
+const HEADER: &str = "vultan-encrypted v1";
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 24;
+
+// Encrypts with XChaCha20-Poly1305 under a key derived from a passphrase with Argon2. Each write
+// picks a fresh salt and nonce and stores them, base64 encoded, on one line after a header.
+#[derive(Debug)]
+pub struct EncryptedFileHandle<F: IO = FileHandle> {
+    inner: F,
+    passphrase: String,
+}
+
+impl<F: IO> EncryptedFileHandle<F> {
+    pub fn new(inner: F, passphrase: &str) -> Self {
+        Self {
+            inner,
+            passphrase: passphrase.to_string(),
+        }
+    }
+}
+
+pub fn is_encrypted(content: &str) -> bool {
+    is_encrypted_bytes(content.as_bytes())
+}
+
+fn is_encrypted_bytes(content: &[u8]) -> bool {
+    content.starts_with(HEADER.as_bytes())
+}
+
+impl<F: IO> IO for EncryptedFileHandle<F> {
+    fn path(&self) -> &str {
+        self.inner.path()
+    }
+
+    // Plaintext is read as is, so a file is encrypted from the first write after a passphrase is
+    // set.
+    fn read_bytes(&self) -> Result<Vec<u8>, io::Error> {
+        let content = self.inner.read_bytes()?;
+        match is_encrypted_bytes(&content) {
+            true => decrypt(&String::from_utf8_lossy(&content), &self.passphrase),
+            false => Ok(content),
+        }
+    }
+
+    fn write_bytes(&self, content: Vec<u8>) -> Result<(), io::Error> {
+        self.inner.write(encrypt(&content, &self.passphrase)?)
+    }
+}
+
+fn encrypt(plaintext: &[u8], passphrase: &str) -> Result<String, io::Error> {
+    let salt: [u8; SALT_LENGTH] = rand::random();
+    let nonce: [u8; NONCE_LENGTH] = rand::random();
+    let ciphertext = cipher(passphrase, &salt)?
+        .encrypt(XNonce::from_slice(&nonce), plaintext)
+        .map_err(|_| invalid_data("Unable to encrypt"))?;
+    Ok(format!(
+        "{} {} {} {}\n",
+        HEADER,
+        STANDARD.encode(salt),
+        STANDARD.encode(nonce),
+        STANDARD.encode(ciphertext)
+    ))
+}
+
+fn decrypt(content: &str, passphrase: &str) -> Result<Vec<u8>, io::Error> {
+    let fields: Vec<Vec<u8>> = content
+        .trim_end()
+        .trim_start_matches(HEADER)
+        .split_whitespace()
+        .map(|field| STANDARD.decode(field))
+        .collect::<Result<_, _>>()
+        .map_err(|_| invalid_data("Encrypted file is corrupted"))?;
+    let [salt, nonce, ciphertext] = &fields[..] else {
+        return Err(invalid_data("Encrypted file is corrupted"));
+    };
+    if nonce.len() != NONCE_LENGTH {
+        return Err(invalid_data("Encrypted file is corrupted"));
+    }
+    cipher(passphrase, salt)?
+        .decrypt(XNonce::from_slice(nonce), ciphertext.as_slice())
+        .map_err(|_| invalid_data("Unable to decrypt: wrong passphrase or corrupted file"))
+}
+
+fn cipher(passphrase: &str, salt: &[u8]) -> Result<XChaCha20Poly1305, io::Error> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| invalid_data(&format!("Unable to derive a key: {}", e)))?;
+    Ok(XChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::super::mocks::MemoryFile;
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let handle = EncryptedFileHandle::new(MemoryFile::default(), "hunter2");
+        handle.write("cards: {}".to_string()).unwrap();
+        let stored = String::from_utf8(handle.inner.content.borrow().clone()).unwrap();
+        assert!(is_encrypted(&stored));
+        assert!(!stored.contains("cards"));
+        assert_eq!("cards: {}", handle.read().unwrap());
+    }
+
+    #[test]
+    fn append_keeps_what_was_written_encrypted() {
+        let handle = EncryptedFileHandle::new(MemoryFile::default(), "hunter2");
+        handle.append("a\n".to_string()).unwrap();
+        handle.append("b\n".to_string()).unwrap();
+        let stored = String::from_utf8(handle.inner.content.borrow().clone()).unwrap();
+        assert!(is_encrypted(&stored));
+        assert_eq!("a\nb\n", handle.read().unwrap());
+    }
+
+    #[test]
+    fn read_fails_with_the_wrong_passphrase() {
+        let handle = EncryptedFileHandle::new(MemoryFile::default(), "hunter2");
+        handle.write("cards: {}".to_string()).unwrap();
+        let handle = EncryptedFileHandle::new(handle.inner, "hunter3");
+        let actual = handle.read().unwrap_err();
+        assert!(actual.to_string().contains("wrong passphrase"));
+    }
+
+    #[test]
+    fn read_passes_plaintext_through() {
+        let file = MemoryFile::default();
+        file.write("cards: {}".to_string()).unwrap();
+        let handle = EncryptedFileHandle::new(file, "hunter2");
+        assert_eq!("cards: {}", handle.read().unwrap());
+    }
+
+    #[test]
+    fn read_fails_for_corrupted_files() {
+        let file = MemoryFile::default();
+        file.write(format!("{} AAAA", HEADER)).unwrap();
+        let handle = EncryptedFileHandle::new(file, "hunter2");
+        assert!(handle.read().is_err());
+    }
+}
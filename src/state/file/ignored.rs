@@ -0,0 +1,119 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use snafu::{prelude::*, Whatever};
+use std::path::Path;
+
+pub const IGNORE_FILE_NAME: &str = ".vultanignore";
+const GITIGNORE_FILE_NAME: &str = ".gitignore";
+
+// Gitignore-style patterns, read from the notes directory's .vultanignore and, optionally, its
+// .gitignore, of notes to leave out of discovery.
+#[derive(Debug)]
+pub struct IgnoreRules {
+    matcher: Gitignore,
+}
+
+impl Default for IgnoreRules {
+    fn default() -> Self {
+        Self {
+            matcher: Gitignore::empty(),
+        }
+    }
+}
+
+impl IgnoreRules {
+    pub fn read(notes_dir: &str, use_gitignore: bool) -> Result<Self, Whatever> {
+        let root = relative_to_current_dir(Path::new(notes_dir));
+        let mut builder = GitignoreBuilder::new(root);
+        let file_names = match use_gitignore {
+            true => vec![IGNORE_FILE_NAME, GITIGNORE_FILE_NAME],
+            false => vec![IGNORE_FILE_NAME],
+        };
+        for file_name in file_names {
+            let path = root.join(file_name);
+            if let Some(error) = path.is_file().then(|| builder.add(&path)).flatten() {
+                return Err(error)
+                    .with_whatever_context(|_| format!("Unable to read {}", path.display()));
+            }
+        }
+        let matcher = builder
+            .build()
+            .with_whatever_context(|_| format!("Unable to read ignore files in {}", notes_dir))?;
+        Ok(Self { matcher })
+    }
+
+    // Paths outside the notes directory are never ignored.
+    pub fn is_ignored(&self, path: &str) -> bool {
+        let path = relative_to_current_dir(Path::new(path));
+        path.starts_with(self.matcher.path())
+            && self
+                .matcher
+                .matched_path_or_any_parents(path, path.is_dir())
+                .is_ignore()
+    }
+}
+
+// Spelled without a leading "./", as glob spells the paths it finds in ".".
+fn relative_to_current_dir(path: &Path) -> &Path {
+    path.strip_prefix(".").unwrap_or(path)
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use rstest::*;
+    use std::fs;
+
+    fn make_notes_dir(name: &str, vultanignore: &str, gitignore: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(IGNORE_FILE_NAME), vultanignore).unwrap();
+        fs::write(dir.join(GITIGNORE_FILE_NAME), gitignore).unwrap();
+        dir.to_string_lossy().to_string()
+    }
+
+    #[rstest]
+    #[case::unmatched("bio/cells.md", false, false)]
+    #[case::matched_file("templates.md", false, true)]
+    #[case::matched_directory("archive/2019/cells.md", false, true)]
+    #[case::negated("archive/keep.md", false, false)]
+    #[case::gitignored_but_unused("node_modules/a/readme.md", false, false)]
+    #[case::gitignored("node_modules/a/readme.md", true, true)]
+    fn is_ignored(#[case] path: &str, #[case] use_gitignore: bool, #[case] expected: bool) {
+        let notes_dir = make_notes_dir(
+            &format!(
+                "vultan_ignored_{}_{}",
+                path.replace('/', "_"),
+                use_gitignore
+            ),
+            "templates.md\narchive/*\n!archive/keep.md\n",
+            "node_modules/\n",
+        );
+        let rules = IgnoreRules::read(&notes_dir, use_gitignore).unwrap();
+        let actual = rules.is_ignored(&format!("{}/{}", notes_dir, path));
+        fs::remove_dir_all(&notes_dir).ok();
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn is_ignored_in_the_current_dir() {
+        let rules = IgnoreRules {
+            matcher: GitignoreBuilder::new(relative_to_current_dir(Path::new(".")))
+                .add_line(None, "mito.md")
+                .unwrap()
+                .build()
+                .unwrap(),
+        };
+        assert!(rules.is_ignored("bio/mito.md"));
+        assert!(rules.is_ignored("./bio/mito.md"));
+        assert!(!rules.is_ignored("bio/dna.md"));
+    }
+
+    #[test]
+    fn without_ignore_files() {
+        let rules = IgnoreRules::read("/nonexistent/vultan/notes", true).unwrap();
+        assert!(!rules.is_ignored("/nonexistent/vultan/notes/a.md"));
+        assert!(!IgnoreRules::default().is_ignored("a.md"));
+    }
+}
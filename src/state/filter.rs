@@ -0,0 +1,71 @@
+use super::card::Card;
+use std::str::FromStr;
+
+// Picks cards for bulk operations, as "deck:<name>", matching the deck's sub-decks too, or
+// "tag:<name>".
+#[derive(Clone, Debug, PartialEq)]
+pub enum CardFilter {
+    Deck(String),
+    Tag(String),
+}
+
+impl CardFilter {
+    pub fn matches(&self, card: &Card) -> bool {
+        match self {
+            CardFilter::Deck(name) => card.in_deck(name),
+            CardFilter::Tag(name) => card.has_tag(name),
+        }
+    }
+
+    // Cards matching every filter.
+    pub fn all_match(filters: &[CardFilter], card: &Card) -> bool {
+        filters.iter().all(|filter| filter.matches(card))
+    }
+}
+
+impl FromStr for CardFilter {
+    type Err = String;
+
+    fn from_str(filter: &str) -> Result<Self, Self::Err> {
+        match filter.split_once(':') {
+            Some(("deck", name)) if !name.is_empty() => Ok(CardFilter::Deck(name.to_string())),
+            Some(("tag", name)) if !name.is_empty() => Ok(CardFilter::Tag(name.to_string())),
+            _ => Err(format!(
+                "Unknown filter \"{}\": expected deck:<name> or tag:<name>",
+                filter
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case::deck("deck:lang::spanish", Ok(CardFilter::Deck("lang::spanish".to_string())))]
+    #[case::tag("tag:exam", Ok(CardFilter::Tag("exam".to_string())))]
+    #[case::unknown("author:me", Err(()))]
+    #[case::empty("deck:", Err(()))]
+    fn from_str(#[case] filter: &str, #[case] expected: Result<CardFilter, ()>) {
+        assert_eq!(expected, filter.parse::<CardFilter>().map_err(|_| ()));
+    }
+
+    #[test]
+    fn all_match() {
+        let card = Card {
+            decks: vec!["lang::spanish".to_string()],
+            tags: vec!["exam".to_string()],
+            ..Card::default()
+        };
+        let filters = [CardFilter::Deck("lang".to_string())];
+        assert!(CardFilter::all_match(&filters, &card));
+        let filters = [
+            CardFilter::Deck("lang".to_string()),
+            CardFilter::Tag("verbs".to_string()),
+        ];
+        assert!(!CardFilter::all_match(&filters, &card));
+    }
+}
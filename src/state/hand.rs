@@ -1,51 +1,324 @@
+pub mod checkpoint;
+pub mod deal_options;
+pub mod interleaving;
+pub mod ordering;
+mod prerequisites;
+pub mod session;
 mod shuffle;
 
-use super::card::{Card, Score};
+use super::card::{Card, Grading, Priority, Scheduler, Score, ScoreSuggestions, TypedAnswers};
 use super::deck::{Deck, IntervalCoefficients};
-use std::collections::VecDeque;
+pub use checkpoint::Checkpoint;
+use chrono::{DateTime, Duration, Utc};
+pub use deal_options::DealOptions;
+pub use interleaving::Interleaving;
+pub use ordering::{Ordering, QueueOrder};
+use prerequisites::Prerequisites;
+pub use session::{Progress, Session};
+use snafu::prelude::*;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::rc::Rc;
+
+#[derive(Debug, PartialEq, Snafu)]
+pub enum DealError {
+    #[snafu(display("No deck named '{}' exists.", deck_name))]
+    NoSuchDeck { deck_name: String },
+    #[snafu(display("Deck({}) contains no due cards", deck_name))]
+    NoDueCards {
+        deck_name: String,
+        next_due: Option<DateTime<Utc>>,
+    },
+    #[snafu(display("Deck({}) has an unusable scheduling script: {}", deck_name, message))]
+    InvalidSchedulingScript { deck_name: String, message: String },
+}
 
 #[derive(Debug)]
 pub struct Hand<'h> {
     queue: VecDeque<Card>,
-    interval_coefficients: &'h IntervalCoefficients,
+    interval_coefficients: Cow<'h, IntervalCoefficients>,
+    relearn_delay: Duration,
+    score_suggestions: ScoreSuggestions,
+    typed_answers: Option<TypedAnswers>,
+    grading: Grading,
+    scheduler: Option<Rc<dyn Scheduler>>,
 }
 
 impl<'h> Hand<'h> {
-    pub fn from(deck: &'h Deck, cards: Vec<&'h Card>) -> Result<Hand<'h>, String> {
-        let hand_cards = shuffle::shuffle_cards(Hand::filter_due_cards_in_deck(deck, cards));
+    pub fn from(deck: &'h Deck, cards: Vec<&'h Card>) -> Result<Hand<'h>, DealError> {
+        Self::from_with_options(deck, cards, &DealOptions::default())
+    }
+
+    pub fn from_with_options(
+        deck: &'h Deck,
+        cards: Vec<&'h Card>,
+        options: &DealOptions,
+    ) -> Result<Hand<'h>, DealError> {
+        let prerequisites = Prerequisites::new(&cards);
+        let prerequisite_interval = options
+            .prerequisite_interval
+            .unwrap_or(prerequisites::DEFAULT_INTERVAL);
+        let cards: Vec<&Card> = cards
+            .iter()
+            .copied()
+            .filter(|c| !c.suspended && options.allows(c))
+            .filter(|c| prerequisites.met(c, prerequisite_interval))
+            .collect();
+        let next_due = Hand::find_next_due_date(deck, &cards);
+        let ordering = options.order.unwrap_or_default().ordering(options.seed);
+        let due_by = Utc::now() + options.review_ahead.unwrap_or_else(Duration::zero);
+        let due_cards = Hand::filter_due_cards_in_deck(deck, cards, due_by);
+        let due_cards = match options.max_cards {
+            Some(max_cards) => Hand::oldest_due(due_cards, max_cards),
+            None => due_cards,
+        };
+        let hand_cards = Hand::order_by_priority(ordering.as_ref(), due_cards);
+        let hand_cards = prerequisites::order(hand_cards);
+        let hand_cards = match options.bury_siblings.unwrap_or_default() {
+            true => Hand::bury_siblings(hand_cards),
+            false => hand_cards,
+        };
         match hand_cards.len() {
-            0 => Err(format!("Deck({}) contains no cards", deck.name)),
-            _ => Ok(Self {
-                queue: hand_cards.into_iter().collect(),
-                interval_coefficients: &deck.interval_coefficients,
+            0 => Err(DealError::NoDueCards {
+                deck_name: deck.name.to_string(),
+                next_due,
             }),
+            _ => Ok(Hand::from_queue(deck, hand_cards)),
+        }
+    }
+
+    pub fn cram(deck: &'h Deck, cards: Vec<&'h Card>) -> Result<Hand<'h>, DealError> {
+        let hand_cards: Vec<Card> = cards
+            .into_iter()
+            .filter(|c| !c.suspended && c.in_deck(&deck.name))
+            .cloned()
+            .collect();
+        match hand_cards.len() {
+            0 => Err(DealError::NoDueCards {
+                deck_name: deck.name.to_string(),
+                next_due: None,
+            }),
+            _ => Ok(Hand::from_queue(
+                deck,
+                shuffle::shuffle_cards(hand_cards, None),
+            )),
+        }
+    }
+
+    // Deals the queued cards of a checkpoint in its order, leaving out those since suspended or
+    // taken out of the deck.
+    pub fn resume(
+        deck: &'h Deck,
+        cards: Vec<&'h Card>,
+        checkpoint: &Checkpoint,
+    ) -> Result<Hand<'h>, DealError> {
+        let by_path: HashMap<&str, &Card> = cards
+            .into_iter()
+            .filter(|c| !c.suspended && c.in_deck(&deck.name))
+            .map(|c| (c.path.as_str(), c))
+            .collect();
+        let hand_cards: Vec<Card> = checkpoint
+            .queue
+            .iter()
+            .filter_map(|path| by_path.get(path.as_str()).map(|c| (*c).clone()))
+            .collect();
+        match hand_cards.len() {
+            0 => Err(DealError::NoDueCards {
+                deck_name: deck.name.to_string(),
+                next_due: None,
+            }),
+            _ => Ok(Hand::from_queue(deck, hand_cards)),
+        }
+    }
+
+    pub fn cards(&self) -> impl Iterator<Item = &Card> {
+        self.queue.iter()
+    }
+
+    pub fn with_interval_coefficients(self, interval_coefficients: IntervalCoefficients) -> Self {
+        Self {
+            interval_coefficients: Cow::Owned(interval_coefficients),
+            ..self
+        }
+    }
+
+    // Failed cards wait at least this long before they're shown again in the same session.
+    pub fn with_relearn_delay(self, relearn_delay: Duration) -> Self {
+        Self {
+            relearn_delay,
+            ..self
+        }
+    }
+
+    pub fn with_score_suggestions(self, score_suggestions: ScoreSuggestions) -> Self {
+        Self {
+            score_suggestions,
+            ..self
+        }
+    }
+
+    pub fn with_typed_answers(self, typed_answers: Option<TypedAnswers>) -> Self {
+        Self {
+            typed_answers,
+            ..self
+        }
+    }
+
+    pub fn with_grading(self, grading: Grading) -> Self {
+        Self { grading, ..self }
+    }
+
+    // Reviews are scheduled by the scheduler rather than the built-in scheduling.
+    pub fn with_scheduler(self, scheduler: Rc<dyn Scheduler>) -> Self {
+        Self {
+            scheduler: Some(scheduler),
+            ..self
         }
     }
 
+    // New cards are those never passed (interval 0); the rest count as reviews.
+    pub fn with_limits(self, max_new_cards: Option<usize>, max_reviews: Option<usize>) -> Self {
+        let (mut new_cards, mut reviews) = (0, 0);
+        let queue = self
+            .queue
+            .into_iter()
+            .filter(|card| {
+                let (count, limit) = match card.is_new() {
+                    true => (&mut new_cards, max_new_cards),
+                    false => (&mut reviews, max_reviews),
+                };
+                *count += 1;
+                limit.is_none_or(|limit| *count <= limit)
+            })
+            .collect();
+        Self { queue, ..self }
+    }
+
+    // Fills in the questions and answers that metadata-only state files leave out, dropping the
+    // cards whose content can't be loaded.
+    pub fn with_card_content(self, load: impl Fn(&Card) -> Option<(String, String)>) -> Self {
+        let queue = self
+            .queue
+            .into_iter()
+            .filter_map(|mut card| {
+                if card.question.is_empty() && card.answer.is_empty() {
+                    (card.question, card.answer) = load(&card)?;
+                }
+                Some(card)
+            })
+            .collect();
+        Self { queue, ..self }
+    }
+
+    // Applied within each priority, so that it never moves a card ahead of a more important one.
+    pub fn with_interleaving(self, interleaving: Option<Interleaving>) -> Self {
+        let Some(interleaving) = interleaving else {
+            return self;
+        };
+        let cards: Vec<Card> = self.queue.iter().cloned().collect();
+        let queue = cards
+            .chunk_by(|a, b| a.priority == b.priority)
+            .flat_map(|tier| interleaving.interleave(tier.to_vec()))
+            .collect();
+        Self { queue, ..self }
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
     pub fn revise_until_none_fail<ReadScoreCallback>(
-        mut self,
+        self,
+        read_score: ReadScoreCallback,
+    ) -> Vec<Card>
+    where
+        ReadScoreCallback: FnMut(&Card) -> Score,
+    {
+        self.revise_until_none_fail_with_hook(read_score, |_, _| {})
+    }
+
+    pub fn revise_until_none_fail_with_hook<ReadScoreCallback, RevisedCardCallback>(
+        self,
         mut read_score: ReadScoreCallback,
+        mut on_revised: RevisedCardCallback,
     ) -> Vec<Card>
     where
         ReadScoreCallback: FnMut(&Card) -> Score,
+        RevisedCardCallback: FnMut(&Card, Score),
     {
-        use Score::*;
-        let mut output = Vec::new();
-        while self.queue.len() > 0 {
-            let card = self.queue.pop_front().unwrap();
-            let transform = |card: Card, score| card.transform(score, self.interval_coefficients);
-            match read_score(&card) {
-                Fail => self.queue.push_back(transform(card, Fail)),
-                any_other_score => output.push(transform(card, any_other_score)),
+        let mut session = Session::from(self);
+        while let Some(card) = session.next_card() {
+            let score = read_score(card);
+            if let Some(revised_card) = session.submit_score(score) {
+                on_revised(revised_card, score);
             }
         }
-        output
+        session.finish()
+    }
+
+    fn from_queue(deck: &'h Deck, cards: Vec<Card>) -> Hand<'h> {
+        Self {
+            queue: cards.into_iter().collect(),
+            interval_coefficients: Cow::Borrowed(&deck.interval_coefficients),
+            relearn_delay: Duration::zero(),
+            score_suggestions: ScoreSuggestions::default(),
+            typed_answers: None,
+            grading: Grading::default(),
+            scheduler: None,
+        }
+    }
+
+    fn find_next_due_date(deck: &Deck, cards: &[&Card]) -> Option<DateTime<Utc>> {
+        cards
+            .iter()
+            .filter(|c| c.in_deck(&deck.name))
+            .map(|c| c.revision_settings.due)
+            .min()
+    }
+
+    // Every high priority card comes before any normal one, and so on; the ordering only
+    // applies within a priority.
+    fn order_by_priority(ordering: &dyn Ordering, cards: Vec<Card>) -> Vec<Card> {
+        let mut by_priority: BTreeMap<Priority, Vec<Card>> = BTreeMap::new();
+        for card in cards.into_iter() {
+            by_priority.entry(card.priority).or_default().push(card);
+        }
+        by_priority
+            .into_values()
+            .flat_map(|cards| ordering.order(cards))
+            .collect()
+    }
+
+    // Siblings are cards made from the same note. Those after the first in the queue are left
+    // out, to be dealt in a later hand once it has been reviewed.
+    fn bury_siblings(cards: Vec<Card>) -> Vec<Card> {
+        let mut note_paths = HashSet::new();
+        cards
+            .into_iter()
+            .filter(|card| note_paths.insert(card.note_path().to_string()))
+            .collect()
+    }
+
+    fn oldest_due(mut cards: Vec<Card>, max_cards: usize) -> Vec<Card> {
+        cards.sort_by(|a, b| {
+            (a.revision_settings.due, &a.path).cmp(&(b.revision_settings.due, &b.path))
+        });
+        cards.truncate(max_cards);
+        cards
     }
 
-    fn filter_due_cards_in_deck(deck: &'h Deck, cards: Vec<&'h Card>) -> Vec<Card> {
+    fn filter_due_cards_in_deck(
+        deck: &'h Deck,
+        cards: Vec<&'h Card>,
+        due_by: DateTime<Utc>,
+    ) -> Vec<Card> {
         cards
             .into_iter()
-            .filter(|c| c.is_due() && c.in_deck(&deck.name))
+            .filter(|c| c.is_due_at(due_by) && c.in_deck(&deck.name))
             .map(|c| c.clone())
             .collect()
     }
@@ -70,7 +343,7 @@ pub mod assertions {
         expected_coefficients: &IntervalCoefficients,
         expected_queued_items: &[Expect<Card>],
     ) {
-        assert_eq!(hand.interval_coefficients, expected_coefficients);
+        assert_eq!(hand.interval_coefficients.as_ref(), expected_coefficients);
         assert_length_matches(&hand.queue, &expected_queued_items);
         for comparator in expected_queued_items.iter() {
             match comparator {
@@ -148,6 +421,10 @@ mod unit_tests {
         concat_cards(fake_cards(vec!["octopus", "squid", "cuttlefish", "nautilus"]), vec![make_card("clam", "bivalvia")]),
         Ok(vec!["squid", "cuttlefish", "nautilus", "octopus"])
     )]
+    #[case::creates_shuffled_card_queue_without_suspended_cards(
+        concat_cards(fake_cards(vec!["squid", "cuttlefish", "nautilus"]), vec![make_card("octopus", FAKE_DECK_ID).with_suspended(true)]),
+        Ok(vec!["cuttlefish", "nautilus", "squid"])
+    )]
     #[case::returns_error_if_no_cards_exist_for_deck(vec![make_card("clam", "bivalvia")], Err(FAKE_DECK_ID))]
     fn from(#[case] cards: Vec<Card>, #[case] expected: Result<Vec<&str>, &str>) {
         let card_paths: Vec<&str> = cards.iter().map(|c| c.path.as_str()).collect();
@@ -160,17 +437,252 @@ mod unit_tests {
                 assertions::assert_hands_near(&expected, &actual);
             }
             Err(err) => {
-                assert!(err.contains(FAKE_DECK_ID));
+                assert!(err.to_string().contains(FAKE_DECK_ID));
             }
         }
     }
 
+    #[test]
+    fn from_puts_higher_priority_cards_first() {
+        let cards = [
+            make_card("octopus", FAKE_DECK_ID).with_priority(Priority::Low),
+            make_card("squid", FAKE_DECK_ID),
+            make_card("cuttlefish", FAKE_DECK_ID).with_priority(Priority::High),
+            make_card("nautilus", FAKE_DECK_ID),
+        ];
+        let deck = make_deck(
+            FAKE_DECK_ID,
+            &["octopus", "squid", "cuttlefish", "nautilus"],
+        );
+        // Equally easy cards are in path order.
+        let options = DealOptions::default().with_order(Some(QueueOrder::EaseAscending));
+        let hand = Hand::from_with_options(&deck, cards.iter().collect(), &options).unwrap();
+        let actual: Vec<&str> = hand.cards().map(|c| c.path.as_str()).collect();
+        assert_eq!(vec!["cuttlefish", "nautilus", "squid", "octopus"], actual);
+    }
+
+    #[test]
+    fn from_holds_new_cards_back_until_their_prerequisites_are_learnt() {
+        let learnt = RevisionSettings {
+            interval: 3.0,
+            ..RevisionSettings::default()
+        };
+        let requires = |path: &str| vec![path.to_string()];
+        let cards = [
+            make_card_with_revision_settings("cuttlefish", FAKE_DECK_ID, &learnt)
+                .with_requires(requires("squid")),
+            make_card("octopus", FAKE_DECK_ID).with_requires(requires("squid")),
+            make_card_with_revision_settings("squid", FAKE_DECK_ID, &learnt),
+            make_card("nautilus", FAKE_DECK_ID).with_requires(requires("octopus")),
+        ];
+        let deck = make_deck(
+            FAKE_DECK_ID,
+            &["cuttlefish", "octopus", "squid", "nautilus"],
+        );
+        let paths = |options: &DealOptions| -> Vec<String> {
+            let hand = Hand::from_with_options(&deck, cards.iter().collect(), options).unwrap();
+            hand.cards().map(|c| c.path.to_string()).collect()
+        };
+        let options = DealOptions::default().with_order(Some(QueueOrder::EaseAscending));
+        assert_eq!(vec!["squid", "cuttlefish", "octopus"], paths(&options));
+        let options = options.with_prerequisite_interval(Some(5.0));
+        assert_eq!(vec!["squid", "cuttlefish"], paths(&options));
+    }
+
+    #[test]
+    fn from_buries_siblings_when_asked_to() {
+        let cards = [
+            make_card("bio/krebs.md#1", FAKE_DECK_ID),
+            make_card("bio/krebs.md#2", FAKE_DECK_ID),
+            make_card("bio/atp.md", FAKE_DECK_ID),
+            make_card("bio/krebs.md#3", FAKE_DECK_ID),
+        ];
+        let deck = make_deck(
+            FAKE_DECK_ID,
+            &[
+                "bio/krebs.md#1",
+                "bio/krebs.md#2",
+                "bio/atp.md",
+                "bio/krebs.md#3",
+            ],
+        );
+        let paths = |options: &DealOptions| -> Vec<String> {
+            let hand = Hand::from_with_options(&deck, cards.iter().collect(), options).unwrap();
+            hand.cards().map(|c| c.path.to_string()).collect()
+        };
+        let options = DealOptions::default().with_order(Some(QueueOrder::EaseAscending));
+        assert_eq!(4, paths(&options).len());
+        let options = options.with_bury_siblings(Some(true));
+        assert_eq!(vec!["bio/atp.md", "bio/krebs.md#1"], paths(&options));
+    }
+
+    #[test]
+    fn with_interleaving_keeps_priorities_apart() {
+        let review = RevisionSettings::new(Utc::now(), 3.0, 2500.0);
+        let cards = vec![
+            make_card("octopus", FAKE_DECK_ID).with_priority(Priority::High),
+            make_card_with_revision_settings("squid", FAKE_DECK_ID, &review)
+                .with_priority(Priority::High),
+            make_card("cuttlefish", FAKE_DECK_ID),
+            make_card_with_revision_settings("nautilus", FAKE_DECK_ID, &review),
+        ];
+        let deck = make_deck(FAKE_DECK_ID, &[]);
+        let hand =
+            Hand::from_queue(&deck, cards).with_interleaving(Some(Interleaving::ReviewFirst));
+        let actual: Vec<&str> = hand.cards().map(|c| c.path.as_str()).collect();
+        assert_eq!(vec!["squid", "octopus", "nautilus", "cuttlefish"], actual);
+    }
+
+    #[test]
+    fn from_when_no_cards_are_due_reports_next_due_date() {
+        let soon = Utc::now() + Duration::days(2);
+        let mut soonest_card = make_card("squid", FAKE_DECK_ID);
+        soonest_card.revision_settings.due = soon;
+        let cards = vec![fake_future_card("octopus"), soonest_card];
+        let deck = make_deck(FAKE_DECK_ID, &["octopus", "squid"]);
+        let expected = DealError::NoDueCards {
+            deck_name: FAKE_DECK_ID.to_string(),
+            next_due: Some(soon),
+        };
+        let actual = Hand::from(&deck, cards.iter().collect()).unwrap_err();
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn cards() {
+        let cards = fake_cards(vec!["squid", "octopus"]);
+        let deck = make_deck(FAKE_DECK_ID, &["squid", "octopus"]);
+        let hand = Hand::from(&deck, cards.iter().collect()).unwrap();
+        let expected: Vec<&Card> = hand.queue.iter().collect();
+        assert_eq!(expected, hand.cards().collect::<Vec<&Card>>());
+    }
+
+    #[test]
+    fn cram_includes_cards_that_are_not_due() {
+        let cards = concat_cards(
+            fake_cards(vec!["squid", "cuttlefish", "nautilus"]),
+            vec![fake_future_card("octopus"), make_card("clam", "bivalvia")],
+        );
+        let deck = make_deck(
+            FAKE_DECK_ID,
+            &["squid", "cuttlefish", "nautilus", "octopus"],
+        );
+        let hand = Hand::cram(&deck, cards.iter().collect()).unwrap();
+        let mut actual: Vec<&str> = hand.queue.iter().map(|c| c.path.as_str()).collect();
+        actual.sort();
+        assert_eq!(vec!["cuttlefish", "nautilus", "octopus", "squid"], actual);
+    }
+
+    #[test]
+    fn cram_when_deck_has_no_cards() {
+        let cards = vec![make_card("clam", "bivalvia")];
+        let deck = make_deck(FAKE_DECK_ID, &[]);
+        let expected = DealError::NoDueCards {
+            deck_name: FAKE_DECK_ID.to_string(),
+            next_due: None,
+        };
+        assert_eq!(
+            expected,
+            Hand::cram(&deck, cards.iter().collect()).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn with_limits() {
+        let make_card = |path: &str, interval: f64| {
+            let mut card = Card::default();
+            card.path = path.into();
+            card.revision_settings.interval = interval;
+            card
+        };
+        let interval_coefficients = IntervalCoefficients::default();
+        let hand = Hand {
+            queue: VecDeque::from(vec![
+                make_card("new1", 0.0),
+                make_card("old1", 2.0),
+                make_card("new2", 0.0),
+                make_card("old2", 3.0),
+                make_card("old3", 1.0),
+            ]),
+            interval_coefficients: Cow::Borrowed(&interval_coefficients),
+            relearn_delay: Duration::zero(),
+            score_suggestions: ScoreSuggestions::default(),
+            typed_answers: None,
+            grading: Grading::default(),
+            scheduler: None,
+        };
+        let actual: Vec<String> = hand
+            .with_limits(Some(1), Some(2))
+            .cards()
+            .map(|c| c.path.to_string())
+            .collect();
+        assert_eq!(vec!["new1", "old1", "old2"], actual);
+    }
+
+    #[test]
+    fn with_card_content() {
+        let make_card = |path: &str, question: &str| Card {
+            path: path.into(),
+            question: question.to_string(),
+            ..Card::default()
+        };
+        let interval_coefficients = IntervalCoefficients::default();
+        let hand = Hand {
+            queue: VecDeque::from(vec![
+                make_card("stored", "kept?"),
+                make_card("loaded", ""),
+                make_card("deleted", ""),
+            ]),
+            interval_coefficients: Cow::Borrowed(&interval_coefficients),
+            relearn_delay: Duration::zero(),
+            score_suggestions: ScoreSuggestions::default(),
+            typed_answers: None,
+            grading: Grading::default(),
+            scheduler: None,
+        };
+        let actual: Vec<(String, String)> = hand
+            .with_card_content(|card| match card.path.as_str() {
+                "deleted" => None,
+                path => Some((format!("{}?", path), format!("{}!", path))),
+            })
+            .cards()
+            .map(|c| (c.question.clone(), c.answer.clone()))
+            .collect();
+        let expected = [
+            ("kept?".to_string(), String::new()),
+            ("loaded?".to_string(), "loaded!".to_string()),
+        ];
+        assert_eq!(expected, actual[..]);
+    }
+
+    #[test]
+    fn with_interval_coefficients() {
+        let interval_coefficients = IntervalCoefficients::default();
+        let hand = Hand {
+            queue: VecDeque::new(),
+            interval_coefficients: Cow::Borrowed(&interval_coefficients),
+            relearn_delay: Duration::zero(),
+            score_suggestions: ScoreSuggestions::default(),
+            typed_answers: None,
+            grading: Grading::default(),
+            scheduler: None,
+        };
+        let expected = IntervalCoefficients::new(2.0, 3.0, 0.5);
+        let actual = hand.with_interval_coefficients(expected.clone());
+        assert_eq!(&expected, actual.interval_coefficients.as_ref());
+    }
+
     #[test]
     fn revise_until_none_fail_with_empty_queue() {
         let interval_coefficients = IntervalCoefficients::default();
         let hand = Hand {
             queue: VecDeque::new(),
-            interval_coefficients: &&interval_coefficients,
+            interval_coefficients: Cow::Borrowed(&interval_coefficients),
+            relearn_delay: Duration::zero(),
+            score_suggestions: ScoreSuggestions::default(),
+            typed_answers: None,
+            grading: Grading::default(),
+            scheduler: None,
         };
         let expected: Vec<Card> = Vec::new();
         let actual = hand.revise_until_none_fail(|card| Score::Easy);
@@ -244,4 +756,42 @@ mod unit_tests {
         assert_eq!(total_number_of_cycles, 5);
         assertions::assert_hands_near(&expected, &actual);
     }
+
+    #[test]
+    fn revise_until_none_fail_with_hook_notifies_every_transformed_card() {
+        let deck_id = "some_deck";
+        let in_date = Utc::now() - Duration::days(4);
+        let in_rs = RevisionSettings::new(in_date, 1.0, 2000.0);
+        let cards = vec![
+            make_card_with_revision_settings("fail", deck_id, &in_rs),
+            make_card_with_revision_settings("pass", deck_id, &in_rs),
+        ];
+        let interval_coefficients = IntervalCoefficients::new(1.0, 2.0, 0.0);
+        let deck = Deck::new(deck_id, vec!["fail", "pass"], interval_coefficients);
+        let hand = Hand::from(&deck, cards.iter().collect()).unwrap();
+        let mut failed_once = false;
+        let mut notified: Vec<(Card, Score)> = Vec::new();
+
+        let actual = hand.revise_until_none_fail_with_hook(
+            |card| match (&card.path[..], failed_once) {
+                ("fail", false) => {
+                    failed_once = true;
+                    Score::Fail
+                }
+                _ => Score::Pass,
+            },
+            |card, score| notified.push((card.clone(), score)),
+        );
+
+        let notified_paths: Vec<(&str, Score)> =
+            notified.iter().map(|(c, s)| (&c.path[..], *s)).collect();
+        let expected_paths = vec![
+            ("pass", Score::Pass),
+            ("fail", Score::Fail),
+            ("fail", Score::Pass),
+        ];
+        assert_eq!(expected_paths, notified_paths);
+        let notified_on_completion = vec![notified[0].0.clone(), notified[2].0.clone()];
+        assertions::assert_hands_near(&actual, &notified_on_completion);
+    }
 }
@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+// Where a session stood, so that an interrupted one can be picked up in the same order. Scores are
+// saved as they're given, so only the cards' paths are kept: those still queued, in order, and
+// those already revised, to count them.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Checkpoint {
+    pub deck_name: String,
+    // The session's shuffle seed, kept for its reviews' log entries.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    pub queue: Vec<String>,
+    #[serde(default)]
+    pub revised: Vec<String>,
+    #[serde(default)]
+    pub skipped: usize,
+}
+
+impl Checkpoint {
+    pub fn with_seed(self, seed: Option<u64>) -> Self {
+        Self { seed, ..self }
+    }
+}
@@ -0,0 +1,109 @@
+use super::QueueOrder;
+use crate::state::card::Card;
+use chrono::Duration;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DealOptions {
+    pub include_tags: Vec<String>,
+    pub exclude_tags: Vec<String>,
+    pub order: Option<QueueOrder>,
+    pub seed: Option<u64>,
+    pub max_cards: Option<usize>,
+    pub review_ahead: Option<Duration>,
+    pub prerequisite_interval: Option<f64>,
+    pub bury_siblings: Option<bool>,
+}
+
+impl DealOptions {
+    pub fn with_include_tags(self, include_tags: Vec<String>) -> Self {
+        Self {
+            include_tags,
+            ..self
+        }
+    }
+
+    pub fn with_exclude_tags(self, exclude_tags: Vec<String>) -> Self {
+        Self {
+            exclude_tags,
+            ..self
+        }
+    }
+
+    // Takes precedence over the deck's order.
+    pub fn with_order(self, order: Option<QueueOrder>) -> Self {
+        Self { order, ..self }
+    }
+
+    // Seeds the shuffle so that a session's order can be reproduced.
+    pub fn with_seed(self, seed: Option<u64>) -> Self {
+        Self { seed, ..self }
+    }
+
+    // Keeps the cards that have been due longest, on top of any limits set by the deck.
+    pub fn with_max_cards(self, max_cards: Option<usize>) -> Self {
+        Self { max_cards, ..self }
+    }
+
+    // Also deals the cards falling due within this long from now.
+    pub fn with_review_ahead(self, review_ahead: Option<Duration>) -> Self {
+        Self {
+            review_ahead,
+            ..self
+        }
+    }
+
+    // New cards wait until the cards they require reach this interval, in days; a day unless set.
+    pub fn with_prerequisite_interval(self, prerequisite_interval: Option<f64>) -> Self {
+        Self {
+            prerequisite_interval,
+            ..self
+        }
+    }
+
+    // Deals only the first card of each note in the queue, leaving its siblings for a later hand.
+    // Takes precedence over the deck's bury_siblings, either way.
+    pub fn with_bury_siblings(self, bury_siblings: Option<bool>) -> Self {
+        Self {
+            bury_siblings,
+            ..self
+        }
+    }
+
+    // With include tags, a card needs at least one of them; any exclude tag rules it out.
+    pub fn allows(&self, card: &Card) -> bool {
+        let included =
+            self.include_tags.is_empty() || self.include_tags.iter().any(|tag| card.has_tag(tag));
+        included && !self.exclude_tags.iter().any(|tag| card.has_tag(tag))
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use rstest::*;
+
+    fn to_strings(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[rstest]
+    #[case::no_filters(&[], &[], &["a"], true)]
+    #[case::included(&["a", "b"], &[], &["b"], true)]
+    #[case::not_included(&["a"], &[], &["b"], false)]
+    #[case::untagged_not_included(&["a"], &[], &[], false)]
+    #[case::excluded(&[], &["needs-rewrite"], &["a", "needs-rewrite"], false)]
+    #[case::exclude_wins(&["a"], &["b"], &["a", "b"], false)]
+    fn allows(
+        #[case] include_tags: &[&str],
+        #[case] exclude_tags: &[&str],
+        #[case] card_tags: &[&str],
+        #[case] expected: bool,
+    ) {
+        let options = DealOptions::default()
+            .with_include_tags(to_strings(include_tags))
+            .with_exclude_tags(to_strings(exclude_tags));
+        let card = Card::default().with_tags(to_strings(card_tags));
+        assert_eq!(expected, options.allows(&card));
+    }
+}
@@ -0,0 +1,85 @@
+use crate::state::card::Card;
+use serde::{Deserialize, Serialize};
+
+// How a session's new cards are spread among its reviews.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, PartialOrd, Serialize)]
+pub enum Interleaving {
+    NewFirst,
+    ReviewFirst,
+    // Rounds of this many reviews followed by this many new cards, until one kind runs out.
+    Mixed { new: usize, reviews: usize },
+}
+
+impl Interleaving {
+    // Each kind keeps its order.
+    pub fn interleave(&self, cards: Vec<Card>) -> Vec<Card> {
+        let total = cards.len();
+        let (new, reviews): (Vec<Card>, Vec<Card>) = cards.into_iter().partition(Card::is_new);
+        match *self {
+            Interleaving::NewFirst => [new, reviews].concat(),
+            Interleaving::ReviewFirst => [reviews, new].concat(),
+            Interleaving::Mixed {
+                new: new_per_round,
+                reviews: reviews_per_round,
+            } => {
+                let (mut new, mut reviews) = (new.into_iter(), reviews.into_iter());
+                let mut output = Vec::with_capacity(total);
+                while output.len() < total {
+                    let before = output.len();
+                    output.extend(reviews.by_ref().take(reviews_per_round));
+                    output.extend(new.by_ref().take(new_per_round));
+                    if output.len() == before {
+                        output.extend(reviews.by_ref().chain(new.by_ref()));
+                    }
+                }
+                output
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use crate::state::card::RevisionSettings;
+    use chrono::Utc;
+    use rstest::*;
+
+    // Paths starting with "n" are new cards; the rest are reviews.
+    fn make_cards(paths: &[&str]) -> Vec<Card> {
+        paths
+            .iter()
+            .map(|path| {
+                let interval = match path.starts_with('n') {
+                    true => 0.0,
+                    false => 3.0,
+                };
+                let card = Card::default().with_revision_settings(RevisionSettings::new(
+                    Utc::now(),
+                    interval,
+                    2500.0,
+                ));
+                Card {
+                    path: (*path).into(),
+                    ..card
+                }
+            })
+            .collect()
+    }
+
+    #[rstest]
+    #[case::new_first(Interleaving::NewFirst, &["n1", "n2", "r1", "r2", "r3"])]
+    #[case::review_first(Interleaving::ReviewFirst, &["r1", "r2", "r3", "n1", "n2"])]
+    #[case::mixed(Interleaving::Mixed { new: 1, reviews: 2 }, &["r1", "r2", "n1", "r3", "n2"])]
+    #[case::mixed_with_nothing_per_round(
+        Interleaving::Mixed { new: 0, reviews: 0 },
+        &["r1", "r2", "r3", "n1", "n2"]
+    )]
+    fn interleave(#[case] interleaving: Interleaving, #[case] expected: &[&str]) {
+        let cards = make_cards(&["n1", "r1", "n2", "r2", "r3"]);
+        let actual = interleaving.interleave(cards);
+        let actual: Vec<&str> = actual.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(expected, actual);
+    }
+}
@@ -0,0 +1,206 @@
+use super::shuffle;
+use crate::state::card::Card;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+
+// Decides the order a hand's cards are reviewed in.
+pub trait Ordering {
+    fn order(&self, cards: Vec<Card>) -> Vec<Card>;
+}
+
+// Shuffled, reproducibly so when seeded.
+pub struct Random(pub Option<u64>);
+
+impl Ordering for Random {
+    fn order(&self, cards: Vec<Card>) -> Vec<Card> {
+        shuffle::shuffle_cards(cards, self.0)
+    }
+}
+
+// The most overdue cards first.
+pub struct OldestDueFirst;
+
+impl Ordering for OldestDueFirst {
+    fn order(&self, mut cards: Vec<Card>) -> Vec<Card> {
+        cards.sort_by(|a, b| {
+            (a.revision_settings.due, &a.path).cmp(&(b.revision_settings.due, &b.path))
+        });
+        cards
+    }
+}
+
+// The cards with the lowest memorisation factor, i.e. the hardest, first.
+pub struct EaseAscending;
+
+impl Ordering for EaseAscending {
+    fn order(&self, mut cards: Vec<Card>) -> Vec<Card> {
+        cards.sort_by(|a, b| {
+            a.revision_settings
+                .memorisation_factor
+                .total_cmp(&b.revision_settings.memorisation_factor)
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        cards
+    }
+}
+
+// Takes a card from each deck in turn, so sub-decks are mixed rather than reviewed in blocks.
+// Cards are grouped by their first deck and shuffled within it.
+pub struct DeckRoundRobin(pub Option<u64>);
+
+impl Ordering for DeckRoundRobin {
+    fn order(&self, cards: Vec<Card>) -> Vec<Card> {
+        let total = cards.len();
+        let mut by_deck: BTreeMap<String, VecDeque<Card>> = BTreeMap::new();
+        for card in shuffle::shuffle_cards(cards, self.0).into_iter() {
+            let deck = card.decks.first().cloned().unwrap_or_default();
+            by_deck.entry(deck).or_default().push_back(card);
+        }
+        let mut ordered = Vec::with_capacity(total);
+        while ordered.len() < total {
+            for queue in by_deck.values_mut() {
+                ordered.extend(queue.pop_front());
+            }
+        }
+        ordered
+    }
+}
+
+// The built-in orderings, as named in deck options and on the command line.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, PartialOrd, Serialize)]
+pub enum QueueOrder {
+    #[default]
+    Random,
+    OldestDueFirst,
+    EaseAscending,
+    DeckRoundRobin,
+}
+
+impl QueueOrder {
+    const NAMES: [(QueueOrder, &'static str); 4] = [
+        (QueueOrder::Random, "random"),
+        (QueueOrder::OldestDueFirst, "oldest-due-first"),
+        (QueueOrder::EaseAscending, "ease-ascending"),
+        (QueueOrder::DeckRoundRobin, "deck-round-robin"),
+    ];
+
+    // The seed only matters to the orderings that shuffle.
+    pub fn ordering(&self, seed: Option<u64>) -> Box<dyn Ordering> {
+        match self {
+            QueueOrder::Random => Box::new(Random(seed)),
+            QueueOrder::OldestDueFirst => Box::new(OldestDueFirst),
+            QueueOrder::EaseAscending => Box::new(EaseAscending),
+            QueueOrder::DeckRoundRobin => Box::new(DeckRoundRobin(seed)),
+        }
+    }
+}
+
+impl fmt::Display for QueueOrder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (_, name) = Self::NAMES
+            .iter()
+            .find(|(order, _)| order == self)
+            .expect("every order is named");
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for QueueOrder {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::NAMES
+            .iter()
+            .find(|(_, n)| *n == name)
+            .map(|(order, _)| *order)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::NAMES.iter().map(|(_, n)| *n).collect();
+                format!(
+                    "Unknown order \"{}\": expected one of {}",
+                    name,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use crate::state::card::RevisionSettings;
+    use chrono::{Duration, Utc};
+    use rstest::*;
+
+    fn make_card(path: &str, deck: &str, days_overdue: i64, memorisation_factor: f64) -> Card {
+        Card::new(
+            path.to_string(),
+            vec![deck.to_string()],
+            "".to_string(),
+            "".to_string(),
+            RevisionSettings::new(
+                Utc::now() - Duration::days(days_overdue),
+                1.0,
+                memorisation_factor,
+            ),
+        )
+    }
+
+    fn make_cards() -> Vec<Card> {
+        vec![
+            make_card("a", "lang::french", 1, 2500.0),
+            make_card("b", "lang::french", 5, 1300.0),
+            make_card("c", "lang::french", 3, 2000.0),
+            make_card("d", "lang::spanish", 2, 1300.0),
+        ]
+    }
+
+    fn paths(cards: &[Card]) -> Vec<&str> {
+        cards.iter().map(|c| &c.path[..]).collect()
+    }
+
+    #[test]
+    fn oldest_due_first() {
+        let actual = OldestDueFirst.order(make_cards());
+        assert_eq!(vec!["b", "c", "d", "a"], paths(&actual));
+    }
+
+    #[test]
+    fn ease_ascending() {
+        let actual = EaseAscending.order(make_cards());
+        assert_eq!(vec!["b", "d", "c", "a"], paths(&actual));
+    }
+
+    #[test]
+    fn deck_round_robin() {
+        let actual = DeckRoundRobin(None).order(make_cards());
+        let decks: Vec<&str> = actual.iter().map(|c| &c.decks[0][..]).collect();
+        assert_eq!(
+            vec![
+                "lang::french",
+                "lang::spanish",
+                "lang::french",
+                "lang::french"
+            ],
+            decks
+        );
+    }
+
+    #[rstest]
+    #[case::random(QueueOrder::Random, "random")]
+    #[case::oldest_due_first(QueueOrder::OldestDueFirst, "oldest-due-first")]
+    #[case::ease_ascending(QueueOrder::EaseAscending, "ease-ascending")]
+    #[case::deck_round_robin(QueueOrder::DeckRoundRobin, "deck-round-robin")]
+    fn names(#[case] order: QueueOrder, #[case] name: &str) {
+        assert_eq!(name, order.to_string());
+        assert_eq!(Ok(order), name.parse());
+    }
+
+    #[test]
+    fn unknown_name() {
+        let actual = "alphabetical".parse::<QueueOrder>();
+        assert!(actual.unwrap_err().contains("oldest-due-first"));
+    }
+}
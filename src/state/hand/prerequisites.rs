@@ -0,0 +1,186 @@
+use crate::state::card::Card;
+use std::collections::HashMap;
+
+// In days, when neither the deck nor the deal sets one.
+pub const DEFAULT_INTERVAL: f64 = 1.0;
+
+// Looks up the cards named in others' requires lines, which are relative to the notes directory
+// while cards' paths start with it.
+pub struct Prerequisites<'c> {
+    index: PathIndex<'c, &'c Card>,
+}
+
+impl<'c> Prerequisites<'c> {
+    pub fn new(cards: &[&'c Card]) -> Self {
+        Self {
+            index: PathIndex::new(cards.iter().map(|c| (c.path.as_str(), *c))),
+        }
+    }
+
+    // Cards already learnt, and requirements without a card or with a suspended one, never hold
+    // a card back.
+    pub fn met(&self, card: &Card, interval: f64) -> bool {
+        !card.is_new()
+            || card.requires.iter().all(|requirement| {
+                self.index.find(requirement).is_none_or(|prerequisite| {
+                    prerequisite.suspended || prerequisite.revision_settings.interval >= interval
+                })
+            })
+    }
+}
+
+// Keeps the cards' order, except that a card's prerequisites in the queue are moved up to just
+// before it, if they came after it. A cycle of requirements is broken where it's entered.
+pub fn order(cards: Vec<Card>) -> Vec<Card> {
+    let index = PathIndex::new(cards.iter().enumerate().map(|(i, c)| (c.path.as_str(), i)));
+    let mut placed = vec![false; cards.len()];
+    let mut indices = Vec::with_capacity(cards.len());
+    for i in 0..cards.len() {
+        place(i, &cards, &index, &mut placed, &mut indices);
+    }
+    let mut cards: Vec<Option<Card>> = cards.into_iter().map(Some).collect();
+    indices
+        .into_iter()
+        .filter_map(|i| cards[i].take())
+        .collect()
+}
+
+// Places the card after its prerequisites, depth first. The stack holds each card being placed
+// with the number of its requirements already visited, so long chains don't overflow.
+fn place(
+    i: usize,
+    cards: &[Card],
+    index: &PathIndex<usize>,
+    placed: &mut [bool],
+    indices: &mut Vec<usize>,
+) {
+    if placed[i] {
+        return;
+    }
+    placed[i] = true;
+    let mut stack = vec![(i, 0)];
+    while let Some((card, visited)) = stack.pop() {
+        let Some(requirement) = cards[card].requires.get(visited) else {
+            indices.push(card);
+            continue;
+        };
+        stack.push((card, visited + 1));
+        if let Some(&j) = index.find(requirement) {
+            if !placed[j] {
+                placed[j] = true;
+                stack.push((j, 0));
+            }
+        }
+    }
+}
+
+// Paths grouped by file name too, as a requirement ends with its card's file name.
+struct PathIndex<'c, T> {
+    by_path: HashMap<&'c str, T>,
+    by_file_name: HashMap<&'c str, Vec<&'c str>>,
+}
+
+impl<'c, T> PathIndex<'c, T> {
+    fn new(entries: impl Iterator<Item = (&'c str, T)>) -> Self {
+        let by_path: HashMap<&'c str, T> = entries.collect();
+        let mut by_file_name: HashMap<&'c str, Vec<&'c str>> = HashMap::new();
+        for path in by_path.keys() {
+            by_file_name.entry(file_name(path)).or_default().push(path);
+        }
+        Self {
+            by_path,
+            by_file_name,
+        }
+    }
+
+    // A requirement names a card by its whole path, or by the end of it after a '/'. When several
+    // paths end with it, the shortest, nearest the notes directory, is taken.
+    fn find(&self, requirement: &str) -> Option<&T> {
+        let requirement = requirement.trim_start_matches("./");
+        self.by_path.get(requirement).or_else(|| {
+            self.by_file_name
+                .get(file_name(requirement))?
+                .iter()
+                .filter(|path| {
+                    path.strip_suffix(requirement)
+                        .is_some_and(|prefix| prefix.ends_with('/'))
+                })
+                .min_by_key(|path| (path.len(), **path))
+                .and_then(|path| self.by_path.get(path))
+        })
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit_once('/').map_or(path, |(_, name)| name)
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use crate::state::card::RevisionSettings;
+
+    fn make_card(path: &str, interval: f64, requires: &[&str]) -> Card {
+        Card {
+            path: path.into(),
+            revision_settings: RevisionSettings {
+                interval,
+                ..RevisionSettings::default()
+            },
+            requires: requires.iter().map(|r| r.to_string()).collect(),
+            ..Card::default()
+        }
+    }
+
+    #[test]
+    fn met_once_prerequisites_reach_the_interval() {
+        let cells = make_card("notes/bio/cells.md", 0.5, &[]);
+        let atp = make_card("notes/bio/atp.md", 3.0, &[]);
+        let suspended = make_card("notes/old.md", 0.0, &[]).with_suspended(true);
+        let cards = [&cells, &atp, &suspended];
+        let prerequisites = Prerequisites::new(&cards);
+        let krebs = make_card("notes/krebs.md", 0.0, &["bio/atp.md", "old.md", "gone.md"]);
+        assert!(prerequisites.met(&krebs, 1.0));
+        assert!(!prerequisites.met(&krebs, 5.0));
+        let mitosis = make_card("notes/mitosis.md", 0.0, &["./bio/cells.md"]);
+        assert!(!prerequisites.met(&mitosis, 1.0));
+        assert!(prerequisites.met(&mitosis.clone().with_requires(vec![]), 1.0));
+        // Learnt cards are dealt whatever their prerequisites.
+        let learnt = make_card("notes/learnt.md", 2.0, &["bio/cells.md"]);
+        assert!(prerequisites.met(&learnt, 1.0));
+        // "ells.md" doesn't name "notes/bio/cells.md" by a part of its file name.
+        let partial = make_card("notes/partial.md", 0.0, &["ells.md"]);
+        assert!(prerequisites.met(&partial, 1.0));
+    }
+
+    #[test]
+    fn order_moves_prerequisites_before_their_dependents() {
+        let cards = vec![
+            make_card("c", 1.0, &["b"]),
+            make_card("a", 1.0, &[]),
+            make_card("b", 1.0, &["a", "missing"]),
+            make_card("d", 1.0, &["e"]),
+            make_card("e", 1.0, &["d"]),
+        ];
+        let actual: Vec<String> = order(cards)
+            .into_iter()
+            .map(|c| c.path.into_string())
+            .collect();
+        assert_eq!(vec!["a", "b", "c", "e", "d"], actual);
+    }
+
+    #[test]
+    fn order_follows_long_requirement_chains() {
+        let count = 100_000;
+        let cards: Vec<Card> = (0..count)
+            .map(|i| make_card(&i.to_string(), 1.0, &[&(i + 1).to_string()]))
+            .collect();
+        let actual: Vec<String> = order(cards)
+            .into_iter()
+            .map(|c| c.path.into_string())
+            .collect();
+        let expected: Vec<String> = (0..count).rev().map(|i| i.to_string()).collect();
+        assert_eq!(expected, actual);
+    }
+}
@@ -0,0 +1,747 @@
+use super::{Checkpoint, Hand};
+use crate::state::card::{
+    Card, Grade, Grading, RevisionSettings, Scheduler, Score, ScoreSuggestions, TypedAnswers,
+};
+use crate::state::deck::IntervalCoefficients;
+use crate::state::id::CardId;
+use crate::state::tools::Merge;
+use chrono::{DateTime, Duration, Utc};
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Progress {
+    pub revised: usize,
+    pub skipped: usize,
+    pub remaining: usize,
+}
+
+impl Progress {
+    pub fn total(&self) -> usize {
+        self.revised + self.skipped + self.remaining
+    }
+}
+
+#[derive(Debug)]
+pub struct Session<'s> {
+    queue: VecDeque<Card>,
+    interval_coefficients: Cow<'s, IntervalCoefficients>,
+    revised: Vec<Card>,
+    reflagged: Vec<CardId>,
+    skipped: usize,
+    relearn_delay: Duration,
+    score_suggestions: ScoreSuggestions,
+    typed_answers: Option<TypedAnswers>,
+    grading: Grading,
+    not_before: HashMap<CardId, DateTime<Utc>>,
+    scheduler: Option<Rc<dyn Scheduler>>,
+}
+
+impl<'s> Session<'s> {
+    pub fn from(hand: Hand<'s>) -> Self {
+        Self {
+            queue: hand.queue,
+            interval_coefficients: hand.interval_coefficients,
+            revised: Vec::new(),
+            reflagged: Vec::new(),
+            skipped: 0,
+            relearn_delay: hand.relearn_delay,
+            score_suggestions: hand.score_suggestions,
+            typed_answers: hand.typed_answers,
+            grading: hand.grading,
+            not_before: HashMap::new(),
+            scheduler: hand.scheduler,
+        }
+    }
+
+    // No longer borrows from the state it was dealt from, e.g. to keep the session while that
+    // state is replaced by the one each review saves.
+    pub fn into_owned(self) -> Session<'static> {
+        Session {
+            interval_coefficients: Cow::Owned(self.interval_coefficients.into_owned()),
+            queue: self.queue,
+            revised: self.revised,
+            reflagged: self.reflagged,
+            skipped: self.skipped,
+            relearn_delay: self.relearn_delay,
+            score_suggestions: self.score_suggestions,
+            typed_answers: self.typed_answers,
+            grading: self.grading,
+            not_before: self.not_before,
+            scheduler: self.scheduler,
+        }
+    }
+
+    // Picks up a checkpointed session's counts. Its revised cards were saved as they were
+    // revised, so they're given as they now are rather than taken from the checkpoint.
+    pub fn with_revised(self, revised: Vec<Card>, skipped: usize) -> Self {
+        Self {
+            revised,
+            skipped,
+            ..self
+        }
+    }
+
+    pub fn checkpoint(&self, deck_name: &str) -> Checkpoint {
+        Checkpoint {
+            deck_name: deck_name.to_string(),
+            seed: None,
+            queue: self.queue.iter().map(|c| c.path.to_string()).collect(),
+            revised: self.revised.iter().map(|c| c.path.to_string()).collect(),
+            skipped: self.skipped,
+        }
+    }
+
+    pub fn next_card(&self) -> Option<&Card> {
+        self.queue.front()
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn progress(&self) -> Progress {
+        Progress {
+            revised: self.revised.len(),
+            skipped: self.skipped,
+            remaining: self.remaining(),
+        }
+    }
+
+    // When the next card is still waiting out its relearn delay. Delayed cards only reach the
+    // front of the queue once every other card has been seen, so this means nothing else is left.
+    pub fn waiting_until(&self) -> Option<DateTime<Utc>> {
+        let not_before = *self.not_before.get(&self.queue.front()?.path)?;
+        (not_before > Utc::now()).then_some(not_before)
+    }
+
+    pub fn grading(&self) -> &Grading {
+        &self.grading
+    }
+
+    // An advisory score for the next card, judged by how long it took to answer. Only the scores
+    // the grading offers are suggested.
+    pub fn suggest_score(&self, answer_time: std::time::Duration) -> Option<Score> {
+        let card = self.queue.front()?;
+        self.score_suggestions
+            .suggest(answer_time, &card.answer_time)
+            .filter(|score| self.grading.offers(*score))
+    }
+
+    pub fn typed_answers(&self) -> Option<&TypedAnswers> {
+        self.typed_answers.as_ref()
+    }
+
+    // The score suggested for the next card's answer typed in, when its deck has cards' answers
+    // typed. An exact answer suggests a pass where the grading has no easy grade.
+    pub fn suggest_typed_score(&self, typed: &str) -> Option<Score> {
+        let card = self.queue.front()?;
+        let score = self.typed_answers?.suggest(typed, &card.answer);
+        let score = match score {
+            Score::Easy if !self.grading.offers(Score::Easy) => Score::Pass,
+            score => score,
+        };
+        Some(score).filter(|score| self.grading.offers(*score))
+    }
+
+    // Like submit_score, also adding how long the answer took to the card's mean answer time.
+    pub fn submit_timed_score(
+        &mut self,
+        score: Score,
+        answer_time: std::time::Duration,
+    ) -> Option<&Card> {
+        self.submit_timed_grade(&Grade::of(score), answer_time)
+    }
+
+    pub fn submit_timed_grade(
+        &mut self,
+        grade: &Grade,
+        answer_time: std::time::Duration,
+    ) -> Option<&Card> {
+        let card = self.queue.front_mut()?;
+        card.answer_time = card.answer_time.record(answer_time);
+        self.submit_grade(grade)
+    }
+
+    // Schedules the next card the given number of days from now in place of a grade, keeping its
+    // memorisation factor.
+    pub fn submit_interval(
+        &mut self,
+        interval: f64,
+        answer_time: std::time::Duration,
+    ) -> Option<&Card> {
+        let mut card = self.queue.pop_front()?;
+        card.answer_time = card.answer_time.record(answer_time);
+        let now = RevisionSettings::new(Utc::now(), 0.0, 0.0);
+        let memorisation_factor = card.revision_settings.memorisation_factor;
+        card.revision_settings = now.rescheduled(interval, memorisation_factor);
+        self.not_before.remove(&card.path);
+        self.bring_available_card_forward();
+        self.revised.push(card);
+        self.revised.last()
+    }
+
+    // What the next card's schedule would become with the grade, e.g. to show on the score
+    // buttons, without submitting it.
+    pub fn preview(&self, grade: &Grade) -> Option<RevisionSettings> {
+        let card = self.queue.front()?.clone();
+        Some(self.transform(card, grade).revision_settings)
+    }
+
+    pub fn submit_score(&mut self, score: Score) -> Option<&Card> {
+        self.submit_grade(&Grade::of(score))
+    }
+
+    pub fn submit_grade(&mut self, grade: &Grade) -> Option<&Card> {
+        let card = self.queue.pop_front()?;
+        let card = self.transform(card, grade);
+        self.not_before.remove(&card.path);
+        match grade.score {
+            Score::Fail => {
+                let path = card.path.clone();
+                if self.relearn_delay > Duration::zero() {
+                    self.not_before
+                        .insert(path.clone(), Utc::now() + self.relearn_delay);
+                }
+                self.queue.push_back(card);
+                self.bring_available_card_forward();
+                self.queue.iter().find(|c| c.path == path)
+            }
+            _ => {
+                self.bring_available_card_forward();
+                self.revised.push(card);
+                self.revised.last()
+            }
+        }
+    }
+
+    // The grade's multiplier scales the interval its score's curve gives, within the note's max
+    // interval. Cards reviewed ahead of their due date are scheduled as if reviewed on it, so that
+    // reviewing early doesn't shorten their intervals.
+    fn transform(&self, card: Card, grade: &Grade) -> Card {
+        let before = card.revision_settings.clone();
+        let reviewed_at = Utc::now().max(before.due);
+        let card = match &self.scheduler {
+            Some(scheduler) => card.transform_with_scheduler_at(
+                grade.score,
+                &self.interval_coefficients,
+                scheduler.as_ref(),
+                reviewed_at,
+            ),
+            None => card.transform_at(grade.score, &self.interval_coefficients, reviewed_at),
+        };
+        if grade.interval_multiplier == 1.0 {
+            return card;
+        }
+        let interval = card.revision_settings.interval * grade.interval_multiplier;
+        let interval = card
+            .overrides
+            .max_interval
+            .map_or(interval, |max| interval.min(max));
+        let memorisation_factor = card.revision_settings.memorisation_factor;
+        card.with_revision_settings(before.rescheduled(interval, memorisation_factor))
+    }
+
+    // The skipped card is handed back untransformed, so it stays due for the next session.
+    pub fn skip(&mut self) -> Option<Card> {
+        let card = self.queue.pop_front()?;
+        self.not_before.remove(&card.path);
+        self.skipped += 1;
+        self.bring_available_card_forward();
+        Some(card)
+    }
+
+    // Keeps the earliest card that may be shown at the front, so delayed cards give way to others.
+    fn bring_available_card_forward(&mut self) {
+        let now = Utc::now();
+        let not_before = |card: &Card| self.not_before.get(&card.path).copied();
+        let next = self
+            .queue
+            .iter()
+            .position(|card| not_before(card).is_none_or(|t| t <= now))
+            .or_else(|| (0..self.queue.len()).min_by_key(|&i| not_before(&self.queue[i])));
+        if let Some(card) = next.filter(|&i| i > 0).and_then(|i| self.queue.remove(i)) {
+            self.queue.push_front(card);
+        }
+    }
+
+    // Brings a card to the front of the queue, adding it if it wasn't dealt in this hand.
+    pub fn jump_to(&mut self, card: &Card) {
+        let card = match self.queue.iter().position(|c| c.path == card.path) {
+            Some(i) => self.queue.remove(i).unwrap_or_else(|| card.clone()),
+            None => card.clone(),
+        };
+        self.not_before.remove(&card.path);
+        self.queue.push_front(card);
+    }
+
+    // Swaps in a re-parsed card's content without touching its schedule in this session.
+    pub fn refresh(&mut self, card: &Card) {
+        for queued in self.queue.iter_mut().filter(|c| c.path == card.path) {
+            *queued = card.clone().merge(queued);
+        }
+    }
+
+    // Suspended cards leave the queue and are kept with the revised cards so the flag is saved.
+    pub fn suspend(&mut self, card: &Card) -> &Card {
+        let suspended = self.take_out(card);
+        suspended.suspended = true;
+        suspended
+    }
+
+    // As with suspending, the card leaves the queue: it's no longer due in this session. Stamped,
+    // so that its review earlier in the session doesn't undo the due date on load.
+    pub fn set_due(&mut self, card: &Card, due: DateTime<Utc>) -> &Card {
+        let rescheduled = self.take_out(card);
+        rescheduled.revision_settings.due = due;
+        rescheduled.rescheduled_at = Some(Utc::now());
+        rescheduled
+    }
+
+    fn take_out(&mut self, card: &Card) -> &mut Card {
+        let queued = self
+            .queue
+            .iter()
+            .position(|c| c.path == card.path)
+            .and_then(|i| self.queue.remove(i));
+        self.not_before.remove(&card.path);
+        self.bring_available_card_forward();
+        let i = match self.revised.iter().position(|c| c.path == card.path) {
+            Some(i) => i,
+            None => {
+                self.revised.push(queued.unwrap_or_else(|| card.clone()));
+                self.revised.len() - 1
+            }
+        };
+        &mut self.revised[i]
+    }
+
+    // Flagging doesn't take the card out of the queue. Cards whose flag changed are returned by
+    // finish even if the session ends before they're revised, so the flag isn't lost.
+    pub fn toggle_flag(&mut self) -> Option<&Card> {
+        let card = self.queue.front_mut()?;
+        card.flagged = !card.flagged;
+        if !self.reflagged.contains(&card.path) {
+            self.reflagged.push(card.path.clone());
+        }
+        self.queue.front()
+    }
+
+    pub fn finish(self) -> Vec<Card> {
+        let mut cards = self.revised;
+        for card in self.queue.into_iter() {
+            let revised = cards.iter().any(|c| c.path == card.path);
+            if !revised && self.reflagged.contains(&card.path) {
+                cards.push(card);
+            }
+        }
+        cards
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use crate::state::card::revision_settings::test_tools::make_expected_revision_settings;
+    use crate::state::card::RevisionSettings;
+    use crate::state::deck::Deck;
+    use crate::state::hand::assertions::assert_hands_near;
+    use crate::state::hand::DealOptions;
+    use chrono::{Duration, Utc};
+
+    const FAKE_DECK_ID: &str = "cephelapoda";
+
+    fn make_cards(paths: &[&str], revision_settings: &RevisionSettings) -> Vec<Card> {
+        paths
+            .iter()
+            .map(|path| {
+                Card::new(
+                    path.to_string(),
+                    vec![FAKE_DECK_ID.to_string()],
+                    "q".to_string(),
+                    "a".to_string(),
+                    revision_settings.clone(),
+                )
+            })
+            .collect()
+    }
+
+    fn make_deck(paths: &[&str]) -> Deck {
+        Deck::new(
+            FAKE_DECK_ID,
+            paths.to_owned(),
+            IntervalCoefficients::new(1.0, 2.0, 0.0),
+        )
+    }
+
+    fn next_path<'a>(session: &'a Session) -> Option<&'a str> {
+        session.next_card().map(|c| &c.path[..])
+    }
+
+    #[test]
+    fn steps_through_the_hand_in_queue_order() {
+        let paths = vec!["octopus", "squid"];
+        let cards = make_cards(&paths, &RevisionSettings::default());
+        let deck = make_deck(&paths);
+        let mut session = Session::from(Hand::from(&deck, cards.iter().collect()).unwrap());
+        assert_eq!(2, session.remaining());
+        assert_eq!(Some("squid"), next_path(&session));
+        session.submit_score(Score::Pass);
+        assert_eq!(Some("octopus"), next_path(&session));
+        session.submit_score(Score::Easy);
+        assert_eq!(None, next_path(&session));
+        assert_eq!(0, session.remaining());
+        assert!(session.submit_score(Score::Pass).is_none());
+    }
+
+    #[test]
+    fn submit_score_transforms_the_current_card() {
+        let in_date = Utc::now() - Duration::days(4);
+        let in_rs = RevisionSettings::new(in_date, 1.0, 2000.0);
+        let cards = make_cards(&["squid"], &in_rs);
+        let deck = make_deck(&["squid"]);
+        let mut session = Session::from(Hand::from(&deck, cards.iter().collect()).unwrap());
+        let out_rs = make_expected_revision_settings(&in_date, 20.0, 2150.0);
+        let expected = make_cards(&["squid"], &out_rs);
+        let actual = session.submit_score(Score::Easy).unwrap().clone();
+        assert_hands_near(&expected, &[actual]);
+        assert_hands_near(&expected, &session.finish());
+    }
+
+    #[test]
+    fn preview_matches_the_submitted_score() {
+        let in_rs = RevisionSettings::new(Utc::now() - Duration::days(4), 1.0, 2000.0);
+        let cards = make_cards(&["squid"], &in_rs);
+        let deck = make_deck(&["squid"]);
+        let mut session = Session::from(Hand::from(&deck, cards.iter().collect()).unwrap());
+        let preview = session.preview(&Grade::of(Score::Easy)).unwrap();
+        assert_eq!(1, session.remaining());
+        let actual = session.submit_score(Score::Easy).unwrap();
+        assert_eq!(preview.interval, actual.revision_settings.interval);
+        assert!(session.preview(&Grade::of(Score::Easy)).is_none());
+    }
+
+    #[test]
+    fn submit_score_schedules_cards_reviewed_ahead_from_their_due_date() {
+        let due = Utc::now() + Duration::days(2);
+        let in_rs = RevisionSettings::new(due, 4.0, 2000.0);
+        let cards = make_cards(&["squid"], &in_rs);
+        let deck = make_deck(&["squid"]);
+        let options = DealOptions::default().with_review_ahead(Some(Duration::days(3)));
+        let hand = Hand::from_with_options(&deck, cards.iter().collect(), &options).unwrap();
+        let mut session = Session::from(hand);
+        let expected = cards[0]
+            .clone()
+            .transform_at(Score::Pass, &deck.interval_coefficients, due);
+        let actual = session.submit_score(Score::Pass).unwrap();
+        assert_eq!(expected.revision_settings, actual.revision_settings);
+    }
+
+    #[test]
+    fn submit_score_requeues_failed_cards() {
+        let paths = vec!["octopus", "squid"];
+        let in_date = Utc::now() - Duration::days(4);
+        let in_rs = RevisionSettings::new(in_date, 1.0, 2000.0);
+        let cards = make_cards(&paths, &in_rs);
+        let deck = make_deck(&paths);
+        let mut session = Session::from(Hand::from(&deck, cards.iter().collect()).unwrap());
+        let failed = session.submit_score(Score::Fail).unwrap().clone();
+        let out_rs = make_expected_revision_settings(&in_date, 0.0, 1800.0);
+        assert_hands_near(&make_cards(&["squid"], &out_rs), &[failed]);
+        assert_eq!(2, session.remaining());
+        assert_eq!(Some("octopus"), next_path(&session));
+        session.submit_score(Score::Pass);
+        assert_eq!(Some("squid"), next_path(&session));
+    }
+
+    #[test]
+    fn suggest_score_uses_the_hands_settings() {
+        let cards = make_cards(&["squid"], &RevisionSettings::default());
+        let deck = make_deck(&["squid"]);
+        let hand = Hand::from(&deck, cards.iter().collect()).unwrap();
+        let disabled = ScoreSuggestions {
+            enabled: false,
+            ..ScoreSuggestions::default()
+        };
+        let answer_time = std::time::Duration::from_secs(45);
+        let session = Session::from(hand);
+        assert_eq!(Some(Score::Hard), session.suggest_score(answer_time));
+        let hand = Hand::from(&deck, cards.iter().collect()).unwrap();
+        let session = Session::from(hand.with_score_suggestions(disabled));
+        assert_eq!(None, session.suggest_score(answer_time));
+        let hand = Hand::from(&deck, cards.iter().collect()).unwrap();
+        let session = Session::from(hand.with_grading(Grading::PassFail));
+        assert_eq!(None, session.suggest_score(answer_time));
+    }
+
+    #[test]
+    fn suggest_typed_score_within_the_grading() {
+        let cards = make_cards(&["squid"], &RevisionSettings::default());
+        let deck = make_deck(&["squid"]);
+        let hand = Hand::from(&deck, cards.iter().collect()).unwrap();
+        assert_eq!(None, Session::from(hand).suggest_typed_score("a"));
+        let typed_answers = Some(TypedAnswers::default());
+        let hand = Hand::from(&deck, cards.iter().collect()).unwrap();
+        let session = Session::from(hand.with_typed_answers(typed_answers));
+        assert_eq!(Some(Score::Easy), session.suggest_typed_score("A"));
+        assert_eq!(Some(Score::Fail), session.suggest_typed_score("b"));
+        let hand = Hand::from(&deck, cards.iter().collect()).unwrap();
+        let hand = hand
+            .with_typed_answers(typed_answers)
+            .with_grading(Grading::PassFail);
+        let session = Session::from(hand);
+        assert_eq!(Some(Score::Pass), session.suggest_typed_score("a"));
+    }
+
+    #[test]
+    fn submit_grade_scales_its_scores_interval() {
+        let in_rs = RevisionSettings::new(Utc::now() - Duration::days(4), 1.0, 2000.0);
+        let cards = make_cards(&["squid"], &in_rs);
+        let deck = make_deck(&["squid"]);
+        let mut session = Session::from(Hand::from(&deck, cards.iter().collect()).unwrap());
+        let grade = Grade {
+            label: "Good".to_string(),
+            score: Score::Pass,
+            interval_multiplier: 1.5,
+        };
+        let out_rs = make_expected_revision_settings(&in_rs.due, 9.0, 2000.0);
+        let actual = session.submit_grade(&grade).unwrap().clone();
+        assert_hands_near(&make_cards(&["squid"], &out_rs), &[actual]);
+    }
+
+    #[test]
+    fn submit_timed_score_records_the_answer_time() {
+        let cards = make_cards(&["squid"], &RevisionSettings::default());
+        let deck = make_deck(&["squid"]);
+        let mut session = Session::from(Hand::from(&deck, cards.iter().collect()).unwrap());
+        let answer_time = std::time::Duration::from_secs(7);
+        let revised = session
+            .submit_timed_score(Score::Pass, answer_time)
+            .unwrap();
+        assert_eq!(Some(answer_time), revised.answer_time.mean());
+        assert_eq!(1, revised.answer_time.reviews);
+    }
+
+    #[test]
+    fn skip_removes_the_current_card_without_transforming_it() {
+        let paths = vec!["octopus", "squid"];
+        let cards = make_cards(&paths, &RevisionSettings::default());
+        let deck = make_deck(&paths);
+        let mut session = Session::from(Hand::from(&deck, cards.iter().collect()).unwrap());
+        let skipped = session.skip().unwrap();
+        assert_hands_near(&cards[1..], &[skipped]);
+        assert_eq!(Some("octopus"), next_path(&session));
+        session.submit_score(Score::Pass);
+        assert!(session.skip().is_none());
+        let expected_progress = Progress {
+            revised: 1,
+            skipped: 1,
+            remaining: 0,
+        };
+        assert_eq!(expected_progress, session.progress());
+        assert_eq!(2, session.progress().total());
+        let revised = session.finish();
+        assert_eq!(1, revised.len());
+        assert_eq!("octopus", revised[0].path);
+    }
+
+    #[test]
+    fn checkpoint_and_resume() {
+        let paths = vec!["octopus", "squid", "nautilus"];
+        let cards = make_cards(&paths, &RevisionSettings::default());
+        let deck = make_deck(&paths);
+        let mut session = Session::from(Hand::from(&deck, cards.iter().collect()).unwrap());
+        session.submit_score(Score::Pass);
+        session.skip();
+        let checkpoint = session.checkpoint(FAKE_DECK_ID);
+        assert_eq!(1, checkpoint.queue.len());
+        assert_eq!(1, checkpoint.revised.len());
+        let revised: Vec<Card> = cards
+            .iter()
+            .filter(|c| checkpoint.revised.contains(&c.path.to_string()))
+            .cloned()
+            .collect();
+        let hand = Hand::resume(&deck, cards.iter().collect(), &checkpoint).unwrap();
+        let resumed = Session::from(hand).with_revised(revised, checkpoint.skipped);
+        assert_eq!(next_path(&session), next_path(&resumed));
+        assert_eq!(session.progress(), resumed.progress());
+        let emptied = Checkpoint {
+            queue: vec!["missing".to_string()],
+            ..checkpoint
+        };
+        assert!(Hand::resume(&deck, cards.iter().collect(), &emptied).is_err());
+    }
+
+    #[test]
+    fn jump_to_moves_a_queued_card_to_the_front() {
+        let paths = vec!["octopus", "squid", "nautilus"];
+        let cards = make_cards(&paths, &RevisionSettings::default());
+        let deck = make_deck(&paths);
+        let mut session = Session::from(Hand::from(&deck, cards.iter().collect()).unwrap());
+        session.jump_to(&cards[0]);
+        assert_eq!(3, session.remaining());
+        assert_eq!(Some("octopus"), next_path(&session));
+    }
+
+    #[test]
+    fn jump_to_adds_a_card_from_outside_the_hand() {
+        let cards = make_cards(&["octopus", "squid"], &RevisionSettings::default());
+        let deck = make_deck(&["octopus"]);
+        let mut session = Session::from(Hand::from(&deck, vec![&cards[0]]).unwrap());
+        session.jump_to(&cards[1]);
+        assert_eq!(2, session.remaining());
+        assert_eq!(Some("squid"), next_path(&session));
+        session.submit_score(Score::Pass);
+        assert_eq!(Some("octopus"), next_path(&session));
+    }
+
+    #[test]
+    fn refresh_replaces_the_content_of_queued_cards() {
+        let paths = vec!["octopus", "squid"];
+        let in_date = Utc::now() - Duration::days(4);
+        let cards = make_cards(&paths, &RevisionSettings::new(in_date, 1.0, 2000.0));
+        let deck = make_deck(&paths);
+        let mut session = Session::from(Hand::from(&deck, cards.iter().collect()).unwrap());
+        session.submit_score(Score::Fail);
+        let mut edited = cards[1].clone();
+        edited.question = "edited q".to_string();
+        edited.revision_settings = RevisionSettings::default();
+        session.refresh(&edited);
+        session.submit_score(Score::Pass);
+        let refreshed = session.next_card().unwrap();
+        assert_eq!("edited q", refreshed.question);
+        let mut expected = make_cards(
+            &["squid"],
+            &make_expected_revision_settings(&in_date, 0.0, 1800.0),
+        );
+        expected[0].question = "edited q".to_string();
+        assert_hands_near(&expected, &[refreshed.clone()]);
+    }
+
+    #[test]
+    fn suspend_removes_the_card_and_keeps_it_as_revised() {
+        let paths = vec!["octopus", "squid"];
+        let cards = make_cards(&paths, &RevisionSettings::default());
+        let deck = make_deck(&paths);
+        let mut session = Session::from(Hand::from(&deck, cards.iter().collect()).unwrap());
+        assert!(session.suspend(&cards[0]).suspended);
+        assert_eq!(1, session.remaining());
+        assert_eq!(Some("squid"), next_path(&session));
+        session.submit_score(Score::Pass);
+        session.suspend(&cards[1]);
+        let revised = session.finish();
+        assert_eq!(
+            vec!["octopus", "squid"],
+            revised.iter().map(|c| &c.path[..]).collect::<Vec<_>>()
+        );
+        assert!(revised.iter().all(|c| c.suspended));
+    }
+
+    #[test]
+    fn submit_interval_schedules_the_card_from_now() {
+        let paths = vec!["octopus"];
+        let revision_settings = RevisionSettings::new(Utc::now(), 12.0, 2200.0);
+        let cards = make_cards(&paths, &revision_settings);
+        let deck = make_deck(&paths);
+        let mut session = Session::from(Hand::from(&deck, cards.iter().collect()).unwrap());
+        let card = session
+            .submit_interval(3.0, std::time::Duration::from_secs(4))
+            .unwrap()
+            .clone();
+        assert_eq!(3.0, card.revision_settings.interval);
+        assert_eq!(2200.0, card.revision_settings.memorisation_factor);
+        let days = (card.revision_settings.due - Utc::now()).num_hours() as f64 / 24.0;
+        assert!((days - 3.0).abs() < 0.1);
+        assert_eq!(0, session.remaining());
+        assert_eq!(1, session.finish().len());
+    }
+
+    #[test]
+    fn set_due_removes_the_card_and_keeps_it_as_revised() {
+        let paths = vec!["octopus", "squid"];
+        let cards = make_cards(&paths, &RevisionSettings::default());
+        let deck = make_deck(&paths);
+        let mut session = Session::from(Hand::from(&deck, cards.iter().collect()).unwrap());
+        let due = Utc::now() + Duration::days(30);
+        assert_eq!(due, session.set_due(&cards[0], due).revision_settings.due);
+        assert_eq!(1, session.remaining());
+        assert_eq!(Some("squid"), next_path(&session));
+        let revised = session.finish();
+        assert_eq!(1, revised.len());
+        assert_eq!(due, revised[0].revision_settings.due);
+        assert!(revised[0].rescheduled_at.is_some());
+    }
+
+    #[test]
+    fn toggle_flag_keeps_the_card_in_the_queue() {
+        let paths = vec!["octopus", "squid"];
+        let cards = make_cards(&paths, &RevisionSettings::default());
+        let deck = make_deck(&paths);
+        let mut session = Session::from(Hand::from(&deck, cards.iter().collect()).unwrap());
+        assert!(session.toggle_flag().unwrap().flagged);
+        assert_eq!(2, session.remaining());
+        session.submit_score(Score::Pass);
+        session.toggle_flag();
+        session.toggle_flag();
+        let revised = session.finish();
+        assert_eq!(
+            vec![("squid", true), ("octopus", false)],
+            revised
+                .iter()
+                .map(|c| (&c.path[..], c.flagged))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    fn make_delayed_session<'d>(deck: &'d Deck, cards: &'d [Card]) -> Session<'d> {
+        let hand = Hand::from(deck, cards.iter().collect())
+            .unwrap()
+            .with_relearn_delay(Duration::minutes(10));
+        Session::from(hand)
+    }
+
+    #[test]
+    fn failed_cards_give_way_to_others_during_their_relearn_delay() {
+        let paths = vec!["octopus", "squid", "nautilus"];
+        let cards = make_cards(&paths, &RevisionSettings::default());
+        let deck = make_deck(&paths);
+        let mut session = make_delayed_session(&deck, &cards);
+        let first = next_path(&session).unwrap().to_string();
+        session.submit_score(Score::Fail);
+        session.submit_score(Score::Fail);
+        assert_eq!(3, session.remaining());
+        assert_eq!(None, session.waiting_until());
+        session.submit_score(Score::Pass);
+        assert_eq!(Some(&first[..]), next_path(&session));
+        let waiting_until = session.waiting_until().unwrap();
+        assert!(waiting_until > Utc::now() + Duration::minutes(9));
+        assert!(waiting_until <= Utc::now() + Duration::minutes(10));
+    }
+
+    #[test]
+    fn failed_cards_are_shown_again_once_their_relearn_delay_passes() {
+        let cards = make_cards(&["octopus"], &RevisionSettings::default());
+        let deck = make_deck(&["octopus"]);
+        let hand = Hand::from(&deck, cards.iter().collect())
+            .unwrap()
+            .with_relearn_delay(Duration::milliseconds(1));
+        let mut session = Session::from(hand);
+        session.submit_score(Score::Fail);
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        assert_eq!(None, session.waiting_until());
+        assert_eq!(Some("octopus"), next_path(&session));
+    }
+
+    #[test]
+    fn jump_to_clears_the_relearn_delay() {
+        let cards = make_cards(&["octopus"], &RevisionSettings::default());
+        let deck = make_deck(&["octopus"]);
+        let mut session = make_delayed_session(&deck, &cards);
+        session.submit_score(Score::Fail);
+        assert!(session.waiting_until().is_some());
+        session.jump_to(&cards[0]);
+        assert_eq!(None, session.waiting_until());
+    }
+}
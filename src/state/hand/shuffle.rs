@@ -1,4 +1,6 @@
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
+use rand::SeedableRng;
 
 #[cfg(test)]
 use rand::rngs::mock::StepRng;
@@ -8,13 +10,19 @@ use rand::thread_rng;
 
 use crate::state::card::Card;
 
-pub fn shuffle_cards(iterable: Vec<Card>) -> Vec<Card> {
-    #[cfg(test)]
-    let mut random_number_generator = StepRng::new(0, 0);
-    #[cfg(not(test))]
-    let mut random_number_generator = thread_rng();
+// The same seed always gives the same order for the same cards.
+pub fn shuffle_cards(iterable: Vec<Card>, seed: Option<u64>) -> Vec<Card> {
     let mut output = iterable.to_owned();
-    output.shuffle(&mut random_number_generator);
+    match seed {
+        Some(seed) => output.shuffle(&mut StdRng::seed_from_u64(seed)),
+        None => {
+            #[cfg(test)]
+            let mut random_number_generator = StepRng::new(0, 0);
+            #[cfg(not(test))]
+            let mut random_number_generator = thread_rng();
+            output.shuffle(&mut random_number_generator);
+        }
+    }
     output
 }
 
@@ -40,8 +48,18 @@ mod unit_tests {
         let deck_id = "cephelapoda";
         let cards: Vec<Card> = card_paths.iter().map(|p| make_fake_card(p)).collect();
         let expected_paths = vec!["squid", "cuttlefish", "nautilus", "octopus"];
-        let actual_cards = shuffle_cards(cards);
+        let actual_cards = shuffle_cards(cards, None);
         let actual_paths: Vec<&str> = actual_cards.iter().map(|c| &c.path[..]).collect();
         assert_eq!(expected_paths, actual_paths);
     }
+
+    #[test]
+    fn shuffling_cards_with_a_seed() {
+        let paths: Vec<String> = (0..20).map(|i| i.to_string()).collect();
+        let cards: Vec<Card> = paths.iter().map(|p| make_fake_card(p)).collect();
+        let first = shuffle_cards(cards.clone(), Some(42));
+        assert_eq!(first, shuffle_cards(cards.clone(), Some(42)));
+        assert_ne!(first, shuffle_cards(cards.clone(), Some(43)));
+        assert_ne!(cards, first);
+    }
 }
@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::fmt;
+use std::ops::Deref;
+
+// Keys of the state's maps, so that a card can't be looked up by a deck's name or the other way
+// round. Both are kept as plain strings in state files, and borrow as str and String so that
+// lookups by a path or name already at hand keep working.
+macro_rules! string_id {
+    ($name:ident) => {
+        #[derive(
+            Clone, Debug, Default, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize,
+        )]
+        #[serde(transparent)]
+        pub struct $name(String);
+
+        impl $name {
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            pub fn into_string(self) -> String {
+                self.0
+            }
+        }
+
+        impl Deref for $name {
+            type Target = str;
+
+            fn deref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl Borrow<str> for $name {
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl Borrow<String> for $name {
+            fn borrow(&self) -> &String {
+                &self.0
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(id: String) -> Self {
+                Self(id)
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(id: &str) -> Self {
+                Self(id.to_string())
+            }
+        }
+
+        impl From<&String> for $name {
+            fn from(id: &String) -> Self {
+                Self(id.clone())
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl PartialEq<String> for $name {
+            fn eq(&self, other: &String) -> bool {
+                &self.0 == other
+            }
+        }
+
+        impl PartialEq<$name> for str {
+            fn eq(&self, other: &$name) -> bool {
+                self == other.0
+            }
+        }
+
+        impl PartialEq<$name> for &str {
+            fn eq(&self, other: &$name) -> bool {
+                *self == other.0
+            }
+        }
+
+        impl PartialEq<$name> for String {
+            fn eq(&self, other: &$name) -> bool {
+                *self == other.0
+            }
+        }
+    };
+}
+
+string_id!(CardId);
+string_id!(DeckName);
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn ids_are_looked_up_by_strings() {
+        let cards = HashMap::from([(CardId::from("bio/krebs.md"), 1)]);
+        let path = String::from("bio/krebs.md");
+        assert_eq!(Some(&1), cards.get(path.as_str()));
+        assert_eq!(Some(&1), cards.get(&path));
+        assert_eq!(None, cards.get("biology"));
+    }
+
+    #[test]
+    fn ids_are_written_as_strings() {
+        let id = DeckName::from("lang::spanish");
+        assert_eq!("\"lang::spanish\"", ron::to_string(&id).unwrap());
+        assert_eq!(id, ron::from_str::<DeckName>("\"lang::spanish\"").unwrap());
+        assert_eq!("lang::spanish", id.to_string());
+    }
+}
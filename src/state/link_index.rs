@@ -0,0 +1,190 @@
+use regex::{Captures, Regex};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+const WIKILINK_PATTERN: &str = r"!?\[\[([^\[\]|#^]*)(?:[#^]([^\[\]|]*))?(?:\|([^\[\]]*))?\]\]";
+const NOTE_EXTENSION: &str = ".md";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WikiLink {
+    pub target: String,
+    pub heading: Option<String>,
+    pub alias: Option<String>,
+}
+
+impl WikiLink {
+    fn from(captures: &Captures) -> Self {
+        let text = |i: usize| {
+            captures
+                .get(i)
+                .map(|m| m.as_str().trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+        Self {
+            target: text(1).unwrap_or_default(),
+            heading: text(2),
+            alias: text(3),
+        }
+    }
+
+    pub fn title(&self) -> String {
+        let name = self.target.rsplit('/').next().unwrap_or_default();
+        let name = name.strip_suffix(NOTE_EXTENSION).unwrap_or(name);
+        match (&self.alias, &self.heading) {
+            (Some(alias), _) => alias.clone(),
+            (None, Some(heading)) if name.is_empty() => heading.clone(),
+            (None, Some(heading)) => format!("{} > {}", name, heading),
+            (None, None) => name.to_string(),
+        }
+    }
+}
+
+pub fn links(text: &str) -> Vec<WikiLink> {
+    wikilink_expression()
+        .captures_iter(text)
+        .map(|c| WikiLink::from(&c))
+        .collect()
+}
+
+fn wikilink_expression() -> Regex {
+    Regex::new(WIKILINK_PATTERN).expect("wikilink pattern is a valid regex")
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LinkIndex {
+    paths: BTreeSet<String>,
+}
+
+impl LinkIndex {
+    pub fn insert(&mut self, path: &str) {
+        self.paths.insert(path.to_string());
+    }
+
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    // Like Obsidian, a bare note name resolves to the matching note nearest the vault root.
+    pub fn resolve(&self, link: &WikiLink) -> Option<&str> {
+        let target = link.target.to_lowercase();
+        let target = target.strip_suffix(NOTE_EXTENSION).unwrap_or(&target);
+        if target.is_empty() {
+            return None;
+        }
+        self.paths
+            .iter()
+            .filter(|path| {
+                let path = path.to_lowercase();
+                let path = path.strip_suffix(NOTE_EXTENSION).unwrap_or(&path);
+                path == target || path.ends_with(&format!("/{}", target))
+            })
+            .min_by_key(|path| Path::new(path).components().count())
+            .map(|path| path.as_str())
+    }
+
+    pub fn linked_paths(&self, text: &str) -> Vec<&str> {
+        let mut paths: Vec<&str> = Vec::new();
+        for path in links(text).iter().filter_map(|link| self.resolve(link)) {
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+        paths
+    }
+}
+
+pub fn render(text: &str) -> String {
+    wikilink_expression()
+        .replace_all(text, |c: &Captures| WikiLink::from(c).title())
+        .to_string()
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use rstest::*;
+
+    fn make_link_index(paths: &[&str]) -> LinkIndex {
+        let mut link_index = LinkIndex::default();
+        paths.iter().for_each(|path| link_index.insert(path));
+        link_index
+    }
+
+    #[rstest]
+    #[case::bare("[[Krebs Cycle]]", "Krebs Cycle", None, None)]
+    #[case::heading("[[Krebs Cycle#Steps]]", "Krebs Cycle", Some("Steps"), None)]
+    #[case::block("[[Krebs Cycle^abc123]]", "Krebs Cycle", Some("abc123"), None)]
+    #[case::alias(
+        "[[bio/Krebs Cycle|the cycle]]",
+        "bio/Krebs Cycle",
+        None,
+        Some("the cycle")
+    )]
+    #[case::embed("![[diagram]]", "diagram", None, None)]
+    #[case::same_note("[[#Steps]]", "", Some("Steps"), None)]
+    fn links(
+        #[case] text: &str,
+        #[case] target: &str,
+        #[case] heading: Option<&str>,
+        #[case] alias: Option<&str>,
+    ) {
+        let expected = vec![WikiLink {
+            target: target.to_string(),
+            heading: heading.map(|s| s.to_string()),
+            alias: alias.map(|s| s.to_string()),
+        }];
+        assert_eq!(expected, super::links(text));
+    }
+
+    #[rstest]
+    #[case::bare("See [[Krebs Cycle]].", "See Krebs Cycle.")]
+    #[case::path_and_extension("[[bio/Krebs Cycle.md]]", "Krebs Cycle")]
+    #[case::heading("[[Krebs Cycle#Steps]]", "Krebs Cycle > Steps")]
+    #[case::alias("[[Krebs Cycle|the cycle]] and [[ATP]]", "the cycle and ATP")]
+    #[case::same_note("[[#Steps]]", "Steps")]
+    #[case::not_a_link("[not] [[a link", "[not] [[a link")]
+    fn render(#[case] text: &str, #[case] expected: &str) {
+        assert_eq!(expected, super::render(text));
+    }
+
+    #[rstest]
+    #[case::by_name("Krebs Cycle", Some("notes/bio/krebs cycle.md"))]
+    #[case::by_path("chem/krebs cycle", Some("notes/chem/Krebs Cycle.md"))]
+    #[case::with_extension("atp.md", Some("notes/atp.md"))]
+    #[case::unknown("Glycolysis", None)]
+    #[case::partial_name("cycle", None)]
+    fn resolve(#[case] target: &str, #[case] expected: Option<&str>) {
+        let link_index = make_link_index(&[
+            "notes/chem/Krebs Cycle.md",
+            "notes/bio/krebs cycle.md",
+            "notes/atp.md",
+        ]);
+        let link = WikiLink {
+            target: target.to_string(),
+            heading: None,
+            alias: None,
+        };
+        assert_eq!(expected, link_index.resolve(&link));
+    }
+
+    #[test]
+    fn resolve_prefers_the_note_nearest_the_root() {
+        let link_index = make_link_index(&["a/b/atp.md", "z/atp.md"]);
+        assert_eq!(
+            Some("z/atp.md"),
+            link_index.resolve(&super::links("[[ATP]]")[0])
+        );
+    }
+
+    #[test]
+    fn linked_paths() {
+        let link_index = make_link_index(&["atp.md", "krebs.md"]);
+        let actual = link_index.linked_paths("[[krebs]], [[nope]], [[ATP]] and [[krebs#Steps]]");
+        assert_eq!(vec!["krebs.md", "atp.md"], actual);
+    }
+}
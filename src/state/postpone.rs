@@ -0,0 +1,126 @@
+use crate::state::card::Card;
+use chrono::{DateTime, Duration, Utc};
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Postponement {
+    pub days: u32,
+    pub spread_days: Option<u32>,
+}
+
+impl Postponement {
+    pub fn with_days(self, days: u32) -> Self {
+        Self { days, ..self }
+    }
+
+    // Spreads the cards that would all fall due on the day of return over this many days after it.
+    pub fn with_spread_days(self, spread_days: Option<u32>) -> Self {
+        Self {
+            spread_days,
+            ..self
+        }
+    }
+}
+
+// Each card's due date moves forward by the postponement's days, counted from now for the cards
+// already due, so that they're due on the day of return rather than overdue by the time away.
+// Returned in the order of their previous due dates.
+pub fn postpone(cards: Vec<Card>, postponement: &Postponement, now: DateTime<Utc>) -> Vec<Card> {
+    let mut cards = cards;
+    cards.sort_by(|a, b| {
+        (a.revision_settings.due, &a.path).cmp(&(b.revision_settings.due, &b.path))
+    });
+    let days = Duration::days(postponement.days.into());
+    let already_due = cards.iter().filter(|c| c.is_due_at(now)).count();
+    cards
+        .into_iter()
+        .enumerate()
+        .map(|(i, card)| {
+            let mut revision_settings = card.revision_settings.clone();
+            revision_settings.due = match postponement.spread_days {
+                // The longest overdue cards are kept first.
+                Some(spread) if i < already_due => {
+                    let offset = i as i64 * i64::from(spread) / already_due as i64;
+                    now + days + Duration::days(offset)
+                }
+                _ => revision_settings.due.max(now) + days,
+            };
+            card.with_revision_settings(revision_settings)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use crate::state::card::RevisionSettings;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap()
+    }
+
+    fn make_card(path: &str, due_in_days: i64) -> Card {
+        let due = now() + Duration::days(due_in_days);
+        Card {
+            path: path.into(),
+            ..Card::default()
+        }
+        .with_revision_settings(RevisionSettings::new(due, 5.0, 2500.0))
+    }
+
+    fn due_in_days(cards: &[Card]) -> Vec<(String, i64)> {
+        cards
+            .iter()
+            .map(|c| {
+                (
+                    c.path.to_string(),
+                    (c.revision_settings.due - now()).num_days(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn postpone_shifts_due_dates() {
+        let cards = vec![
+            make_card("later", 20),
+            make_card("overdue", -3),
+            make_card("soon", 2),
+        ];
+        let actual = postpone(cards, &Postponement::default().with_days(14), now());
+        assert_eq!(
+            vec![
+                ("overdue".to_string(), 14),
+                ("soon".to_string(), 16),
+                ("later".to_string(), 34),
+            ],
+            due_in_days(&actual)
+        );
+    }
+
+    #[test]
+    fn postpone_spreads_the_cards_already_due() {
+        let cards = vec![
+            make_card("a", -1),
+            make_card("b", -4),
+            make_card("c", -2),
+            make_card("d", 0),
+            make_card("e", 3),
+        ];
+        let postponement = Postponement::default()
+            .with_days(7)
+            .with_spread_days(Some(4));
+        let actual = postpone(cards, &postponement, now());
+        assert_eq!(
+            vec![
+                ("b".to_string(), 7),
+                ("c".to_string(), 8),
+                ("a".to_string(), 9),
+                ("d".to_string(), 10),
+                ("e".to_string(), 10),
+            ],
+            due_in_days(&actual)
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use crate::state::card::Card;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use std::collections::BTreeMap;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RebalanceOptions {
+    pub window_days: u32,
+    pub max_per_day: Option<usize>,
+}
+
+impl Default for RebalanceOptions {
+    fn default() -> Self {
+        Self {
+            window_days: 7,
+            max_per_day: None,
+        }
+    }
+}
+
+impl RebalanceOptions {
+    // How far, before or after, a card may be moved from its due date.
+    pub fn with_window_days(self, window_days: u32) -> Self {
+        Self {
+            window_days,
+            ..self
+        }
+    }
+
+    // Without one, each day's limit is the mean of the days within the window around it.
+    pub fn with_max_per_day(self, max_per_day: Option<usize>) -> Self {
+        Self {
+            max_per_day,
+            ..self
+        }
+    }
+}
+
+// Moves cards off the days with more due than their limit onto the nearest days within the window
+// with room to spare, later days first. Cards already due count towards today and are never
+// moved into the past. The cards with the longest intervals are moved first, as a few days make
+// the least difference to them. Only the moved cards are returned, soonest due first.
+pub fn rebalance(cards: Vec<Card>, options: &RebalanceOptions, now: DateTime<Utc>) -> Vec<Card> {
+    let mut cards = cards;
+    cards.sort_by(|a, b| a.path.cmp(&b.path));
+    let today = now.date_naive();
+    let day_of = |card: &Card| card.revision_settings.due.date_naive().max(today);
+    let mut days: BTreeMap<NaiveDate, Vec<usize>> = BTreeMap::new();
+    for (i, card) in cards.iter().enumerate() {
+        days.entry(day_of(card)).or_default().push(i);
+    }
+    let loads: BTreeMap<NaiveDate, usize> = days.iter().map(|(day, i)| (*day, i.len())).collect();
+    let window = i64::from(options.window_days);
+    let limit = |day: NaiveDate| {
+        options.max_per_day.unwrap_or_else(|| {
+            let first = (day - Duration::days(window)).max(today);
+            let last = day + Duration::days(window);
+            let total: usize = loads.range(first..=last).map(|(_, load)| load).sum();
+            let span = (last - first).num_days() as usize + 1;
+            total.div_ceil(span)
+        })
+    };
+    let load = |days: &BTreeMap<NaiveDate, Vec<usize>>, day| days.get(&day).map_or(0, Vec::len);
+    let mut moved: Vec<(usize, NaiveDate)> = Vec::new();
+    for day in loads.keys().copied() {
+        while load(&days, day) > limit(day) {
+            let target = (1..=window)
+                .flat_map(|offset| [day + Duration::days(offset), day - Duration::days(offset)])
+                .filter(|target| *target >= today)
+                .find(|target| load(&days, *target) < limit(*target));
+            let Some(target) = target else {
+                break;
+            };
+            let queued = days.entry(day).or_default();
+            let Some(position) = (0..queued.len()).max_by(|a, b| {
+                let interval = |p: &usize| cards[queued[*p]].revision_settings.interval;
+                interval(a).total_cmp(&interval(b))
+            }) else {
+                break;
+            };
+            let i = queued.remove(position);
+            days.entry(target).or_default().push(i);
+            moved.push((i, target));
+        }
+    }
+    let mut moved: Vec<Card> = moved
+        .into_iter()
+        .map(|(i, target)| {
+            let card = &cards[i];
+            let shift = Duration::days((target - day_of(card)).num_days());
+            let mut revision_settings = card.revision_settings.clone();
+            revision_settings.due = revision_settings.due.max(now) + shift;
+            card.clone().with_revision_settings(revision_settings)
+        })
+        .collect();
+    moved.sort_by(|a, b| {
+        (a.revision_settings.due, &a.path).cmp(&(b.revision_settings.due, &b.path))
+    });
+    moved
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use crate::state::card::RevisionSettings;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap()
+    }
+
+    fn make_cards(count: usize, due_in_days: i64, interval: f64) -> Vec<Card> {
+        let due = now() + Duration::days(due_in_days);
+        (0..count)
+            .map(|i| {
+                Card {
+                    path: format!("{}-{}-{}", due_in_days, interval, i).into(),
+                    ..Card::default()
+                }
+                .with_revision_settings(RevisionSettings::new(due, interval, 2500.0))
+            })
+            .collect()
+    }
+
+    fn loads(cards: &[Card], moved: &[Card]) -> Vec<usize> {
+        let mut loads = vec![0; 8];
+        for card in cards {
+            let moved = moved.iter().find(|c| c.path == card.path).unwrap_or(card);
+            let day = (moved.revision_settings.due.date_naive() - now().date_naive()).num_days();
+            loads[day.max(0) as usize] += 1;
+        }
+        loads
+    }
+
+    #[test]
+    fn rebalance_spreads_a_spike_under_the_limit() {
+        let cards: Vec<Card> = [make_cards(2, -3, 5.0), make_cards(8, 2, 30.0)].concat();
+        let options = RebalanceOptions::default()
+            .with_window_days(2)
+            .with_max_per_day(Some(3));
+        let actual = rebalance(cards.clone(), &options, now());
+        assert_eq!(5, actual.len());
+        assert_eq!(vec![2, 2, 3, 3, 0, 0, 0, 0], loads(&cards, &actual));
+    }
+
+    #[test]
+    fn rebalance_moves_the_longest_intervals_first() {
+        let cards: Vec<Card> = [make_cards(2, 3, 2.0), make_cards(1, 3, 40.0)].concat();
+        let options = RebalanceOptions::default()
+            .with_window_days(1)
+            .with_max_per_day(Some(2));
+        let actual = rebalance(cards, &options, now());
+        assert_eq!(1, actual.len());
+        assert_eq!(40.0, actual[0].revision_settings.interval);
+        assert_eq!(now() + Duration::days(4), actual[0].revision_settings.due);
+    }
+
+    #[test]
+    fn rebalance_defaults_to_the_mean_load_around_each_day() {
+        let cards: Vec<Card> = [make_cards(6, 3, 10.0), make_cards(3, 4, 10.0)].concat();
+        let options = RebalanceOptions::default().with_window_days(1);
+        let actual = rebalance(cards.clone(), &options, now());
+        // Day 2's limit is 2, the mean of days 1 to 3, and day 4 is already at its limit of 3.
+        assert_eq!(vec![0, 0, 2, 4, 3, 0, 0, 0], loads(&cards, &actual));
+    }
+}
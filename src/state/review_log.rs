@@ -0,0 +1,428 @@
+use super::card::{AnswerTime, Card, RevisionSettings, Score};
+use super::file::IO;
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use snafu::{prelude::*, Whatever};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+#[cfg(test)]
+use super::file::MockFileHandle as FileHandle;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ReviewEvent {
+    pub card_path: String,
+    pub device: String,
+    pub reviewed_at: DateTime<Utc>,
+    pub score: Score,
+    pub revision_settings: RevisionSettings,
+    #[serde(default)]
+    pub answer_time: Option<Duration>,
+    #[serde(default)]
+    pub session_seed: Option<u64>,
+}
+
+impl ReviewEvent {
+    pub fn new(card: &Card, score: Score, device: &str, reviewed_at: DateTime<Utc>) -> Self {
+        Self {
+            card_path: card.path.to_string(),
+            device: device.to_string(),
+            reviewed_at,
+            score,
+            revision_settings: card.revision_settings.clone(),
+            answer_time: None,
+            session_seed: None,
+        }
+    }
+
+    pub fn with_answer_time(self, answer_time: Option<Duration>) -> Self {
+        Self {
+            answer_time,
+            ..self
+        }
+    }
+
+    // The seed the review's session was dealt with, for replaying its order.
+    pub fn with_session_seed(self, session_seed: Option<u64>) -> Self {
+        Self {
+            session_seed,
+            ..self
+        }
+    }
+
+    fn is_newer_than(&self, other: &ReviewEvent) -> bool {
+        (self.reviewed_at, &self.device) > (other.reviewed_at, &other.device)
+    }
+}
+
+pub fn append(file_handle: impl IO, event: &ReviewEvent) -> Result<(), Whatever> {
+    let file_path = file_handle.path();
+    let line = ron::to_string(event)
+        .with_whatever_context(|_| format!("Unable to serialise ReviewEvent to {}", file_path))?;
+    file_handle
+        .append(format!("{}\n", line))
+        .with_whatever_context(|_| format!("Unable to append ReviewEvent to {}", file_path))
+}
+
+// Lines that fail to parse, such as one left half written by a crash, are skipped.
+pub fn read(file_handle: impl IO) -> Result<Vec<ReviewEvent>, Whatever> {
+    let file_path = file_handle.path();
+    let content = file_handle
+        .read()
+        .with_whatever_context(|_| format!("Unable to read review log from {}", file_path))?;
+    Ok(content
+        .lines()
+        .filter_map(|line| ron::from_str(line).ok())
+        .collect())
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AnkiRevlogEntry {
+    pub id: i64,
+    pub cid: i64,
+    pub usn: i64,
+    pub ease: u8,
+    pub ivl: i64,
+    #[serde(rename = "lastIvl")]
+    pub last_ivl: i64,
+    pub factor: i64,
+    pub time: i64,
+    #[serde(rename = "type")]
+    pub review_type: u8,
+}
+
+impl AnkiRevlogEntry {
+    pub const CSV_HEADER: &'static str = "id,cid,usn,ease,ivl,lastIvl,factor,time,type";
+
+    pub fn to_csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{},{}",
+            self.id,
+            self.cid,
+            self.usn,
+            self.ease,
+            self.ivl,
+            self.last_ivl,
+            self.factor,
+            self.time,
+            self.review_type
+        )
+    }
+}
+
+// Mirrors the columns of Anki's revlog table. Untimed reviews have a `time` of 0.
+pub fn to_anki_revlog(events: &[ReviewEvent]) -> Vec<AnkiRevlogEntry> {
+    let mut sorted: Vec<&ReviewEvent> = events.iter().collect();
+    sorted.sort_by(|a, b| (a.reviewed_at, &a.device).cmp(&(b.reviewed_at, &b.device)));
+    let mut previous: HashMap<&str, &ReviewEvent> = HashMap::new();
+    let mut entries = Vec::with_capacity(sorted.len());
+    for event in sorted.into_iter() {
+        let last = previous.insert(&event.card_path, event);
+        let last_ivl = last.map_or(0, |e| e.revision_settings.interval.round() as i64);
+        let review_type = match last {
+            None => 0,
+            Some(e) if e.revision_settings.interval == 0.0 => 0,
+            Some(e) if e.score == Score::Fail => 2,
+            Some(_) => 1,
+        };
+        entries.push(AnkiRevlogEntry {
+            id: event.reviewed_at.timestamp_millis(),
+            cid: card_id(&event.card_path),
+            usn: -1,
+            ease: anki_ease(event.score),
+            ivl: event.revision_settings.interval.round() as i64,
+            last_ivl,
+            factor: event.revision_settings.memorisation_factor.round() as i64,
+            time: event.answer_time.map_or(0, |t| t.as_millis() as i64),
+            review_type,
+        });
+    }
+    entries
+}
+
+fn anki_ease(score: Score) -> u8 {
+    match score {
+        Score::Fail => 1,
+        Score::Hard => 2,
+        Score::Pass => 3,
+        Score::Easy => 4,
+    }
+}
+
+// A stable (FNV-1a) hash of the card's path, kept positive to suit Anki's integer ids.
+fn card_id(card_path: &str) -> i64 {
+    let hash = card_path
+        .bytes()
+        .fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+    (hash >> 1) as i64
+}
+
+// Every device's timed reviews of a card, in the order they happened.
+pub fn answer_times_by_card(events: &[ReviewEvent]) -> HashMap<String, AnswerTime> {
+    let mut sorted: Vec<&ReviewEvent> = events.iter().collect();
+    sorted.sort_by(|a, b| (a.reviewed_at, &a.device).cmp(&(b.reviewed_at, &b.device)));
+    let mut answer_times: HashMap<String, AnswerTime> = HashMap::new();
+    for event in sorted.into_iter() {
+        if let Some(answer_time) = event.answer_time {
+            let entry = answer_times.entry(event.card_path.clone()).or_default();
+            *entry = entry.record(answer_time);
+        }
+    }
+    answer_times
+}
+
+// How many reviews, across every device, fell on each day in the given time zone.
+pub fn daily_review_counts<Tz: TimeZone>(
+    events: &[ReviewEvent],
+    time_zone: &Tz,
+) -> BTreeMap<NaiveDate, usize> {
+    let mut counts = BTreeMap::new();
+    for event in events.iter() {
+        let day = event.reviewed_at.with_timezone(time_zone).date_naive();
+        *counts.entry(day).or_insert(0) += 1;
+    }
+    counts
+}
+
+pub fn latest_by_card(events: Vec<ReviewEvent>) -> HashMap<String, ReviewEvent> {
+    let mut latest: HashMap<String, ReviewEvent> = HashMap::new();
+    for event in events.into_iter() {
+        match latest.get(&event.card_path) {
+            Some(current) if !event.is_newer_than(current) => {}
+            _ => {
+                latest.insert(event.card_path.clone(), event);
+            }
+        }
+    }
+    latest
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use chrono::Duration;
+    use mockall::predicate::eq;
+
+    fn make_event(
+        path: &str,
+        device: &str,
+        reviewed_at: DateTime<Utc>,
+        interval: f64,
+    ) -> ReviewEvent {
+        let mut card = Card::default();
+        card.path = path.into();
+        card.revision_settings = RevisionSettings::new(reviewed_at, interval, 1300.0);
+        ReviewEvent::new(&card, Score::Pass, device, reviewed_at)
+    }
+
+    #[test]
+    fn append() {
+        let event = make_event("a", "laptop", Utc::now(), 1.0);
+        let expected = format!("{}\n", ron::to_string(&event).unwrap());
+        let mut mock_file_handle = FileHandle::new();
+        mock_file_handle
+            .expect_path()
+            .return_const("log".to_string());
+        mock_file_handle
+            .expect_append()
+            .with(eq(expected))
+            .returning(|_| Ok(()));
+        assert!(super::append(mock_file_handle, &event).is_ok());
+    }
+
+    #[test]
+    fn append_when_file_handle_append_fails() {
+        let event = make_event("a", "laptop", Utc::now(), 1.0);
+        let mut mock_file_handle = FileHandle::new();
+        mock_file_handle
+            .expect_path()
+            .return_const("log".to_string());
+        mock_file_handle
+            .expect_append()
+            .returning(|_| Err(std::io::Error::from(std::io::ErrorKind::NotFound)));
+        let actual = super::append(mock_file_handle, &event).unwrap_err();
+        assert!(actual
+            .to_string()
+            .contains("Unable to append ReviewEvent to log"));
+    }
+
+    #[test]
+    fn read_skips_malformed_lines() {
+        let events = vec![
+            make_event("a", "laptop", Utc::now(), 1.0),
+            make_event("b", "laptop", Utc::now(), 2.0),
+        ];
+        let content = format!(
+            "{}\n{}\n(card_path:\"c\",dev",
+            ron::to_string(&events[0]).unwrap(),
+            ron::to_string(&events[1]).unwrap()
+        );
+        let mut mock_file_handle = FileHandle::new();
+        mock_file_handle
+            .expect_path()
+            .return_const("log".to_string());
+        mock_file_handle
+            .expect_read()
+            .returning(move || Ok(content.clone()));
+        assert_eq!(events, super::read(mock_file_handle).unwrap());
+    }
+
+    #[test]
+    fn read_when_file_handle_read_fails() {
+        let mut mock_file_handle = FileHandle::new();
+        mock_file_handle
+            .expect_path()
+            .return_const("log".to_string());
+        mock_file_handle
+            .expect_read()
+            .returning(|| Err(std::io::Error::from(std::io::ErrorKind::NotFound)));
+        let actual = super::read(mock_file_handle).unwrap_err();
+        assert!(actual
+            .to_string()
+            .contains("Unable to read review log from log"));
+    }
+
+    fn make_scored_event(
+        path: &str,
+        reviewed_at: DateTime<Utc>,
+        score: Score,
+        interval: f64,
+    ) -> ReviewEvent {
+        let mut event = make_event(path, "laptop", reviewed_at, interval);
+        event.score = score;
+        event
+    }
+
+    #[test]
+    fn to_anki_revlog() {
+        let first = Utc::now() - Duration::days(10);
+        let second = first + Duration::days(1);
+        let third = first + Duration::days(5);
+        let events = vec![
+            make_scored_event("a", third, Score::Pass, 2.0),
+            make_scored_event("a", first, Score::Easy, 3.4),
+            make_scored_event("a", second, Score::Fail, 0.6)
+                .with_answer_time(Some(std::time::Duration::from_millis(2500))),
+        ];
+        let actual = super::to_anki_revlog(&events);
+        let cid = card_id("a");
+        let expected = vec![
+            AnkiRevlogEntry {
+                id: first.timestamp_millis(),
+                cid,
+                usn: -1,
+                ease: 4,
+                ivl: 3,
+                last_ivl: 0,
+                factor: 1300,
+                time: 0,
+                review_type: 0,
+            },
+            AnkiRevlogEntry {
+                id: second.timestamp_millis(),
+                cid,
+                usn: -1,
+                ease: 1,
+                ivl: 1,
+                last_ivl: 3,
+                factor: 1300,
+                time: 2500,
+                review_type: 1,
+            },
+            AnkiRevlogEntry {
+                id: third.timestamp_millis(),
+                cid,
+                usn: -1,
+                ease: 3,
+                ivl: 2,
+                last_ivl: 1,
+                factor: 1300,
+                time: 0,
+                review_type: 2,
+            },
+        ];
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn answer_times_by_card() {
+        let now = Utc::now();
+        let seconds = std::time::Duration::from_secs;
+        let events = vec![
+            make_event("a", "laptop", now, 1.0).with_answer_time(Some(seconds(6))),
+            make_event("a", "phone", now - Duration::days(1), 1.0)
+                .with_answer_time(Some(seconds(2))),
+            make_event("a", "phone", now - Duration::days(2), 1.0),
+            make_event("b", "phone", now, 1.0),
+        ];
+        let actual = super::answer_times_by_card(&events);
+        assert_eq!(1, actual.len());
+        assert_eq!(2, actual["a"].reviews);
+        assert_eq!(Some(seconds(4)), actual["a"].mean());
+    }
+
+    #[test]
+    fn daily_review_counts() {
+        let at = |hour| Utc.with_ymd_and_hms(2024, 3, 1, hour, 30, 0).unwrap();
+        let events = vec![
+            make_event("a", "laptop", at(1), 1.0),
+            make_event("b", "phone", at(9), 1.0),
+            make_event("a", "phone", at(23), 1.0),
+        ];
+        let day = |d| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        let in_utc = super::daily_review_counts(&events, &Utc);
+        assert_eq!(BTreeMap::from([(day(1), 3)]), in_utc);
+        let ahead = chrono::FixedOffset::east_opt(2 * 3600).unwrap();
+        let in_ahead = super::daily_review_counts(&events, &ahead);
+        assert_eq!(BTreeMap::from([(day(1), 2), (day(2), 1)]), in_ahead);
+    }
+
+    #[test]
+    fn reads_events_logged_before_answer_times_were_recorded() {
+        let line = "(card_path:\"a\",device:\"laptop\",reviewed_at:\"2024-01-02T03:04:05Z\",score:Pass,revision_settings:(due:\"2024-01-03T03:04:05Z\",interval:1.0,memorisation_factor:1300.0))";
+        let event: ReviewEvent = ron::from_str(line).unwrap();
+        assert_eq!(None, event.answer_time);
+    }
+
+    #[test]
+    fn card_id_is_stable_and_positive() {
+        assert_eq!(card_id("bio/a.md"), card_id("bio/a.md"));
+        assert_ne!(card_id("bio/a.md"), card_id("bio/b.md"));
+        assert!(card_id("bio/a.md") > 0);
+    }
+
+    #[test]
+    fn to_csv_row() {
+        let entry = AnkiRevlogEntry {
+            id: 1,
+            cid: 2,
+            usn: -1,
+            ease: 3,
+            ivl: 4,
+            last_ivl: 5,
+            factor: 1300,
+            time: 0,
+            review_type: 1,
+        };
+        assert_eq!("1,2,-1,3,4,5,1300,0,1", entry.to_csv_row());
+    }
+
+    #[test]
+    fn latest_by_card_keeps_the_last_writer() {
+        let earlier = Utc::now() - Duration::days(1);
+        let later = Utc::now();
+        let events = vec![
+            make_event("a", "phone", later, 2.0),
+            make_event("a", "laptop", earlier, 1.0),
+            make_event("b", "laptop", earlier, 3.0),
+            make_event("b", "phone", earlier, 4.0),
+        ];
+        let actual = latest_by_card(events.clone());
+        assert_eq!(2, actual.len());
+        assert_eq!(events[0], actual["a"]);
+        assert_eq!(events[3], actual["b"]);
+    }
+}
@@ -0,0 +1,290 @@
+use crate::state::card::Card;
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+use std::ops::Range;
+
+const SNIPPET_CONTEXT: usize = 40;
+const QUESTION_WEIGHT: usize = 2;
+const ELLIPSIS: &str = "…";
+const FUZZY_CONSECUTIVE_BONUS: usize = 4;
+const FUZZY_WORD_START_BONUS: usize = 3;
+
+#[derive(Clone, Debug)]
+pub struct Query {
+    expression: Regex,
+}
+
+impl Query {
+    // Matches the words in order, across any whitespace (including line breaks).
+    pub fn text(text: &str) -> Result<Self, String> {
+        let words: Vec<String> = text.split_whitespace().map(regex::escape).collect();
+        match words.is_empty() {
+            true => Err("Search query is empty".to_string()),
+            false => Self::regex(&words.join(r"\s+")),
+        }
+    }
+
+    pub fn regex(pattern: &str) -> Result<Self, String> {
+        let expression = RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| format!("Invalid search pattern \"{}\" -> {}", pattern, e))?;
+        Ok(Self { expression })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Snippet {
+    pub text: String,
+    pub highlights: Vec<Range<usize>>,
+}
+
+impl Snippet {
+    fn from(text: &str, matches: &[Range<usize>]) -> Self {
+        let first = &matches[0];
+        let start = floor_char_boundary(text, first.start.saturating_sub(SNIPPET_CONTEXT));
+        let end = ceil_char_boundary(text, first.end + SNIPPET_CONTEXT);
+        let prefix = match start {
+            0 => "",
+            _ => ELLIPSIS,
+        };
+        let suffix = match end == text.len() {
+            true => "",
+            false => ELLIPSIS,
+        };
+        // Replacing line breaks one byte for another keeps the match offsets valid.
+        let window = text[start..end].replace(['\n', '\r', '\t'], " ");
+        let offset = prefix.len();
+        let highlights = matches
+            .iter()
+            .filter(|m| m.start >= start && m.end <= end)
+            .map(|m| m.start - start + offset..m.end - start + offset)
+            .collect();
+        Self {
+            text: format!("{}{}{}", prefix, window, suffix),
+            highlights,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SearchHit<'c> {
+    pub path: &'c str,
+    pub score: usize,
+    pub snippet: Snippet,
+}
+
+// A linear scan is quick enough for a notes directory and needs no index to keep up to date.
+// Matches in the question count for more than those in the answer.
+pub fn search<'c>(cards: impl IntoIterator<Item = &'c Card>, query: &Query) -> Vec<SearchHit<'c>> {
+    let mut hits: Vec<SearchHit> = cards
+        .into_iter()
+        .filter_map(|card| {
+            let in_question = find(&query.expression, &card.question);
+            let in_answer = find(&query.expression, &card.answer);
+            let score = in_question.len() * QUESTION_WEIGHT + in_answer.len();
+            let snippet = match (in_question.is_empty(), in_answer.is_empty()) {
+                (false, _) => Snippet::from(&card.question, &in_question),
+                (true, false) => Snippet::from(&card.answer, &in_answer),
+                (true, true) => return None,
+            };
+            Some(SearchHit {
+                path: &card.path,
+                score,
+                snippet,
+            })
+        })
+        .collect();
+    hits.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.path.cmp(b.path)));
+    hits
+}
+
+// Skim-style matching: the pattern's characters must appear in order, and runs of consecutive
+// characters or characters starting a word score higher.
+pub fn fuzzy_score(pattern: &str, candidate: &str) -> Option<usize> {
+    let mut pattern = pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .peekable();
+    pattern.peek()?;
+    let (mut score, mut previous, mut previous_matched) = (0, None, false);
+    for c in candidate.chars() {
+        let matched = pattern
+            .peek()
+            .is_some_and(|p| c.to_lowercase().eq(std::iter::once(*p)));
+        if matched {
+            pattern.next();
+            score += 1;
+            if previous_matched {
+                score += FUZZY_CONSECUTIVE_BONUS;
+            }
+            if previous.is_none_or(|p: char| !p.is_alphanumeric()) {
+                score += FUZZY_WORD_START_BONUS;
+            }
+        }
+        previous = Some(c);
+        previous_matched = matched;
+    }
+    pattern.peek().is_none().then_some(score)
+}
+
+// Ranks cards by the better of their path's and question's fuzzy score.
+pub fn fuzzy_find<'c>(cards: impl IntoIterator<Item = &'c Card>, pattern: &str) -> Vec<&'c Card> {
+    let mut scored: Vec<(usize, &Card)> = cards
+        .into_iter()
+        .filter_map(|card| {
+            let score = fuzzy_score(pattern, &card.path).max(fuzzy_score(pattern, &card.question));
+            score.map(|score| (score, card))
+        })
+        .collect();
+    scored.sort_by(|(a, a_card), (b, b_card)| b.cmp(a).then_with(|| a_card.path.cmp(&b_card.path)));
+    scored.into_iter().map(|(_, card)| card).collect()
+}
+
+fn find(expression: &Regex, text: &str) -> Vec<Range<usize>> {
+    expression
+        .find_iter(text)
+        .filter(|m| !m.is_empty())
+        .map(|m| m.range())
+        .collect()
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+fn ceil_char_boundary(text: &str, mut index: usize) -> usize {
+    index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index += 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use rstest::*;
+
+    fn make_card(path: &str, question: &str, answer: &str) -> Card {
+        let mut card = Card::default();
+        card.path = path.into();
+        card.question = question.to_string();
+        card.answer = answer.to_string();
+        card
+    }
+
+    #[rstest]
+    #[case::case_insensitive("KREBS cycle", true)]
+    #[case::across_lines("citric acid", true)]
+    #[case::words_out_of_order("cycle krebs", false)]
+    #[case::special_characters("(ATP)", true)]
+    fn text_query(#[case] text: &str, #[case] matches: bool) {
+        let card = make_card(
+            "a",
+            "What does the Krebs Cycle make?",
+            "Energy (ATP) and\ncitric\n acid",
+        );
+        let query = Query::text(text).unwrap();
+        assert_eq!(matches, !search(vec![&card], &query).is_empty());
+    }
+
+    #[test]
+    fn empty_text_query_fails() {
+        assert!(Query::text("  ").is_err());
+    }
+
+    #[test]
+    fn invalid_regex_fails() {
+        assert!(Query::regex("krebs(")
+            .unwrap_err()
+            .contains("Invalid search pattern"));
+    }
+
+    #[test]
+    fn search_ranks_question_matches_first() {
+        let cards = vec![
+            make_card("answer.md", "What makes ATP?", "The Krebs cycle"),
+            make_card("neither.md", "What is glycolysis?", "Splitting glucose"),
+            make_card(
+                "question.md",
+                "Where does the Krebs cycle happen?",
+                "Mitochondria",
+            ),
+            make_card("both.md", "Krebs cycle?", "A krebs cycle is..."),
+        ];
+        let query = Query::regex(r"krebs\s+cycle").unwrap();
+        let paths: Vec<&str> = search(&cards, &query).iter().map(|hit| hit.path).collect();
+        assert_eq!(vec!["both.md", "question.md", "answer.md"], paths);
+    }
+
+    #[test]
+    fn snippets_highlight_matches_around_the_first_one() {
+        let question = format!(
+            "{}krebs\ncycle, the Krebs cycle{}",
+            "a".repeat(50),
+            "z".repeat(50)
+        );
+        let card = make_card("a", &question, "");
+        let hit = &search(vec![&card], &Query::text("krebs cycle").unwrap())[0];
+        let expected_text = format!(
+            "{}{}krebs cycle, the Krebs cycle{}{}",
+            ELLIPSIS,
+            "a".repeat(40),
+            "z".repeat(23),
+            ELLIPSIS
+        );
+        assert_eq!(expected_text, hit.snippet.text);
+        let highlighted: Vec<&str> = hit
+            .snippet
+            .highlights
+            .iter()
+            .map(|range| &hit.snippet.text[range.clone()])
+            .collect();
+        assert_eq!(vec!["krebs cycle", "Krebs cycle"], highlighted);
+    }
+
+    #[rstest]
+    #[case::exact("krebs", "krebs", Some(5 + 4 * FUZZY_CONSECUTIVE_BONUS + FUZZY_WORD_START_BONUS))]
+    #[case::ignores_case_and_spaces("K C", "krebs cycle", Some(2 + 2 * FUZZY_WORD_START_BONUS))]
+    #[case::out_of_order("ck", "krebs cycle", None)]
+    #[case::empty_pattern("", "krebs", None)]
+    fn fuzzy_score(
+        #[case] pattern: &str,
+        #[case] candidate: &str,
+        #[case] expected: Option<usize>,
+    ) {
+        assert_eq!(expected, super::fuzzy_score(pattern, candidate));
+    }
+
+    #[test]
+    fn fuzzy_find_ranks_closer_matches_first() {
+        let cards = vec![
+            make_card("bio/glycolysis.md", "What does glycolysis make?", ""),
+            make_card("bio/krebs.md", "Where does the cycle happen?", ""),
+            make_card("chem/atp.md", "What is ATP?", ""),
+            make_card("bio/kinetics.md", "Kinetic rate equations by substrate", ""),
+        ];
+        let paths: Vec<&str> = fuzzy_find(&cards, "krebs")
+            .iter()
+            .map(|card| &card.path[..])
+            .collect();
+        assert_eq!(vec!["bio/krebs.md", "bio/kinetics.md"], paths);
+    }
+
+    #[test]
+    fn snippets_respect_char_boundaries() {
+        let card = make_card("a", &format!("{}krebs", "€".repeat(30)), "");
+        let hit = &search(vec![&card], &Query::text("krebs").unwrap())[0];
+        assert!(hit.snippet.text.ends_with("krebs"));
+        assert_eq!(
+            "krebs",
+            &hit.snippet.text[hit.snippet.highlights[0].clone()]
+        );
+    }
+}
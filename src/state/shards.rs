@@ -0,0 +1,234 @@
+use super::card::{parser::ParsingConfig, Card};
+use super::deck::Deck;
+use super::error::{StorageError, UnreadableSnafu, UnwritableSnafu};
+use super::id::{CardId, DeckName};
+use super::State;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use snafu::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const SHARDS_DIR_NAME: &str = "decks";
+const SHARD_EXTENSION: &str = "ron";
+// Holds the cards that aren't in any deck.
+const UNFILED_SHARD_NAME: &str = "_unfiled";
+
+// Everything but the cards, which are kept in their decks' shards. Sorted so that an unchanged
+// index or shard serialises to the same text, and isn't rewritten.
+#[derive(Deserialize, Serialize)]
+struct Index {
+    card_parsing_config: ParsingConfig,
+    decks: BTreeMap<DeckName, Deck>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    metadata_only: bool,
+}
+
+impl State {
+    // Reads an index file and the shards in the "decks" directory beside it.
+    pub fn read_sharded(index_path: &str) -> Result<Self, StorageError> {
+        let index: Index = parse(&read(Path::new(index_path))?, Path::new(index_path))?;
+        let mut cards = HashMap::new();
+        for path in shard_paths(index_path)? {
+            let shard: HashMap<CardId, Card> = parse(&read(&path)?, &path)?;
+            cards.extend(shard);
+        }
+        Ok(Self {
+            card_parsing_config: index.card_parsing_config,
+            cards,
+            decks: index.decks.into_iter().collect(),
+            metadata_only: index.metadata_only,
+            ..Self::default()
+        })
+    }
+
+    // Each card goes in the shard of the first deck its note names. Files whose content is
+    // unchanged are left alone, and the shards of decks that no longer have cards are removed.
+    pub fn write_sharded(&self, index_path: &str) -> Result<(), StorageError> {
+        let stored = self.stored();
+        let index = Index {
+            card_parsing_config: self.card_parsing_config.clone(),
+            decks: self.decks.clone().into_iter().collect(),
+            metadata_only: self.metadata_only,
+        };
+        let mut shards: BTreeMap<PathBuf, BTreeMap<&str, &Card>> = BTreeMap::new();
+        for card in stored.cards.values() {
+            let deck_name = card
+                .decks
+                .first()
+                .map_or(UNFILED_SHARD_NAME, String::as_str);
+            shards
+                .entry(shard_path(index_path, deck_name))
+                .or_default()
+                .insert(&card.path, card);
+        }
+        let shards_dir = shards_dir(index_path);
+        fs::create_dir_all(&shards_dir).context(UnwritableSnafu {
+            path: shards_dir.display().to_string(),
+        })?;
+        for path in shard_paths(index_path)? {
+            if !shards.contains_key(&path) {
+                fs::remove_file(&path).context(UnwritableSnafu {
+                    path: path.display().to_string(),
+                })?;
+            }
+        }
+        for (path, cards) in shards.iter() {
+            write_if_changed(path, &serialise(cards, path)?)?;
+        }
+        write_if_changed(
+            Path::new(index_path),
+            &serialise(&index, Path::new(index_path))?,
+        )
+    }
+}
+
+pub fn shards_dir(index_path: &str) -> PathBuf {
+    Path::new(index_path)
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(SHARDS_DIR_NAME)
+}
+
+pub fn shard_paths(index_path: &str) -> Result<Vec<PathBuf>, StorageError> {
+    let shards_dir = shards_dir(index_path);
+    if !shards_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut paths: Vec<PathBuf> = fs::read_dir(&shards_dir)
+        .context(UnreadableSnafu {
+            path: shards_dir.display().to_string(),
+        })?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.extension().is_some_and(|e| e == SHARD_EXTENSION))
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+// Deck names may contain "/", as with inline tags, so it is escaped along with "%".
+fn shard_path(index_path: &str, deck_name: &str) -> PathBuf {
+    let file_name = deck_name
+        .replace('%', "%25")
+        .replace('/', "%2F")
+        .replace('\\', "%5C");
+    shards_dir(index_path).join(format!("{}.{}", file_name, SHARD_EXTENSION))
+}
+
+fn read(path: &Path) -> Result<String, StorageError> {
+    fs::read_to_string(path).context(UnreadableSnafu {
+        path: path.display().to_string(),
+    })
+}
+
+fn parse<T: DeserializeOwned>(content: &str, path: &Path) -> Result<T, StorageError> {
+    ron::from_str(content).map_err(|e| StorageError::Malformed {
+        path: path.display().to_string(),
+        message: e.to_string(),
+    })
+}
+
+fn serialise<T: Serialize>(value: &T, path: &Path) -> Result<String, StorageError> {
+    ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default()).map_err(|e| {
+        StorageError::Unserialisable {
+            path: path.display().to_string(),
+            message: e.to_string(),
+        }
+    })
+}
+
+fn write_if_changed(path: &Path, content: &str) -> Result<(), StorageError> {
+    if fs::read_to_string(path).is_ok_and(|existing| existing == content) {
+        return Ok(());
+    }
+    fs::write(path, content).context(UnwritableSnafu {
+        path: path.display().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use std::time::SystemTime;
+
+    fn make_card(path: &str, decks: &[&str]) -> Card {
+        Card {
+            path: path.into(),
+            decks: decks.iter().map(|d| d.to_string()).collect(),
+            ..Card::default()
+        }
+    }
+
+    fn make_index_path(name: &str) -> (PathBuf, String) {
+        let dir = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        let index_path = dir.join("state.ron").to_string_lossy().to_string();
+        fs::create_dir_all(&dir).unwrap();
+        (dir, index_path)
+    }
+
+    fn modified(path: &Path) -> SystemTime {
+        fs::metadata(path).unwrap().modified().unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let (dir, index_path) = make_index_path("vultan_shards_round_trip");
+        let state = State::default().with_loaded_cards(vec![
+            make_card("a.md", &["bio::cells"]),
+            make_card("b.md", &["bio::cells", "chem"]),
+            make_card("c.md", &["web/css"]),
+            make_card("d.md", &[]),
+        ]);
+        state.write_sharded(&index_path).unwrap();
+        let names: Vec<String> = shard_paths(&index_path)
+            .unwrap()
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            ["_unfiled.ron", "bio::cells.ron", "web%2Fcss.ron"],
+            names[..]
+        );
+        assert_eq!(state, State::read_sharded(&index_path).unwrap());
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn write_only_rewrites_changed_shards() {
+        let (dir, index_path) = make_index_path("vultan_shards_changed");
+        let state = State::default().with_loaded_cards(vec![
+            make_card("a.md", &["bio"]),
+            make_card("b.md", &["chem"]),
+        ]);
+        state.write_sharded(&index_path).unwrap();
+        let (bio, chem) = (
+            shard_path(&index_path, "bio"),
+            shard_path(&index_path, "chem"),
+        );
+        let (bio_modified, chem_modified) = (modified(&bio), modified(&chem));
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        let mut card = state.cards()["a.md"].clone();
+        card.question = "changed".to_string();
+        state
+            .with_overriden_cards(vec![card])
+            .write_sharded(&index_path)
+            .unwrap();
+        assert_ne!(bio_modified, modified(&bio));
+        assert_eq!(chem_modified, modified(&chem));
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn write_removes_the_shards_of_emptied_decks() {
+        let (dir, index_path) = make_index_path("vultan_shards_emptied");
+        State::default()
+            .with_loaded_cards(vec![make_card("a.md", &["bio"])])
+            .write_sharded(&index_path)
+            .unwrap();
+        State::default().write_sharded(&index_path).unwrap();
+        assert!(shard_paths(&index_path).unwrap().is_empty());
+        fs::remove_dir_all(dir).ok();
+    }
+}
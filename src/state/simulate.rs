@@ -0,0 +1,223 @@
+use crate::state::card::{Card, Score};
+use crate::state::deck::IntervalCoefficients;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+use std::collections::VecDeque;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimulationOptions {
+    pub days: usize,
+    pub new_cards_per_day: usize,
+    pub retention: f64,
+    pub coefficients: Option<IntervalCoefficients>,
+    pub seed: u64,
+}
+
+impl Default for SimulationOptions {
+    fn default() -> Self {
+        Self {
+            days: 90,
+            new_cards_per_day: 20,
+            retention: 0.9,
+            coefficients: None,
+            seed: 0,
+        }
+    }
+}
+
+impl SimulationOptions {
+    pub fn with_days(self, days: usize) -> Self {
+        Self { days, ..self }
+    }
+
+    pub fn with_new_cards_per_day(self, new_cards_per_day: usize) -> Self {
+        Self {
+            new_cards_per_day,
+            ..self
+        }
+    }
+
+    pub fn with_retention(self, retention: f64) -> Self {
+        Self { retention, ..self }
+    }
+
+    // Used for every card in place of its deck's coefficients.
+    pub fn with_coefficients(self, coefficients: Option<IntervalCoefficients>) -> Self {
+        Self {
+            coefficients,
+            ..self
+        }
+    }
+
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DailyLoad {
+    pub date: NaiveDate,
+    pub new: usize,
+    pub reviews: usize,
+}
+
+// Projects the daily workload by reviewing every card on the day it falls due, recalling it with
+// the given retention. Each day brings up to new_cards_per_day new cards: the collection's unseen
+// cards first, then imagined ones that schedule like a default card.
+pub fn simulate(
+    cards: Vec<(Card, IntervalCoefficients)>,
+    options: &SimulationOptions,
+    start: DateTime<Utc>,
+) -> Result<Vec<DailyLoad>, String> {
+    if !(0.0..=1.0).contains(&options.retention) {
+        return Err(format!(
+            "Retention must be between 0 and 1, not {}",
+            options.retention
+        ));
+    }
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let (unseen, mut scheduled): (Vec<_>, Vec<_>) = cards
+        .into_iter()
+        .partition(|(card, _)| card.revision_settings.interval == 0.0);
+    let mut unseen = VecDeque::from(unseen);
+    let imagined_coefficients = options.coefficients.clone().unwrap_or_default();
+    let mut loads = Vec::with_capacity(options.days);
+    for day in 0..options.days {
+        let day_start = start + Duration::days(day as i64);
+        let day_end = day_start + Duration::days(1);
+        let mut reviews = 0;
+        for (card, coefficients) in scheduled.iter_mut() {
+            if card.revision_settings.due < day_end {
+                *card = review(
+                    card.clone(),
+                    coefficients,
+                    day_start,
+                    day_end,
+                    options,
+                    &mut rng,
+                );
+                reviews += 1;
+            }
+        }
+        let mut new = 0;
+        while new < options.new_cards_per_day {
+            let (mut card, coefficients) = unseen
+                .pop_front()
+                .unwrap_or_else(|| (Card::default(), imagined_coefficients.clone()));
+            card.revision_settings.due = day_start;
+            let card = review(card, &coefficients, day_start, day_end, options, &mut rng);
+            scheduled.push((card, coefficients));
+            new += 1;
+        }
+        loads.push(DailyLoad {
+            date: day_start.date_naive(),
+            new,
+            reviews,
+        });
+    }
+    Ok(loads)
+}
+
+// Overdue cards count as reviewed on time, and a card is seen at most once a day.
+fn review(
+    card: Card,
+    coefficients: &IntervalCoefficients,
+    day_start: DateTime<Utc>,
+    day_end: DateTime<Utc>,
+    options: &SimulationOptions,
+    rng: &mut StdRng,
+) -> Card {
+    let reviewed_at = card.revision_settings.due.max(day_start);
+    let score = match rng.gen_bool(options.retention) {
+        true => Score::Pass,
+        false => Score::Fail,
+    };
+    let coefficients = options.coefficients.as_ref().unwrap_or(coefficients);
+    let mut revision_settings = card.revision_settings.clone();
+    revision_settings.due = reviewed_at;
+    let mut card = card.with_revision_settings(revision_settings).transform_at(
+        score,
+        coefficients,
+        reviewed_at,
+    );
+    if card.revision_settings.due < day_end {
+        card.revision_settings.due = day_end;
+    }
+    card
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use crate::state::card::RevisionSettings;
+    use chrono::TimeZone;
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap()
+    }
+
+    fn make_card(due: DateTime<Utc>, interval: f64) -> (Card, IntervalCoefficients) {
+        let card =
+            Card::default().with_revision_settings(RevisionSettings::new(due, interval, 2500.0));
+        (card, IntervalCoefficients::default())
+    }
+
+    fn counts(loads: &[DailyLoad]) -> Vec<(usize, usize)> {
+        loads.iter().map(|load| (load.new, load.reviews)).collect()
+    }
+
+    #[test]
+    fn simulate_new_cards() {
+        let options = SimulationOptions::default()
+            .with_days(3)
+            .with_new_cards_per_day(2)
+            .with_retention(1.0);
+        let actual = simulate(vec![], &options, start()).unwrap();
+        assert_eq!(vec![(2, 0), (2, 0), (2, 2)], counts(&actual));
+        assert_eq!(NaiveDate::from_ymd_opt(2024, 3, 3), Some(actual[2].date));
+    }
+
+    #[test]
+    fn simulate_reviews_overdue_cards_on_the_first_day() {
+        let cards = vec![
+            make_card(start() - Duration::days(10), 5.0),
+            make_card(start() + Duration::hours(6), 5.0),
+            make_card(start() + Duration::days(2), 5.0),
+            make_card(start(), 0.0),
+        ];
+        let options = SimulationOptions::default()
+            .with_days(3)
+            .with_new_cards_per_day(0)
+            .with_retention(1.0);
+        let actual = simulate(cards, &options, start()).unwrap();
+        assert_eq!(vec![(0, 2), (0, 0), (0, 1)], counts(&actual));
+    }
+
+    #[test]
+    fn simulate_shows_failed_cards_again_the_next_day() {
+        let cards = vec![make_card(start(), 5.0)];
+        let options = SimulationOptions::default()
+            .with_days(3)
+            .with_new_cards_per_day(0)
+            .with_retention(0.0);
+        let actual = simulate(cards, &options, start()).unwrap();
+        assert_eq!(vec![(0, 1), (0, 1), (0, 1)], counts(&actual));
+    }
+
+    #[test]
+    fn simulate_is_repeatable_with_a_seed() {
+        let options = SimulationOptions::default().with_days(30).with_seed(7);
+        let first = simulate(vec![], &options, start()).unwrap();
+        let second = simulate(vec![], &options, start()).unwrap();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn simulate_rejects_invalid_retention() {
+        let options = SimulationOptions::default().with_retention(1.5);
+        assert!(simulate(vec![], &options, start()).is_err());
+    }
+}
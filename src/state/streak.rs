@@ -0,0 +1,75 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+// Consecutive days, in local time, with at least one review.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Streak {
+    pub current: u32,
+    pub best: u32,
+    pub last_day: Option<NaiveDate>,
+}
+
+impl Streak {
+    pub fn with_review_on(self, day: NaiveDate) -> Self {
+        let current = match self.last_day {
+            Some(last_day) if last_day == day => self.current,
+            Some(last_day) if last_day.succ_opt() == Some(day) => self.current + 1,
+            _ => 1,
+        };
+        Self {
+            current,
+            best: self.best.max(current),
+            last_day: Some(day),
+        }
+    }
+
+    // A streak isn't broken until a whole day passes without a review, so one reviewed yesterday
+    // still counts today.
+    pub fn as_of(self, today: NaiveDate) -> Self {
+        let unbroken = self
+            .last_day
+            .is_some_and(|last_day| last_day == today || last_day.succ_opt() == Some(today));
+        match unbroken {
+            true => self,
+            false => Self { current: 0, ..self },
+        }
+    }
+}
+
+#[cfg(test)]
+mod unit_tests {
+
+    use super::*;
+    use rstest::*;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, d).unwrap()
+    }
+
+    fn make_streak(current: u32, best: u32, last_day: u32) -> Streak {
+        Streak {
+            current,
+            best,
+            last_day: Some(day(last_day)),
+        }
+    }
+
+    #[rstest]
+    #[case::first_review(Streak::default(), 10, make_streak(1, 1, 10))]
+    #[case::same_day(make_streak(3, 5, 10), 10, make_streak(3, 5, 10))]
+    #[case::next_day(make_streak(5, 5, 10), 11, make_streak(6, 6, 11))]
+    #[case::after_a_gap(make_streak(3, 5, 10), 12, make_streak(1, 5, 12))]
+    fn with_review_on(#[case] streak: Streak, #[case] on: u32, #[case] expected: Streak) {
+        assert_eq!(expected, streak.with_review_on(day(on)));
+    }
+
+    #[rstest]
+    #[case::reviewed_today(10, 3)]
+    #[case::reviewed_yesterday(11, 3)]
+    #[case::broken(12, 0)]
+    fn as_of(#[case] today: u32, #[case] expected_current: u32) {
+        let actual = make_streak(3, 5, 10).as_of(day(today));
+        assert_eq!(expected_current, actual.current);
+        assert_eq!(5, actual.best);
+    }
+}
@@ -1,5 +1,16 @@
 pub trait UID {
-    fn uid(&self) -> &str;
+    type Id: Clone + Eq + std::hash::Hash;
+
+    fn uid(&self) -> Self::Id;
+
+    // The id as the plain string uid returned before ids were typed.
+    #[deprecated(note = "use uid, which returns a CardId or DeckName")]
+    fn uid_string(&self) -> String
+    where
+        Self::Id: Into<String>,
+    {
+        self.uid().into()
+    }
 }
 
 pub trait Merge<T> {
@@ -28,11 +39,11 @@ pub mod test_tools {
         })
     }
 
-    fn uid_map_contains<'a, T>(map: &HashMap<String, T>, item: &'a T) -> bool
+    fn uid_map_contains<'a, T>(map: &HashMap<T::Id, T>, item: &'a T) -> bool
     where
         T: PartialEq + UID,
     {
-        map.contains_key(item.uid()) && *item == map[item.uid()]
+        map.get(&item.uid()) == Some(item)
     }
 
     pub mod assertions {
@@ -55,7 +66,7 @@ pub mod test_tools {
             assert!(container.len() == expected_length);
         }
 
-        pub fn assert_uid_map_contains<'a, T>(map: &HashMap<String, T>, expected: &'a [Expect<T>])
+        pub fn assert_uid_map_contains<'a, T>(map: &HashMap<T::Id, T>, expected: &'a [Expect<T>])
         where
             T: Default + std::fmt::Debug + PartialEq + UID,
         {